@@ -0,0 +1,230 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{LazyLock, Mutex},
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::Message;
+use tracing::warn;
+
+use crate::{
+    ids::group_channel_ids,
+    privacy::is_erased,
+    storage::Storage,
+    utils::time::{local_today, timezone},
+    Data,
+};
+
+const ACTIVITY_KEY: &str = "activity.groups";
+/// Days of counters kept, enough for the weekly summary.
+const RETENTION_DAYS: i64 = 14;
+/// A message only counts as a response if it follows the previous one within this time,
+/// otherwise it starts a new conversation.
+const MAX_RESPONSE_SECONDS: i64 = 6 * 60 * 60;
+
+/// Activity counted since the last [`flush_activity`], so group chatter doesn't rewrite
+/// the store on every message. Lost if the bot stops before the flush.
+static PENDING: LazyLock<Mutex<ActivityLog>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ActivityLog {
+    days: Vec<GroupDay>,
+    /// Author and time of the latest message in each group, used for response times.
+    last_messages: HashMap<u64, (u64, DateTime<Utc>)>,
+}
+
+/// Message counters of a group channel for a single day.
+#[derive(Debug, Serialize, Deserialize)]
+struct GroupDay {
+    group: u64,
+    date: NaiveDate,
+    messages: u64,
+    members: HashSet<u64>,
+    response_seconds: i64,
+    responses: u64,
+}
+
+/// Activity of a group channel over a period.
+#[derive(Debug)]
+pub struct GroupActivity {
+    pub group: u64,
+    pub messages: u64,
+    pub active_members: usize,
+    /// Average time before someone else replied, if anyone did.
+    pub average_response: Option<chrono::Duration>,
+}
+
+/// Counts a message posted in one of the group channels.
+pub async fn record_message(data: &Data, message: &Message) {
+    if message.author.bot {
+        return;
+    }
+    let Some((group, _)) = group_channel_ids()
+        .into_iter()
+        .find(|(_, channel)| *channel == message.channel_id.get())
+    else {
+        return;
+    };
+
+    match is_erased(&data.storage, &message.author.id.to_string()).await {
+        Ok(false) => {}
+        Ok(true) => return,
+        Err(e) => {
+            warn!("Failed to check whether the author was erased: {:?}", e);
+            return;
+        }
+    }
+
+    let author = message.author.id.get();
+    let sent_at =
+        DateTime::from_timestamp(message.timestamp.unix_timestamp(), 0).unwrap_or_else(Utc::now);
+    let date = sent_at.with_timezone(&timezone()).date_naive();
+    // The group's previous message, from the store if none came in since the bot started.
+    let known = PENDING
+        .lock()
+        .expect("Activity lock poisoned")
+        .last_messages
+        .contains_key(&group);
+    let stored = if known {
+        None
+    } else {
+        match data.storage.get::<ActivityLog>(ACTIVITY_KEY).await {
+            Ok(log) => log.last_messages.get(&group).copied(),
+            Err(e) => {
+                warn!("Failed to read group activity: {:?}", e);
+                return;
+            }
+        }
+    };
+
+    let mut pending = PENDING.lock().expect("Activity lock poisoned");
+    let previous = pending.last_messages.insert(group, (author, sent_at));
+    let response = previous
+        .or(stored)
+        .filter(|(previous_author, _)| *previous_author != author)
+        .map(|(_, previous)| (sent_at - previous).num_seconds())
+        .filter(|seconds| (0..MAX_RESPONSE_SECONDS).contains(seconds));
+    let day = GroupDay {
+        group,
+        date,
+        messages: 1,
+        members: HashSet::from([author]),
+        response_seconds: response.unwrap_or_default(),
+        responses: u64::from(response.is_some()),
+    };
+    add_day(&mut pending.days, day);
+}
+
+/// Adds the counters of `day` to those of the same group and date in `days`.
+fn add_day(days: &mut Vec<GroupDay>, day: GroupDay) {
+    match days
+        .iter_mut()
+        .find(|d| d.group == day.group && d.date == day.date)
+    {
+        Some(existing) => {
+            existing.messages += day.messages;
+            existing.members.extend(day.members);
+            existing.response_seconds += day.response_seconds;
+            existing.responses += day.responses;
+        }
+        None => days.push(day),
+    }
+}
+
+/// Adds the activity counted in memory to the store.
+pub async fn flush_activity(storage: &Storage) -> anyhow::Result<()> {
+    let (days, last_messages) = {
+        let mut pending = PENDING.lock().expect("Activity lock poisoned");
+        // The latest messages stay in memory for the response times of the next ones.
+        (
+            std::mem::take(&mut pending.days),
+            pending.last_messages.clone(),
+        )
+    };
+    if days.is_empty() {
+        return Ok(());
+    }
+
+    let oldest = local_today() - chrono::Duration::days(RETENTION_DAYS);
+    storage
+        .update(ACTIVITY_KEY, |log: &mut ActivityLog| {
+            for day in days {
+                add_day(&mut log.days, day);
+            }
+            log.days.retain(|day| day.date >= oldest);
+            log.last_messages.extend(last_messages);
+        })
+        .await
+}
+
+/// Activity of every group channel since `since`, including groups without messages.
+pub async fn group_activity(
+    storage: &Storage,
+    since: NaiveDate,
+) -> anyhow::Result<Vec<GroupActivity>> {
+    let log: ActivityLog = storage.get(ACTIVITY_KEY).await?;
+
+    Ok(group_channel_ids()
+        .iter()
+        .map(|(group, _)| {
+            let days: Vec<&GroupDay> = log
+                .days
+                .iter()
+                .filter(|day| day.group == *group && day.date >= since)
+                .collect();
+            let members: HashSet<u64> = days
+                .iter()
+                .flat_map(|day| day.members.iter().copied())
+                .collect();
+            let responses: u64 = days.iter().map(|day| day.responses).sum();
+            let response_seconds: i64 = days.iter().map(|day| day.response_seconds).sum();
+
+            GroupActivity {
+                group: *group,
+                messages: days.iter().map(|day| day.messages).sum(),
+                active_members: members.len(),
+                average_response: (responses > 0)
+                    .then(|| chrono::Duration::seconds(response_seconds / responses as i64)),
+            }
+        })
+        .collect())
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    {
+        let mut pending = PENDING.lock().expect("Activity lock poisoned");
+        for day in &mut pending.days {
+            day.members.remove(&user_id);
+        }
+        pending
+            .last_messages
+            .retain(|_, (author, _)| *author != user_id);
+    }
+    storage
+        .update(ACTIVITY_KEY, |log: &mut ActivityLog| {
+            for day in &mut log.days {
+                day.members.remove(&user_id);
+            }
+            log.last_messages
+                .retain(|_, (author, _)| *author != user_id);
+        })
+        .await
+}
@@ -0,0 +1,281 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ChannelId, ComponentInteraction, Context as SerenityContext, CreateActionRow,
+    CreateButton, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMessage,
+    EditMessage, MessageId, UserId,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    preferences::{allows, Notification},
+    storage::Storage,
+    utils::{
+        long_message::long_message,
+        time::{discord_timestamp, TimestampStyle},
+    },
+    Data,
+};
+
+/// Custom ID prefix of the RSVP buttons, routed here by [`crate::interactions`].
+pub const ANNOUNCEMENT_COMPONENT: &str = "announcement";
+const ANNOUNCEMENTS_KEY: &str = "announcements.rsvps";
+
+/// An announcement members can RSVP to, until the event it announces starts.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Announcement {
+    pub id: u64,
+    pub channel_id: u64,
+    pub message_id: u64,
+    /// Who posted it, and gets the attendee list when the event starts.
+    pub organizer_id: u64,
+    pub content: String,
+    pub starts_at: DateTime<Utc>,
+    pub attendees: Vec<u64>,
+    /// Whether the attendee list was sent and RSVPs closed.
+    pub closed: bool,
+}
+
+impl Announcement {
+    pub fn embed(&self) -> CreateEmbed {
+        CreateEmbed::new()
+            .description(&self.content)
+            .field(
+                "When",
+                discord_timestamp(self.starts_at, TimestampStyle::LongDateTime),
+                true,
+            )
+            .field("Going", self.attendees.len().to_string(), true)
+            .footer(CreateEmbedFooter::new(format!("Announcement #{}", self.id)))
+    }
+
+    fn rsvp_button(&self) -> CreateActionRow {
+        CreateActionRow::Buttons(vec![CreateButton::new(format!(
+            "{}:rsvp:{}",
+            ANNOUNCEMENT_COMPONENT, self.id
+        ))
+        .label("RSVP")
+        .style(ButtonStyle::Primary)])
+    }
+}
+
+pub async fn announcements(storage: &Storage) -> anyhow::Result<Vec<Announcement>> {
+    storage.get(ANNOUNCEMENTS_KEY).await
+}
+
+/// Applies `f` to the announcement with `id`, returning `None` if there is no such one.
+async fn update_announcement<R>(
+    storage: &Storage,
+    id: u64,
+    f: impl FnOnce(&mut Announcement) -> R,
+) -> anyhow::Result<Option<R>> {
+    storage
+        .update(
+            ANNOUNCEMENTS_KEY,
+            |announcements: &mut Vec<Announcement>| {
+                announcements.iter_mut().find(|a| a.id == id).map(f)
+            },
+        )
+        .await
+}
+
+/// Drops the member's RSVPs. Announcements they posted stay, they're public anyway.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(
+            ANNOUNCEMENTS_KEY,
+            |announcements: &mut Vec<Announcement>| {
+                for announcement in announcements {
+                    announcement.attendees.retain(|id| *id != user_id);
+                }
+            },
+        )
+        .await
+}
+
+/// Posts `content` to `channel_id` with an RSVP button that stays open until `starts_at`.
+pub async fn post_with_rsvp(
+    ctx: &SerenityContext,
+    data: &Data,
+    channel_id: ChannelId,
+    organizer_id: UserId,
+    content: String,
+    starts_at: DateTime<Utc>,
+) -> anyhow::Result<Announcement> {
+    let mut announcement = Announcement {
+        id: announcements(&data.storage)
+            .await?
+            .iter()
+            .map(|a| a.id)
+            .max()
+            .unwrap_or(0)
+            + 1,
+        channel_id: channel_id.get(),
+        message_id: 0,
+        organizer_id: organizer_id.get(),
+        content,
+        starts_at,
+        attendees: Vec::new(),
+        closed: false,
+    };
+    let message = CreateMessage::new()
+        .embed(announcement.embed())
+        .components(vec![announcement.rsvp_button()]);
+    announcement.message_id = channel_id.send_message(&ctx.http, message).await?.id.get();
+
+    data.storage
+        .update(
+            ANNOUNCEMENTS_KEY,
+            |announcements: &mut Vec<Announcement>| announcements.push(announcement.clone()),
+        )
+        .await?;
+    info!(
+        "Announcement #{} with RSVPs posted by {}",
+        announcement.id, organizer_id
+    );
+    Ok(announcement)
+}
+
+/// DMs the organizer of announcement `id` its attendee list, then closes its RSVPs. The
+/// announcement is read again first so RSVPs toggled since it was listed are included,
+/// and stays open if the DM fails so the list is sent on the next try.
+pub async fn close_rsvps(ctx: &SerenityContext, data: &Data, id: u64) -> anyhow::Result<()> {
+    let Some(announcement) = announcements(&data.storage)
+        .await?
+        .into_iter()
+        .find(|a| a.id == id && !a.closed)
+    else {
+        return Ok(());
+    };
+
+    if allows(
+        &data.storage,
+        announcement.organizer_id,
+        Notification::Reminders,
+    )
+    .await
+    {
+        let mut content = format!(
+            "Your announcement #{} is starting, {} members said they're going:\n",
+            announcement.id,
+            announcement.attendees.len()
+        );
+        for user_id in &announcement.attendees {
+            content.push_str(&format!("- <@{}> ({})\n", user_id, user_id));
+        }
+        let filename = format!("announcement_{}_attendees.md", announcement.id);
+        UserId::new(announcement.organizer_id)
+            .direct_message(&ctx.http, long_message(&content, &filename))
+            .await?;
+    }
+
+    let Some(closed) = update_announcement(&data.storage, announcement.id, |a| {
+        a.closed = true;
+        a.clone()
+    })
+    .await?
+    else {
+        return Ok(());
+    };
+    let edit = EditMessage::new().embed(closed.embed()).components(vec![]);
+    if let Err(e) = ChannelId::new(announcement.channel_id)
+        .edit_message(&ctx.http, MessageId::new(announcement.message_id), edit)
+        .await
+    {
+        warn!(
+            "Failed to close RSVPs on announcement #{}: {}",
+            announcement.id, e
+        );
+    }
+    Ok(())
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    arg: &str,
+    data: &Data,
+) {
+    let (Ok(id), "rsvp") = (arg.parse(), action) else {
+        return;
+    };
+    if let Err(e) = toggle_rsvp(ctx, component, id, data).await {
+        error!(
+            "Failed to handle announcement interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+async fn toggle_rsvp(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    id: u64,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let user_id = component.user.id.get();
+    let announcement = update_announcement(&data.storage, id, |announcement| {
+        if announcement.closed {
+            return None;
+        }
+        if announcement.attendees.contains(&user_id) {
+            announcement.attendees.retain(|id| *id != user_id);
+        } else {
+            announcement.attendees.push(user_id);
+        }
+        Some(announcement.clone())
+    })
+    .await?
+    .flatten();
+    let Some(announcement) = announcement else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("RSVPs for this announcement are closed.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    };
+
+    // Refresh the live count on the announcement, then confirm privately.
+    let response = CreateInteractionResponseMessage::new().embed(announcement.embed());
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(response),
+        )
+        .await?;
+    let reply = if announcement.attendees.contains(&user_id) {
+        "You're on the list, the organizer will know you're coming."
+    } else {
+        "You're no longer on the list."
+    };
+    component
+        .create_followup(
+            &ctx.http,
+            CreateInteractionResponseFollowup::new()
+                .content(reply)
+                .ephemeral(true),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,559 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use poise::Modal;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ChannelId, ComponentInteraction, Context as SerenityContext, CreateActionRow,
+    CreateButton, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditMessage, MessageId,
+    ModalInteractionCollector, Permissions, UserId,
+};
+use tokio::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::{
+    config::ReportKind,
+    graphql::{models::Member, queries::fetch_members},
+    history::{recent_status_update_days, status_update_day, update_member_result},
+    preferences::{allows, Notification},
+    storage::Storage,
+    streaks::{self, UpdateStreaks},
+    utils::{
+        broadcast::broadcast,
+        delivery::{latest_report, ReportMessage},
+        permissions::clicker_has,
+        time::{format_date, format_day},
+    },
+    Data,
+};
+
+/// Custom ID prefix of the appeal buttons, routed here by [`crate::interactions`].
+pub const APPEAL_COMPONENT: &str = "appeal";
+const APPEALS_KEY: &str = "appeals.queue";
+/// Whether each member's defaulter notices reached them, by user ID.
+const RECEIPTS_KEY: &str = "appeals.receipts";
+/// How long a member has to fill in the appeal form after clicking the button.
+const APPEAL_FORM_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, poise::Modal)]
+#[name = "Appeal a missed update"]
+struct AppealModal {
+    #[name = "Why should this day count?"]
+    #[paragraph]
+    #[max_length = 1000]
+    reason: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AppealStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A defaulter's request to have a missed status update excused.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Appeal {
+    pub id: u64,
+    pub date: NaiveDate,
+    pub user_id: u64,
+    pub member_id: i32,
+    pub name: String,
+    pub reason: String,
+    pub status: AppealStatus,
+    /// The report the appeal was made from, outcomes are appended to it.
+    pub report: Option<ReportMessage>,
+}
+
+impl Appeal {
+    fn embed(&self) -> CreateEmbed {
+        CreateEmbed::new()
+            .title(format!("Appeal from {}", self.name))
+            .description(&self.reason)
+            .field("Member", format!("<@{}>", self.user_id), true)
+            .field("Missed update", format_date(self.date), true)
+            .footer(CreateEmbedFooter::new(format!("Appeal #{}", self.id)))
+    }
+}
+
+/// Whether a member's defaulter notices have been reaching them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DeliveryReceipt {
+    /// The last day a notice was delivered, [`None`] if none ever was.
+    pub last_delivered: Option<NaiveDate>,
+    /// Days a notice failed since the last delivery, usually because their DMs are closed.
+    pub failed: Vec<NaiveDate>,
+}
+
+impl DeliveryReceipt {
+    /// Whether the latest notice failed.
+    pub fn unreachable(&self) -> bool {
+        !self.failed.is_empty()
+    }
+}
+
+async fn record_receipts(
+    storage: &Storage,
+    date: NaiveDate,
+    attempted: &[u64],
+    failed: &[u64],
+) -> anyhow::Result<BTreeMap<u64, DeliveryReceipt>> {
+    storage
+        .update(
+            RECEIPTS_KEY,
+            |receipts: &mut BTreeMap<u64, DeliveryReceipt>| {
+                for user_id in attempted {
+                    let receipt = receipts.entry(*user_id).or_default();
+                    if failed.contains(user_id) {
+                        if !receipt.failed.contains(&date) {
+                            receipt.failed.push(date);
+                        }
+                    } else {
+                        receipt.last_delivered = Some(date);
+                        receipt.failed.clear();
+                    }
+                }
+                receipts.clone()
+            },
+        )
+        .await
+}
+
+pub async fn appeals(storage: &Storage) -> anyhow::Result<Vec<Appeal>> {
+    storage.get(APPEALS_KEY).await
+}
+
+/// Drops every appeal the member made.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(APPEALS_KEY, |appeals: &mut Vec<Appeal>| {
+            appeals.retain(|a| a.user_id != user_id);
+        })
+        .await?;
+    storage
+        .update(
+            RECEIPTS_KEY,
+            |receipts: &mut BTreeMap<u64, DeliveryReceipt>| {
+                receipts.remove(&user_id);
+            },
+        )
+        .await
+}
+
+/// The "Appeal" button for the status update report of `date`. Buttons sent outside the
+/// report carry a link back to it in `report`.
+pub fn appeal_button(date: NaiveDate, report: Option<ReportMessage>) -> CreateButton {
+    let arg = match report {
+        Some(report) => format!("{}:{}:{}", date, report.channel_id, report.message_id),
+        None => date.to_string(),
+    };
+    CreateButton::new(format!("{}:open:{}", APPEAL_COMPONENT, arg))
+        .label("Appeal")
+        .style(ButtonStyle::Danger)
+}
+
+/// DMs the defaulters of `date` a notice with an "Appeal" button, unless they muted
+/// defaulter notices. Nothing is sent when there is no queue for mentors to review in.
+/// Returns the delivery receipts of everyone notified so far, so the mentor report can
+/// name who never got a warning.
+pub async fn notify_defaulters(
+    ctx: &SerenityContext,
+    data: &Data,
+    date: NaiveDate,
+    defaulters: &[&Member],
+) -> BTreeMap<u64, DeliveryReceipt> {
+    if queue_channel(data).await.is_none() {
+        return BTreeMap::new();
+    }
+    let report = match latest_report(&data.storage, ReportKind::StatusUpdate).await {
+        Ok(report) => report,
+        Err(e) => {
+            warn!("Failed to look up the status update report: {:?}", e);
+            None
+        }
+    };
+
+    let mut messages = Vec::new();
+    for member in defaulters {
+        let Ok(user_id) = member.discord_id.parse::<u64>() else {
+            continue;
+        };
+        if !allows(&data.storage, user_id, Notification::DefaulterNotices).await {
+            continue;
+        }
+        let dm = CreateMessage::new()
+            .content(
+                "You were marked as a defaulter in today's status update report. \
+                 If you did send an update or had a good reason not to, you can appeal.",
+            )
+            .components(vec![CreateActionRow::Buttons(vec![appeal_button(
+                date, report,
+            )])]);
+        messages.push((user_id, dm));
+    }
+    let attempted: Vec<u64> = messages.iter().map(|(user_id, _)| *user_id).collect();
+    let summary = broadcast(ctx, data, "Defaulter notices", messages).await;
+    match record_receipts(&data.storage, date, &attempted, &summary.failed).await {
+        Ok(receipts) => receipts,
+        Err(e) => {
+            warn!("Failed to record defaulter notice receipts: {:?}", e);
+            BTreeMap::new()
+        }
+    }
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    arg: &str,
+    data: &Data,
+) {
+    let result = match action {
+        "open" => open_appeal(ctx, component, arg, data).await,
+        "approve" | "reject" => match arg.parse() {
+            Ok(id) => review_appeal(ctx, component, id, action == "approve", data).await,
+            Err(_) => return,
+        },
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        error!(
+            "Failed to handle appeal interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+/// Where appeals are posted for mentors to review.
+async fn queue_channel(data: &Data) -> Option<u64> {
+    let config = data.config.read().await;
+    config
+        .status_update
+        .appeal_channel_id
+        .or(config.bot.ops_channel_id)
+}
+
+async fn open_appeal(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    arg: &str,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let mut parts = arg.split(':');
+    let date: NaiveDate = parts.next().unwrap_or_default().parse()?;
+    let report = match (parts.next(), parts.next()) {
+        (Some(channel_id), Some(message_id)) => Some(ReportMessage {
+            channel_id: channel_id.parse()?,
+            message_id: message_id.parse()?,
+        }),
+        // Outside of DMs the button sits on the report itself.
+        _ if component.guild_id.is_some() => Some(ReportMessage {
+            channel_id: component.channel_id.get(),
+            message_id: component.message.id.get(),
+        }),
+        _ => None,
+    };
+
+    let user_id = component.user.id.get();
+    let result = status_update_day(&data.storage, date)
+        .await?
+        .and_then(|day| day.member(&user_id.to_string()).cloned());
+    let already_appealed = appeals(&data.storage)
+        .await?
+        .iter()
+        .any(|a| a.user_id == user_id && a.date == date);
+    let accepting = queue_channel(data).await.is_some();
+    let refusal = match &result {
+        _ if !accepting => Some("Appeals aren't being accepted."),
+        None => Some("You weren't part of this report."),
+        Some(result) if result.sent_update => Some("Your update was counted for this day."),
+        Some(_) if already_appealed => Some("You already appealed this day."),
+        Some(_) => None,
+    };
+    if let Some(refusal) = refusal {
+        let response = CreateInteractionResponseMessage::new()
+            .content(refusal)
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    }
+    let result = result.expect("Checked above");
+
+    let modal_id = component.id.to_string();
+    component
+        .create_response(&ctx.http, AppealModal::create(None, modal_id.clone()))
+        .await?;
+    let Some(submission) = ModalInteractionCollector::new(&ctx.shard)
+        .filter(move |m| m.data.custom_id == modal_id)
+        .timeout(APPEAL_FORM_TIMEOUT)
+        .await
+    else {
+        return Ok(());
+    };
+    let form = AppealModal::parse(submission.data.clone()).map_err(anyhow::Error::msg)?;
+
+    let appeal = data
+        .storage
+        .update(APPEALS_KEY, |appeals: &mut Vec<Appeal>| {
+            let appeal = Appeal {
+                id: appeals.iter().map(|a| a.id).max().unwrap_or(0) + 1,
+                date,
+                user_id,
+                member_id: result.member_id,
+                name: result.name.clone(),
+                reason: form.reason,
+                status: AppealStatus::Pending,
+                report,
+            };
+            appeals.push(appeal.clone());
+            appeal
+        })
+        .await?;
+    info!("Appeal #{} submitted by {}", appeal.id, appeal.name);
+
+    let Some(channel_id) = queue_channel(data).await else {
+        return Ok(());
+    };
+    let buttons = CreateActionRow::Buttons(vec![
+        review_button("approve", appeal.id, "Approve", ButtonStyle::Success),
+        review_button("reject", appeal.id, "Reject", ButtonStyle::Danger),
+    ]);
+    ChannelId::new(channel_id)
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .content("New defaulter appeal:")
+                .embed(appeal.embed())
+                .components(vec![buttons]),
+        )
+        .await?;
+
+    let response = CreateInteractionResponseMessage::new()
+        .content("Your appeal was sent to the mentors.")
+        .ephemeral(true);
+    submission
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+
+    Ok(())
+}
+
+fn review_button(action: &str, id: u64, label: &str, style: ButtonStyle) -> CreateButton {
+    CreateButton::new(format!("{}:{}:{}", APPEAL_COMPONENT, action, id))
+        .label(label)
+        .style(style)
+}
+
+async fn review_appeal(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    id: u64,
+    approved: bool,
+    data: &Data,
+) -> anyhow::Result<()> {
+    if !clicker_has(component, Permissions::MANAGE_GUILD) {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Only mentors can review appeals.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    }
+
+    let status = if approved {
+        AppealStatus::Approved
+    } else {
+        AppealStatus::Rejected
+    };
+    let appeal = data
+        .storage
+        .update(APPEALS_KEY, |appeals: &mut Vec<Appeal>| {
+            let appeal = appeals
+                .iter_mut()
+                .find(|a| a.id == id && a.status == AppealStatus::Pending)?;
+            appeal.status = status;
+            Some(appeal.clone())
+        })
+        .await?;
+    let Some(appeal) = appeal else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("This appeal was already reviewed.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    };
+
+    if approved {
+        if let Err(e) = restore_streak(data, &appeal).await {
+            // Let mentors retry rather than silently approving without the streak.
+            data.storage
+                .update(APPEALS_KEY, |appeals: &mut Vec<Appeal>| {
+                    if let Some(a) = appeals.iter_mut().find(|a| a.id == id) {
+                        a.status = AppealStatus::Pending;
+                    }
+                })
+                .await?;
+            let response = CreateInteractionResponseMessage::new()
+                .content(format!(
+                    "Failed to restore the streak: {:#}\nApproving again carries on from there.",
+                    e
+                ))
+                .ephemeral(true);
+            component
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Err(e);
+        }
+    }
+
+    let outcome = if approved { "Approved" } else { "Rejected" };
+    info!("Appeal #{} {} by {}", id, outcome, component.user.name);
+    let response = CreateInteractionResponseMessage::new()
+        .content(format!("{} by {}.", outcome, component.user.name))
+        .components(vec![]);
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(response),
+        )
+        .await?;
+
+    if let Err(e) = append_outcomes(ctx, data, &appeal).await {
+        warn!("Failed to add appeal #{} to its report: {:?}", id, e);
+    }
+    if !allows(&data.storage, appeal.user_id, Notification::Decisions).await {
+        return Ok(());
+    }
+    let dm = CreateMessage::new().content(format!(
+        "Your appeal for {} was {}.",
+        format_day(appeal.date),
+        outcome.to_lowercase()
+    ));
+    if let Err(e) = UserId::new(appeal.user_id)
+        .direct_message(&ctx.http, dm)
+        .await
+    {
+        warn!("Failed to DM the outcome of appeal #{}: {}", id, e);
+    }
+
+    Ok(())
+}
+
+/// Gives the member back the streak they would have had if the missed update had counted,
+/// including any days they have sent updates on since.
+async fn restore_streak(data: &Data, appeal: &Appeal) -> anyhow::Result<()> {
+    let discord_id = appeal.user_id.to_string();
+    let history = recent_status_update_days(&data.storage, usize::MAX).await?;
+    let before = history
+        .iter()
+        .rev()
+        .filter(|day| day.date < appeal.date)
+        .find_map(|day| day.member(&discord_id))
+        .map(|m| m.current_streak)
+        .unwrap_or(0);
+    let on_the_day = history
+        .iter()
+        .find(|day| day.date == appeal.date)
+        .and_then(|day| day.member(&discord_id))
+        .map(|m| m.current_streak)
+        .unwrap_or(0);
+
+    let mut member = fetch_members()
+        .await?
+        .into_iter()
+        .find(|m| m.member_id == appeal.member_id)
+        .ok_or_else(|| anyhow::anyhow!("Member {} is no longer tracked", appeal.name))?;
+    let max = member.streak.first().map_or(0, |s| s.max_streak);
+    // Days since are counted from the history rather than Root, which a partial restore
+    // has already moved, so approving again after one aims for the same streak.
+    let latest = history
+        .iter()
+        .rev()
+        .find_map(|day| day.member(&discord_id))
+        .map_or(on_the_day, |m| m.current_streak);
+    let extended = streaks::extended(before);
+    let restored = extended + (latest - on_the_day).max(0);
+    let policy = UpdateStreaks {
+        storage: &data.storage,
+        resets: true,
+        milestones: &[],
+    };
+    streaks::overturn_miss(&policy, &mut member, restored, max, false).await?;
+
+    update_member_result(&data.storage, appeal.date, &discord_id, |result| {
+        result.sent_update = true;
+        result.current_streak = extended;
+        result.max_streak = result.max_streak.max(extended);
+    })
+    .await?;
+    info!(
+        "Restored the streak of {} to {} after appeal #{}",
+        appeal.name, restored, appeal.id
+    );
+    Ok(())
+}
+
+/// Rewrites the "Appeals" field of the report `appeal` was made from with the outcome of
+/// every reviewed appeal against it.
+async fn append_outcomes(
+    ctx: &SerenityContext,
+    data: &Data,
+    appeal: &Appeal,
+) -> anyhow::Result<()> {
+    let Some(report) = appeal.report else {
+        return Ok(());
+    };
+    let mut outcomes = String::new();
+    for other in appeals(&data.storage).await? {
+        let outcome = match other.status {
+            _ if other.date != appeal.date => continue,
+            AppealStatus::Pending => continue,
+            AppealStatus::Approved => "appeal approved, streak restored",
+            AppealStatus::Rejected => "appeal rejected",
+        };
+        outcomes.push_str(&format!("- {} | {}\n", other.name, outcome));
+    }
+
+    let channel_id = ChannelId::new(report.channel_id);
+    let message = channel_id
+        .message(&ctx.http, MessageId::new(report.message_id))
+        .await?;
+    let Some(mut embed) = message.embeds.into_iter().next() else {
+        return Ok(());
+    };
+    embed.fields.retain(|field| field.name != "Appeals");
+    let embed = CreateEmbed::from(embed).field("Appeals", outcomes, false);
+    channel_id
+        .edit_message(
+            &ctx.http,
+            report.message_id,
+            EditMessage::new().embed(embed),
+        )
+        .await?;
+    Ok(())
+}
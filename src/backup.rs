@@ -0,0 +1,163 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    io::{Read, Write},
+    num::NonZeroU32,
+};
+
+use anyhow::{anyhow, bail, Context as _};
+use chrono::Utc;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde_json::{Map, Value};
+use serenity::all::{
+    ChannelId, Context as SerenityContext, CreateAttachment, CreateMessage, Permissions,
+};
+use tracing::info;
+
+use crate::{
+    migrations::migrate,
+    storage::Storage,
+    utils::{permissions::check_permissions, time::format_local},
+    Data,
+};
+
+/// Identifies backup archives and their format version.
+const MAGIC: &[u8] = b"AMDBAK2";
+const SALT_LEN: usize = 16;
+/// PBKDF2-HMAC-SHA256 rounds, slowing down guessing the passphrase of a leaked backup.
+const KDF_ITERATIONS: NonZeroU32 = NonZeroU32::new(600_000).expect("Non-zero iterations");
+
+fn passphrase() -> anyhow::Result<String> {
+    std::env::var("BACKUP_KEY").context("BACKUP_KEY was not found in the ENV")
+}
+
+/// Encryption key derived from the `BACKUP_KEY` passphrase and the archive's `salt`. The
+/// derivation is slow on purpose, so it runs on the blocking pool.
+async fn backup_key(salt: &[u8]) -> anyhow::Result<LessSafeKey> {
+    let passphrase = passphrase()?;
+    let salt = salt.to_vec();
+    let key = tokio::task::spawn_blocking(move || {
+        let mut key = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            KDF_ITERATIONS,
+            &salt,
+            passphrase.as_bytes(),
+            &mut key,
+        );
+        key
+    })
+    .await?;
+    let key = UnboundKey::new(&AES_256_GCM, &key)
+        .map_err(|_| anyhow!("Failed to derive the backup key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Compresses and encrypts the whole store. The archive is the magic bytes, the key's
+/// salt, the nonce and the AES-256-GCM sealed gzip of the exported JSON.
+pub async fn create_backup(storage: &Storage) -> anyhow::Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| anyhow!("Failed to generate a salt"))?;
+    let key = backup_key(&salt).await?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&storage.export().await?)?;
+    let mut sealed = encoder.finish()?;
+
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("Failed to generate a nonce"))?;
+    let header = [MAGIC, &salt].concat();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(&header),
+        &mut sealed,
+    )
+    .map_err(|_| anyhow!("Failed to encrypt the backup"))?;
+
+    Ok([&header, &nonce[..], &sealed].concat())
+}
+
+/// Decrypts an archive made by [`create_backup`] and replaces the store with it.
+pub async fn restore_backup(storage: &Storage, archive: &[u8]) -> anyhow::Result<()> {
+    let Some(rest) = archive.strip_prefix(MAGIC) else {
+        bail!("Not an amD backup archive");
+    };
+    if rest.len() < SALT_LEN {
+        bail!("Backup archive is truncated");
+    }
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let key = backup_key(salt).await?;
+    let header = [MAGIC, salt].concat();
+    if rest.len() < NONCE_LEN {
+        bail!("Backup archive is truncated");
+    }
+    let (nonce, sealed) = rest.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| anyhow!("Backup archive has an invalid nonce"))?;
+
+    let mut sealed = sealed.to_vec();
+    let compressed = key
+        .open_in_place(nonce, Aad::from(&header), &mut sealed)
+        .map_err(|_| anyhow!("Failed to decrypt the backup, is BACKUP_KEY the same?"))?;
+    let mut contents = Vec::new();
+    GzDecoder::new(&compressed[..]).read_to_end(&mut contents)?;
+    let mut values: Map<String, Value> =
+        serde_json::from_slice(&contents).context("Backup contents are corrupt")?;
+
+    // Backups taken by older builds may predate some migrations. They're applied first, so
+    // a backup that can't be migrated leaves the store as it was.
+    migrate(&mut values)?;
+    storage.import(values).await
+}
+
+/// Uploads a fresh backup to the configured backup channel.
+pub async fn upload_backup(ctx: &SerenityContext, data: &Data) -> anyhow::Result<()> {
+    let Some(channel_id) = data.config.read().await.backup.channel_id else {
+        bail!("No backup channel is configured");
+    };
+
+    check_permissions(
+        ctx,
+        channel_id,
+        Permissions::SEND_MESSAGES | Permissions::ATTACH_FILES,
+    )?;
+    let archive = create_backup(&data.storage).await?;
+    let size = archive.len();
+    let timezone = data.config.read().await.bot.timezone;
+    let filename = format!(
+        "amd_backup_{}.bin",
+        format_local(Utc::now(), timezone, "%Y%m%d_%H%M%S")
+    );
+    let message = CreateMessage::new()
+        .content(format!("Backup `{}`", filename))
+        .add_file(CreateAttachment::bytes(archive, filename));
+    ChannelId::new(channel_id)
+        .send_message(&ctx.http, message)
+        .await?;
+
+    info!("Uploaded a {} byte backup", size);
+    Ok(())
+}
@@ -0,0 +1,148 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ChannelId, Context, PermissionOverwrite, PermissionOverwriteType, Permissions, RoleId,
+};
+use tracing::{info, warn};
+
+use crate::{config::ChannelLockConfig, storage::Storage, utils::time::local_now, Data};
+
+const OVERRIDES_KEY: &str = "channel_locks.overrides";
+
+/// An admin's manual lock or unlock of a scheduled channel. It holds until the schedule
+/// next changes the channel's state, so a forgotten override doesn't stick forever.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct LockOverride {
+    locked: bool,
+    scheduled_locked: bool,
+}
+
+/// Brings every scheduled channel in line with its schedule or an active override.
+pub async fn apply_locks(ctx: &Context, data: &Data) -> anyhow::Result<()> {
+    let locks = data.config.read().await.channel_locks.clone();
+    if locks.is_empty() {
+        return Ok(());
+    }
+
+    let now = local_now().time();
+    let overrides = data
+        .storage
+        .update(
+            OVERRIDES_KEY,
+            |overrides: &mut HashMap<u64, LockOverride>| {
+                overrides.retain(|channel_id, o| {
+                    locks.iter().any(|lock| {
+                        lock.channel_id == *channel_id && lock.is_locked(now) == o.scheduled_locked
+                    })
+                });
+                overrides.clone()
+            },
+        )
+        .await?;
+
+    for lock in &locks {
+        let locked = overrides
+            .get(&lock.channel_id)
+            .map_or_else(|| lock.is_locked(now), |o| o.locked);
+        if let Err(e) = set_locked(ctx, lock, locked).await {
+            warn!(
+                "Failed to update the lock on channel {}: {:?}",
+                lock.channel_id, e
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Locks or unlocks `lock`'s channel until the schedule next changes its state.
+pub async fn override_lock(
+    ctx: &Context,
+    data: &Data,
+    lock: &ChannelLockConfig,
+    locked: bool,
+) -> anyhow::Result<()> {
+    let now = local_now().time();
+    let lock_override = LockOverride {
+        locked,
+        scheduled_locked: lock.is_locked(now),
+    };
+    data.storage
+        .update(
+            OVERRIDES_KEY,
+            |overrides: &mut HashMap<u64, LockOverride>| {
+                overrides.insert(lock.channel_id, lock_override);
+            },
+        )
+        .await?;
+    set_locked(ctx, lock, locked).await
+}
+
+/// Drops the override on `channel_id`, returning whether there was one.
+pub async fn clear_override(storage: &Storage, channel_id: u64) -> anyhow::Result<bool> {
+    storage
+        .update(
+            OVERRIDES_KEY,
+            |overrides: &mut HashMap<u64, LockOverride>| overrides.remove(&channel_id).is_some(),
+        )
+        .await
+}
+
+/// Denies or allows Send Messages for the lock's role, keeping the rest of its overwrite.
+async fn set_locked(ctx: &Context, lock: &ChannelLockConfig, locked: bool) -> anyhow::Result<()> {
+    let channel = ChannelId::new(lock.channel_id)
+        .to_channel(ctx)
+        .await?
+        .guild()
+        .ok_or_else(|| anyhow!("Channel {} is not in a guild", lock.channel_id))?;
+    // The @everyone role shares its ID with the guild.
+    let role_id = RoleId::new(lock.role_id.unwrap_or(channel.guild_id.get()));
+    let kind = PermissionOverwriteType::Role(role_id);
+
+    let (allow, deny) = channel
+        .permission_overwrites
+        .iter()
+        .find(|overwrite| overwrite.kind == kind)
+        .map_or((Permissions::empty(), Permissions::empty()), |overwrite| {
+            (overwrite.allow, overwrite.deny)
+        });
+    if deny.contains(Permissions::SEND_MESSAGES) == locked {
+        return Ok(());
+    }
+
+    let (allow, deny) = if locked {
+        (
+            allow - Permissions::SEND_MESSAGES,
+            deny | Permissions::SEND_MESSAGES,
+        )
+    } else {
+        (allow, deny - Permissions::SEND_MESSAGES)
+    };
+    channel
+        .create_permission(&ctx.http, PermissionOverwrite { allow, deny, kind })
+        .await?;
+    info!(
+        "{} channel {}",
+        if locked { "Locked" } else { "Unlocked" },
+        channel.name
+    );
+    Ok(())
+}
@@ -0,0 +1,241 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::io::Cursor;
+
+use anyhow::{anyhow, Context as _};
+use chrono::{Datelike, Duration, NaiveDate};
+use image::{ImageFormat, RgbImage};
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 400;
+
+/// Renders `points` as a line chart over time and returns it PNG encoded, ready to
+/// be sent as a Discord attachment.
+pub fn render_line_chart(
+    title: &str,
+    y_label: &str,
+    points: &[(NaiveDate, f64)],
+) -> anyhow::Result<Vec<u8>> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Err(anyhow!("Cannot render a chart without any data points"));
+    };
+    // Pad the ranges so a single data point still produces a valid chart.
+    let x_range = first.0..last.0 + Duration::days(1);
+    let y_min = points.iter().map(|(_, v)| *v).fold(0.0, f64::min);
+    let y_max = points.iter().map(|(_, v)| *v).fold(1.0, f64::max) * 1.1;
+
+    let mut buffer = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)
+            .context("Failed to fill chart background")?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, ("sans-serif", 24))
+            .margin(16)
+            .x_label_area_size(32)
+            .y_label_area_size(48)
+            .build_cartesian_2d(x_range, y_min..y_max)
+            .context("Failed to build chart")?;
+
+        chart
+            .configure_mesh()
+            .x_labels(7)
+            .x_label_formatter(&|date| date.format("%b %d").to_string())
+            .y_desc(y_label)
+            .draw()
+            .context("Failed to draw chart mesh")?;
+
+        chart
+            .draw_series(LineSeries::new(
+                points.iter().copied(),
+                RGBColor(0xea, 0xb3, 0x08).stroke_width(3),
+            ))
+            .context("Failed to draw chart series")?;
+
+        root.present().context("Failed to render chart")?;
+    }
+
+    encode_png(buffer, WIDTH, HEIGHT)
+}
+
+/// Renders a GitHub-style calendar heatmap where every cell is a day (weeks as columns,
+/// weekdays as rows) shaded by its value between 0 and 100. Days without a value are grey.
+pub fn render_calendar_heatmap(
+    title: &str,
+    values: &[(NaiveDate, f64)],
+) -> anyhow::Result<Vec<u8>> {
+    let (Some(first), Some(last)) = (values.first(), values.last()) else {
+        return Err(anyhow!("Cannot render a heatmap without any data points"));
+    };
+    let start = first.0 - Duration::days(first.0.weekday().num_days_from_monday() as i64);
+    let weeks = ((last.0 - start).num_days() / 7 + 1) as i32;
+    let cell_of = |date: NaiveDate| {
+        let offset = (date - start).num_days() as i32;
+        (offset / 7, 6 - offset % 7)
+    };
+
+    let mut buffer = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)
+            .context("Failed to fill heatmap background")?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, ("sans-serif", 24))
+            .margin(16)
+            .x_label_area_size(32)
+            .y_label_area_size(48)
+            .build_cartesian_2d((0..weeks - 1).into_segmented(), (0..6).into_segmented())
+            .context("Failed to build heatmap")?;
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_labels(weeks as usize)
+            .y_labels(7)
+            .x_label_formatter(&|week| match week {
+                SegmentValue::CenterOf(week) => (start + Duration::weeks(*week as i64))
+                    .format("%b %d")
+                    .to_string(),
+                _ => String::new(),
+            })
+            .y_label_formatter(&|row| match row {
+                SegmentValue::CenterOf(row) => ["Sun", "Sat", "Fri", "Thu", "Wed", "Tue", "Mon"]
+                    .get(*row as usize)
+                    .map(|day| day.to_string())
+                    .unwrap_or_default(),
+                _ => String::new(),
+            })
+            .draw()
+            .context("Failed to draw heatmap mesh")?;
+
+        let mut cells = Vec::new();
+        let mut date = start;
+        while date <= last.0 {
+            let value = values.iter().find(|(d, _)| *d == date).map(|(_, v)| *v);
+            let (x, y) = cell_of(date);
+            cells.push(Rectangle::new(
+                [
+                    (SegmentValue::Exact(x), SegmentValue::Exact(y)),
+                    (SegmentValue::Exact(x + 1), SegmentValue::Exact(y + 1)),
+                ],
+                heat_color(value).filled(),
+            ));
+            date += Duration::days(1);
+        }
+        chart
+            .draw_series(cells)
+            .context("Failed to draw heatmap cells")?;
+
+        root.present().context("Failed to render heatmap")?;
+    }
+
+    encode_png(buffer, WIDTH, HEIGHT)
+}
+
+/// A member's level and XP, as shown on their rank card.
+pub struct RankCard<'a> {
+    pub name: &'a str,
+    pub level: u32,
+    pub rank: Option<usize>,
+    pub xp: u64,
+    /// Total XP at which the current level started and the next one starts.
+    pub level_start: u64,
+    pub next_level: u64,
+}
+
+/// Renders a card with the member's level, rank and a bar of their progress towards the
+/// next level, PNG encoded.
+pub fn render_rank_card(card: &RankCard) -> anyhow::Result<Vec<u8>> {
+    const CARD_HEIGHT: u32 = 200;
+    const BAR: (i32, i32, i32, i32) = (32, 130, WIDTH as i32 - 32, 160);
+    let accent = RGBColor(0xea, 0xb3, 0x08);
+
+    let mut buffer = vec![0u8; (WIDTH * CARD_HEIGHT * 3) as usize];
+    {
+        let root =
+            BitMapBackend::with_buffer(&mut buffer, (WIDTH, CARD_HEIGHT)).into_drawing_area();
+        root.fill(&RGBColor(0x2b, 0x2d, 0x31))
+            .context("Failed to fill rank card background")?;
+
+        let text = |size| ("sans-serif", size).into_font().color(&WHITE);
+        let rank = card
+            .rank
+            .map(|rank| format!("Rank #{}", rank))
+            .unwrap_or_else(|| String::from("Unranked"));
+        let progress = card.xp.saturating_sub(card.level_start);
+        let needed = card.next_level.saturating_sub(card.level_start).max(1);
+        root.draw(&Text::new(card.name.to_string(), (32, 24), text(36)))
+            .and_then(|_| {
+                root.draw(&Text::new(
+                    format!("Level {}  |  {}", card.level, rank),
+                    (32, 76),
+                    text(24),
+                ))
+            })
+            .and_then(|_| {
+                root.draw(&Text::new(
+                    format!("{} / {} XP", progress, needed),
+                    (BAR.2, 76),
+                    text(24).pos(Pos::new(HPos::Right, VPos::Top)),
+                ))
+            })
+            .context("Failed to draw rank card text")?;
+
+        let filled = BAR.0 + ((BAR.2 - BAR.0) as f64 * progress as f64 / needed as f64) as i32;
+        root.draw(&Rectangle::new(
+            [(BAR.0, BAR.1), (BAR.2, BAR.3)],
+            RGBColor(0x4e, 0x50, 0x58).filled(),
+        ))
+        .and_then(|_| {
+            root.draw(&Rectangle::new(
+                [(BAR.0, BAR.1), (filled.min(BAR.2), BAR.3)],
+                accent.filled(),
+            ))
+        })
+        .context("Failed to draw rank card progress bar")?;
+
+        root.present().context("Failed to render rank card")?;
+    }
+
+    encode_png(buffer, WIDTH, CARD_HEIGHT)
+}
+
+/// Interpolates from a pale to a dark green based on a percentage.
+fn heat_color(value: Option<f64>) -> RGBColor {
+    let Some(value) = value else {
+        return RGBColor(0xeb, 0xed, 0xf0);
+    };
+    let t = (value / 100.0).clamp(0.0, 1.0);
+    let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t).round() as u8;
+    RGBColor(mix(0xd6, 0x0e), mix(0xf5, 0x61), mix(0xd6, 0x2b))
+}
+
+fn encode_png(buffer: Vec<u8>, width: u32, height: u32) -> anyhow::Result<Vec<u8>> {
+    let image = RgbImage::from_raw(width, height, buffer)
+        .ok_or_else(|| anyhow!("Chart buffer has the wrong size"))?;
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .context("Failed to encode chart as PNG")?;
+
+    Ok(png.into_inner())
+}
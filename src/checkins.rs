@@ -0,0 +1,142 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{DateTime, NaiveDate, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graphql::models::AttendanceRecord,
+    storage::Storage,
+    utils::time::{local_today, timezone},
+};
+
+const CHECKIN_CODE_KEY: &str = "checkin.code";
+const MANUAL_CHECKINS_KEY: &str = "checkin.manual";
+/// Manual check-ins older than this are dropped, they're only needed for the nightly report.
+const CHECKIN_RETENTION_DAYS: i64 = 30;
+const CODE_LENGTH: usize = 6;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DailyCode {
+    date: Option<NaiveDate>,
+    code: String,
+}
+
+/// A check-in made with `$checkin` while the lab's attendance hardware was down.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManualCheckIn {
+    pub date: NaiveDate,
+    pub discord_id: String,
+    pub name: String,
+    pub time: DateTime<Utc>,
+}
+
+/// Returns today's check-in code, generating a new one on the first request of the day.
+pub async fn todays_code(storage: &Storage) -> anyhow::Result<String> {
+    let today = local_today();
+    storage
+        .update(CHECKIN_CODE_KEY, |code: &mut DailyCode| {
+            if code.date != Some(today) {
+                code.date = Some(today);
+                code.code = rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(CODE_LENGTH)
+                    .map(|c| char::from(c).to_ascii_uppercase())
+                    .collect();
+            }
+            code.code.clone()
+        })
+        .await
+}
+
+/// Whether `code` matches today's code. There is no valid code until a mentor has
+/// generated one.
+pub async fn is_valid_code(storage: &Storage, code: &str) -> anyhow::Result<bool> {
+    let stored: DailyCode = storage.get(CHECKIN_CODE_KEY).await?;
+    Ok(stored.date == Some(local_today()) && stored.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// Records a check-in for today, returns `false` if the member already checked in.
+pub async fn record_checkin(
+    storage: &Storage,
+    discord_id: String,
+    name: String,
+) -> anyhow::Result<bool> {
+    let today = local_today();
+    storage
+        .update(MANUAL_CHECKINS_KEY, |checkins: &mut Vec<ManualCheckIn>| {
+            checkins.retain(|c| (today - c.date).num_days() < CHECKIN_RETENTION_DAYS);
+            if checkins
+                .iter()
+                .any(|c| c.date == today && c.discord_id == discord_id)
+            {
+                return false;
+            }
+            checkins.push(ManualCheckIn {
+                date: today,
+                discord_id,
+                name,
+                time: Utc::now(),
+            });
+            true
+        })
+        .await
+}
+
+pub async fn manual_checkins(
+    storage: &Storage,
+    date: NaiveDate,
+) -> anyhow::Result<Vec<ManualCheckIn>> {
+    let checkins: Vec<ManualCheckIn> = storage.get(MANUAL_CHECKINS_KEY).await?;
+    Ok(checkins.into_iter().filter(|c| c.date == date).collect())
+}
+
+pub async fn forget_member(storage: &Storage, discord_id: &str) -> anyhow::Result<()> {
+    storage
+        .update(MANUAL_CHECKINS_KEY, |checkins: &mut Vec<ManualCheckIn>| {
+            checkins.retain(|c| c.discord_id != discord_id)
+        })
+        .await
+}
+
+/// Marks members with a manual check-in as present, returning the names that were
+/// merged in. Root records are matched by name since they don't carry Discord IDs.
+pub fn merge_manual_checkins(
+    records: &mut [AttendanceRecord],
+    checkins: &[ManualCheckIn],
+) -> Vec<String> {
+    let mut merged = Vec::new();
+    for record in records.iter_mut().filter(|r| !r.is_present) {
+        let Some(checkin) = checkins
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(&record.name))
+        else {
+            continue;
+        };
+        record.is_present = true;
+        record.time_in = Some(
+            checkin
+                .time
+                .with_timezone(&timezone())
+                .format("%H:%M:%S")
+                .to_string(),
+        );
+        merged.push(record.name.clone());
+    }
+    merged
+}
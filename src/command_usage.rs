@@ -0,0 +1,96 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{storage::Storage, utils::time::local_today};
+
+/// Command usage by month, e.g. "2026-10".
+const USAGE_KEY: &str = "command_usage.months";
+/// How many months of usage are kept.
+const MAX_MONTHS: usize = 12;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CommandStats {
+    pub calls: u64,
+    pub failures: u64,
+}
+
+impl CommandStats {
+    pub fn failure_ratio(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0;
+        }
+        self.failures as f64 / self.calls as f64
+    }
+}
+
+/// Every command run in a month, by qualified command name and by user.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MonthlyUsage {
+    pub commands: BTreeMap<String, CommandStats>,
+    /// Invocations by user ID.
+    pub users: BTreeMap<u64, u64>,
+}
+
+fn month_key(date: NaiveDate) -> String {
+    date.format("%Y-%m").to_string()
+}
+
+/// Counts a finished run of `command` by `user_id` towards this month.
+pub async fn record(
+    storage: &Storage,
+    command: &str,
+    user_id: u64,
+    succeeded: bool,
+) -> anyhow::Result<()> {
+    let today = local_today();
+    storage
+        .update(USAGE_KEY, |months: &mut BTreeMap<String, MonthlyUsage>| {
+            let usage = months.entry(month_key(today)).or_default();
+            let stats = usage.commands.entry(command.to_string()).or_default();
+            stats.calls += 1;
+            if !succeeded {
+                stats.failures += 1;
+            }
+            *usage.users.entry(user_id).or_default() += 1;
+            while months.len() > MAX_MONTHS {
+                months.pop_first();
+            }
+        })
+        .await
+}
+
+/// Usage in the month of `date`, empty if nothing was recorded.
+pub async fn usage_in(storage: &Storage, date: NaiveDate) -> anyhow::Result<MonthlyUsage> {
+    let mut months: BTreeMap<String, MonthlyUsage> = storage.get(USAGE_KEY).await?;
+    Ok(months.remove(&month_key(date)).unwrap_or_default())
+}
+
+/// Drops the member from the per-user counts, command totals stay.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(USAGE_KEY, |months: &mut BTreeMap<String, MonthlyUsage>| {
+            for usage in months.values_mut() {
+                usage.users.remove(&user_id);
+            }
+        })
+        .await
+}
@@ -15,11 +15,56 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
+mod announce;
+mod attendance;
+mod backup;
+mod channel_locks;
+mod checkin;
+mod db;
+mod debug;
+mod gql;
+mod groups;
+mod history;
+mod holidays;
+mod inventory;
+mod invites;
+mod kudos;
+mod lab;
+mod me;
+mod members;
+mod onboarding;
+mod oncall;
+mod paste;
+mod practice;
+pub mod prefix;
+mod prefs;
+mod privacy;
+mod rank;
+mod reaction_roles;
+mod report;
+mod schedule;
+mod semester;
+mod sessions;
+mod settings;
+mod setup;
+mod shield;
+mod stats;
+mod streaks;
+pub mod subscriptions;
+mod summarize;
+
 use anyhow::Context as _;
-use tracing::{info, trace};
+use tracing::{info, trace, warn};
 use tracing_subscriber::EnvFilter;
 
-use crate::{Context, Data, Error};
+use crate::{
+    config::Config,
+    events::sync_events,
+    ids,
+    tasks::status_update::{recheck_member_update, RecheckOutcome},
+    utils::time,
+    Context, Data, Error,
+};
 
 #[poise::command(prefix_command)]
 async fn amdctl(ctx: Context<'_>) -> Result<(), Error> {
@@ -94,7 +139,107 @@ async fn set_log_level(ctx: Context<'_>, level: String) -> Result<(), Error> {
     Ok(())
 }
 
+/// Re-reads the config file so changes (e.g. report theming) apply without a restart.
+#[poise::command(prefix_command, owners_only)]
+async fn reload_config(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running reload_config command");
+    let config = Config::load()?;
+    ids::configure(&config.ids);
+    time::configure(config.bot.timezone);
+    *ctx.data().config.write().await = config;
+    if let Err(e) = sync_events(ctx.serenity_context(), ctx.data()).await {
+        warn!("Failed to sync scheduled events after reload: {:?}", e);
+    }
+
+    ctx.say("Config reloaded.").await?;
+    info!("Config reloaded");
+    Ok(())
+}
+
+/// Re-validates a member's status update for the latest check, e.g. after they edited it.
+/// Anyone can recheck themselves, rechecking someone else is restricted to owners.
+#[poise::command(prefix_command)]
+async fn recheck(ctx: Context<'_>, user: Option<serenity::all::User>) -> Result<(), Error> {
+    trace!("Running recheck command");
+    let user = user.unwrap_or_else(|| ctx.author().clone());
+    if user.id != ctx.author().id && !ctx.framework().options().owners.contains(&ctx.author().id) {
+        ctx.say("You can only recheck your own update.").await?;
+        return Ok(());
+    }
+
+    let reply = match recheck_member_update(ctx.serenity_context(), ctx.data(), user.id).await? {
+        RecheckOutcome::NotTracked => format!("{} was not part of the latest check.", user.name),
+        RecheckOutcome::AlreadyCounted => format!("{}'s update was already counted.", user.name),
+        RecheckOutcome::NoValidUpdate => format!(
+            "Still couldn't find a valid update from {} for the latest check.",
+            user.name
+        ),
+        RecheckOutcome::Restored { current_streak } => {
+            info!("Restored streak for {} after recheck", user.name);
+            format!(
+                "Found a valid update from {}, streak restored to {}.",
+                user.name, current_streak
+            )
+        }
+    };
+    ctx.say(reply).await?;
+
+    Ok(())
+}
+
 /// Returns a vector containg [Poise Commands][`poise::Command`]
 pub fn get_commands() -> Vec<poise::Command<Data, Error>> {
-    vec![amdctl(), set_log_level()]
+    vec![
+        amdctl(),
+        set_log_level(),
+        reload_config(),
+        recheck(),
+        streaks::profile(),
+        streaks::leaderboard(),
+        practice::practice(),
+        members::whois(),
+        members::link(),
+        members::unlink(),
+        groups::groups(),
+        prefix::prefix(),
+        me::streak(),
+        me::attendance(),
+        summarize::summarize(),
+        paste::paste(),
+        schedule::schedule(),
+        debug::debug(),
+        gql::gql(),
+        subscriptions::subscribe(),
+        subscriptions::unsubscribe(),
+        subscriptions::notify(),
+        announce::announce(),
+        sessions::talk(),
+        checkin::checkin(),
+        privacy::forget_me(),
+        privacy::erase(),
+        backup::backup(),
+        backup::restore(),
+        db::db(),
+        stats::stats(),
+        onboarding::onboarding(),
+        history::history(),
+        report::report(),
+        report::push_summary(),
+        prefs::prefs(),
+        lab::lab(),
+        oncall::oncall(),
+        invites::invites(),
+        reaction_roles::reactionroles(),
+        channel_locks::channellock(),
+        settings::set(),
+        settings::get(),
+        settings::config(),
+        shield::shield(),
+        rank::rank(),
+        kudos::kudos(),
+        inventory::inventory(),
+        semester::semester(),
+        holidays::holidays(),
+        setup::setup(),
+    ]
 }
@@ -0,0 +1,93 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::NaiveDateTime;
+use serenity::all::{CreateEmbed, CreateMessage};
+use tracing::{info, trace};
+
+use crate::{
+    announcements::post_with_rsvp,
+    utils::time::{discord_timestamp, local_to_utc, TimestampStyle},
+    Context, Error,
+};
+
+/// Flag that adds an RSVP button, followed by when the event starts.
+const RSVP_FLAG: &str = "--rsvp";
+const START_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Posts `message` to this channel as an announcement. With
+/// `--rsvp YYYY-MM-DD HH:MM` members can RSVP until the event starts, after which you
+/// get the list of who's going.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_MESSAGES")]
+pub async fn announce(ctx: Context<'_>, #[rest] message: String) -> Result<(), Error> {
+    trace!("Running announce command");
+    let Some(rest) = message.strip_prefix(RSVP_FLAG) else {
+        let announcement = CreateMessage::new().embed(CreateEmbed::new().description(message));
+        ctx.channel_id()
+            .send_message(ctx.http(), announcement)
+            .await?;
+        return Ok(());
+    };
+
+    let mut words = rest.split_whitespace();
+    let when = format!(
+        "{} {}",
+        words.next().unwrap_or_default(),
+        words.next().unwrap_or_default()
+    );
+    let content = words.collect::<Vec<_>>().join(" ");
+    let timezone = ctx.data().config.read().await.bot.timezone;
+    let starts_at = NaiveDateTime::parse_from_str(&when, START_FORMAT)
+        .ok()
+        .map(|time| local_to_utc(time.date(), time.time(), timezone))
+        .filter(|time| *time > chrono::Utc::now());
+    let Some(starts_at) = starts_at else {
+        ctx.say(format!(
+            "Usage: `announce {} YYYY-MM-DD HH:MM <message>` with a future start time in {}.",
+            RSVP_FLAG, timezone
+        ))
+        .await?;
+        return Ok(());
+    };
+    if content.is_empty() {
+        ctx.say("The announcement needs a message.").await?;
+        return Ok(());
+    }
+
+    let announcement = post_with_rsvp(
+        ctx.serenity_context(),
+        ctx.data(),
+        ctx.channel_id(),
+        ctx.author().id,
+        content,
+        starts_at,
+    )
+    .await?;
+    info!(
+        "{} announced #{} starting {}",
+        ctx.author().name,
+        announcement.id,
+        starts_at
+    );
+    ctx.say(format!(
+        "Announcement #{} posted, you'll get the attendee list {}.",
+        announcement.id,
+        discord_timestamp(starts_at, TimestampStyle::Relative)
+    ))
+    .await?;
+    Ok(())
+}
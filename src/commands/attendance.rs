@@ -0,0 +1,136 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{NaiveDate, NaiveTime};
+use serenity::all::User;
+use tracing::{info, trace};
+
+use crate::{
+    config::ReportKind,
+    graphql::queries::{correct_attendance, fetch_members},
+    history::update_attendance_record,
+    utils::delivery::annotate_report,
+    Context, Error,
+};
+
+/// Corrects a member's attendance on a date when the biometric system got it wrong, in
+/// Root and in the posted report. `time` is when they came in, needed when marking
+/// someone present.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn correct(
+    ctx: Context<'_>,
+    user: User,
+    date: String,
+    status: String,
+    time: Option<String>,
+) -> Result<(), Error> {
+    trace!("Running attendance correct command");
+    let Ok(date) = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") else {
+        ctx.say("Dates are in the YYYY-MM-DD format.").await?;
+        return Ok(());
+    };
+    let is_present = match status.to_lowercase().as_str() {
+        "present" => true,
+        "absent" => false,
+        _ => {
+            ctx.say("The status is either `present` or `absent`.")
+                .await?;
+            return Ok(());
+        }
+    };
+    let time_in = match (is_present, time.as_deref().map(parse_time)) {
+        (false, _) => None,
+        (true, Some(Some(time))) => Some(time),
+        (true, Some(None)) => {
+            ctx.say("Times are in the HH:MM format.").await?;
+            return Ok(());
+        }
+        (true, None) => {
+            ctx.say("Give the time they came in, e.g. `present 17:30`.")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let discord_id = user.id.to_string();
+    let Some(member) = fetch_members()
+        .await?
+        .into_iter()
+        .find(|m| m.discord_id == discord_id)
+    else {
+        ctx.say(format!("{} isn't linked to a member on Root.", user.name))
+            .await?;
+        return Ok(());
+    };
+
+    let time_in_text = time_in.map(|time| time.format("%H:%M:%S").to_string());
+    correct_attendance(member.member_id, date, is_present, time_in_text.as_deref()).await?;
+    info!(
+        "{} corrected {}'s attendance on {} to {}",
+        ctx.author().name,
+        member.name,
+        date,
+        status
+    );
+
+    let data = ctx.data();
+    update_attendance_record(&data.storage, date, &member.name, |record| {
+        record.is_present = is_present;
+        record.time_in = time_in_text.clone();
+    })
+    .await?;
+
+    let note = match time_in {
+        Some(time_in) => format!(
+            "{} was present, in at {} (corrected by <@{}>)",
+            member.name,
+            time_in.format("%H:%M"),
+            ctx.author().id
+        ),
+        None => format!(
+            "{} was absent (corrected by <@{}>)",
+            member.name,
+            ctx.author().id
+        ),
+    };
+    let annotated = annotate_report(
+        ctx.serenity_context(),
+        &data.storage,
+        ReportKind::Attendance,
+        date,
+        &note,
+    )
+    .await?;
+
+    let reply = if annotated > 0 {
+        format!("Corrected in Root and noted on the report of {}.", date)
+    } else {
+        format!(
+            "Corrected in Root, there was no report of {} to annotate.",
+            date
+        )
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    let time = time.trim();
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+        .ok()
+}
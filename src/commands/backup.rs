@@ -0,0 +1,53 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::Attachment;
+use tracing::{info, trace};
+
+use crate::{
+    backup::{restore_backup, upload_backup},
+    Context, Error,
+};
+
+#[poise::command(prefix_command, owners_only, subcommands("now"))]
+pub async fn backup(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `backup now`").await?;
+    Ok(())
+}
+
+/// Uploads an encrypted backup of the bot's storage to the backup channel.
+#[poise::command(prefix_command, owners_only)]
+pub async fn now(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running backup now command");
+    upload_backup(ctx.serenity_context(), ctx.data()).await?;
+    ctx.say("Backup uploaded.").await?;
+    Ok(())
+}
+
+/// Replaces the bot's storage with the attached backup archive, e.g. on a fresh
+/// deployment. Everything stored since the backup was taken is lost.
+#[poise::command(prefix_command, owners_only)]
+pub async fn restore(ctx: Context<'_>, archive: Attachment) -> Result<(), Error> {
+    trace!("Running restore command");
+    let contents = archive.download().await?;
+    restore_backup(&ctx.data().storage, &contents).await?;
+
+    info!("Storage restored from {}", archive.filename);
+    ctx.say(format!("Restored storage from `{}`.", archive.filename))
+        .await?;
+    Ok(())
+}
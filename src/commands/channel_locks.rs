@@ -0,0 +1,99 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::GuildChannel;
+use tracing::{info, trace};
+
+use crate::{
+    channel_locks::{apply_locks, clear_override, override_lock},
+    config::ChannelLockConfig,
+    Context, Error,
+};
+
+#[poise::command(
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    subcommands("lock", "unlock", "resume")
+)]
+pub async fn channellock(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `channellock lock <channel>`, `channellock unlock <channel>` or `channellock resume <channel>`")
+        .await?;
+    Ok(())
+}
+
+/// Locks a scheduled channel until its schedule next opens or closes it.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn lock(ctx: Context<'_>, channel: GuildChannel) -> Result<(), Error> {
+    trace!("Running channellock lock command");
+    toggle(ctx, channel, true).await
+}
+
+/// Unlocks a scheduled channel until its schedule next opens or closes it.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn unlock(ctx: Context<'_>, channel: GuildChannel) -> Result<(), Error> {
+    trace!("Running channellock unlock command");
+    toggle(ctx, channel, false).await
+}
+
+/// Drops a manual lock or unlock so the channel follows its schedule again.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn resume(ctx: Context<'_>, channel: GuildChannel) -> Result<(), Error> {
+    trace!("Running channellock resume command");
+    if !clear_override(&ctx.data().storage, channel.id.get()).await? {
+        ctx.say(format!("<#{}> already follows its schedule.", channel.id))
+            .await?;
+        return Ok(());
+    }
+
+    apply_locks(ctx.serenity_context(), ctx.data()).await?;
+    ctx.say(format!("<#{}> follows its schedule again.", channel.id))
+        .await?;
+    Ok(())
+}
+
+async fn toggle(ctx: Context<'_>, channel: GuildChannel, locked: bool) -> Result<(), Error> {
+    let Some(lock) = scheduled_lock(ctx, &channel).await else {
+        ctx.say(format!(
+            "<#{}> has no posting hours, add it to `channel_locks` in the config first.",
+            channel.id
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    override_lock(ctx.serenity_context(), ctx.data(), &lock, locked).await?;
+    let state = if locked { "Locked" } else { "Unlocked" };
+    info!("{} {} by {}", state, channel.name, ctx.author().name);
+    ctx.say(format!(
+        "{} <#{}> until its schedule next changes.",
+        state, channel.id
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn scheduled_lock(ctx: Context<'_>, channel: &GuildChannel) -> Option<ChannelLockConfig> {
+    ctx.data()
+        .config
+        .read()
+        .await
+        .channel_locks
+        .iter()
+        .find(|lock| lock.channel_id == channel.id.get())
+        .cloned()
+}
@@ -0,0 +1,73 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use tracing::{info, trace, warn};
+
+use crate::{
+    checkins::{is_valid_code, record_checkin, todays_code},
+    graphql::queries::fetch_members,
+    Context, Error,
+};
+
+/// Checks you in to the lab with the code posted there, for days when the attendance
+/// hardware is down. Send it in a DM so the code stays in the lab.
+#[poise::command(prefix_command, subcommands("code"))]
+pub async fn checkin(ctx: Context<'_>, daily_code: String) -> Result<(), Error> {
+    trace!("Running checkin command");
+    let data = ctx.data();
+    if ctx.guild_id().is_some() {
+        if let poise::Context::Prefix(prefix) = ctx {
+            if let Err(e) = prefix.msg.delete(ctx.http()).await {
+                warn!("Failed to delete check-in message: {}", e);
+            }
+        }
+    }
+
+    if !is_valid_code(&data.storage, &daily_code).await? {
+        ctx.say("That isn't today's check-in code.").await?;
+        return Ok(());
+    }
+
+    let discord_id = ctx.author().id.to_string();
+    let members = fetch_members().await?;
+    let Some(member) = members.into_iter().find(|m| m.discord_id == discord_id) else {
+        ctx.say("You aren't registered as a member on Root.")
+            .await?;
+        return Ok(());
+    };
+
+    if record_checkin(&data.storage, discord_id, member.name.clone()).await? {
+        info!("{} checked in manually", member.name);
+        ctx.say("Checked in! You'll be counted as present in tonight's report.")
+            .await?;
+    } else {
+        ctx.say("You already checked in today.").await?;
+    }
+    Ok(())
+}
+
+/// DMs you today's check-in code to post in the lab.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn code(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running checkin code command");
+    let code = todays_code(&ctx.data().storage).await?;
+    let dm = serenity::all::CreateMessage::new()
+        .content(format!("Today's check-in code is `{}`.", code));
+    ctx.author().direct_message(ctx.http(), dm).await?;
+    ctx.say("Sent you today's code.").await?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use tracing::trace;
+
+use crate::{
+    migrations::{latest_version, schema_version, MIGRATIONS},
+    Context, Error,
+};
+
+#[poise::command(prefix_command, owners_only, subcommands("version"))]
+pub async fn db(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `db version`").await?;
+    Ok(())
+}
+
+/// Shows the storage schema version and which migrations have been applied.
+#[poise::command(prefix_command, owners_only)]
+pub async fn version(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running db version command");
+    let current = schema_version(&ctx.data().storage).await?;
+
+    let mut reply = format!(
+        "Storage schema version {} (latest {}).\n",
+        current,
+        latest_version()
+    );
+    for migration in MIGRATIONS {
+        let status = if migration.version <= current {
+            "applied"
+        } else {
+            "pending"
+        };
+        reply.push_str(&format!(
+            "- {}: {} ({})\n",
+            migration.version, migration.description, status
+        ));
+    }
+
+    ctx.say(reply).await?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use poise::CreateReply;
+use serde_json::json;
+use serenity::all::CreateAttachment;
+use tracing::trace;
+
+use crate::{Context, Error};
+
+#[poise::command(prefix_command, owners_only, subcommands("dump"))]
+pub async fn debug(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Use `debug dump` to get a snapshot of the bot's state.")
+        .await?;
+    Ok(())
+}
+
+/// Sends a JSON snapshot of the bot's state: config, cache sizes, scheduler slots,
+/// shard health and the number of entries under every storage key.
+#[poise::command(prefix_command, owners_only)]
+pub async fn dump(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running debug dump command");
+    let data = ctx.data();
+    let cache = &ctx.serenity_context().cache;
+
+    let snapshot = json!({
+        "generated_at": chrono::Utc::now(),
+        "version": env!("CARGO_PKG_VERSION"),
+        "config": *data.config.read().await,
+        "cache": {
+            "guilds": cache.guild_count(),
+            "channels": cache.guild_channel_count(),
+            "users": cache.user_count(),
+            "shards": cache.shard_count(),
+        },
+        "disconnected_shards": data.shard_health.disconnected_seconds(),
+        "scheduler": data.scheduler.snapshot(),
+        "storage": data.storage.key_sizes().await,
+    });
+
+    let contents =
+        serde_json::to_vec_pretty(&snapshot).context("Failed to serialize the snapshot")?;
+    ctx.send(
+        CreateReply::default()
+            .content("Current state:")
+            .attachment(CreateAttachment::bytes(contents, "amd_debug_dump.json")),
+    )
+    .await?;
+    Ok(())
+}
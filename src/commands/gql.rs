@@ -0,0 +1,50 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use tracing::{info, trace};
+
+use crate::{graphql::queries::raw_query, utils::long_message::say_long, Context, Error};
+
+/// Runs a GraphQL query against Root and shows the raw JSON reply. The query may be
+/// wrapped in a code block.
+#[poise::command(prefix_command, owners_only)]
+pub async fn gql(ctx: Context<'_>, #[rest] query: String) -> Result<(), Error> {
+    trace!("Running gql command");
+    let query = strip_code_block(&query);
+    info!("{} ran a GraphQL query: {}", ctx.author().name, query);
+
+    let response = raw_query(query).await?;
+    let pretty = serde_json::to_string_pretty(&response).context("Failed to format the reply")?;
+    say_long(ctx, &format!("```json\n{}\n```", pretty), "gql_response.md").await
+}
+
+fn strip_code_block(query: &str) -> &str {
+    let query = query.trim();
+    let Some(inner) = query
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    else {
+        return query;
+    };
+    // Drop a language tag such as ```graphql.
+    let inner = inner
+        .strip_prefix("graphql")
+        .or_else(|| inner.strip_prefix("gql"))
+        .unwrap_or(inner);
+    inner.trim()
+}
@@ -0,0 +1,314 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use serenity::all::{
+    ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, GuildId, RoleId, UserId,
+};
+use tokio::time::Duration;
+use tracing::{info, trace, warn};
+
+use crate::{
+    graphql::{models::Member, queries::fetch_members},
+    history::{recent_attendance_days, recent_status_update_days},
+    Context, Error,
+};
+
+/// Days of history used to score member activity.
+const ACTIVITY_DAYS: usize = 30;
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A proposed move of a member from one group to another.
+struct Move {
+    member: Member,
+    to_group: i32,
+}
+
+/// A move whose role changes couldn't be applied.
+struct FailedMove {
+    member_id: i32,
+    name: String,
+    reason: String,
+}
+
+#[poise::command(
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_ROLES",
+    subcommands("rebalance")
+)]
+pub async fn groups(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `$groups rebalance [--dry-run]`").await?;
+    Ok(())
+}
+
+/// Proposes a redistribution of members across groups based on their recent activity.
+/// Without `--dry-run`, the proposal can be confirmed to apply the group role changes.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_ROLES")]
+pub async fn rebalance(ctx: Context<'_>, flag: Option<String>) -> Result<(), Error> {
+    trace!("Running groups rebalance command");
+    let dry_run = flag.as_deref() == Some("--dry-run");
+    let data = ctx.data();
+
+    let members = fetch_members().await?;
+    let scores = activity_scores(data, &members).await?;
+    let moves = propose_moves(&members, &scores);
+    let embed = proposal_embed(&members, &moves);
+
+    if dry_run || moves.is_empty() {
+        ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        return Ok(());
+    }
+
+    let confirm_id = format!("groups_rebalance:confirm:{}", ctx.id());
+    let cancel_id = format!("groups_rebalance:cancel:{}", ctx.id());
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(&confirm_id)
+            .label("Apply")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ]);
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(embed)
+                .components(vec![buttons]),
+        )
+        .await?;
+    let message = reply.message().await?;
+
+    let Some(interaction) = message
+        .await_component_interaction(ctx.serenity_context().shard.clone())
+        .author_id(ctx.author().id)
+        .timeout(CONFIRM_TIMEOUT)
+        .await
+    else {
+        ctx.say("Rebalance timed out, nothing was changed.").await?;
+        return Ok(());
+    };
+
+    let applying = interaction.data.custom_id == confirm_id;
+    let status = if applying {
+        "Applying group changes..."
+    } else {
+        "Rebalance cancelled."
+    };
+    interaction
+        .create_response(
+            ctx.http(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(status)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    if !applying {
+        return Ok(());
+    }
+
+    let guild_id = ctx.guild_id().expect("Command is guild only");
+    let failures = apply_moves(ctx, guild_id, &moves).await;
+    let applied: Vec<Move> = moves
+        .into_iter()
+        .filter(|m| !failures.iter().any(|f| f.member_id == m.member.member_id))
+        .collect();
+    info!(
+        "Applied group rebalance with {} moves, {} failed",
+        applied.len(),
+        failures.len()
+    );
+
+    let mut summary = format!(
+        "Moved {} members. Remember to update their groups on Root too.\n",
+        applied.len()
+    );
+    if !failures.is_empty() {
+        summary.push_str(&format!(
+            "Failed to move {} members, check their group roles:\n",
+            failures.len()
+        ));
+        for failure in &failures {
+            summary.push_str(&format!("- {}: {}\n", failure.name, failure.reason));
+        }
+    }
+    ctx.say(summary).await?;
+    ctx.send(poise::CreateReply::default().embed(group_lists_embed(&members, &applied)))
+        .await?;
+
+    Ok(())
+}
+
+/// Scores members by status updates sent and days present in the lab recently.
+async fn activity_scores(
+    data: &crate::Data,
+    members: &[Member],
+) -> anyhow::Result<HashMap<i32, usize>> {
+    let update_days = recent_status_update_days(&data.storage, ACTIVITY_DAYS).await?;
+    let attendance_days = recent_attendance_days(&data.storage, ACTIVITY_DAYS).await?;
+
+    let scores = members
+        .iter()
+        .map(|member| {
+            let updates = update_days
+                .iter()
+                .filter(|day| {
+                    day.member(&member.discord_id)
+                        .is_some_and(|m| m.sent_update)
+                })
+                .count();
+            let present = attendance_days
+                .iter()
+                .filter(|day| {
+                    day.records
+                        .iter()
+                        .any(|r| r.name == member.name && r.is_present)
+                })
+                .count();
+            (member.member_id, updates + present)
+        })
+        .collect();
+
+    Ok(scores)
+}
+
+/// Snake-drafts members ordered by activity into the existing groups so every group
+/// gets a similar mix of more and less active members.
+fn propose_moves(members: &[Member], scores: &HashMap<i32, usize>) -> Vec<Move> {
+    let mut groups: Vec<i32> = members.iter().map(|m| m.group_id).collect();
+    groups.sort_unstable();
+    groups.dedup();
+    if groups.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<&Member> = members.iter().collect();
+    ranked.sort_by_key(|m| std::cmp::Reverse(scores.get(&m.member_id).copied().unwrap_or(0)));
+
+    ranked
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, member)| {
+            let round = index / groups.len();
+            let position = index % groups.len();
+            let slot = if round.is_multiple_of(2) {
+                position
+            } else {
+                groups.len() - 1 - position
+            };
+            let to_group = groups[slot];
+            (to_group != member.group_id).then(|| Move {
+                member: member.clone(),
+                to_group,
+            })
+        })
+        .collect()
+}
+
+fn proposal_embed(members: &[Member], moves: &[Move]) -> CreateEmbed {
+    let mut description = format!(
+        "{} of {} members would change groups.\n",
+        moves.len(),
+        members.len()
+    );
+    for group_move in moves {
+        description.push_str(&format!(
+            "- {}: Group {} → Group {}\n",
+            group_move.member.name, group_move.member.group_id, group_move.to_group
+        ));
+    }
+
+    CreateEmbed::new()
+        .title("Proposed Group Rebalance")
+        .description(description)
+}
+
+fn group_lists_embed(members: &[Member], moves: &[Move]) -> CreateEmbed {
+    let mut groups: HashMap<i32, Vec<&str>> = HashMap::new();
+    for member in members {
+        let group = moves
+            .iter()
+            .find(|m| m.member.member_id == member.member_id)
+            .map(|m| m.to_group)
+            .unwrap_or(member.group_id);
+        groups.entry(group).or_default().push(&member.name);
+    }
+
+    let mut group_ids: Vec<_> = groups.keys().copied().collect();
+    group_ids.sort_unstable();
+    let mut description = String::new();
+    for group in group_ids {
+        description.push_str(&format!("## Group {}\n", group));
+        for name in &groups[&group] {
+            description.push_str(&format!("- {}\n", name));
+        }
+    }
+
+    CreateEmbed::new()
+        .title("New Groups")
+        .description(description)
+}
+
+/// Swaps group roles for every moved member, returning the moves that failed.
+async fn apply_moves(ctx: Context<'_>, guild_id: GuildId, moves: &[Move]) -> Vec<FailedMove> {
+    let groups_config = ctx.data().config.read().await.groups.clone();
+    let mut failures = Vec::new();
+
+    for group_move in moves {
+        let member = &group_move.member;
+        let fail = |reason: String| FailedMove {
+            member_id: member.member_id,
+            name: member.name.clone(),
+            reason,
+        };
+        let (Some(old_role), Some(new_role)) = (
+            groups_config.role_for(member.group_id),
+            groups_config.role_for(group_move.to_group),
+        ) else {
+            failures.push(fail(format!(
+                "no role is configured for Group {} or Group {}",
+                member.group_id, group_move.to_group
+            )));
+            continue;
+        };
+        let Ok(user_id) = member.discord_id.parse::<u64>().map(UserId::new) else {
+            failures.push(fail(String::from("no Discord account is linked")));
+            continue;
+        };
+
+        let result = async {
+            let guild_member = guild_id.member(ctx.http(), user_id).await?;
+            guild_member
+                .remove_role(ctx.http(), RoleId::new(old_role))
+                .await?;
+            guild_member
+                .add_role(ctx.http(), RoleId::new(new_role))
+                .await
+        }
+        .await;
+        if let Err(e) = result {
+            warn!("Failed to move {} to a new group: {}", member.name, e);
+            failures.push(fail(e.to_string()));
+        }
+    }
+
+    failures
+}
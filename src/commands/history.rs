@@ -0,0 +1,72 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::User;
+use tracing::trace;
+
+use crate::{history::recent_status_update_days, utils::time::local_today, Context, Error};
+
+const HISTORY_DAYS: i64 = 30;
+
+/// Shows a member's status updates over the last 30 days as a calendar, one row per
+/// week: ✅ sent, ❌ missed, 🏖️ no check that day.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn history(ctx: Context<'_>, member: User) -> Result<(), Error> {
+    trace!("Running history command");
+    let days = recent_status_update_days(&ctx.data().storage, HISTORY_DAYS as usize).await?;
+    let discord_id = member.id.to_string();
+
+    let today = local_today();
+    let first = today - chrono::Duration::days(HISTORY_DAYS - 1);
+
+    let mut calendar = String::new();
+    let (mut sent, mut missed) = (0, 0);
+    for (index, date) in first.iter_days().take(HISTORY_DAYS as usize).enumerate() {
+        if index % 7 == 0 {
+            if index > 0 {
+                calendar.push('\n');
+            }
+            calendar.push_str(&format!("`{}` ", date.format("%b %d")));
+        }
+        let result = days
+            .iter()
+            .find(|day| day.date == date)
+            .and_then(|day| day.member(&discord_id));
+        calendar.push_str(match result {
+            Some(result) if result.sent_update => {
+                sent += 1;
+                "✅"
+            }
+            Some(_) => {
+                missed += 1;
+                "❌"
+            }
+            None => "🏖️",
+        });
+    }
+
+    ctx.say(format!(
+        "**{}**'s status updates since {} ({} sent, {} missed):\n{}",
+        member.name,
+        first.format("%b %d"),
+        sent,
+        missed,
+        calendar
+    ))
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,91 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::NaiveDate;
+use tracing::{info, trace};
+
+use crate::{
+    holidays::{observe, observed, unobserve, Holiday},
+    utils::time::{format_date, local_today},
+    Context, Error,
+};
+
+/// Lists the upcoming holidays on which member checks are skipped.
+#[poise::command(prefix_command, subcommands("add", "remove"))]
+pub async fn holidays(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running holidays command");
+    let today = local_today();
+    let upcoming: Vec<String> = observed(&ctx.data().storage)
+        .await?
+        .range(today..)
+        .map(|(date, name)| format!("- **{}**: {}", format_date(*date), name))
+        .collect();
+
+    let reply = if upcoming.is_empty() {
+        String::from("No upcoming holidays are observed.")
+    } else {
+        format!("Upcoming holidays:\n{}", upcoming.join("\n"))
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Observes a holiday on `date` (YYYY-MM-DD) that the calendar doesn't have.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn add(ctx: Context<'_>, date: NaiveDate, #[rest] name: String) -> Result<(), Error> {
+    trace!("Running holidays add command");
+    observe(
+        &ctx.data().storage,
+        Holiday {
+            date,
+            name: name.clone(),
+        },
+    )
+    .await?;
+    info!(
+        "{} added the holiday {} on {}",
+        ctx.author().name,
+        name,
+        date
+    );
+    ctx.say(format!(
+        "{} is now a holiday, member checks are skipped that day.",
+        format_date(date)
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Stops observing the holiday on `date` (YYYY-MM-DD).
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn remove(ctx: Context<'_>, date: NaiveDate) -> Result<(), Error> {
+    trace!("Running holidays remove command");
+    let reply = match unobserve(&ctx.data().storage, date).await? {
+        Some(name) => {
+            info!(
+                "{} removed the holiday {} on {}",
+                ctx.author().name,
+                name,
+                date
+            );
+            format!("{} is no longer observed.", name)
+        }
+        None => format!("{} isn't a holiday.", format_date(date)),
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
@@ -0,0 +1,119 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use tracing::trace;
+
+use crate::{
+    config::InventoryItem,
+    inventory::{
+        outstanding_counts, request_checkout, return_item as return_checkout, CheckoutOutcome,
+    },
+    Context, Error,
+};
+
+/// Lists the lab's hardware and how much of it is available.
+#[poise::command(prefix_command, guild_only, subcommands("checkout", "return_item"))]
+pub async fn inventory(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running inventory command");
+    let items = ctx.data().config.read().await.inventory.items.clone();
+    if items.is_empty() {
+        ctx.say("There is no inventory configured.").await?;
+        return Ok(());
+    }
+
+    let outstanding = outstanding_counts(&ctx.data().storage).await?;
+    let mut reply = String::from("Inventory:\n");
+    for item in &items {
+        let taken = outstanding.get(&item.name).copied().unwrap_or(0);
+        reply.push_str(&format!(
+            "- {}: {}/{} available{}\n",
+            item.name,
+            item.quantity.saturating_sub(taken),
+            item.quantity,
+            if item.requires_approval {
+                " (needs mentor approval)"
+            } else {
+                ""
+            }
+        ));
+    }
+    reply.push_str(
+        "Borrow with `inventory checkout <item>`, hand back with `inventory return <item>`.",
+    );
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Borrows an item, expensive items wait for a mentor to approve.
+#[poise::command(prefix_command, guild_only)]
+pub async fn checkout(ctx: Context<'_>, #[rest] item: String) -> Result<(), Error> {
+    trace!("Running inventory checkout command");
+    let Some(item) = find_item(ctx, &item).await else {
+        ctx.say(format!("There is no item called \"{}\".", item.trim()))
+            .await?;
+        return Ok(());
+    };
+
+    let outcome =
+        request_checkout(ctx.serenity_context(), ctx.data(), &item, ctx.author().id).await?;
+    let reply = match outcome {
+        CheckoutOutcome::Out => format!("Checked out {}, return it when you're done.", item.name),
+        CheckoutOutcome::Pending(Some(_)) => format!(
+            "{} needs a mentor's approval, you'll get a DM once it's reviewed.",
+            item.name
+        ),
+        CheckoutOutcome::Pending(None) => format!(
+            "{} needs a mentor's approval, ask one to review it.",
+            item.name
+        ),
+        CheckoutOutcome::Unavailable => format!("All units of {} are out.", item.name),
+        CheckoutOutcome::AlreadyHolding => format!("You already have {}.", item.name),
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Hands an item back, or withdraws a checkout still waiting for approval.
+#[poise::command(prefix_command, guild_only, rename = "return")]
+pub async fn return_item(ctx: Context<'_>, #[rest] item: String) -> Result<(), Error> {
+    trace!("Running inventory return command");
+    let Some(item) = find_item(ctx, &item).await else {
+        ctx.say(format!("There is no item called \"{}\".", item.trim()))
+            .await?;
+        return Ok(());
+    };
+
+    let reply = if return_checkout(&ctx.data().storage, ctx.author().id.get(), &item.name).await? {
+        format!("Returned {}, thanks!", item.name)
+    } else {
+        format!("You don't have {} checked out.", item.name)
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+async fn find_item(ctx: Context<'_>, name: &str) -> Option<InventoryItem> {
+    ctx.data()
+        .config
+        .read()
+        .await
+        .inventory
+        .items
+        .iter()
+        .find(|item| item.name.eq_ignore_ascii_case(name.trim()))
+        .cloned()
+}
@@ -0,0 +1,74 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use tracing::trace;
+
+use crate::{invites::joins_since, utils::time::format_day, Context, Error};
+
+const LEADERBOARD_SIZE: usize = 10;
+
+#[poise::command(prefix_command, guild_only, subcommands("leaderboard"))]
+pub async fn invites(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `invites leaderboard`").await?;
+    Ok(())
+}
+
+/// Ranks members by how many people joined through their invites this recruitment season.
+#[poise::command(prefix_command, guild_only)]
+pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running invites leaderboard command");
+    let season_start = ctx.data().config.read().await.invites.season_start;
+    let since = season_start
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for join in joins_since(&ctx.data().storage, since).await? {
+        if let Some(inviter_id) = join.inviter_id {
+            *counts.entry(inviter_id).or_default() += 1;
+        }
+    }
+    let mut ranking: Vec<(u64, usize)> = counts.into_iter().collect();
+    ranking.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut reply = match season_start {
+        Some(date) => format!("Recruiters since {}:\n", format_day(date)),
+        None => String::from("Recruiters:\n"),
+    };
+    if ranking.is_empty() {
+        reply.push_str("Nobody has joined through a tracked invite yet.");
+    }
+    for (rank, (inviter_id, joins)) in ranking.iter().take(LEADERBOARD_SIZE).enumerate() {
+        reply.push_str(&format!(
+            "{}. <@{}> - {} joins\n",
+            rank + 1,
+            inviter_id,
+            joins
+        ));
+    }
+    ctx.send(
+        poise::CreateReply::default()
+            .content(reply)
+            .allowed_mentions(serenity::all::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
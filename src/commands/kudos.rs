@@ -0,0 +1,82 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::Utc;
+use serenity::all::{ChannelId, CreateAllowedMentions, CreateEmbed, CreateMessage, User};
+use tracing::{info, trace, warn};
+
+use crate::{
+    kudos::{give, Kudos, KUDOS_COLOR},
+    Context, Error,
+};
+
+/// Thanks a member for something they did, shared in the kudos channel.
+#[poise::command(prefix_command, guild_only)]
+pub async fn kudos(
+    ctx: Context<'_>,
+    member: User,
+    #[rest] reason: Option<String>,
+) -> Result<(), Error> {
+    trace!("Running kudos command");
+    if member.id == ctx.author().id || member.bot {
+        ctx.say("Kudos are for other members.").await?;
+        return Ok(());
+    }
+    let Some(reason) = reason.filter(|reason| !reason.trim().is_empty()) else {
+        ctx.say("Say what the kudos are for: `kudos @member <reason>`")
+            .await?;
+        return Ok(());
+    };
+
+    let config = ctx.data().config.read().await.kudos.clone();
+    let kudos = Kudos {
+        from: ctx.author().id.get(),
+        to: member.id.get(),
+        reason: reason.trim().to_string(),
+        given_at: Utc::now(),
+    };
+    if !give(&ctx.data().storage, kudos.clone(), config.daily_limit).await? {
+        ctx.say(format!(
+            "You can give {} kudos a day, try again tomorrow.",
+            config.daily_limit
+        ))
+        .await?;
+        return Ok(());
+    }
+    info!("{} gave kudos to {}", ctx.author().name, member.name);
+
+    if let Some(channel_id) = config.channel_id {
+        let embed = CreateEmbed::new()
+            .description(format!(
+                "<@{}> gave kudos to <@{}>\n> {}",
+                kudos.from, kudos.to, kudos.reason
+            ))
+            .color(KUDOS_COLOR);
+        let message = CreateMessage::new()
+            .content(format!("<@{}>", kudos.to))
+            .embed(embed)
+            .allowed_mentions(CreateAllowedMentions::new().users([member.id]));
+        if let Err(e) = ChannelId::new(channel_id)
+            .send_message(ctx.http(), message)
+            .await
+        {
+            warn!("Failed to post kudos to the feed: {}", e);
+        }
+    }
+    ctx.say(format!("Kudos sent to {}!", member.name)).await?;
+    Ok(())
+}
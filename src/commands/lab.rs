@@ -0,0 +1,60 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{NaiveTime, Utc};
+use tracing::trace;
+
+use crate::{
+    tasks::occupancy::members_inside,
+    utils::time::{discord_timestamp, local_today, timezone, TimestampStyle},
+    Context, Error,
+};
+
+#[poise::command(prefix_command, subcommands("who"))]
+pub async fn lab(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `lab who`").await?;
+    Ok(())
+}
+
+/// Lists the members who are in the lab, with the time they checked in.
+#[poise::command(prefix_command)]
+pub async fn who(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running lab who command");
+    let mut inside = members_inside(&ctx.data().storage).await?;
+    if inside.is_empty() {
+        ctx.say("Nobody is in the lab right now.").await?;
+        return Ok(());
+    }
+
+    inside.sort_by(|a, b| a.time_in.cmp(&b.time_in));
+    let today = local_today();
+    let mut reply = format!("{} in the lab:\n", inside.len());
+    for record in &inside {
+        // Root reports times in the bot's timezone as HH:MM:SS with optional fractional seconds.
+        let since = record
+            .time_in
+            .as_deref()
+            .and_then(|time| time.split('.').next())
+            .and_then(|time| NaiveTime::parse_from_str(time, "%H:%M:%S").ok())
+            .and_then(|time| today.and_time(time).and_local_timezone(timezone()).single())
+            .map(|time| discord_timestamp(time.with_timezone(&Utc), TimestampStyle::ShortTime))
+            .unwrap_or_else(|| String::from("?"));
+        reply.push_str(&format!("- {} (since {})\n", record.name, since));
+    }
+    ctx.say(reply).await?;
+    Ok(())
+}
@@ -0,0 +1,133 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::NaiveDate;
+use poise::CreateReply;
+use serde::Serialize;
+use tracing::trace;
+
+use crate::{
+    graphql::{models::Member, queries::fetch_members},
+    history::recent_attendance_days,
+    utils::export::{attach_json, wants_json},
+    Context, Error,
+};
+
+const ATTENDANCE_DAYS: usize = 30;
+
+// These commands only ever show the author's own data, so they are safe to use in DMs
+// where the prefix is optional (e.g. just sending `streak` to the bot).
+
+/// Shows your current and highest status update streak.
+#[poise::command(prefix_command)]
+pub async fn streak(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running streak command");
+    let Some(member) = linked_member(ctx).await? else {
+        return Ok(());
+    };
+
+    let (current_streak, max_streak) = member
+        .streak
+        .first()
+        .map(|streak| (streak.current_streak, streak.max_streak))
+        .unwrap_or_default();
+    ctx.say(format!(
+        "Your current streak is {} days, your best is {} days.",
+        current_streak, max_streak
+    ))
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct AttendanceExport<'a> {
+    name: &'a str,
+    present: usize,
+    recorded: usize,
+    days: Vec<AttendanceExportDay<'a>>,
+}
+
+#[derive(Serialize)]
+struct AttendanceExportDay<'a> {
+    date: NaiveDate,
+    is_present: bool,
+    time_in: Option<&'a str>,
+}
+
+/// Shows your lab attendance over the last month. `--json` attaches each recorded day
+/// as JSON.
+#[poise::command(prefix_command, subcommands("super::attendance::correct"))]
+pub async fn attendance(ctx: Context<'_>, option: Option<String>) -> Result<(), Error> {
+    trace!("Running attendance command");
+    let json = wants_json(option.as_deref())?;
+    let Some(member) = linked_member(ctx).await? else {
+        return Ok(());
+    };
+
+    let days = recent_attendance_days(&ctx.data().storage, ATTENDANCE_DAYS).await?;
+    let recorded: Vec<_> = days
+        .iter()
+        .filter_map(|day| {
+            day.records
+                .iter()
+                .find(|r| r.name == member.name)
+                .map(|record| (day.date, record))
+        })
+        .collect();
+    let present = recorded.iter().filter(|(_, r)| r.is_present).count();
+
+    let mut reply = CreateReply::default().content(format!(
+        "You were present on {} of the last {} recorded lab days.",
+        present,
+        recorded.len()
+    ));
+    if json {
+        let export = AttendanceExport {
+            name: &member.name,
+            present,
+            recorded: recorded.len(),
+            days: recorded
+                .iter()
+                .map(|(date, record)| AttendanceExportDay {
+                    date: *date,
+                    is_present: record.is_present,
+                    time_in: record.time_in.as_deref(),
+                })
+                .collect(),
+        };
+        reply = attach_json(reply, "attendance", &export)?;
+    }
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
+/// Finds the Root member linked to the author, telling them if there is none.
+async fn linked_member(ctx: Context<'_>) -> anyhow::Result<Option<Member>> {
+    let discord_id = ctx.author().id.to_string();
+    let member = fetch_members()
+        .await?
+        .into_iter()
+        .find(|m| m.discord_id == discord_id);
+
+    if member.is_none() {
+        ctx.say("Your Discord account isn't linked to a member on Root.")
+            .await?;
+    }
+    Ok(member)
+}
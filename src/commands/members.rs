@@ -0,0 +1,117 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::{CreateEmbed, User};
+use tracing::{info, trace};
+
+use crate::{
+    graphql::queries::{fetch_members, set_discord_id},
+    Context, Error,
+};
+
+/// Looks up the Root member linked to a Discord user.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_MESSAGES")]
+pub async fn whois(ctx: Context<'_>, user: User) -> Result<(), Error> {
+    trace!("Running whois command");
+    let discord_id = user.id.to_string();
+    let members = fetch_members().await?;
+
+    let Some(member) = members.iter().find(|m| m.discord_id == discord_id) else {
+        ctx.say(format!("No Root member is linked to {}.", user.name))
+            .await?;
+        return Ok(());
+    };
+
+    let (current_streak, max_streak) = member
+        .streak
+        .first()
+        .map(|streak| (streak.current_streak, streak.max_streak))
+        .unwrap_or_default();
+    let embed = CreateEmbed::new()
+        .title(format!("{} is {}", user.name, member.name))
+        .description(format!(
+            "- Member ID: {}\n- Group: {}\n- Current Streak: {} days\n- Max Streak: {} days",
+            member.member_id, member.group_id, current_streak, max_streak
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Links a Discord user to a Root member, replacing the member's current link.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn link(ctx: Context<'_>, user: User, member_id: i32) -> Result<(), Error> {
+    trace!("Running link command");
+    let discord_id = user.id.to_string();
+    let members = fetch_members().await?;
+
+    let Some(member) = members.iter().find(|m| m.member_id == member_id) else {
+        ctx.say(format!("There is no Root member with ID {}.", member_id))
+            .await?;
+        return Ok(());
+    };
+    if let Some(linked) = members.iter().find(|m| m.discord_id == discord_id) {
+        let reply = if linked.member_id == member_id {
+            format!("{} is already linked to {}.", user.name, member.name)
+        } else {
+            format!(
+                "{} is linked to {} (ID {}), `$unlink` them first.",
+                user.name, linked.name, linked.member_id
+            )
+        };
+        ctx.say(reply).await?;
+        return Ok(());
+    }
+
+    set_discord_id(member_id, Some(&discord_id)).await?;
+    info!(
+        "{} linked {} to member {} ({})",
+        ctx.author().name,
+        user.name,
+        member.name,
+        member_id
+    );
+    ctx.say(format!("Linked {} to {}.", user.name, member.name))
+        .await?;
+    Ok(())
+}
+
+/// Removes the link between a Discord user and their Root member.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn unlink(ctx: Context<'_>, user: User) -> Result<(), Error> {
+    trace!("Running unlink command");
+    let discord_id = user.id.to_string();
+    let members = fetch_members().await?;
+
+    let Some(member) = members.iter().find(|m| m.discord_id == discord_id) else {
+        ctx.say(format!("No Root member is linked to {}.", user.name))
+            .await?;
+        return Ok(());
+    };
+
+    set_discord_id(member.member_id, None).await?;
+    info!(
+        "{} unlinked {} from member {} ({})",
+        ctx.author().name,
+        user.name,
+        member.name,
+        member.member_id
+    );
+    ctx.say(format!("Unlinked {} from {}.", user.name, member.name))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,191 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::{CreateEmbed, CreateMessage};
+use tracing::{info, trace};
+
+use crate::{
+    onboarding::{self as quiz, QuizQuestion},
+    utils::time::{discord_timestamp, TimestampStyle},
+    Context, Error,
+};
+
+/// Button labels are capped at 80 characters and a message fits 5 rows of 5 buttons.
+const MAX_OPTION_LENGTH: usize = 80;
+const MAX_OPTIONS: usize = 25;
+
+#[poise::command(
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("post", "add", "remove", "questions", "results")
+)]
+pub async fn onboarding(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `onboarding post`, `onboarding add <answer> <question> | <option> | ...`, `onboarding remove <number>`, `onboarding questions` or `onboarding results`")
+        .await?;
+    Ok(())
+}
+
+/// Posts the quiz button in this channel, e.g. the welcome channel.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn post(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running onboarding post command");
+    let message = CreateMessage::new()
+        .content("Welcome! Answer a few questions about our rules and code of conduct to unlock the rest of the server.")
+        .components(vec![quiz::start_button()]);
+    ctx.channel_id().send_message(ctx.http(), message).await?;
+    Ok(())
+}
+
+/// Adds a question. `answer` is the number of the correct option, e.g.
+/// `onboarding add 2 Where do status updates go? | #general | The group thread`.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn add(ctx: Context<'_>, answer: usize, #[rest] text: String) -> Result<(), Error> {
+    trace!("Running onboarding add command");
+    let mut parts = text.split('|').map(str::trim);
+    let question = parts.next().unwrap_or_default().to_string();
+    let options: Vec<String> = parts
+        .filter(|option| !option.is_empty())
+        .map(String::from)
+        .collect();
+
+    if question.is_empty() || options.len() < 2 || options.len() > MAX_OPTIONS {
+        ctx.say(format!(
+            "A question needs between 2 and {} options, separated with `|`.",
+            MAX_OPTIONS
+        ))
+        .await?;
+        return Ok(());
+    }
+    if options
+        .iter()
+        .any(|o| o.chars().count() > MAX_OPTION_LENGTH)
+    {
+        ctx.say(format!(
+            "Options can be at most {} characters long.",
+            MAX_OPTION_LENGTH
+        ))
+        .await?;
+        return Ok(());
+    }
+    if answer == 0 || answer > options.len() {
+        ctx.say("The answer must be the number of one of the options.")
+            .await?;
+        return Ok(());
+    }
+
+    let number = quiz::add_question(
+        &ctx.data().storage,
+        QuizQuestion {
+            question,
+            options,
+            answer: answer - 1,
+        },
+    )
+    .await?;
+    info!(
+        "Onboarding question {} added by {}",
+        number,
+        ctx.author().name
+    );
+    ctx.say(format!("Added question {}.", number)).await?;
+    Ok(())
+}
+
+/// Removes the question with the number shown by `onboarding questions`.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn remove(ctx: Context<'_>, number: usize) -> Result<(), Error> {
+    trace!("Running onboarding remove command");
+    let removed = match number.checked_sub(1) {
+        Some(index) => quiz::remove_question(&ctx.data().storage, index).await?,
+        None => None,
+    };
+    match removed {
+        Some(question) => {
+            info!("Onboarding question removed by {}", ctx.author().name);
+            ctx.say(format!("Removed \"{}\".", question.question))
+                .await?
+        }
+        None => ctx.say("There's no question with that number.").await?,
+    };
+    Ok(())
+}
+
+/// Lists the quiz questions with their correct answers.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn questions(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running onboarding questions command");
+    let questions = quiz::questions(&ctx.data().storage).await?;
+    if questions.is_empty() {
+        ctx.say("No questions yet, add one with `onboarding add`.")
+            .await?;
+        return Ok(());
+    }
+
+    let mut description = String::new();
+    for (number, question) in questions.iter().enumerate() {
+        description.push_str(&format!("**{}. {}**\n", number + 1, question.question));
+        for (index, option) in question.options.iter().enumerate() {
+            let marker = if index == question.answer {
+                "✅"
+            } else {
+                "▫️"
+            };
+            description.push_str(&format!("{} {}\n", marker, option));
+        }
+        description.push('\n');
+    }
+
+    let embed = CreateEmbed::new()
+        .title("Onboarding Quiz")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Shows the latest quiz attempts, optionally only those of `user`.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn results(ctx: Context<'_>, user: Option<serenity::all::User>) -> Result<(), Error> {
+    trace!("Running onboarding results command");
+    let results = quiz::results(&ctx.data().storage).await?;
+    let lines: Vec<String> = results
+        .iter()
+        .rev()
+        .filter(|r| user.as_ref().is_none_or(|u| u.id.get() == r.user_id))
+        .take(20)
+        .map(|r| {
+            format!(
+                "- <@{}> {}/{} {} {}",
+                r.user_id,
+                r.score,
+                r.total,
+                if r.passed { "passed" } else { "failed" },
+                discord_timestamp(r.taken_at, TimestampStyle::Relative)
+            )
+        })
+        .collect();
+
+    if lines.is_empty() {
+        ctx.say("No quiz attempts recorded.").await?;
+        return Ok(());
+    }
+    let embed = CreateEmbed::new()
+        .title("Onboarding Quiz Results")
+        .description(lines.join("\n"));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
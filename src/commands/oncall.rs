@@ -0,0 +1,55 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::Duration as ChronoDuration;
+use tracing::trace;
+
+use crate::{
+    oncall::{on_call, week_start},
+    utils::time::{format_day, local_today},
+    Context, Error,
+};
+
+/// How many upcoming weeks of the rotation are listed.
+const UPCOMING_WEEKS: i64 = 3;
+
+/// Shows which mentor is on call this week and who is up next.
+#[poise::command(prefix_command, guild_only)]
+pub async fn oncall(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running oncall command");
+    let config = ctx.data().config.read().await.on_call.clone();
+    let this_week = week_start(local_today());
+    let Some(current) = on_call(&config, this_week) else {
+        ctx.say("There is no on-call rotation configured.").await?;
+        return Ok(());
+    };
+
+    let mut reply = format!("On call this week: <@{}>\n", current);
+    for week in 1..=UPCOMING_WEEKS {
+        let start = this_week + ChronoDuration::weeks(week);
+        if let Some(mentor) = on_call(&config, start) {
+            reply.push_str(&format!("- Week of {}: <@{}>\n", format_day(start), mentor));
+        }
+    }
+    ctx.send(
+        poise::CreateReply::default()
+            .content(reply)
+            .allowed_mentions(serenity::all::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
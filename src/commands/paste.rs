@@ -0,0 +1,96 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use poise::CreateReply;
+use serenity::all::CreateAllowedMentions;
+use tracing::{info, trace, warn};
+
+use crate::{
+    paste::{detect_language, parse_code_block, upload},
+    Context, Error,
+};
+
+/// Larger files are rejected instead of being downloaded.
+const MAX_PASTE_BYTES: u32 = 512 * 1024;
+
+/// Uploads an attached file or a code block to the paste service and replies with the
+/// link, removing the original message so long code doesn't flood the channel.
+#[poise::command(prefix_command, guild_only)]
+pub async fn paste(ctx: Context<'_>, #[rest] code: Option<String>) -> Result<(), Error> {
+    trace!("Running paste command");
+    let config = ctx.data().config.read().await.paste.clone();
+    if config.endpoint.is_none() {
+        ctx.say("Pasting isn't set up on this deployment.").await?;
+        return Ok(());
+    }
+    let poise::Context::Prefix(prefix) = ctx else {
+        return Ok(());
+    };
+    let message = prefix.msg;
+
+    let (tag, filename, content) = if let Some(attachment) = message.attachments.first() {
+        if attachment.size > MAX_PASTE_BYTES {
+            ctx.say(format!(
+                "That file is too large, pastes are limited to {} KiB.",
+                MAX_PASTE_BYTES / 1024
+            ))
+            .await?;
+            return Ok(());
+        }
+        let Ok(content) = String::from_utf8(attachment.download().await?) else {
+            ctx.say("That file isn't text.").await?;
+            return Ok(());
+        };
+        (None, Some(attachment.filename.as_str()), content)
+    } else if let Some(code) = code.as_deref() {
+        let (tag, code) = parse_code_block(code);
+        (tag, None, code.to_string())
+    } else {
+        ctx.say("Attach a file or include a code block to paste.")
+            .await?;
+        return Ok(());
+    };
+
+    let language = detect_language(tag, filename, &content);
+    let lines = content.lines().count();
+    let mut link = upload(&config, content).await?;
+    if let Some(language) = language.filter(|_| config.append_extension) {
+        link = format!("{}.{}", link, language.extension);
+    }
+    info!("{} pasted {} lines to {}", ctx.author().name, lines, link);
+
+    let language = language.map_or("code", |language| language.name);
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "<@{}> shared {} lines of {}: {}",
+                ctx.author().id,
+                lines,
+                language,
+                link
+            ))
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    if config.delete_original {
+        if let Err(e) = message.delete(ctx.http()).await {
+            warn!("Failed to delete the pasted message: {}", e);
+        }
+    }
+    Ok(())
+}
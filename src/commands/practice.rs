@@ -0,0 +1,37 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::Datelike;
+use tracing::trace;
+
+use crate::{
+    tasks::practice::{leaderboard_embed, monthly_leaderboard},
+    utils::time::local_today,
+    Context, Error,
+};
+
+/// Shows this month's practice leaderboard.
+#[poise::command(prefix_command)]
+pub async fn practice(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running practice command");
+    let today = local_today();
+    let leaderboard = monthly_leaderboard(&ctx.data().storage, today.year(), today.month()).await?;
+
+    ctx.send(poise::CreateReply::default().embed(leaderboard_embed(today, &leaderboard)))
+        .await?;
+    Ok(())
+}
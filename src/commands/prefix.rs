@@ -0,0 +1,111 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use serenity::all::{GuildId, Message};
+use tracing::{info, trace};
+
+use crate::{Context, Data, Error};
+
+const GUILD_PREFIXES_KEY: &str = "guild.prefixes";
+/// Personal queries that can be sent in DMs without a prefix.
+const DM_COMMANDS: [&str; 3] = ["streak", "attendance", "leaderboard"];
+
+/// Splits a message into its prefix and the rest, used as poise's stripped dynamic
+/// prefix callback. In DMs the prefix is optional for [`DM_COMMANDS`] so members can
+/// simply send `streak`.
+pub async fn strip_prefix<'a>(
+    data: &Data,
+    msg: &'a Message,
+) -> anyhow::Result<Option<(&'a str, &'a str)>> {
+    let prefix = resolve_prefix(data, msg.guild_id).await?;
+    if msg.content.starts_with(prefix.as_str()) {
+        return Ok(Some(msg.content.split_at(prefix.len())));
+    }
+    let command = msg.content.split_whitespace().next().unwrap_or_default();
+    if msg.guild_id.is_none() && DM_COMMANDS.contains(&command) {
+        return Ok(Some(("", &msg.content)));
+    }
+
+    Ok(None)
+}
+
+/// Resolves the prefix for a guild: its override if one is set, otherwise the
+/// configured default.
+pub async fn resolve_prefix(data: &Data, guild_id: Option<GuildId>) -> anyhow::Result<String> {
+    if let Some(guild_id) = guild_id {
+        let prefixes: HashMap<u64, String> = data.storage.get(GUILD_PREFIXES_KEY).await?;
+        if let Some(prefix) = prefixes.get(&guild_id.get()) {
+            return Ok(prefix.clone());
+        }
+    }
+
+    Ok(data.config.read().await.bot.prefix.clone())
+}
+
+#[poise::command(
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("set", "reset")
+)]
+pub async fn prefix(ctx: Context<'_>) -> Result<(), Error> {
+    let current = resolve_prefix(ctx.data(), ctx.guild_id()).await?;
+    ctx.say(format!("The prefix for this server is `{}`.", current))
+        .await?;
+    Ok(())
+}
+
+/// Overrides the command prefix for this server.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn set(ctx: Context<'_>, new_prefix: String) -> Result<(), Error> {
+    trace!("Running prefix set command");
+    if new_prefix.trim().is_empty() {
+        ctx.say("The prefix can't be empty, every message would be read as a command.")
+            .await?;
+        return Ok(());
+    }
+    let guild_id = ctx.guild_id().expect("Command is guild only");
+    ctx.data()
+        .storage
+        .update(GUILD_PREFIXES_KEY, |prefixes: &mut HashMap<u64, String>| {
+            prefixes.insert(guild_id.get(), new_prefix.clone())
+        })
+        .await?;
+
+    info!("Prefix for guild {} set to {}", guild_id, new_prefix);
+    ctx.say(format!("Prefix set to `{}`.", new_prefix)).await?;
+    Ok(())
+}
+
+/// Removes this server's prefix override, falling back to the configured default.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn reset(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running prefix reset command");
+    let guild_id = ctx.guild_id().expect("Command is guild only");
+    ctx.data()
+        .storage
+        .update(GUILD_PREFIXES_KEY, |prefixes: &mut HashMap<u64, String>| {
+            prefixes.remove(&guild_id.get())
+        })
+        .await?;
+
+    let prefix = ctx.data().config.read().await.bot.prefix.clone();
+    ctx.say(format!("Prefix reset to `{}`.", prefix)).await?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashSet;
+
+use serenity::all::{
+    ComponentInteractionDataKind, CreateActionRow, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuKind,
+    CreateSelectMenuOption,
+};
+use tokio::time::Duration;
+use tracing::{info, trace};
+
+use crate::{
+    preferences::{muted_notifications, set_muted_notifications, Notification},
+    Context, Error,
+};
+
+const PREFS_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Choose which DMs the bot sends you.
+#[poise::command(prefix_command)]
+pub async fn prefs(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running prefs command");
+    let user_id = ctx.author().id.get();
+    let muted = muted_notifications(&ctx.data().storage, user_id).await?;
+
+    let options = Notification::ALL
+        .into_iter()
+        .map(|kind| {
+            CreateSelectMenuOption::new(kind.label(), kind.id())
+                .default_selection(!muted.contains(&kind))
+        })
+        .collect();
+    let menu_id = format!("prefs:select:{}", ctx.id());
+    let menu = CreateSelectMenu::new(&menu_id, CreateSelectMenuKind::String { options })
+        .placeholder("DMs you want to receive")
+        .min_values(0)
+        .max_values(Notification::ALL.len() as u8);
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content("Pick the DMs you want to receive:")
+                .components(vec![CreateActionRow::SelectMenu(menu)]),
+        )
+        .await?
+        .into_message()
+        .await?;
+
+    let Some(interaction) = reply
+        .await_component_interaction(ctx.serenity_context().shard.clone())
+        .author_id(ctx.author().id)
+        .custom_ids(vec![menu_id])
+        .timeout(PREFS_TIMEOUT)
+        .await
+    else {
+        return Ok(());
+    };
+    let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind else {
+        return Ok(());
+    };
+
+    let enabled: HashSet<Notification> = values
+        .iter()
+        .filter_map(|value| Notification::from_id(value))
+        .collect();
+    let muted: HashSet<Notification> = Notification::ALL
+        .into_iter()
+        .filter(|kind| !enabled.contains(kind))
+        .collect();
+    let summary = if muted.is_empty() {
+        String::from("You'll receive every kind of DM.")
+    } else {
+        let labels: Vec<&str> = muted.iter().map(|kind| kind.label()).collect();
+        format!("Saved, you won't receive: {}.", labels.join(", "))
+    };
+    set_muted_notifications(&ctx.data().storage, user_id, muted).await?;
+    info!(
+        "{} updated their notification preferences",
+        ctx.author().name
+    );
+
+    interaction
+        .create_response(
+            ctx.http(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(summary)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,118 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::{
+    ButtonStyle, CreateActionRow, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage, User,
+};
+use tokio::time::Duration;
+use tracing::trace;
+
+use crate::{graphql::queries::fetch_members, privacy::erase_member, Context, Error};
+
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Deletes everything the bot stores about you and stops tracking you in the future.
+/// Your data on Root is not affected.
+#[poise::command(prefix_command)]
+pub async fn forget_me(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running forget_me command");
+    confirm_and_erase(ctx, ctx.author().clone()).await
+}
+
+/// Deletes everything the bot stores about `member` and stops tracking them.
+#[poise::command(prefix_command, guild_only, required_permissions = "ADMINISTRATOR")]
+pub async fn erase(ctx: Context<'_>, member: User) -> Result<(), Error> {
+    trace!("Running erase command");
+    confirm_and_erase(ctx, member).await
+}
+
+async fn confirm_and_erase(ctx: Context<'_>, user: User) -> Result<(), Error> {
+    let confirm_id = format!("erase:confirm:{}", ctx.id());
+    let cancel_id = format!("erase:cancel:{}", ctx.id());
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(&confirm_id)
+            .label("Erase")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ]);
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(format!(
+                    "This permanently deletes the reports, reminders, check-ins, RSVPs and \
+                     subscriptions stored for {} and stops the bot from tracking them. Continue?",
+                    user.name
+                ))
+                .components(vec![buttons]),
+        )
+        .await?;
+    let message = reply.message().await?;
+
+    let Some(interaction) = message
+        .await_component_interaction(ctx.serenity_context().shard.clone())
+        .author_id(ctx.author().id)
+        .timeout(CONFIRM_TIMEOUT)
+        .await
+    else {
+        ctx.say("Erasure timed out, nothing was deleted.").await?;
+        return Ok(());
+    };
+
+    let erasing = interaction.data.custom_id == confirm_id;
+    let status = if erasing {
+        "Erasing..."
+    } else {
+        "Erasure cancelled."
+    };
+    interaction
+        .create_response(
+            ctx.http(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(status)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    if !erasing {
+        return Ok(());
+    }
+
+    // Attendance history only has names, look the member up on Root to match it.
+    let discord_id = user.id.to_string();
+    let name = fetch_members()
+        .await?
+        .into_iter()
+        .find(|m| m.discord_id == discord_id)
+        .map(|m| m.name)
+        .unwrap_or_else(|| user.name.clone());
+    erase_member(
+        ctx.serenity_context(),
+        ctx.data(),
+        user.id,
+        &name,
+        ctx.author().id,
+    )
+    .await?;
+
+    ctx.say(format!("Erased all stored data of {}.", user.name))
+        .await?;
+    Ok(())
+}
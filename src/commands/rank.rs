@@ -0,0 +1,60 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use poise::CreateReply;
+use serenity::all::{CreateAttachment, User};
+use tracing::{trace, warn};
+
+use crate::{
+    charts::{render_rank_card, RankCard},
+    xp::{progress, rank as xp_rank, xp_for_level},
+    Context, Error,
+};
+
+/// Shows a member's level and XP as a card.
+#[poise::command(prefix_command)]
+pub async fn rank(ctx: Context<'_>, user: Option<User>) -> Result<(), Error> {
+    trace!("Running rank command");
+    let user = user.unwrap_or_else(|| ctx.author().clone());
+    let storage = &ctx.data().storage;
+    let progress = progress(storage, user.id.get()).await?;
+    let level = progress.level();
+    let card = RankCard {
+        name: user.display_name(),
+        level,
+        rank: xp_rank(storage, user.id.get()).await?,
+        xp: progress.xp,
+        level_start: xp_for_level(level),
+        next_level: xp_for_level(level + 1),
+    };
+
+    let reply = match render_rank_card(&card) {
+        Ok(png) => CreateReply::default().attachment(CreateAttachment::bytes(png, "rank.png")),
+        Err(e) => {
+            warn!("Sending rank without a card: {:?}", e);
+            CreateReply::default().content(format!(
+                "{} is level {} with {} XP, {} XP short of the next level.",
+                card.name,
+                card.level,
+                card.xp,
+                card.next_level - card.xp
+            ))
+        }
+    };
+    ctx.send(reply).await?;
+    Ok(())
+}
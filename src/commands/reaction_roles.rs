@@ -0,0 +1,158 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serenity::all::{ReactionType, Role};
+use tracing::{info, trace};
+
+use crate::{
+    reaction_roles::{add_reaction_role, custom_reaction_roles, remove_reaction_role},
+    Context, Error,
+};
+
+#[poise::command(
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_ROLES",
+    subcommands("add", "remove", "list")
+)]
+pub async fn reactionroles(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `reactionroles add <emoji> <role>`, `reactionroles remove <emoji>` or `reactionroles list`")
+        .await?;
+    Ok(())
+}
+
+/// Maps an emoji to a role on the roles message. Accepts Unicode emoji as well as custom
+/// and animated guild emoji, e.g. `<:amfoss:123>` or `<a:party:456>`. Only roles below
+/// both the invoker's and the bot's highest role can be mapped, so nobody can hand
+/// themselves a role they couldn't assign.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_ROLES")]
+pub async fn add(ctx: Context<'_>, emoji: String, role: Role) -> Result<(), Error> {
+    trace!("Running reactionroles add command");
+    let Some(emoji) = parse_emoji(&emoji) else {
+        ctx.say("That doesn't look like an emoji.").await?;
+        return Ok(());
+    };
+    if let Some(reason) = unassignable(ctx, &role).await? {
+        ctx.say(format!(
+            "**{}** can't be a reaction role, {}.",
+            role.name, reason
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    add_reaction_role(&ctx.data().storage, emoji.clone(), role.id.get()).await?;
+    info!("Mapped {} to role {}", emoji, role.name);
+    ctx.say(format!(
+        "Reacting with {} on the roles message now grants **{}**. React to the message \
+         yourself so members can see the option.",
+        emoji, role.name
+    ))
+    .await?;
+    Ok(())
+}
+
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_ROLES")]
+pub async fn remove(ctx: Context<'_>, emoji: String) -> Result<(), Error> {
+    trace!("Running reactionroles remove command");
+    let Some(emoji) = parse_emoji(&emoji) else {
+        ctx.say("That doesn't look like an emoji.").await?;
+        return Ok(());
+    };
+
+    if remove_reaction_role(&ctx.data().storage, &emoji).await? {
+        ctx.say(format!("Removed the reaction role for {}.", emoji))
+            .await?;
+    } else {
+        ctx.say(format!(
+            "{} isn't a reaction role added with this command.",
+            emoji
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Lists the reaction roles added at runtime. The built-in group roles aren't shown.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_ROLES")]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running reactionroles list command");
+    let roles = custom_reaction_roles(&ctx.data().storage).await?;
+    if roles.is_empty() {
+        ctx.say("No reaction roles have been added.").await?;
+        return Ok(());
+    }
+
+    let reply = roles
+        .iter()
+        .map(|r| format!("{} → <@&{}>", r.emoji, r.role_id))
+        .collect::<Vec<_>>()
+        .join("\n");
+    ctx.send(
+        poise::CreateReply::default()
+            .content(reply)
+            .allowed_mentions(serenity::all::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Why `role` can't be handed out by reaction on behalf of the invoker, if it can't.
+async fn unassignable(ctx: Context<'_>, role: &Role) -> anyhow::Result<Option<&'static str>> {
+    let guild_id = ctx.guild_id().expect("Command is guild only");
+    if role.id.get() == guild_id.get() {
+        return Ok(Some("everyone already has it"));
+    }
+    if role.managed {
+        return Ok(Some("it's managed by an integration"));
+    }
+
+    let author = ctx
+        .author_member()
+        .await
+        .ok_or_else(|| anyhow!("Failed to look up the invoking member"))?
+        .into_owned();
+    let bot_id = ctx.cache().current_user().id;
+    let bot = guild_id.member(ctx.http(), bot_id).await?;
+    let guild = ctx
+        .guild()
+        .ok_or_else(|| anyhow!("Guild {} isn't cached", guild_id))?;
+    let highest = |member| {
+        guild
+            .member_highest_role(member)
+            .map_or(0, |highest| highest.position)
+    };
+    if role.position >= highest(&bot) {
+        return Ok(Some("it's not below my highest role"));
+    }
+    if author.user.id != guild.owner_id && role.position >= highest(&author) {
+        return Ok(Some("it's not below your highest role"));
+    }
+    Ok(None)
+}
+
+/// Parses `<:name:id>`, `<a:name:id>` or a Unicode emoji.
+fn parse_emoji(input: &str) -> Option<ReactionType> {
+    let emoji = ReactionType::from_str(input.trim()).ok()?;
+    match &emoji {
+        ReactionType::Unicode(name) if name.is_empty() || name.is_ascii() => None,
+        _ => Some(emoji),
+    }
+}
@@ -0,0 +1,223 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::NaiveDate;
+use serenity::all::User;
+use tracing::{info, trace};
+
+use crate::{
+    config::ReportKind,
+    graphql::queries::{push_attendance_stats, push_status_update_stats},
+    history::{attendance_day, status_update_day},
+    tasks::{
+        lab_attendance,
+        status_update::{self, format_exempt_authors, set_format_exempt},
+    },
+    utils::delivery::latest_report,
+    Context, Error,
+};
+
+#[poise::command(prefix_command, subcommands("latest", "replay", "exempt_format"))]
+pub async fn report(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `report latest [status_update|attendance]`, `report replay <YYYY-MM-DD> [status_update|attendance]` or `report exempt_format [add|remove] <member>`")
+        .await?;
+    Ok(())
+}
+
+/// The report named by `kind`, defaulting to the status update report.
+async fn parse_kind(ctx: Context<'_>, kind: Option<String>) -> Result<Option<ReportKind>, Error> {
+    match kind.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("status_update") | Some("status") => Ok(Some(ReportKind::StatusUpdate)),
+        Some("attendance") => Ok(Some(ReportKind::Attendance)),
+        Some(_) => {
+            ctx.say("Reports are either `status_update` or `attendance`.")
+                .await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Links to the most recent status update report, or attendance report if asked for.
+#[poise::command(prefix_command, guild_only)]
+pub async fn latest(ctx: Context<'_>, kind: Option<String>) -> Result<(), Error> {
+    trace!("Running report latest command");
+    let Some(kind) = parse_kind(ctx, kind).await? else {
+        return Ok(());
+    };
+
+    match latest_report(&ctx.data().storage, kind).await? {
+        Some(report) => ctx.say(report.link(ctx.guild_id())).await?,
+        None => ctx.say("No report has been posted yet.").await?,
+    };
+    Ok(())
+}
+
+/// Regenerates the status update report, or attendance report if asked for, of a past
+/// date from its stored results and posts it in this channel.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn replay(ctx: Context<'_>, date: String, kind: Option<String>) -> Result<(), Error> {
+    trace!("Running report replay command");
+    let Ok(date) = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") else {
+        ctx.say("Dates are in the YYYY-MM-DD format.").await?;
+        return Ok(());
+    };
+    let Some(kind) = parse_kind(ctx, kind).await? else {
+        return Ok(());
+    };
+
+    let storage = &ctx.data().storage;
+    let message = match kind {
+        ReportKind::StatusUpdate => match status_update_day(storage, date).await? {
+            Some(day) => {
+                let channel_id = ctx.channel_id().get();
+                let report = status_update::replay_report(
+                    ctx.serenity_context(),
+                    ctx.data(),
+                    &day,
+                    channel_id,
+                );
+                Some(report.await?)
+            }
+            None => None,
+        },
+        ReportKind::Attendance => match attendance_day(storage, date).await? {
+            Some(day) => {
+                Some(lab_attendance::replay_report(ctx.serenity_context(), ctx.data(), &day).await?)
+            }
+            None => None,
+        },
+    };
+    let Some(message) = message else {
+        ctx.say(format!("No results were stored for {}.", date))
+            .await?;
+        return Ok(());
+    };
+
+    info!("Replaying the {:?} report of {}", kind, date);
+    ctx.channel_id().send_message(ctx.http(), message).await?;
+    Ok(())
+}
+
+/// Pushes the summary counts of a day's status update report, or attendance report if
+/// asked for, to Root. The nightly tasks do this on their own when `push_summaries` is set.
+#[poise::command(prefix_command, owners_only)]
+pub async fn push_summary(
+    ctx: Context<'_>,
+    date: String,
+    kind: Option<String>,
+) -> Result<(), Error> {
+    trace!("Running push_summary command");
+    let Ok(date) = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") else {
+        ctx.say("Dates are in the YYYY-MM-DD format.").await?;
+        return Ok(());
+    };
+    let Some(kind) = parse_kind(ctx, kind).await? else {
+        return Ok(());
+    };
+
+    let storage = &ctx.data().storage;
+    let pushed = match kind {
+        ReportKind::StatusUpdate => match status_update_day(storage, date).await? {
+            Some(day) => {
+                push_status_update_stats(&day.stats()).await?;
+                true
+            }
+            None => false,
+        },
+        ReportKind::Attendance => match attendance_day(storage, date).await? {
+            Some(day) => {
+                push_attendance_stats(&day.stats()).await?;
+                true
+            }
+            None => false,
+        },
+    };
+    if !pushed {
+        ctx.say(format!("No results were stored for {}.", date))
+            .await?;
+        return Ok(());
+    }
+
+    info!("Pushed the {:?} summary of {} to Root", kind, date);
+    ctx.say(format!("Pushed the summary of {} to Root.", date))
+        .await?;
+    Ok(())
+}
+
+/// Lists the members allowed to sign off their updates with just "regards".
+#[poise::command(
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("add", "remove")
+)]
+pub async fn exempt_format(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running report exempt_format command");
+    let authors = format_exempt_authors(&ctx.data().storage).await?;
+    if authors.is_empty() {
+        ctx.say("Nobody is allowed an alternate update format.")
+            .await?;
+        return Ok(());
+    }
+
+    let mut reply = String::from("Allowed an alternate update format:\n");
+    for author in authors {
+        reply.push_str(&format!("- <@{}>\n", author));
+    }
+    ctx.send(
+        poise::CreateReply::default()
+            .content(reply)
+            .allowed_mentions(serenity::all::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Allows `member` to sign off their updates with just "regards".
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn add(ctx: Context<'_>, member: User) -> Result<(), Error> {
+    trace!("Running report exempt_format add command");
+    if set_format_exempt(&ctx.data().storage, member.id.to_string(), true).await? {
+        info!("{} allowed an alternate update format", member.name);
+        ctx.say(format!("{} can now use the alternate format.", member.name))
+            .await?;
+    } else {
+        ctx.say(format!("{} is already exempt.", member.name))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Requires `member` to use the standard update format again.
+#[poise::command(prefix_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn remove(ctx: Context<'_>, member: User) -> Result<(), Error> {
+    trace!("Running report exempt_format remove command");
+    if set_format_exempt(&ctx.data().storage, member.id.to_string(), false).await? {
+        info!(
+            "{} no longer allowed an alternate update format",
+            member.name
+        );
+        ctx.say(format!(
+            "{} must use the standard format again.",
+            member.name
+        ))
+        .await?;
+    } else {
+        ctx.say(format!("{} wasn't exempt.", member.name)).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,116 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::NaiveTime;
+use tracing::{info, trace};
+
+use crate::{
+    scheduler::{next_run_in, schedule_overrides, set_schedule_override},
+    tasks::{get_tasks, Task},
+    Context, Error,
+};
+
+/// Lists the tasks that can be rescheduled along with their current overrides.
+#[poise::command(prefix_command, owners_only, subcommands("set", "reset"))]
+pub async fn schedule(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running schedule command");
+    let overrides = schedule_overrides(&ctx.data().storage).await?;
+
+    let mut reply = String::from("Tasks that can be rescheduled:\n");
+    for task in get_tasks() {
+        if task.run_in_at(0, 0).is_none() {
+            continue;
+        }
+        let time = match overrides.get(task.name()) {
+            Some(time) => time.format("%H:%M").to_string(),
+            None => String::from("default"),
+        };
+        reply.push_str(&format!("- {}: {}\n", task.name(), time));
+    }
+
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Runs `task` at `time` (HH:MM, in the bot's timezone) instead of its default time.
+#[poise::command(prefix_command, owners_only)]
+pub async fn set(ctx: Context<'_>, task: String, time: String) -> Result<(), Error> {
+    trace!("Running schedule set command");
+    let Some(task) = find_task(&task) else {
+        ctx.say(format!("There is no task called `{}`.", task))
+            .await?;
+        return Ok(());
+    };
+    let Ok(time) = NaiveTime::parse_from_str(&time, "%H:%M") else {
+        ctx.say("Times must be in the HH:MM format, e.g. `05:30`.")
+            .await?;
+        return Ok(());
+    };
+    if task.run_in_at(0, 0).is_none() {
+        ctx.say(format!(
+            "{} runs on an interval and can't be rescheduled.",
+            task.name()
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    set_schedule_override(ctx.data(), task.name(), Some(time)).await?;
+    info!("Task {} rescheduled to {}", task.name(), time);
+
+    let next_run_in = next_run_in(&ctx.data().storage, task.as_ref()).await;
+    ctx.say(format!(
+        "{} now runs at {}, next run in {} minutes.",
+        task.name(),
+        time.format("%H:%M"),
+        next_run_in.as_secs() / 60
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Removes the override for `task`, it runs at its default time again.
+#[poise::command(prefix_command, owners_only)]
+pub async fn reset(ctx: Context<'_>, task: String) -> Result<(), Error> {
+    trace!("Running schedule reset command");
+    let Some(task) = find_task(&task) else {
+        ctx.say(format!("There is no task called `{}`.", task))
+            .await?;
+        return Ok(());
+    };
+
+    set_schedule_override(ctx.data(), task.name(), None).await?;
+    info!("Task {} schedule reset", task.name());
+    ctx.say(format!("{} runs at its default time again.", task.name()))
+        .await?;
+    Ok(())
+}
+
+/// Finds a task by name, ignoring case and separators so `status_update_check` matches
+/// "Status Update Check".
+fn find_task(name: &str) -> Option<Box<dyn Task>> {
+    let normalize = |name: &str| -> String {
+        name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let name = normalize(name);
+    get_tasks()
+        .into_iter()
+        .find(|task| normalize(task.name()) == name)
+}
@@ -0,0 +1,61 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use tracing::trace;
+
+use crate::{
+    semester::{next_term, term_on},
+    utils::time::{format_date, local_today},
+    Context, Error,
+};
+
+#[poise::command(prefix_command, subcommands("status"))]
+pub async fn semester(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `semester status`").await?;
+    Ok(())
+}
+
+/// Shows the current term, or when checks resume if they are paused for the break.
+#[poise::command(prefix_command)]
+pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running semester status command");
+    let config = ctx.data().config.read().await.semester.clone();
+    let today = local_today();
+
+    let reply = if config.terms.is_empty() {
+        String::from("No terms are configured, checks run all year round.")
+    } else if let Some(term) = term_on(&config, today) {
+        format!(
+            "{} is on, checks run until {} ({} days left).",
+            term.name,
+            format_date(term.end),
+            (term.end - today).num_days()
+        )
+    } else {
+        match next_term(&config, today) {
+            Some(next) => format!(
+                "Checks are paused for the break. {} starts on {} ({} days from now).",
+                next.name,
+                format_date(next.start),
+                (next.start - today).num_days()
+            ),
+            None => String::from("Checks are paused, no upcoming term is configured."),
+        }
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
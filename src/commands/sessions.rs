@@ -0,0 +1,175 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use serenity::all::{ButtonStyle, CreateActionRow, CreateButton, CreateEmbed};
+use tokio::time::Duration;
+use tracing::trace;
+
+use crate::{
+    sessions::{propose_session, sessions, SessionStatus},
+    utils::time::{discord_timestamp, timezone, TimestampStyle},
+    Context, Error,
+};
+
+const PROPOSAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, poise::Modal)]
+#[name = "Propose a talk"]
+struct ProposalModal {
+    #[name = "Title"]
+    #[max_length = 100]
+    title: String,
+    #[name = "What is it about?"]
+    #[paragraph]
+    #[max_length = 1000]
+    description: String,
+    #[name = "When (YYYY-MM-DD HH:MM, server timezone)"]
+    #[placeholder = "2026-01-31 17:30"]
+    starts_at: String,
+}
+
+#[poise::command(prefix_command, subcommands("propose", "list", "attendees"))]
+pub async fn talk(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `talk propose`, `talk list` or `talk attendees <id>`")
+        .await?;
+    Ok(())
+}
+
+/// Opens a form to propose a talk, which mentors then approve or reject.
+#[poise::command(prefix_command)]
+pub async fn propose(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running talk propose command");
+    // Prefix commands can't open modals directly, a button click is needed first.
+    let open_id = format!("talk_propose:open:{}", ctx.id());
+    let button = CreateActionRow::Buttons(vec![CreateButton::new(&open_id)
+        .label("Open proposal form")
+        .style(ButtonStyle::Primary)]);
+    let reply = ctx
+        .send(poise::CreateReply::default().components(vec![button]))
+        .await?
+        .into_message()
+        .await?;
+
+    let Some(interaction) = reply
+        .await_component_interaction(ctx.serenity_context().shard.clone())
+        .author_id(ctx.author().id)
+        .timeout(PROPOSAL_TIMEOUT)
+        .await
+    else {
+        return Ok(());
+    };
+    let Some(proposal) = poise::execute_modal_on_component_interaction::<ProposalModal>(
+        ctx,
+        interaction,
+        None,
+        Some(PROPOSAL_TIMEOUT),
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+
+    let starts_at = NaiveDateTime::parse_from_str(proposal.starts_at.trim(), "%Y-%m-%d %H:%M")
+        .ok()
+        .and_then(|time| timezone().from_local_datetime(&time).single())
+        .map(|time| time.with_timezone(&Utc));
+    let Some(starts_at) = starts_at.filter(|time| *time > Utc::now()) else {
+        ctx.say("The start time must be a future date in the YYYY-MM-DD HH:MM format.")
+            .await?;
+        return Ok(());
+    };
+
+    let session = propose_session(
+        ctx.serenity_context(),
+        ctx.data(),
+        proposal.title,
+        proposal.description,
+        ctx.author().id,
+        starts_at,
+    )
+    .await?;
+    ctx.say(format!(
+        "Thanks! Talk #{} was sent to the mentors for approval.",
+        session.id
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Lists upcoming approved talks.
+#[poise::command(prefix_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running talk list command");
+    let now = Utc::now();
+    let mut upcoming: Vec<_> = sessions(&ctx.data().storage)
+        .await?
+        .into_iter()
+        .filter(|s| s.status == SessionStatus::Approved && s.starts_at > now)
+        .collect();
+    upcoming.sort_by_key(|s| s.starts_at);
+
+    let mut description = String::new();
+    for session in &upcoming {
+        description.push_str(&format!(
+            "**#{} {}** by <@{}>, {} ({} attending)\n",
+            session.id,
+            session.title,
+            session.speaker_id,
+            discord_timestamp(session.starts_at, TimestampStyle::LongDateTime),
+            session.attendees.len()
+        ));
+    }
+    if description.is_empty() {
+        description.push_str("No talks are scheduled yet, `talk propose` to give one!");
+    }
+
+    let embed = CreateEmbed::new()
+        .title("Upcoming Talks")
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Shows who RSVP'd to a talk.
+#[poise::command(prefix_command)]
+pub async fn attendees(ctx: Context<'_>, id: u64) -> Result<(), Error> {
+    trace!("Running talk attendees command");
+    let Some(session) = sessions(&ctx.data().storage)
+        .await?
+        .into_iter()
+        .find(|s| s.id == id)
+    else {
+        ctx.say(format!("There is no talk #{}.", id)).await?;
+        return Ok(());
+    };
+
+    let mut description: String = session
+        .attendees
+        .iter()
+        .map(|id| format!("- <@{}>\n", id))
+        .collect();
+    if description.is_empty() {
+        description.push_str("Nobody has RSVP'd yet.");
+    }
+
+    let embed = CreateEmbed::new()
+        .title(format!("Attending \"{}\"", session.title))
+        .description(description);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
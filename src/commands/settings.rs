@@ -0,0 +1,160 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serde_json::Value;
+use tracing::{info, trace};
+
+use crate::{
+    ids,
+    utils::{
+        long_message::say_long,
+        time::{discord_timestamp, TimestampStyle},
+    },
+    Context, Error,
+};
+
+/// How many versions `$config history` lists.
+const HISTORY_LENGTH: usize = 15;
+
+/// Sets `namespace.key` to `value`, parsed as JSON so `5`, `true` and `[1, 2]` keep their
+/// types, and taken as text otherwise. `null` removes the setting.
+#[poise::command(prefix_command, owners_only)]
+pub async fn set(ctx: Context<'_>, setting: String, #[rest] value: String) -> Result<(), Error> {
+    trace!("Running set command");
+    let Some((namespace, key)) = setting.split_once('.') else {
+        ctx.say("Settings are named `namespace.key`.").await?;
+        return Ok(());
+    };
+    let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+
+    let settings = &ctx.data().settings;
+    if value.is_null() {
+        let reply = if settings.remove(namespace, key).await? {
+            format!("`{}` is back to its default.", setting)
+        } else {
+            format!("`{}` isn't set.", setting)
+        };
+        ctx.say(reply).await?;
+        return Ok(());
+    }
+
+    if namespace == ids::SETTINGS_NAMESPACE && value.as_u64().is_none_or(|id| id == 0) {
+        ctx.say(format!("`{}` must be a Discord ID.", setting))
+            .await?;
+        return Ok(());
+    }
+
+    settings.set(namespace, key, &value).await?;
+    info!("{} set {} to {}", ctx.author().name, setting, value);
+    ctx.say(format!("`{}` is now `{}`.", setting, value))
+        .await?;
+    Ok(())
+}
+
+/// Shows a setting, every setting in a namespace, or all of them.
+#[poise::command(prefix_command, owners_only)]
+pub async fn get(ctx: Context<'_>, setting: Option<String>) -> Result<(), Error> {
+    trace!("Running get command");
+    let namespaces = ctx.data().settings.all().await?;
+    let (namespace, key) = match setting.as_deref().map(|s| s.split_once('.')) {
+        Some(Some((namespace, key))) => (Some(namespace), Some(key)),
+        Some(None) => (setting.as_deref(), None),
+        None => (None, None),
+    };
+
+    let mut reply = String::new();
+    for (name, settings) in &namespaces {
+        if namespace.is_some_and(|namespace| namespace != name) {
+            continue;
+        }
+        for (setting_key, value) in settings {
+            if key.is_some_and(|key| key != setting_key) {
+                continue;
+            }
+            reply.push_str(&format!("- `{}.{}`: `{}`\n", name, setting_key, value));
+        }
+    }
+
+    if reply.is_empty() {
+        reply = match setting {
+            Some(setting) => format!("`{}` isn't set.", setting),
+            None => String::from("No settings have been changed."),
+        };
+    }
+    say_long(ctx, &reply, "settings.md").await
+}
+
+/// Versioned snapshots of the settings, taken on every change.
+#[poise::command(prefix_command, owners_only, subcommands("history", "rollback"))]
+pub async fn config(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `config history` or `config rollback <version>`")
+        .await?;
+    Ok(())
+}
+
+/// Lists the most recent versions of the settings.
+#[poise::command(prefix_command, owners_only)]
+async fn history(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running config history command");
+    let history = ctx.data().settings.history().await?;
+    if history.is_empty() {
+        ctx.say("No settings have been changed.").await?;
+        return Ok(());
+    }
+
+    let mut reply = String::new();
+    for snapshot in history.iter().rev().take(HISTORY_LENGTH) {
+        reply.push_str(&format!(
+            "- **v{}** {}: {}\n",
+            snapshot.version,
+            discord_timestamp(snapshot.taken_at, TimestampStyle::Relative),
+            snapshot.change
+        ));
+    }
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Restores the settings of an earlier version.
+#[poise::command(prefix_command, owners_only)]
+async fn rollback(ctx: Context<'_>, version: u32) -> Result<(), Error> {
+    trace!("Running config rollback command");
+    let settings = &ctx.data().settings;
+    let known = settings
+        .history()
+        .await?
+        .iter()
+        .any(|snapshot| snapshot.version == version);
+    if !known {
+        ctx.say(format!(
+            "There is no version {}, see `config history`.",
+            version
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    settings.rollback(version).await?;
+    info!(
+        "{} rolled the settings back to v{}",
+        ctx.author().name,
+        version
+    );
+    ctx.say(format!("The settings are back to v{}.", version))
+        .await?;
+    Ok(())
+}
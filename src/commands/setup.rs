@@ -0,0 +1,330 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::str::FromStr;
+
+use chrono::{Duration as ChronoDuration, NaiveTime};
+use serenity::all::{
+    ButtonStyle, ChannelId, ChannelType, ComponentInteraction, ComponentInteractionDataKind,
+    CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, GetMessages, Message,
+};
+use tokio::time::Duration;
+use tracing::{info, trace};
+
+use crate::{
+    ids::{self, roles_message_id, ChannelKind, ROLES_MESSAGE_KEY, SETTINGS_NAMESPACE},
+    scheduler::set_schedule_override,
+    utils::time::local_now,
+    Context, Error,
+};
+
+const SETUP_TIMEOUT: Duration = Duration::from_secs(300);
+/// Messages of the roles channel offered as the roles message, Discord allows 25 options.
+const ROLES_MESSAGE_CHOICES: u8 = 25;
+/// The preview runs this long before the status update check.
+const PREVIEW_LEAD_MINUTES: i64 = 15;
+
+/// What the admin picked in one step of the wizard.
+enum Answer<T> {
+    Keep,
+    Picked(T),
+}
+
+impl Answer<String> {
+    /// Parses the picked value, which Discord fills from the options the bot offered.
+    fn parse<T: FromStr>(self) -> Answer<T> {
+        match self {
+            Answer::Picked(value) => value.parse().map_or(Answer::Keep, Answer::Picked),
+            Answer::Keep => Answer::Keep,
+        }
+    }
+}
+
+/// Walks you through picking the bot's channels, roles message and report times. Nothing
+/// is saved until the last step, and every step can keep the current value.
+#[poise::command(prefix_command, guild_only, required_permissions = "ADMINISTRATOR")]
+pub async fn setup(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running setup command");
+    let mut wizard = Wizard {
+        ctx,
+        message: ctx.say("Starting setup...").await?.into_message().await?,
+        interaction: None,
+    };
+
+    // Every step returns `None` once the wizard timed out.
+    let mut channels = Vec::new();
+    let Some(status_update) = wizard
+        .channel(
+            "Where should the status update report go?",
+            ids::channel(ChannelKind::StatusUpdate),
+            &[ChannelType::Text],
+        )
+        .await?
+    else {
+        return Ok(());
+    };
+    channels.push((ChannelKind::StatusUpdate, status_update));
+    for kind in ChannelKind::GROUPS {
+        let prompt = format!(
+            "Where does group {} post their status updates?",
+            kind.group().unwrap_or_default()
+        );
+        // Groups may post in a forum, with a post per member.
+        let types = [ChannelType::Text, ChannelType::Forum];
+        let current = ids::channel(kind);
+        let Some(channel) = wizard.channel(&prompt, current, &types).await? else {
+            return Ok(());
+        };
+        channels.push((kind, channel));
+    }
+    let Some(lab) = wizard
+        .channel(
+            "Where should the attendance report go?",
+            ids::channel(ChannelKind::Lab),
+            &[ChannelType::Text],
+        )
+        .await?
+    else {
+        return Ok(());
+    };
+    channels.push((ChannelKind::Lab, lab));
+    let Some(roles_message) = wizard.roles_message().await? else {
+        return Ok(());
+    };
+    let Some(check_time) = wizard
+        .time("When should the status update check run?", 3..=8)
+        .await?
+    else {
+        return Ok(());
+    };
+    let Some(attendance_time) = wizard
+        .time("When should the attendance report go out?", 16..=21)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let Some(interaction) = wizard.interaction else {
+        return Ok(());
+    };
+    let data = ctx.data();
+    let mut saved = Vec::new();
+    for (kind, answer) in channels {
+        if let Answer::Picked(channel_id) = answer {
+            data.settings
+                .set(SETTINGS_NAMESPACE, kind.settings_key(), &channel_id)
+                .await?;
+            saved.push(format!("- {}: <#{}>", kind, channel_id));
+        }
+    }
+    if let Answer::Picked(message_id) = roles_message {
+        data.settings
+            .set(SETTINGS_NAMESPACE, ROLES_MESSAGE_KEY, &message_id)
+            .await?;
+        saved.push(String::from("- roles message"));
+    }
+    if let Answer::Picked(time) = check_time {
+        let preview = time - ChronoDuration::minutes(PREVIEW_LEAD_MINUTES);
+        set_schedule_override(data, "Status Update Check", Some(time)).await?;
+        set_schedule_override(data, "Status Update Preview", Some(preview)).await?;
+        saved.push(format!("- status update check at {}", time.format("%H:%M")));
+    }
+    if let Answer::Picked(time) = attendance_time {
+        set_schedule_override(data, "Lab Attendance Check", Some(time)).await?;
+        saved.push(format!("- attendance report at {}", time.format("%H:%M")));
+    }
+    info!(
+        "{} ran setup, changing {} settings",
+        ctx.author().name,
+        saved.len()
+    );
+
+    let summary = if saved.is_empty() {
+        String::from("Setup done, nothing was changed.")
+    } else {
+        format!("Setup done! Saved:\n{}", saved.join("\n"))
+    };
+    interaction
+        .create_response(
+            ctx.http(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(summary)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// The setup message and the interaction that answered its latest step, which the next
+/// step responds to.
+struct Wizard<'a> {
+    ctx: Context<'a>,
+    message: Message,
+    interaction: Option<ComponentInteraction>,
+}
+
+impl Wizard<'_> {
+    async fn channel(
+        &mut self,
+        prompt: &str,
+        current: ChannelId,
+        types: &[ChannelType],
+    ) -> Result<Option<Answer<u64>>, Error> {
+        let kind = CreateSelectMenuKind::Channel {
+            channel_types: Some(types.to_vec()),
+            default_channels: None,
+        };
+        let content = format!("{}\nCurrently <#{}>.", prompt, current);
+        Ok(self.ask(content, kind).await?.map(Answer::parse))
+    }
+
+    /// Asks for the channel of the roles message, then for the message among its latest.
+    async fn roles_message(&mut self) -> Result<Option<Answer<u64>>, Error> {
+        let kind = CreateSelectMenuKind::Channel {
+            channel_types: Some(vec![ChannelType::Text]),
+            default_channels: None,
+        };
+        let content = format!(
+            "Which channel has the message members react to for roles?\nCurrently message {}.",
+            roles_message_id()
+        );
+        let channel_id = match self.ask(content, kind).await?.map(Answer::parse) {
+            Some(Answer::Picked(channel_id)) => channel_id,
+            keep_or_timeout => return Ok(keep_or_timeout),
+        };
+
+        let messages = ChannelId::new(channel_id)
+            .messages(
+                self.ctx.http(),
+                GetMessages::new().limit(ROLES_MESSAGE_CHOICES),
+            )
+            .await?;
+        if messages.is_empty() {
+            return Ok(Some(Answer::Keep));
+        }
+        let options = messages
+            .iter()
+            .map(|message| {
+                CreateSelectMenuOption::new(message_label(message), message.id.to_string())
+            })
+            .collect();
+        let kind = CreateSelectMenuKind::String { options };
+        let answer = self.ask(String::from("Which message is it?"), kind).await?;
+        Ok(answer.map(Answer::parse))
+    }
+
+    /// Asks for a time on the half hour between `hours`, in the bot's timezone.
+    async fn time(
+        &mut self,
+        prompt: &str,
+        hours: std::ops::RangeInclusive<u32>,
+    ) -> Result<Option<Answer<NaiveTime>>, Error> {
+        let options = hours
+            .flat_map(|hour| [(hour, 0), (hour, 30)])
+            .filter_map(|(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0))
+            .map(|time| {
+                let label = time.format("%H:%M").to_string();
+                CreateSelectMenuOption::new(&label, label.clone())
+            })
+            .collect();
+        let kind = CreateSelectMenuKind::String { options };
+        let answer = self
+            .ask(format!("{} ({})", prompt, local_now().format("%Z")), kind)
+            .await?;
+        Ok(answer.map(Answer::parse))
+    }
+
+    /// Shows `content` with a select menu of `kind` and a button to keep the current
+    /// value, then waits for either.
+    async fn ask(
+        &mut self,
+        content: String,
+        kind: CreateSelectMenuKind,
+    ) -> Result<Option<Answer<String>>, Error> {
+        let ctx = self.ctx;
+        let select_id = format!("setup:select:{}", ctx.id());
+        let keep_id = format!("setup:keep:{}", ctx.id());
+        let components = vec![
+            CreateActionRow::SelectMenu(CreateSelectMenu::new(&select_id, kind)),
+            CreateActionRow::Buttons(vec![CreateButton::new(&keep_id)
+                .label("Keep current")
+                .style(ButtonStyle::Secondary)]),
+        ];
+        match self.interaction.take() {
+            Some(interaction) => {
+                let response = CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(components);
+                interaction
+                    .create_response(
+                        ctx.http(),
+                        CreateInteractionResponse::UpdateMessage(response),
+                    )
+                    .await?;
+            }
+            None => {
+                let edit = serenity::all::EditMessage::new()
+                    .content(content)
+                    .components(components);
+                self.message.edit(ctx.http(), edit).await?;
+            }
+        }
+
+        let Some(interaction) = self
+            .message
+            .await_component_interaction(ctx.serenity_context().shard.clone())
+            .author_id(ctx.author().id)
+            .custom_ids(vec![select_id, keep_id])
+            .timeout(SETUP_TIMEOUT)
+            .await
+        else {
+            ctx.say("Setup timed out, nothing was saved.").await?;
+            return Ok(None);
+        };
+        let answer = match &interaction.data.kind {
+            ComponentInteractionDataKind::ChannelSelect { values } => values
+                .first()
+                .map_or(Answer::Keep, |id| Answer::Picked(id.to_string())),
+            ComponentInteractionDataKind::StringSelect { values } => values
+                .first()
+                .map_or(Answer::Keep, |value| Answer::Picked(value.clone())),
+            _ => Answer::Keep,
+        };
+        self.interaction = Some(interaction);
+        Ok(Some(answer))
+    }
+}
+
+fn message_label(message: &Message) -> String {
+    let text = match message
+        .embeds
+        .first()
+        .and_then(|embed| embed.title.as_ref())
+    {
+        Some(title) => format!("Embed: {}", title),
+        None if message.content.is_empty() => format!("Message {}", message.id),
+        None => message.content.clone(),
+    };
+    // Labels are limited to 100 characters.
+    format!("{}: {}", message.author.name, text)
+        .chars()
+        .take(100)
+        .collect()
+}
@@ -0,0 +1,79 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use tracing::{info, trace};
+
+use crate::{
+    points::{buy_shield, wallet, Purchase, Wallet},
+    Context, Error,
+};
+
+/// Shows your activity points and streak shields.
+#[poise::command(prefix_command, subcommands("buy"))]
+pub async fn shield(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running shield command");
+    let wallet = wallet(&ctx.data().storage, ctx.author().id.get()).await?;
+    let shield_cost = ctx.data().config.read().await.points.shield_cost;
+    ctx.say(format!(
+        "{}\nA shield costs {} points, buy one with `shield buy`.",
+        describe(wallet),
+        shield_cost
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Spends points on a shield that saves your streak the next time you miss an update.
+#[poise::command(prefix_command)]
+pub async fn buy(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running shield buy command");
+    let config = ctx.data().config.read().await.points.clone();
+    let purchase = buy_shield(
+        &ctx.data().storage,
+        ctx.author().id.get(),
+        config.shield_cost,
+        config.max_shields,
+    )
+    .await?;
+
+    let reply = match purchase {
+        Purchase::Bought(wallet) => {
+            info!("{} bought a streak shield", ctx.author().name);
+            format!(
+                "Shield bought, it will be used the next time you miss an update.\n{}",
+                describe(wallet)
+            )
+        }
+        Purchase::NotEnoughPoints(wallet) => format!(
+            "A shield costs {} points, you have {}.",
+            config.shield_cost, wallet.points
+        ),
+        Purchase::AtLimit(wallet) => format!(
+            "You already hold {} shields, the most allowed.",
+            wallet.shields
+        ),
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+fn describe(wallet: Wallet) -> String {
+    format!(
+        "You have {} points and {} streak shields.",
+        wallet.points, wallet.shields
+    )
+}
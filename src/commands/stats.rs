@@ -0,0 +1,97 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::CreateEmbed;
+use tracing::trace;
+
+use crate::{
+    metrics::{cache_summaries, command_error_summaries, endpoint_summaries},
+    Context, Error,
+};
+
+#[poise::command(prefix_command, owners_only, subcommands("bot"))]
+pub async fn stats(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Usage: `stats bot`").await?;
+    Ok(())
+}
+
+/// Room left in the embed's description for endpoints, under Discord's 4096.
+const ENDPOINTS_LENGTH: usize = 4000;
+
+/// Shows latency percentiles and error rates per endpoint and command, cache hit ratios
+/// and failed commands by cause, since the bot started.
+#[poise::command(prefix_command, owners_only)]
+pub async fn bot(ctx: Context<'_>) -> Result<(), Error> {
+    trace!("Running stats bot command");
+    let summaries = endpoint_summaries();
+    let mut endpoints = String::new();
+    for (listed, summary) in summaries.iter().enumerate() {
+        let errors = match summary.errors {
+            Some(errors) => format!(
+                "{:.1}% errors",
+                errors as f64 / summary.calls as f64 * 100.0
+            ),
+            None => "errors not tracked".to_string(),
+        };
+        let line = format!(
+            "`{}`: {} calls, {}, p50 {}ms, p95 {}ms\n",
+            summary.endpoint,
+            summary.calls,
+            errors,
+            summary.p50.as_millis(),
+            summary.p95.as_millis()
+        );
+        if endpoints.len() + line.len() > ENDPOINTS_LENGTH {
+            endpoints.push_str(&format!("…and {} more\n", summaries.len() - listed));
+            break;
+        }
+        endpoints.push_str(&line);
+    }
+    if endpoints.is_empty() {
+        endpoints.push_str("Nothing recorded yet.");
+    }
+
+    let mut caches = String::new();
+    for (cache, hits, misses) in cache_summaries() {
+        caches.push_str(&format!(
+            "`{}`: {:.1}% hits ({} of {})\n",
+            cache,
+            hits as f64 / (hits + misses) as f64 * 100.0,
+            hits,
+            hits + misses
+        ));
+    }
+    if caches.is_empty() {
+        caches.push_str("Nothing recorded yet.");
+    }
+
+    let mut errors = String::new();
+    for (category, count) in command_error_summaries() {
+        errors.push_str(&format!("`{}`: {}\n", category, count));
+    }
+    if errors.is_empty() {
+        errors.push_str("No failed commands.");
+    }
+
+    let embed = CreateEmbed::new()
+        .title("Bot Stats")
+        .description(endpoints)
+        .field("Caches", caches, false)
+        .field("Command Errors", errors, false);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
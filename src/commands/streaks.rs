@@ -0,0 +1,169 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::NaiveDate;
+use poise::CreateReply;
+use serde::Serialize;
+use serenity::all::{CreateAttachment, CreateEmbed, User};
+use tracing::{trace, warn};
+
+use crate::{
+    charts::render_line_chart,
+    graphql::queries::fetch_members,
+    history::recent_status_update_days,
+    utils::export::{attach_json, wants_json},
+    Context, Error,
+};
+
+const CHART_DAYS: usize = 30;
+const LEADERBOARD_SIZE: usize = 10;
+
+/// Shows a member's streaks along with a chart of the last month.
+#[poise::command(prefix_command)]
+pub async fn profile(ctx: Context<'_>, user: Option<User>) -> Result<(), Error> {
+    trace!("Running profile command");
+    let user = user.unwrap_or_else(|| ctx.author().clone());
+    let discord_id = user.id.to_string();
+
+    let members = fetch_members().await?;
+    let Some(member) = members.iter().find(|m| m.discord_id == discord_id) else {
+        ctx.say(format!("{} is not linked to a member on Root.", user.name))
+            .await?;
+        return Ok(());
+    };
+
+    let (current_streak, max_streak) = member
+        .streak
+        .first()
+        .map(|streak| (streak.current_streak, streak.max_streak))
+        .unwrap_or_default();
+    let embed = CreateEmbed::new().title(&member.name).description(format!(
+        "- Group: {}\n- Current Streak: {} days\n- Max Streak: {} days",
+        member.group_id, current_streak, max_streak
+    ));
+
+    let days = recent_status_update_days(&ctx.data().storage, CHART_DAYS).await?;
+    let points: Vec<_> = days
+        .iter()
+        .filter_map(|day| {
+            day.member(&discord_id)
+                .map(|result| (day.date, result.current_streak as f64))
+        })
+        .collect();
+    let title = format!("Streak of {}", member.name);
+    ctx.send(with_chart(embed, &title, &points)).await?;
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct LeaderboardExport {
+    /// Every member, not just the ones the embed has room for.
+    members: Vec<LeaderboardEntry>,
+    average_streak: Vec<DailyAverage>,
+}
+
+#[derive(Serialize)]
+struct LeaderboardEntry {
+    rank: usize,
+    name: String,
+    discord_id: String,
+    current_streak: i32,
+    max_streak: i32,
+}
+
+#[derive(Serialize)]
+struct DailyAverage {
+    date: NaiveDate,
+    average: f64,
+}
+
+/// Shows the members with the highest current streaks and the club's average streak over time.
+/// `--json` attaches the whole leaderboard as JSON.
+#[poise::command(prefix_command)]
+pub async fn leaderboard(ctx: Context<'_>, option: Option<String>) -> Result<(), Error> {
+    trace!("Running leaderboard command");
+    let json = wants_json(option.as_deref())?;
+    let mut members = fetch_members().await?;
+    members.sort_by_key(|m| {
+        std::cmp::Reverse(m.streak.first().map(|s| s.current_streak).unwrap_or(0))
+    });
+
+    let mut description = String::new();
+    for (rank, member) in members.iter().take(LEADERBOARD_SIZE).enumerate() {
+        let streak = member.streak.first().map(|s| s.current_streak).unwrap_or(0);
+        description.push_str(&format!(
+            "{}. {} - {} days\n",
+            rank + 1,
+            member.name,
+            streak
+        ));
+    }
+    let embed = CreateEmbed::new()
+        .title("Streak Leaderboard")
+        .description(description);
+
+    let days = recent_status_update_days(&ctx.data().storage, CHART_DAYS).await?;
+    let points: Vec<_> = days
+        .iter()
+        .map(|day| (day.date, day.average_streak()))
+        .collect();
+    let mut reply = with_chart(embed, "Average Club Streak", &points);
+    if json {
+        let export = LeaderboardExport {
+            members: members
+                .iter()
+                .enumerate()
+                .map(|(rank, member)| {
+                    let (current_streak, max_streak) = member
+                        .streak
+                        .first()
+                        .map(|streak| (streak.current_streak, streak.max_streak))
+                        .unwrap_or_default();
+                    LeaderboardEntry {
+                        rank: rank + 1,
+                        name: member.name.clone(),
+                        discord_id: member.discord_id.clone(),
+                        current_streak,
+                        max_streak,
+                    }
+                })
+                .collect(),
+            average_streak: points
+                .iter()
+                .map(|&(date, average)| DailyAverage { date, average })
+                .collect(),
+        };
+        reply = attach_json(reply, "leaderboard", &export)?;
+    }
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
+fn with_chart(embed: CreateEmbed, title: &str, points: &[(chrono::NaiveDate, f64)]) -> CreateReply {
+    const FILENAME: &str = "streak.png";
+    match render_line_chart(title, "Days", points) {
+        Ok(png) => CreateReply::default()
+            .embed(embed.image(format!("attachment://{}", FILENAME)))
+            .attachment(CreateAttachment::bytes(png, FILENAME)),
+        Err(e) => {
+            warn!("Sending reply without chart: {:?}", e);
+            CreateReply::default().embed(embed)
+        }
+    }
+}
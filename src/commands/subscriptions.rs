@@ -0,0 +1,298 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ChannelId, CreateAllowedMentions, CreateMessage, EditRole, GuildId, Member, RoleId,
+};
+use tracing::{info, trace, warn};
+
+use crate::{storage::Storage, Context, Data, Error};
+
+/// Topics of every guild, keyed by guild ID and then topic name.
+const SUBSCRIPTIONS_KEY: &str = "subscriptions.topics";
+
+/// A notification role created by the bot. The role is deleted once its last
+/// subscriber leaves, subscribers are tracked here so that doesn't need the
+/// privileged members intent.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct Topic {
+    role_id: u64,
+    subscribers: Vec<u64>,
+}
+
+type GuildTopics = HashMap<u64, HashMap<String, Topic>>;
+
+/// Subscribes you to pings for `topic`, e.g. `ctf-pings`. Without a topic, lists the
+/// existing ones. Topics that don't exist yet can be started if they're configured, or by
+/// members with Manage Roles.
+#[poise::command(prefix_command, guild_only)]
+pub async fn subscribe(ctx: Context<'_>, topic: Option<String>) -> Result<(), Error> {
+    trace!("Running subscribe command");
+    let guild_id = ctx.guild_id().expect("Command is guild only");
+    let Some(topic) = topic else {
+        ctx.say(list_topics(ctx.data(), guild_id).await?).await?;
+        return Ok(());
+    };
+    let Some(topic) = normalize_topic(&topic) else {
+        ctx.say("Topics can only contain letters, digits and dashes, up to 32 characters.")
+            .await?;
+        return Ok(());
+    };
+
+    let existing = topic_role(ctx.data(), guild_id, &topic).await?;
+    let role_id = match existing {
+        Some(role_id) => role_id,
+        None => {
+            let config = ctx.data().config.read().await.subscriptions.clone();
+            let author = ctx
+                .author_member()
+                .await
+                .ok_or_else(|| anyhow!("Failed to look up the invoking member"))?
+                .into_owned();
+            let configured = config
+                .topics
+                .iter()
+                .any(|t| normalize_topic(t).as_ref() == Some(&topic));
+            if !configured && !can_manage_roles(ctx, &author) {
+                ctx.say(format!(
+                    "There is no topic called `{}`, ask someone with Manage Roles to start it.",
+                    topic
+                ))
+                .await?;
+                return Ok(());
+            }
+            if topic_count(ctx.data(), guild_id).await? >= config.max_topics {
+                ctx.say(format!(
+                    "This server already has {} topics, the most allowed.",
+                    config.max_topics
+                ))
+                .await?;
+                return Ok(());
+            }
+            // Not mentionable, pings go through `notify` so they're authorized and logged.
+            let role = guild_id
+                .create_role(ctx.http(), EditRole::new().name(&topic))
+                .await?;
+            info!("Created role for topic {} in guild {}", topic, guild_id);
+            role.id
+        }
+    };
+
+    ctx.http()
+        .add_member_role(
+            guild_id,
+            ctx.author().id,
+            role_id,
+            Some("Subscribed to topic"),
+        )
+        .await?;
+    let user_id = ctx.author().id.get();
+    ctx.data()
+        .storage
+        .update(SUBSCRIPTIONS_KEY, |topics: &mut GuildTopics| {
+            let entry = topics
+                .entry(guild_id.get())
+                .or_default()
+                .entry(topic.clone())
+                .or_insert_with(|| Topic {
+                    role_id: role_id.get(),
+                    subscribers: Vec::new(),
+                });
+            if !entry.subscribers.contains(&user_id) {
+                entry.subscribers.push(user_id);
+            }
+        })
+        .await?;
+
+    ctx.say(format!("Subscribed to `{}`.", topic)).await?;
+    Ok(())
+}
+
+/// Unsubscribes you from `topic`. The topic's role is removed once nobody is subscribed.
+#[poise::command(prefix_command, guild_only)]
+pub async fn unsubscribe(ctx: Context<'_>, topic: String) -> Result<(), Error> {
+    trace!("Running unsubscribe command");
+    let guild_id = ctx.guild_id().expect("Command is guild only");
+    let topic = normalize_topic(&topic).unwrap_or(topic);
+    let Some(role_id) = topic_role(ctx.data(), guild_id, &topic).await? else {
+        ctx.say(format!("There is no topic called `{}`.", topic))
+            .await?;
+        return Ok(());
+    };
+
+    ctx.http()
+        .remove_member_role(
+            guild_id,
+            ctx.author().id,
+            role_id,
+            Some("Unsubscribed from topic"),
+        )
+        .await?;
+    let user_id = ctx.author().id.get();
+    let now_empty = ctx
+        .data()
+        .storage
+        .update(SUBSCRIPTIONS_KEY, |topics: &mut GuildTopics| {
+            let guild_topics = topics.entry(guild_id.get()).or_default();
+            let Some(entry) = guild_topics.get_mut(&topic) else {
+                return false;
+            };
+            entry.subscribers.retain(|id| *id != user_id);
+            if entry.subscribers.is_empty() {
+                guild_topics.remove(&topic);
+                return true;
+            }
+            false
+        })
+        .await?;
+
+    if now_empty {
+        if let Err(e) = guild_id.delete_role(ctx.http(), role_id).await {
+            warn!("Failed to delete role for topic {}: {}", topic, e);
+        } else {
+            info!(
+                "Deleted role for empty topic {} in guild {}",
+                topic, guild_id
+            );
+        }
+    }
+
+    ctx.say(format!("Unsubscribed from `{}`.", topic)).await?;
+    Ok(())
+}
+
+/// Pings everyone subscribed to `topic` with `message`. Every use is logged to the
+/// ops channel. Topic roles aren't mentionable, the bot needs Mention Everyone to ping them.
+#[poise::command(
+    prefix_command,
+    guild_only,
+    required_permissions = "MENTION_EVERYONE",
+    required_bot_permissions = "MENTION_EVERYONE"
+)]
+pub async fn notify(ctx: Context<'_>, topic: String, #[rest] message: String) -> Result<(), Error> {
+    trace!("Running notify command");
+    let guild_id = ctx.guild_id().expect("Command is guild only");
+    let topic = normalize_topic(&topic).unwrap_or(topic);
+    let Some(role_id) = topic_role(ctx.data(), guild_id, &topic).await? else {
+        ctx.say(format!("There is no topic called `{}`.", topic))
+            .await?;
+        return Ok(());
+    };
+
+    let ping = CreateMessage::new()
+        .content(format!("<@&{}> {}", role_id, message))
+        .allowed_mentions(CreateAllowedMentions::new().roles(vec![role_id]));
+    ctx.channel_id().send_message(ctx.http(), ping).await?;
+
+    info!(
+        "{} notified topic {} in channel {}",
+        ctx.author().name,
+        topic,
+        ctx.channel_id()
+    );
+    let ops_channel_id = ctx.data().config.read().await.bot.ops_channel_id;
+    if let Some(ops_channel_id) = ops_channel_id {
+        let log = CreateMessage::new()
+            .content(format!(
+                "{} notified `{}` in <#{}>.",
+                ctx.author().name,
+                topic,
+                ctx.channel_id()
+            ))
+            .allowed_mentions(CreateAllowedMentions::new());
+        if let Err(e) = ChannelId::new(ops_channel_id)
+            .send_message(ctx.http(), log)
+            .await
+        {
+            warn!("Failed to log notify usage: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Drops the member from every topic's subscriber list. Their topic roles are left as they
+/// are, only the stored subscriptions are forgotten.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(SUBSCRIPTIONS_KEY, |topics: &mut GuildTopics| {
+            for topic in topics.values_mut().flat_map(|t| t.values_mut()) {
+                topic.subscribers.retain(|id| *id != user_id);
+            }
+        })
+        .await
+}
+
+async fn topic_role(data: &Data, guild_id: GuildId, topic: &str) -> anyhow::Result<Option<RoleId>> {
+    let topics: GuildTopics = data.storage.get(SUBSCRIPTIONS_KEY).await?;
+    Ok(topics
+        .get(&guild_id.get())
+        .and_then(|guild_topics| guild_topics.get(topic))
+        .map(|topic| RoleId::new(topic.role_id)))
+}
+
+async fn topic_count(data: &Data, guild_id: GuildId) -> anyhow::Result<usize> {
+    let topics: GuildTopics = data.storage.get(SUBSCRIPTIONS_KEY).await?;
+    Ok(topics.get(&guild_id.get()).map_or(0, |t| t.len()))
+}
+
+/// Whether `member` has Manage Roles in the channel the command was used in, or its
+/// parent for a thread.
+fn can_manage_roles(ctx: Context<'_>, member: &Member) -> bool {
+    let Some(guild) = ctx.guild() else {
+        return false;
+    };
+    let channel_id = ctx.channel_id();
+    let channel = guild.channels.get(&channel_id).or_else(|| {
+        let thread = guild.threads.iter().find(|t| t.id == channel_id)?;
+        guild.channels.get(&thread.parent_id?)
+    });
+    channel.is_some_and(|channel| guild.user_permissions_in(channel, member).manage_roles())
+}
+
+async fn list_topics(data: &Data, guild_id: GuildId) -> anyhow::Result<String> {
+    let topics: GuildTopics = data.storage.get(SUBSCRIPTIONS_KEY).await?;
+    let Some(guild_topics) = topics.get(&guild_id.get()).filter(|t| !t.is_empty()) else {
+        return Ok(String::from(
+            "There are no topics yet, `subscribe <topic>` creates one.",
+        ));
+    };
+
+    let mut names: Vec<_> = guild_topics.iter().collect();
+    names.sort_by_key(|(name, _)| name.as_str());
+    let mut reply = String::from("Topics you can subscribe to:\n");
+    for (name, topic) in names {
+        reply.push_str(&format!(
+            "- `{}` ({} subscribers)\n",
+            name,
+            topic.subscribers.len()
+        ));
+    }
+    Ok(reply)
+}
+
+fn normalize_topic(topic: &str) -> Option<String> {
+    let topic = topic.trim().to_lowercase();
+    let is_valid = !topic.is_empty()
+        && topic.len() <= 32
+        && topic.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    is_valid.then_some(topic)
+}
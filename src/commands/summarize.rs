@@ -0,0 +1,52 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::GetMessages;
+use tracing::trace;
+
+use crate::{tasks::summaries::summarize_messages, utils::long_message::say_long, Context, Error};
+
+const DEFAULT_MESSAGE_COUNT: u8 = 50;
+
+/// Summarizes the last `count` (up to 100) messages of this thread or channel.
+#[poise::command(prefix_command, guild_only)]
+pub async fn summarize(ctx: Context<'_>, count: Option<u8>) -> Result<(), Error> {
+    trace!("Running summarize command");
+    let config = ctx.data().config.read().await.llm.clone();
+    if config.endpoint.is_none() {
+        ctx.say("Summaries aren't set up on this deployment.")
+            .await?;
+        return Ok(());
+    }
+
+    let count = count.unwrap_or(DEFAULT_MESSAGE_COUNT).min(100);
+    ctx.defer_or_broadcast().await?;
+    let messages: Vec<_> = ctx
+        .channel_id()
+        .messages(ctx.http(), GetMessages::new().before(ctx.id()).limit(count))
+        .await?
+        .into_iter()
+        .filter(|m| !m.author.bot)
+        .collect();
+    if messages.is_empty() {
+        ctx.say("There's nothing to summarize yet.").await?;
+        return Ok(());
+    }
+
+    let summary = summarize_messages(&config, messages).await?;
+    say_long(ctx, &summary, "summary.md").await
+}
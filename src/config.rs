@@ -0,0 +1,1048 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    collections::BTreeMap,
+    num::{NonZeroU32, NonZeroU64},
+    path::Path,
+};
+
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use chrono_tz::Tz;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use serenity::all::GatewayIntents;
+use tracing::info;
+
+use crate::{
+    error::ConfigError,
+    ids::{ChannelKind, RoleKind},
+    templates,
+    utils::time::DailyWindow,
+};
+
+/// Deployment configuration loaded from a TOML file (`CONFIG_PATH`, defaults to `config.toml`).
+///
+/// Every section has defaults matching the bot's built-in behaviour, so the file
+/// is optional and only needs to contain the values that should be changed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub bot: BotConfig,
+    pub ids: IdsConfig,
+    pub theme: ThemeConfig,
+    pub templates: TemplatesConfig,
+    pub status_update: StatusUpdateConfig,
+    pub feeds: FeedsConfig,
+    pub resources: ResourcesConfig,
+    pub practice: PracticeConfig,
+    pub groups: GroupsConfig,
+    pub llm: LlmConfig,
+    pub paste: PasteConfig,
+    pub scheduler: SchedulerConfig,
+    pub sessions: SessionsConfig,
+    pub backup: BackupConfig,
+    pub attendance: AttendanceConfig,
+    pub onboarding: OnboardingConfig,
+    pub reports: ReportsConfig,
+    pub events: EventsConfig,
+    pub quiet_hours: QuietHoursConfig,
+    pub invites: InvitesConfig,
+    pub roles: RolesConfig,
+    pub channel_locks: Vec<ChannelLockConfig>,
+    pub watchers: Vec<WatcherConfig>,
+    pub retention: Vec<RetentionConfig>,
+    pub on_call: OnCallConfig,
+    pub points: PointsConfig,
+    pub xp: XpConfig,
+    pub kudos: KudosConfig,
+    pub inventory: InventoryConfig,
+    pub channel_topics: ChannelTopicsConfig,
+    pub semester: SemesterConfig,
+    pub weekend: WeekendConfig,
+    pub holidays: HolidaysConfig,
+    pub spotlight: SpotlightConfig,
+    pub subscriptions: SubscriptionsConfig,
+    pub features: FeaturesConfig,
+    pub deployment: DeploymentConfig,
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Self> {
+        let path = std::env::var("CONFIG_PATH").unwrap_or_else(|_| String::from("config.toml"));
+        Self::load_from(Path::new(&path))
+    }
+
+    fn load_from(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            info!("No config found at {}, using defaults", path.display());
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| ConfigError(format!("Failed to read config {}", path.display())))?;
+        let config: Self = toml::from_str(&contents)
+            .with_context(|| ConfigError(format!("Failed to parse {}", path.display())))?;
+        if config.bot.prefix.trim().is_empty() {
+            return Err(anyhow::Error::msg(ConfigError(format!(
+                "bot.prefix in {} can't be empty",
+                path.display()
+            ))));
+        }
+        config
+            .templates
+            .check()
+            .with_context(|| ConfigError(format!("Invalid templates in {}", path.display())))?;
+        Ok(config)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// Every run is delayed by a random amount up to this many seconds, so tasks sharing
+    /// a slot don't all hit Root at the same moment. 0 disables jitter.
+    pub max_jitter_seconds: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BotConfig {
+    /// Default command prefix, servers can override it with `$prefix set`.
+    pub prefix: String,
+    /// Channel for operational messages meant for mentors, such as report previews.
+    pub ops_channel_id: Option<u64>,
+    /// Alert the ops channel when a shard stays disconnected for this many minutes.
+    pub shard_alert_minutes: u64,
+    /// Timezone days, schedules and plain-text times follow. Times in messages use Discord
+    /// timestamps, which every reader sees in their own timezone.
+    pub timezone: Tz,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            prefix: String::from("$"),
+            ops_channel_id: None,
+            shard_alert_minutes: 5,
+            timezone: chrono_tz::Asia::Kolkata,
+        }
+    }
+}
+
+/// Overrides of the built-in amFOSS channel and role IDs, for running the bot on another
+/// server. Channels and the roles message picked with `$setup` take precedence.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IdsConfig {
+    pub channels: BTreeMap<ChannelKind, NonZeroU64>,
+    pub roles: BTreeMap<RoleKind, NonZeroU64>,
+    pub roles_message_id: Option<NonZeroU64>,
+}
+
+/// Where the nightly reports are posted and in how much detail. A report without any
+/// deliveries is posted in full to its usual channel.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReportsConfig {
+    pub deliveries: Vec<ReportDelivery>,
+    /// Push each night's summary counts to Root, so the website can show club stats.
+    /// Needs a Root version with the summary mutations.
+    pub push_summaries: bool,
+    /// Open a discussion thread under each night's pinned report, and archive the previous
+    /// night's, so explanations and appeals don't clutter the report channel.
+    pub discussion_threads: bool,
+}
+
+impl ReportsConfig {
+    pub fn deliveries_for(
+        &self,
+        report: ReportKind,
+        default_channel_id: u64,
+    ) -> Vec<ReportDelivery> {
+        let deliveries: Vec<ReportDelivery> = self
+            .deliveries
+            .iter()
+            .filter(|delivery| delivery.report == report)
+            .cloned()
+            .collect();
+        if deliveries.is_empty() {
+            return vec![ReportDelivery {
+                report,
+                channel_id: default_channel_id,
+                detail: ReportDetail::Full,
+                group: None,
+                recognition: false,
+            }];
+        }
+        deliveries
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReportDelivery {
+    pub report: ReportKind,
+    pub channel_id: u64,
+    #[serde(default)]
+    pub detail: ReportDetail,
+    /// The group whose excerpt is posted when `detail` is [`ReportDetail::Group`].
+    pub group: Option<u64>,
+    /// Also recognize the members who did send their update, so the report isn't only
+    /// a list of defaulters. Applies to the full and group status update reports.
+    #[serde(default)]
+    pub recognition: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    StatusUpdate,
+    Attendance,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportDetail {
+    /// The whole report, with names.
+    #[default]
+    Full,
+    /// Anonymized numbers only, for public channels.
+    Stats,
+    /// Only the part concerning one group, for that group's channel.
+    Group,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StatusUpdateConfig {
+    /// When each day's window for updates opens, in the bot's timezone. Updates sent
+    /// before the latest opening don't count for the next check.
+    pub window_opens_at: NaiveTime,
+    /// Minutes after the deadline during which updates still count. When non-zero, the
+    /// check re-scans the channels once after this period before resetting any streaks.
+    pub grace_period_minutes: u64,
+    pub group_mentors: Vec<GroupMentors>,
+    /// When set, streak resets are posted here for mentors to approve or deny before
+    /// they are applied.
+    pub reset_approval_channel_id: Option<u64>,
+    /// Resets are approved automatically if nobody responds in time.
+    pub reset_approval_timeout_minutes: u64,
+    /// Streak lengths called out in the report when a member reaches them.
+    pub streak_milestones: Vec<i32>,
+    /// Updates at least this similar (0 to 1) to the member's previous one are flagged
+    /// as suspected copy-pastes. 0 disables the check.
+    pub duplicate_threshold: f64,
+    /// Where defaulters' appeals are posted for mentors to approve or reject. Falls back
+    /// to the ops channel.
+    pub appeal_channel_id: Option<u64>,
+    /// When set, reports only show how many members of each group missed their update,
+    /// and the full report naming them is posted to this mentors-only channel instead.
+    pub private_defaulters_channel_id: Option<u64>,
+}
+
+impl Default for StatusUpdateConfig {
+    fn default() -> Self {
+        Self {
+            window_opens_at: NaiveTime::from_hms_opt(20, 0, 0).expect("Valid time"),
+            grace_period_minutes: 0,
+            group_mentors: Vec::new(),
+            reset_approval_channel_id: None,
+            reset_approval_timeout_minutes: 60,
+            streak_milestones: vec![7, 30, 100],
+            duplicate_threshold: 0.85,
+            appeal_channel_id: None,
+            private_defaulters_channel_id: None,
+        }
+    }
+}
+
+impl StatusUpdateConfig {
+    pub fn window(&self, timezone: Tz) -> DailyWindow {
+        DailyWindow {
+            opens_at: self.window_opens_at,
+            timezone,
+        }
+    }
+
+    /// Whether a report posted in `channel_id` may name the defaulters.
+    pub fn names_defaulters_in(&self, channel_id: u64) -> bool {
+        self.private_defaulters_channel_id
+            .is_none_or(|private| private == channel_id)
+    }
+
+    pub fn mentors_for(&self, group: u64) -> Option<&GroupMentors> {
+        self.group_mentors
+            .iter()
+            .find(|mentors| mentors.group == group)
+    }
+}
+
+/// Mentors responsible for a group, mentioned in that group's section of the defaulters
+/// report. Users listed here also get a DM summarizing their group's defaulters.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GroupMentors {
+    pub group: u64,
+    pub user_ids: Vec<u64>,
+    pub role_ids: Vec<u64>,
+}
+
+impl GroupMentors {
+    pub fn mentions(&self) -> String {
+        self.user_ids
+            .iter()
+            .map(|id| format!("<@{}>", id))
+            .chain(self.role_ids.iter().map(|id| format!("<@&{}>", id)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// RSS/Atom feeds whose new articles are announced in `channel_id`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FeedsConfig {
+    pub channel_id: Option<u64>,
+    pub urls: Vec<String>,
+}
+
+/// Channel where members are expected to share one learning resource per week.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResourcesConfig {
+    pub channel_id: Option<u64>,
+}
+
+/// Daily problem posted to `channel_id`. Problems rotate through `problems`, or are
+/// picked from Codeforces within the rating range when the pool is empty.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PracticeConfig {
+    pub channel_id: Option<u64>,
+    pub problems: Vec<PracticeProblem>,
+    pub codeforces_min_rating: u64,
+    pub codeforces_max_rating: u64,
+}
+
+impl Default for PracticeConfig {
+    fn default() -> Self {
+        Self {
+            channel_id: None,
+            problems: Vec::new(),
+            codeforces_min_rating: 800,
+            codeforces_max_rating: 1400,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PracticeProblem {
+    pub title: String,
+    pub url: String,
+}
+
+/// `presence_source_url` is an optional second source of lab presence that Root's
+/// attendance is cross-checked against. It must return a JSON array of member names seen today.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AttendanceConfig {
+    pub presence_source_url: Option<String>,
+    /// A locked channel renamed to show how many members are in the lab.
+    pub occupancy_channel_id: Option<u64>,
+    /// The lab is only polled for occupancy between these times, in the bot's timezone, and shown as
+    /// closed otherwise.
+    pub lab_opens_at: NaiveTime,
+    pub lab_closes_at: NaiveTime,
+    /// Role handed to the `regular_count` members with the best attendance each month.
+    pub regular_role_id: Option<u64>,
+    pub regular_count: usize,
+}
+
+impl Default for AttendanceConfig {
+    fn default() -> Self {
+        Self {
+            presence_source_url: None,
+            occupancy_channel_id: None,
+            lab_opens_at: NaiveTime::from_hms_opt(8, 0, 0).expect("Valid time"),
+            lab_closes_at: NaiveTime::from_hms_opt(22, 0, 0).expect("Valid time"),
+            regular_role_id: None,
+            regular_count: 3,
+        }
+    }
+}
+
+/// Role granted to newcomers who pass the onboarding quiz, it should unlock the
+/// member-only channels.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OnboardingConfig {
+    pub member_role_id: Option<u64>,
+    /// Share of questions that must be answered correctly to pass.
+    pub pass_percentage: u8,
+}
+
+impl Default for OnboardingConfig {
+    fn default() -> Self {
+        Self {
+            member_role_id: None,
+            pass_percentage: 100,
+        }
+    }
+}
+
+/// Recurring lab hours and meetings, published as Discord scheduled events in `guild_id`.
+/// Reminders with the number of interested members go to `reminder_channel_id`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    pub guild_id: Option<u64>,
+    pub reminder_channel_id: Option<u64>,
+    pub reminder_minutes: i64,
+    pub recurring: Vec<RecurringEvent>,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            guild_id: None,
+            reminder_channel_id: None,
+            reminder_minutes: 30,
+            recurring: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RecurringEvent {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub weekdays: Vec<Weekday>,
+    /// Start time in the bot's timezone.
+    pub start: NaiveTime,
+    pub duration_minutes: i64,
+    /// Where the event takes place, unless it's held in the voice channel `channel_id`.
+    #[serde(default)]
+    pub location: String,
+    pub channel_id: Option<u64>,
+}
+
+/// Between `start` and `end` (in the bot's timezone, may wrap past midnight) non-urgent messages such as
+/// digests, nudges and feed posts are held back and sent in one batch afterwards.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct QuietHoursConfig {
+    pub start: Option<NaiveTime>,
+    pub end: Option<NaiveTime>,
+}
+
+impl QuietHoursConfig {
+    pub fn is_quiet(&self, time: NaiveTime) -> bool {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) if start <= end => start <= time && time < end,
+            (Some(start), Some(end)) => time >= start || time < end,
+            _ => false,
+        }
+    }
+}
+
+/// Mentors take turns being on call for a week each, in the order of `mentor_ids`,
+/// starting with the week of `rotation_start`. Task failures and other alerts ping
+/// whoever is on call.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OnCallConfig {
+    pub mentor_ids: Vec<u64>,
+    pub rotation_start: Option<NaiveDate>,
+}
+
+/// Members earn activity points for showing up and spend them on streak shields with
+/// `$shield buy`. A shield is used up instead of the streak the next time they miss a
+/// status update.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PointsConfig {
+    /// For each status update that counted.
+    pub per_update: u64,
+    /// For each day marked present in the lab.
+    pub per_attendance: u64,
+    /// For attending or giving a talk.
+    pub per_session: u64,
+    pub shield_cost: u64,
+    /// How many shields a member can hold at once.
+    pub max_shields: u32,
+}
+
+impl Default for PointsConfig {
+    fn default() -> Self {
+        Self {
+            per_update: 10,
+            per_attendance: 5,
+            per_session: 20,
+            shield_cost: 100,
+            max_shields: 1,
+        }
+    }
+}
+
+/// XP accrues from status updates, lab attendance and chatting, and decides a member's
+/// level. Level-ups are announced in `announce_channel_id` and earn the matching entry of
+/// `level_roles`, members keep only the role of their highest level.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct XpConfig {
+    pub per_update: u64,
+    pub per_attendance: u64,
+    pub per_message: u64,
+    /// Messages earn XP at most once per this many seconds.
+    pub message_cooldown_seconds: u64,
+    pub announce_channel_id: Option<u64>,
+    pub level_roles: Vec<LevelRole>,
+}
+
+impl Default for XpConfig {
+    fn default() -> Self {
+        Self {
+            per_update: 25,
+            per_attendance: 15,
+            per_message: 2,
+            message_cooldown_seconds: 60,
+            announce_channel_id: None,
+            level_roles: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LevelRole {
+    pub level: u32,
+    pub role_id: u64,
+}
+
+/// Kudos given with `$kudos` are posted to `channel_id`, which also gets the monthly
+/// tally of the `top_count` most appreciated members.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KudosConfig {
+    pub channel_id: Option<u64>,
+    /// How many kudos a member can give in a day.
+    pub daily_limit: usize,
+    pub top_count: usize,
+}
+
+impl Default for KudosConfig {
+    fn default() -> Self {
+        Self {
+            channel_id: None,
+            daily_limit: 3,
+            top_count: 3,
+        }
+    }
+}
+
+/// Lab hardware members can borrow with `$inventory checkout`. Checkouts of items that
+/// `requires_approval` are sent to `approval_channel_id` (or the ops channel) and only
+/// count once a mentor approves them.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InventoryConfig {
+    pub approval_channel_id: Option<u64>,
+    pub items: Vec<InventoryItem>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InventoryItem {
+    pub name: String,
+    #[serde(default = "default_quantity")]
+    pub quantity: u32,
+    #[serde(default)]
+    pub requires_approval: bool,
+}
+
+fn default_quantity() -> u32 {
+    1
+}
+
+/// Each morning the topics of the status update and lab channels are set to the latest
+/// figures. This needs Manage Channels there.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChannelTopicsConfig {
+    pub enabled: bool,
+}
+
+/// Lighter rules for weekend days, by the day being judged, for status updates the day
+/// their window opened on. On `optional_update_days` members who didn't send an update
+/// keep their streak and aren't notified, on `no_attendance_days` the attendance report
+/// isn't posted.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WeekendConfig {
+    pub optional_update_days: Vec<Weekday>,
+    pub no_attendance_days: Vec<Weekday>,
+}
+
+impl WeekendConfig {
+    pub fn updates_optional(&self, date: NaiveDate) -> bool {
+        self.optional_update_days.contains(&date.weekday())
+    }
+
+    pub fn attendance_skipped(&self, date: NaiveDate) -> bool {
+        self.no_attendance_days.contains(&date.weekday())
+    }
+}
+
+/// Terms during which members are checked. Outside them, tasks checking status updates,
+/// attendance and resources are paused. Without any terms they always run.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SemesterConfig {
+    pub terms: Vec<Term>,
+    /// Where pauses and resumptions are announced. Falls back to the status update channel.
+    pub announce_channel_id: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Term {
+    pub name: String,
+    /// First day the checks run.
+    pub start: NaiveDate,
+    /// Last day the checks run.
+    pub end: NaiveDate,
+}
+
+/// Subsystems a deployment can turn off. Disabled subsystems ignore their events, and
+/// the gateway intents only they need aren't requested. Intents are only computed at
+/// startup, the rest follows `$reload_config`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FeaturesConfig {
+    /// Prefix commands in servers, which need the content of every message. Commands in
+    /// DMs and ones that mention the bot work without it.
+    pub prefix_commands: bool,
+    /// Reading messages for status updates, activity, XP, summaries, resources and
+    /// practice solutions, which needs the Message Content intent.
+    pub message_scanning: bool,
+    pub reaction_roles: bool,
+    /// Invite attribution and restoring roles on rejoin, which need the Server Members
+    /// intent.
+    pub member_tracking: bool,
+    /// Pushing KPIs to METRICS_PUSH_URL.
+    pub webhooks: bool,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            prefix_commands: true,
+            message_scanning: true,
+            reaction_roles: true,
+            member_tracking: true,
+            webhooks: true,
+        }
+    }
+}
+
+impl FeaturesConfig {
+    /// The gateway intents the enabled subsystems need.
+    pub fn intents(&self) -> GatewayIntents {
+        // Commands in DMs, interactions and the channel cache need no more than this.
+        let mut intents = GatewayIntents::GUILDS
+            | GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::DIRECT_MESSAGES;
+        if self.prefix_commands || self.message_scanning {
+            intents |= GatewayIntents::MESSAGE_CONTENT;
+        }
+        if self.reaction_roles || self.message_scanning {
+            // Duplicate update appeals are reactions too.
+            intents |= GatewayIntents::GUILD_MESSAGE_REACTIONS;
+        }
+        if self.member_tracking {
+            intents |= GatewayIntents::GUILD_MEMBERS | GatewayIntents::GUILD_INVITES;
+        }
+        intents
+    }
+}
+
+/// A `canary` instance runs alongside the primary during upgrades. It only dry runs its
+/// tasks while the primary answers the handshake on `handshake_addr`, takes over once the
+/// primary has missed several in a row, and stands by again when it answers. The mode is
+/// only read at startup.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DeploymentConfig {
+    pub mode: DeploymentMode,
+    pub handshake_addr: String,
+    /// How often a canary asks the primary for a handshake.
+    pub handshake_interval_seconds: u64,
+}
+
+impl Default for DeploymentConfig {
+    fn default() -> Self {
+        Self {
+            mode: DeploymentMode::Primary,
+            handshake_addr: String::from("127.0.0.1:7171"),
+            handshake_interval_seconds: 30,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentMode {
+    #[default]
+    Primary,
+    Canary,
+}
+
+/// Posting in `channel_id` is only allowed between `open` and `close` (in the bot's timezone,
+/// may wrap past midnight). The rest of the day `role_id`, or @everyone if unset, can't send messages.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChannelLockConfig {
+    pub channel_id: u64,
+    pub role_id: Option<u64>,
+    pub open: NaiveTime,
+    pub close: NaiveTime,
+}
+
+impl ChannelLockConfig {
+    pub fn is_locked(&self, time: NaiveTime) -> bool {
+        if self.open <= self.close {
+            time < self.open || time >= self.close
+        } else {
+            time < self.open && time >= self.close
+        }
+    }
+}
+
+/// Alerts `alert_channel_id` (or the ops channel), pinging `role_id` if set, when a message
+/// in one of `channel_ids` (every channel if empty) contains one of `keywords`, ignoring
+/// case. A watcher fires at most once per `cooldown_minutes`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WatcherConfig {
+    pub name: String,
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub channel_ids: Vec<u64>,
+    pub alert_channel_id: Option<u64>,
+    pub role_id: Option<u64>,
+    #[serde(default = "default_watcher_cooldown")]
+    pub cooldown_minutes: u64,
+}
+
+fn default_watcher_cooldown() -> u64 {
+    30
+}
+
+impl WatcherConfig {
+    /// The first keyword `content` contains.
+    pub fn matches(&self, content: &str) -> Option<&str> {
+        let content = content.to_lowercase();
+        self.keywords
+            .iter()
+            .find(|keyword| content.contains(&keyword.to_lowercase()))
+            .map(String::as_str)
+    }
+}
+
+/// The bot's own messages in `channel_id` are deleted once they're older than `days`.
+/// Messages from anyone else are never touched.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RetentionConfig {
+    pub channel_id: u64,
+    /// At least a day, so today's reports are never deleted.
+    pub days: NonZeroU32,
+}
+
+/// Public holidays are imported weekly from the iCalendar feed at `ics_url` and posted to
+/// `review_channel_id` (or the ops channel) for an admin to approve. Member checks are
+/// skipped on observed holidays.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HolidaysConfig {
+    pub ics_url: Option<String>,
+    pub review_channel_id: Option<u64>,
+}
+
+/// Members who joined in the past week are DMed for a short intro, and the intros are
+/// posted every Friday in `channel_id`. Joins are only tracked with member tracking on.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SpotlightConfig {
+    pub channel_id: Option<u64>,
+}
+
+/// Topics members can start with `$subscribe` without Manage Roles, anything else has to be
+/// started by someone who has it. No more than `max_topics` topic roles are created per
+/// server.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SubscriptionsConfig {
+    pub topics: Vec<String>,
+    pub max_topics: usize,
+}
+
+impl Default for SubscriptionsConfig {
+    fn default() -> Self {
+        Self {
+            topics: Vec::new(),
+            max_topics: 20,
+        }
+    }
+}
+
+/// Joins are attributed to invites, which needs the Server Members intent and the Manage
+/// Server permission. `report_channel_id` gets a weekly joins-per-invite summary and the
+/// recruitment leaderboard counts joins since `season_start`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InvitesConfig {
+    pub report_channel_id: Option<u64>,
+    pub season_start: Option<NaiveDate>,
+}
+
+/// Roles given back to members who leave and rejoin, such as reaction, group and
+/// verification roles. Roles not listed here are never restored.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RolesConfig {
+    pub restore_on_rejoin: Vec<u64>,
+}
+
+/// Private channel that receives the encrypted nightly backups of the bot's storage.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub channel_id: Option<u64>,
+}
+
+/// Weekly talks: proposals are sent to `approval_channel_id` (or the ops channel) and
+/// approved talks are announced in `channel_id`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SessionsConfig {
+    pub channel_id: Option<u64>,
+    pub approval_channel_id: Option<u64>,
+    /// Attendees are reminded this many minutes before a talk starts.
+    pub reminder_minutes: i64,
+    /// The feedback poll is posted this many minutes after a talk starts.
+    pub duration_minutes: i64,
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self {
+            channel_id: None,
+            approval_channel_id: None,
+            reminder_minutes: 60,
+            duration_minutes: 60,
+        }
+    }
+}
+
+/// Discord roles that correspond to Root groups. Members whose roles drift from their
+/// Root group, or who can't post in their group channel, are reported in
+/// `audit_channel_id` (or the ops channel).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GroupsConfig {
+    pub roles: Vec<GroupRole>,
+    pub audit_channel_id: Option<u64>,
+}
+
+impl GroupsConfig {
+    pub fn role_for(&self, group: i32) -> Option<u64> {
+        self.roles
+            .iter()
+            .find(|role| role.group == group)
+            .map(|role| role.role_id)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GroupRole {
+    pub group: i32,
+    pub role_id: u64,
+}
+
+/// OpenAI-compatible chat completions endpoint used for summaries. LLM features are
+/// disabled while `endpoint` is unset.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LlmConfig {
+    pub endpoint: Option<String>,
+    pub model: String,
+    /// Channels whose daily discussion is summarized every night.
+    pub summarize_channel_ids: Vec<u64>,
+    /// Score every valid status update and DM its author some feedback.
+    pub update_feedback: bool,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            model: String::from("gpt-4o-mini"),
+            summarize_channel_ids: Vec::new(),
+            update_feedback: false,
+        }
+    }
+}
+
+/// Paste service `$paste` uploads code to. The command is disabled while `endpoint` is
+/// unset. With `append_extension`, the language's extension is added to the link, which
+/// is how paste.rs picks the highlighting.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PasteConfig {
+    pub endpoint: Option<String>,
+    pub append_extension: bool,
+    /// Delete the message with the code once it's uploaded, to keep the channel clean.
+    pub delete_original: bool,
+}
+
+impl Default for PasteConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            append_extension: false,
+            delete_original: true,
+        }
+    }
+}
+
+/// Tera templates replacing the built-in report bodies in `src/templates`. Broken
+/// templates are rejected when the config is loaded.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TemplatesConfig {
+    pub status_update: Option<String>,
+    pub status_update_group: Option<String>,
+}
+
+impl TemplatesConfig {
+    fn check(&self) -> anyhow::Result<()> {
+        for (name, template) in [
+            ("status_update", &self.status_update),
+            ("status_update_group", &self.status_update_group),
+        ] {
+            if let Some(template) = template {
+                templates::check(name, template)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub embed: EmbedTheme,
+    pub status_update: StatusUpdateTheme,
+    pub attendance: AttendanceTheme,
+}
+
+/// Parts of the look shared by every report embed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmbedTheme {
+    pub author_name: String,
+    pub author_url: String,
+    pub title_url: String,
+    pub footer: Option<String>,
+}
+
+impl Default for EmbedTheme {
+    fn default() -> Self {
+        Self {
+            author_name: String::from("amD"),
+            author_url: String::from("https://github.com/amfoss/amd"),
+            title_url: String::from("https://www.amfoss.in/"),
+            footer: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StatusUpdateTheme {
+    pub title: String,
+    pub color: u32,
+    pub leaderboard_header: String,
+    pub defaulters_header: String,
+    pub late_updates_header: String,
+    /// Shown next to defaulters who missed a single day.
+    pub missed_once_emoji: String,
+    /// Shown next to defaulters who missed two days in a row.
+    pub missed_twice_emoji: String,
+    /// Shown next to defaulters who missed three or more days in a row.
+    pub streak_lost_emoji: String,
+    /// Shown next to defaulters whose streak shield was used up instead of their streak.
+    pub shield_used_emoji: String,
+    /// Title of the embed recognizing members who sent their update.
+    pub recognition_header: String,
+    pub recognition_color: u32,
+}
+
+impl Default for StatusUpdateTheme {
+    fn default() -> Self {
+        Self {
+            title: String::from("Status Update Report"),
+            color: 0xeab308,
+            leaderboard_header: String::from("Leaderboard Updates"),
+            defaulters_header: String::from("Defaulters"),
+            late_updates_header: String::from("Late Updates"),
+            missed_once_emoji: String::from(":x:"),
+            missed_twice_emoji: String::from(":x::x:"),
+            streak_lost_emoji: String::from(":headstone:"),
+            shield_used_emoji: String::from(":shield:"),
+            recognition_header: String::from("Kept It Up"),
+            recognition_color: 0x22c55e,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AttendanceTheme {
+    pub title: String,
+    pub stats_header: String,
+    /// Used when more than 75% of members are present.
+    pub high_attendance_color: u32,
+    /// Used when more than 50% of members are present.
+    pub medium_attendance_color: u32,
+    pub low_attendance_color: u32,
+    pub lab_closed_color: u32,
+    pub lab_closed_message: String,
+}
+
+impl Default for AttendanceTheme {
+    fn default() -> Self {
+        Self {
+            title: String::from("Presense Report"),
+            stats_header: String::from("Stats"),
+            high_attendance_color: 0x1f8b4c,
+            medium_attendance_color: 0xf1c40f,
+            low_attendance_color: 0xe74c3c,
+            lab_closed_color: 0xe74c3c,
+            lab_closed_message: String::from(
+                "Uh-oh, seems like the lab is closed today! 🏖️ Everyone is absent!",
+            ),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::{DeploymentConfig, DeploymentMode},
+    migrations, Data,
+};
+
+/// How long a canary waits for the primary to answer a handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Consecutive handshakes the primary has to miss before a canary takes over, so a
+/// single slow answer doesn't leave two instances posting reports.
+const MAX_MISSED_HANDSHAKES: u32 = 3;
+
+/// What an instance answers on the handshake address.
+#[derive(Debug, Deserialize, Serialize)]
+struct HandshakeStatus {
+    version: String,
+    /// Process ID of the answering instance, so a canary that took over the handshake
+    /// address recognizes its own answers.
+    #[serde(default)]
+    instance: u32,
+}
+
+/// Whether this instance is the one talking to Discord. The primary always is, a canary
+/// only while the primary isn't answering the handshake.
+pub struct Deployment {
+    active: AtomicBool,
+    /// Whether this instance answers handshakes.
+    serving: AtomicBool,
+}
+
+impl Deployment {
+    pub fn new(config: &DeploymentConfig) -> Self {
+        Self {
+            active: AtomicBool::new(config.mode == DeploymentMode::Primary),
+            serving: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Starts answering handshakes if this is the primary, or starts watching the primary if
+/// this is a canary.
+pub async fn start(data: &Data) {
+    let config = data.config.read().await.deployment.clone();
+    match config.mode {
+        DeploymentMode::Primary => serve_handshake(data, config.handshake_addr).await,
+        DeploymentMode::Canary => {
+            tokio::spawn(watch_primary(data.clone()));
+        }
+    }
+}
+
+/// Whether this instance should run tasks, commands and events right now. A canary
+/// standing by only computes, see [`crate::tasks::Task::dry_run`].
+pub fn is_active(data: &Data) -> bool {
+    data.deployment.active.load(Ordering::Relaxed)
+}
+
+/// Asks the primary for a handshake every `handshake_interval_seconds`. The canary takes
+/// over after [`MAX_MISSED_HANDSHAKES`] misses in a row, and stands by again as soon as
+/// the primary answers.
+async fn watch_primary(data: Data) {
+    let mut missed = 0;
+    loop {
+        let config = data.config.read().await.deployment.clone();
+        match handshake(&config.handshake_addr).await {
+            // This instance took over the handshake address.
+            Ok(status) if status.instance == std::process::id() => {}
+            Ok(status) => {
+                missed = 0;
+                if data.deployment.active.swap(false, Ordering::Relaxed) {
+                    warn!(
+                        "Primary v{} is answering again, canary is standing by",
+                        status.version
+                    );
+                    data.storage.set_read_only(true);
+                } else {
+                    debug!("Primary v{} answered the handshake", status.version);
+                }
+            }
+            Err(e) => {
+                missed += 1;
+                debug!("Primary missed handshake {}: {:?}", missed, e);
+                if missed >= MAX_MISSED_HANDSHAKES && !is_active(&data) {
+                    take_over(&data, &config).await;
+                }
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(config.handshake_interval_seconds)).await;
+    }
+}
+
+/// Makes this canary the active instance. The storage is reloaded first, it holds only
+/// what the primary wrote plus this instance's dry runs until now.
+async fn take_over(data: &Data, config: &DeploymentConfig) {
+    if let Err(e) = data.storage.reload().await {
+        warn!(
+            "Canary failed to reload the storage, not taking over: {:?}",
+            e
+        );
+        return;
+    }
+    data.storage.set_read_only(false);
+    if let Err(e) = migrations::run_migrations(&data.storage).await {
+        warn!(
+            "Canary failed to migrate the storage, not taking over: {:?}",
+            e
+        );
+        data.storage.set_read_only(true);
+        return;
+    }
+
+    warn!("Primary stopped answering the handshake, canary is taking over");
+    data.deployment.active.store(true, Ordering::Relaxed);
+    // The next canary hands over to this instance the same way.
+    serve_handshake(data, config.handshake_addr.clone()).await;
+}
+
+async fn handshake(addr: &str) -> anyhow::Result<HandshakeStatus> {
+    let client = reqwest::Client::builder()
+        .timeout(HANDSHAKE_TIMEOUT)
+        .build()
+        .context("Failed to build handshake client")?;
+    client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .context("Primary did not answer")?
+        .error_for_status()
+        .context("Primary answered with an error")?
+        .json()
+        .await
+        .context("Failed to parse handshake")
+}
+
+/// Answers every request on `addr` with this instance's version until the process exits.
+async fn serve_handshake(data: &Data, addr: String) {
+    if data.deployment.serving.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Failed to listen for handshakes on {}: {}", addr, e);
+            data.deployment.serving.store(false, Ordering::Relaxed);
+            return;
+        }
+    };
+    info!("Answering deployment handshakes on {}", addr);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(async move {
+                        if let Err(e) = answer_handshake(stream).await {
+                            debug!("Failed to answer handshake: {:?}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to accept handshake: {}", e),
+            }
+        }
+    });
+}
+
+async fn answer_handshake(mut stream: TcpStream) -> anyhow::Result<()> {
+    // The request itself doesn't matter, every path gets the status.
+    let mut request = [0; 1024];
+    let _ = stream.read(&mut request).await?;
+
+    let body = serde_json::to_string(&HandshakeStatus {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        instance: std::process::id(),
+    })?;
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
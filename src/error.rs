@@ -0,0 +1,106 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::fmt;
+
+use crate::graphql::breaker::RootUnavailable;
+
+/// The error commands and the framework return, split by where it came from so the
+/// error handler and metrics can tell a Discord outage from a Root outage.
+#[derive(Debug, thiserror::Error)]
+pub enum AmdError {
+    #[error("Discord request failed: {0:#}")]
+    Discord(anyhow::Error),
+    #[error("{0:#}")]
+    Root(anyhow::Error),
+    #[error("{0:#}")]
+    Config(anyhow::Error),
+    #[error("{0:#}")]
+    Storage(anyhow::Error),
+    #[error("{0:#}")]
+    Other(anyhow::Error),
+}
+
+impl AmdError {
+    /// Short name of the variant, used as a metrics label.
+    pub fn category(&self) -> &'static str {
+        match self {
+            AmdError::Discord(_) => "discord",
+            AmdError::Root(_) => "root",
+            AmdError::Config(_) => "config",
+            AmdError::Storage(_) => "storage",
+            AmdError::Other(_) => "other",
+        }
+    }
+}
+
+/// Context marking an error as a failed Root call, added by
+/// [`crate::graphql::breaker::guarded`].
+#[derive(Debug)]
+pub struct RootError(pub String);
+
+impl fmt::Display for RootError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Root call {} failed", self.0)
+    }
+}
+
+/// Context marking an error as a failure to read or write the bot's storage.
+#[derive(Debug)]
+pub struct StorageError(pub String);
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Context marking an error as a broken config.
+#[derive(Debug)]
+pub struct ConfigError(pub String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Sorts an error by the markers in its chain. Discord errors are recognized by type,
+/// everything else by the context its subsystem added.
+impl From<anyhow::Error> for AmdError {
+    fn from(error: anyhow::Error) -> Self {
+        if error.downcast_ref::<RootError>().is_some()
+            || error.downcast_ref::<RootUnavailable>().is_some()
+        {
+            AmdError::Root(error)
+        } else if error.downcast_ref::<StorageError>().is_some() {
+            AmdError::Storage(error)
+        } else if error.downcast_ref::<ConfigError>().is_some() {
+            AmdError::Config(error)
+        } else if error.chain().any(|cause| cause.is::<serenity::Error>()) {
+            AmdError::Discord(error)
+        } else {
+            AmdError::Other(error)
+        }
+    }
+}
+
+impl From<serenity::Error> for AmdError {
+    fn from(error: serenity::Error) -> Self {
+        AmdError::Discord(error.into())
+    }
+}
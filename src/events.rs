@@ -0,0 +1,207 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ChannelId, Context as SerenityContext, CreateMessage, CreateScheduledEvent, EditScheduledEvent,
+    GuildId, ScheduledEventId, ScheduledEventType,
+};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::{
+    config::RecurringEvent,
+    utils::time::{discord_timestamp, timezone, TimestampStyle},
+    Data,
+};
+
+/// Discord events created for the next occurrence of each recurring event, keyed by name.
+const SCHEDULED_EVENTS_KEY: &str = "events.scheduled";
+
+/// Serializes syncs, they run both on a timer and after `$reload_config`.
+static SYNC_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ScheduledOccurrence {
+    event_id: u64,
+    starts_at: DateTime<Utc>,
+    /// The config the event was created from, to notice when it changes.
+    definition: RecurringEvent,
+    reminder_sent: bool,
+}
+
+/// Makes sure the next occurrence of every configured recurring event exists as a Discord
+/// scheduled event matching the config, removes events that are no longer configured,
+/// and posts reminders with the number of interested members.
+pub async fn sync_events(ctx: &SerenityContext, data: &Data) -> anyhow::Result<()> {
+    let _guard = SYNC_LOCK.lock().await;
+    let config = data.config.read().await.events.clone();
+    let Some(guild_id) = config.guild_id.map(GuildId::new) else {
+        return Ok(());
+    };
+
+    let mut occurrences: HashMap<String, ScheduledOccurrence> =
+        data.storage.get(SCHEDULED_EVENTS_KEY).await?;
+    let now = Utc::now();
+
+    // Events dropped from the config are cancelled unless they already started.
+    let removed: Vec<String> = occurrences
+        .keys()
+        .filter(|name| !config.recurring.iter().any(|event| &event.name == *name))
+        .cloned()
+        .collect();
+    for name in removed {
+        let occurrence = occurrences.remove(&name).expect("Name was just listed");
+        if occurrence.starts_at > now {
+            if let Err(e) = guild_id
+                .delete_scheduled_event(&ctx.http, occurrence.event_id)
+                .await
+            {
+                warn!("Failed to delete scheduled event {}: {}", name, e);
+            }
+        }
+        info!("Removed recurring event {}", name);
+    }
+
+    for event in &config.recurring {
+        let Some(starts_at) = next_occurrence(event, now) else {
+            warn!("Recurring event {} has no weekdays set", event.name);
+            continue;
+        };
+        let existing = occurrences.get(&event.name);
+        let up_to_date =
+            existing.is_some_and(|o| o.starts_at == starts_at && o.definition == *event);
+        if up_to_date {
+            continue;
+        }
+
+        // Only events that haven't started yet can be moved, otherwise create the next one.
+        let result = match existing.filter(|o| o.starts_at > now) {
+            Some(occurrence) => {
+                let mut edit = EditScheduledEvent::new()
+                    .name(&event.name)
+                    .description(&event.description)
+                    .start_time(starts_at)
+                    .end_time(starts_at + Duration::minutes(event.duration_minutes));
+                edit = match event.channel_id {
+                    Some(channel_id) => edit.kind(ScheduledEventType::Voice).channel_id(channel_id),
+                    None => edit
+                        .kind(ScheduledEventType::External)
+                        .location(&event.location),
+                };
+                guild_id
+                    .edit_scheduled_event(&ctx.http, occurrence.event_id, edit)
+                    .await
+            }
+            None => {
+                let mut create =
+                    CreateScheduledEvent::new(ScheduledEventType::External, &event.name, starts_at)
+                        .description(&event.description)
+                        .end_time(starts_at + Duration::minutes(event.duration_minutes));
+                create = match event.channel_id {
+                    Some(channel_id) => create
+                        .kind(ScheduledEventType::Voice)
+                        .channel_id(channel_id),
+                    None => create.location(&event.location),
+                };
+                guild_id.create_scheduled_event(&ctx.http, create).await
+            }
+        };
+
+        match result {
+            Ok(scheduled) => {
+                info!("Scheduled {} for {}", event.name, starts_at);
+                occurrences.insert(
+                    event.name.clone(),
+                    ScheduledOccurrence {
+                        event_id: scheduled.id.get(),
+                        starts_at,
+                        definition: event.clone(),
+                        reminder_sent: false,
+                    },
+                );
+            }
+            Err(e) => warn!("Failed to schedule {}: {}", event.name, e),
+        }
+    }
+
+    if let Some(channel_id) = config.reminder_channel_id {
+        let reminder_window = Duration::minutes(config.reminder_minutes);
+        for (name, occurrence) in occurrences.iter_mut() {
+            let until_start = occurrence.starts_at - now;
+            if occurrence.reminder_sent
+                || until_start <= Duration::zero()
+                || until_start > reminder_window
+            {
+                continue;
+            }
+            send_reminder(ctx, guild_id, ChannelId::new(channel_id), name, occurrence).await;
+            occurrence.reminder_sent = true;
+        }
+    }
+
+    data.storage.set(SCHEDULED_EVENTS_KEY, &occurrences).await
+}
+
+async fn send_reminder(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    name: &str,
+    occurrence: &ScheduledOccurrence,
+) {
+    let interested = match guild_id
+        .scheduled_event(&ctx.http, ScheduledEventId::new(occurrence.event_id), true)
+        .await
+    {
+        Ok(event) => event.user_count.unwrap_or_default(),
+        Err(e) => {
+            warn!("Failed to fetch interested count for {}: {}", name, e);
+            0
+        }
+    };
+
+    let message = CreateMessage::new().content(format!(
+        "**{}** starts {}, {} interested so far! https://discord.com/events/{}/{}",
+        name,
+        discord_timestamp(occurrence.starts_at, TimestampStyle::Relative),
+        interested,
+        guild_id,
+        occurrence.event_id
+    ));
+    if let Err(e) = channel_id.send_message(&ctx.http, message).await {
+        warn!("Failed to send reminder for {}: {}", name, e);
+    }
+}
+
+/// Start of the next occurrence of `event`, in the bot's timezone, that hasn't ended yet.
+fn next_occurrence(event: &RecurringEvent, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let today = now.with_timezone(&timezone()).date_naive();
+    (0..=7)
+        .map(|offset| today + Duration::days(offset))
+        .filter(|date| event.weekdays.contains(&date.weekday()))
+        .filter_map(|date| {
+            date.and_time(event.start)
+                .and_local_timezone(timezone())
+                .earliest()
+        })
+        .map(|start| start.with_timezone(&Utc))
+        .find(|start| *start + Duration::minutes(event.duration_minutes) > now)
+}
@@ -0,0 +1,117 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    fmt,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context as _};
+use tracing::{info, warn};
+
+use crate::{error::RootError, metrics::timed};
+
+/// Consecutive failed or timed out calls after which Root is considered down.
+const FAILURE_THRESHOLD: u32 = 3;
+/// How long calls are short-circuited once the breaker opens. The first call after
+/// the cooldown is let through to probe whether Root recovered.
+const COOLDOWN: Duration = Duration::from_secs(5 * 60);
+/// Calls taking longer than this count as failures.
+const CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+static BREAKER: Mutex<Breaker> = Mutex::new(Breaker {
+    consecutive_failures: 0,
+    open_until: None,
+});
+
+fn breaker() -> std::sync::MutexGuard<'static, Breaker> {
+    BREAKER.lock().expect("Circuit breaker lock poisoned")
+}
+
+/// Returned instead of calling Root while the circuit breaker is open.
+#[derive(Debug)]
+pub struct RootUnavailable {
+    pub retry_in: Duration,
+}
+
+impl fmt::Display for RootUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Root is unavailable, retrying in {} seconds",
+            self.retry_in.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for RootUnavailable {}
+
+/// Time left until the breaker lets calls through again, [`None`] if it is closed.
+pub fn root_unavailable_for() -> Option<Duration> {
+    breaker()
+        .open_until
+        .and_then(|until| until.checked_duration_since(Instant::now()))
+}
+
+/// Runs a Root call under the timeout budget and the circuit breaker, recording it as
+/// `endpoint` in the metrics.
+pub async fn guarded<T>(
+    endpoint: &str,
+    call: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    if let Some(retry_in) = root_unavailable_for() {
+        return Err(RootUnavailable { retry_in }.into());
+    }
+
+    let result = timed(endpoint, async {
+        tokio::time::timeout(CALL_TIMEOUT, call)
+            .await
+            .map_err(|_| anyhow!("{} timed out after {:?}", endpoint, CALL_TIMEOUT))?
+    })
+    .await;
+
+    let mut breaker = breaker();
+    match &result {
+        Ok(_) => {
+            if breaker.consecutive_failures >= FAILURE_THRESHOLD {
+                info!("Root recovered, closing the circuit breaker");
+            }
+            breaker.consecutive_failures = 0;
+            breaker.open_until = None;
+        }
+        Err(_) => {
+            breaker.consecutive_failures += 1;
+            if breaker.consecutive_failures >= FAILURE_THRESHOLD {
+                warn!(
+                    "{} consecutive Root failures, short-circuiting calls for {:?}",
+                    breaker.consecutive_failures, COOLDOWN
+                );
+                breaker.open_until = Some(Instant::now() + COOLDOWN);
+            }
+        }
+    }
+
+    result.with_context(|| RootError(endpoint.to_string()))
+}
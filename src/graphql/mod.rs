@@ -15,5 +15,7 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
+/// Timeout budget and circuit breaker around the calls to Root.
+pub mod breaker;
 pub mod models;
 pub mod queries;
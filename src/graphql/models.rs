@@ -15,7 +15,8 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
-use serde::Deserialize;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct StreakWithMemberId {
@@ -48,7 +49,7 @@ pub struct Member {
     pub streak: Vec<Streak>, // Note that Root will NOT have multiple Streak elements but it may be an empty list which is why we use a vector here
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AttendanceRecord {
     pub name: String,
     pub year: i32,
@@ -56,4 +57,28 @@ pub struct AttendanceRecord {
     pub is_present: bool,
     #[serde(rename = "timeIn")]
     pub time_in: Option<String>,
+    /// When the member was last seen in the lab, kept up to date by Root while they're in.
+    #[serde(rename = "timeOut", default)]
+    pub time_out: Option<String>,
+}
+
+/// The bot's summary of a day's status update check, pushed back to Root.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusUpdateStats {
+    pub date: NaiveDate,
+    pub member_count: i32,
+    pub update_count: i32,
+    pub defaulter_count: i32,
+    pub late_count: i32,
+}
+
+/// The bot's summary of a day's lab attendance, pushed back to Root.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceStats {
+    pub date: NaiveDate,
+    pub present_count: i32,
+    pub absent_count: i32,
+    pub attendance_percentage: f64,
 }
@@ -16,298 +16,584 @@ You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 use anyhow::{anyhow, Context};
-use chrono::Local;
+use chrono::NaiveDate;
+use serde::Serialize;
 use serde_json::Value;
-use tracing::debug;
+use tracing::{debug, warn};
 
-use crate::graphql::models::{AttendanceRecord, Member, Streak};
+use crate::graphql::models::{
+    AttendanceRecord, AttendanceStats, Member, StatusUpdateStats, Streak,
+};
+use crate::run_id;
+use crate::utils::time::local_today;
 
-use super::models::StreakWithMemberId;
+use super::{breaker::guarded, models::StreakWithMemberId};
 
 pub async fn fetch_members() -> anyhow::Result<Vec<Member>> {
-    let request_url = std::env::var("ROOT_URL").context("ROOT_URL not found in ENV")?;
-
-    let client = reqwest::Client::new();
-    let query = r#"
-        { 
-          members {
-            memberId
-            name
-            discordId
-            groupId
-            streak {
-              currentStreak
-              maxStreak
+    guarded("root.fetch_members", async {
+        let request_url = std::env::var("ROOT_URL").context("ROOT_URL not found in ENV")?;
+
+        let client = reqwest::Client::new();
+        let query = r#"
+            { 
+              members {
+                memberId
+                name
+                discordId
+                groupId
+                streak {
+                  currentStreak
+                  maxStreak
+                }
             }
+        }"#;
+
+        debug!("Sending query {}", query);
+        let response = run_id::tag(client.post(request_url))
+            .json(&serde_json::json!({"query": query}))
+            .send()
+            .await
+            .context("Failed to successfully post request")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Server responded with an error: {:?}",
+                response.status()
+            ));
         }
-    }"#;
 
-    debug!("Sending query {}", query);
-    let response = client
-        .post(request_url)
-        .json(&serde_json::json!({"query": query}))
-        .send()
-        .await
-        .context("Failed to successfully post request")?;
-
-    if !response.status().is_success() {
-        return Err(anyhow!(
-            "Server responded with an error: {:?}",
-            response.status()
-        ));
-    }
+        let response_json: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to serialize response")?;
+
+        debug!("Response: {}", response_json);
+        let members = response_json
+            .get("data")
+            .and_then(|data| data.get("members"))
+            .and_then(|members| members.as_array())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Malformed response: Could not access Members from {}",
+                    response_json
+                )
+            })?;
+
+        let members: Vec<Member> =
+            serde_json::from_value(serde_json::Value::Array(members.clone()))
+                .context("Failed to parse 'members' into Vec<Member>")?;
+
+        Ok(members)
+    })
+    .await
+}
 
-    let response_json: serde_json::Value = response
-        .json()
-        .await
-        .context("Failed to serialize response")?;
-
-    debug!("Response: {}", response_json);
-    let members = response_json
-        .get("data")
-        .and_then(|data| data.get("members"))
-        .and_then(|members| members.as_array())
-        .ok_or_else(|| {
-            anyhow::anyhow!(
-                "Malformed response: Could not access Members from {}",
+pub async fn increment_streak(member: &mut Member) -> anyhow::Result<()> {
+    guarded("root.increment_streak", async {
+        let request_url = std::env::var("ROOT_URL").context("ROOT_URL was not found in ENV")?;
+
+        let client = reqwest::Client::new();
+        let mutation = format!(
+            r#"
+            mutation {{
+                incrementStreak(input: {{ memberId: {} }}) {{
+                    currentStreak
+                    maxStreak
+                }}
+            }}"#,
+            member.member_id
+        );
+
+        debug!("Sending mutation {}", mutation);
+        let response = run_id::tag(client.post(request_url))
+            .json(&serde_json::json!({"query": mutation}))
+            .send()
+            .await
+            .context("Failed to succesfully post query to Root")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Server responded with an error: {:?}",
+                response.status()
+            ));
+        }
+        let response_json: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse response JSON")?;
+        debug!("Response: {}", response_json);
+
+        if let Some(data) = response_json
+            .get("data")
+            .and_then(|data| data.get("incrementStreak"))
+        {
+            let current_streak = data
+                .get("currentStreak")
+                .and_then(|v| v.as_i64())
+                .ok_or_else(|| anyhow!("current_streak was parsed as None"))?
+                as i32;
+            let max_streak = data
+                .get("maxStreak")
+                .and_then(|v| v.as_i64())
+                .ok_or_else(|| anyhow!("max_streak was parsed as None"))?
+                as i32;
+
+            if member.streak.is_empty() {
+                member.streak.push(Streak {
+                    current_streak,
+                    max_streak,
+                });
+            } else {
+                for streak in &mut member.streak {
+                    streak.current_streak = current_streak;
+                    streak.max_streak = max_streak;
+                }
+            }
+        } else {
+            return Err(anyhow!(
+                "Failed to access data from response: {}",
                 response_json
-            )
-        })?;
-
-    let members: Vec<Member> = serde_json::from_value(serde_json::Value::Array(members.clone()))
-        .context("Failed to parse 'members' into Vec<Member>")?;
+            ));
+        }
 
-    Ok(members)
+        Ok(())
+    })
+    .await
 }
 
-pub async fn increment_streak(member: &mut Member) -> anyhow::Result<()> {
-    let request_url = std::env::var("ROOT_URL").context("ROOT_URL was not found in ENV")?;
+pub async fn reset_streak(member: &mut Member) -> anyhow::Result<()> {
+    guarded("root.reset_streak", async {
+        let request_url = std::env::var("ROOT_URL").context("ROOT_URL was not found in the ENV")?;
+
+        let client = reqwest::Client::new();
+        let mutation = format!(
+            r#"
+            mutation {{
+                resetStreak(input: {{ memberId: {} }}) {{
+                    currentStreak
+                    maxStreak
+                }}
+            }}"#,
+            member.member_id
+        );
+
+        debug!("Sending mutation {}", mutation);
+        let response = run_id::tag(client.post(&request_url))
+            .json(&serde_json::json!({ "query": mutation }))
+            .send()
+            .await
+            .context("Failed to succesfully post query to Root")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Server responded with an error: {:?}",
+                response.status()
+            ));
+        }
 
-    let client = reqwest::Client::new();
-    let mutation = format!(
-        r#"
-        mutation {{
-            incrementStreak(input: {{ memberId: {} }}) {{
-                currentStreak
-                maxStreak
-            }}
-        }}"#,
-        member.member_id
-    );
-
-    debug!("Sending mutation {}", mutation);
-    let response = client
-        .post(request_url)
-        .json(&serde_json::json!({"query": mutation}))
-        .send()
-        .await
-        .context("Failed to succesfully post query to Root")?;
-
-    if !response.status().is_success() {
-        return Err(anyhow!(
-            "Server responded with an error: {:?}",
-            response.status()
-        ));
-    }
-    let response_json: serde_json::Value = response
-        .json()
-        .await
-        .context("Failed to parse response JSON")?;
-    debug!("Response: {}", response_json);
-
-    if let Some(data) = response_json
-        .get("data")
-        .and_then(|data| data.get("incrementStreak"))
-    {
-        let current_streak =
-            data.get("currentStreak")
+        let response_json: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse response JSON")?;
+        debug!("Response: {}", response_json);
+
+        if let Some(data) = response_json
+            .get("data")
+            .and_then(|data| data.get("resetStreak"))
+        {
+            let current_streak = data
+                .get("currentStreak")
                 .and_then(|v| v.as_i64())
-                .ok_or_else(|| anyhow!("current_streak was parsed as None"))? as i32;
-        let max_streak =
-            data.get("maxStreak")
+                .ok_or_else(|| anyhow!("current_streak was parsed as None"))?
+                as i32;
+            let max_streak = data
+                .get("maxStreak")
                 .and_then(|v| v.as_i64())
-                .ok_or_else(|| anyhow!("max_streak was parsed as None"))? as i32;
-
-        if member.streak.is_empty() {
-            member.streak.push(Streak {
-                current_streak,
-                max_streak,
-            });
-        } else {
-            for streak in &mut member.streak {
-                streak.current_streak = current_streak;
-                streak.max_streak = max_streak;
+                .ok_or_else(|| anyhow!("max_streak was parsed as None"))?
+                as i32;
+
+            if member.streak.is_empty() {
+                member.streak.push(Streak {
+                    current_streak,
+                    max_streak,
+                });
+            } else {
+                for streak in &mut member.streak {
+                    streak.current_streak = current_streak;
+                    streak.max_streak = max_streak;
+                }
             }
+        } else {
+            return Err(anyhow!("Failed to access data from {}", response_json));
         }
-    } else {
+
+        Ok(())
+    })
+    .await
+}
+
+/// Most increments [`set_streak`] sends to Root for one restore, each is a mutation.
+const MAX_STREAK_INCREMENTS: i32 = 365;
+
+/// Brings a member's streak to `current_streak`, used to correct resets caused by false
+/// negatives. Root has no mutation to set a streak, so it is incremented up a day at a
+/// time, after a reset if it's above the target. Root keeps the max streak itself, so it
+/// can rise to `max_streak` this way but never be lowered.
+///
+/// A restore stopped by a failing increment is reported as partial. Running it again
+/// carries on from where it stopped instead of starting over.
+pub async fn set_streak(
+    member: &mut Member,
+    current_streak: i32,
+    max_streak: i32,
+) -> anyhow::Result<()> {
+    let current = |member: &Member| member.streak.first().map(|s| s.current_streak);
+
+    if current(member).is_none_or(|streak| streak > current_streak) {
+        reset_streak(member).await?;
+    }
+    // A reset can leave the streak below zero, so count up from wherever it landed.
+    let start = current(member).unwrap_or(0);
+    if current_streak - start > MAX_STREAK_INCREMENTS {
         return Err(anyhow!(
-            "Failed to access data from response: {}",
-            response_json
+            "Restoring the streak of {} from {} to {} takes more than {} calls to Root, set it there by hand",
+            member.name,
+            start,
+            current_streak,
+            MAX_STREAK_INCREMENTS
         ));
     }
+    while let Some(before) = current(member).filter(|streak| *streak < current_streak) {
+        let result = increment_streak(member).await;
+        if let Err(e) = result {
+            return Err(e.context(format!(
+                "Partially restored the streak of {}: {} of {} days",
+                member.name, before, current_streak
+            )));
+        }
+        if current(member).is_none_or(|after| after <= before) {
+            return Err(anyhow!(
+                "Root didn't increment the streak of {}, partially restored it: {} of {} days",
+                member.name,
+                before,
+                current_streak
+            ));
+        }
+    }
 
+    if let Some(streak) = member.streak.first() {
+        if streak.max_streak != max_streak {
+            warn!(
+                "Max streak of {} is {} instead of {}, Root can't set it",
+                member.name, streak.max_streak, max_streak
+            );
+        }
+    }
     Ok(())
 }
 
-pub async fn reset_streak(member: &mut Member) -> anyhow::Result<()> {
-    let request_url = std::env::var("ROOT_URL").context("ROOT_URL was not found in the ENV")?;
-
-    let client = reqwest::Client::new();
-    let mutation = format!(
-        r#"
-        mutation {{
-            resetStreak(input: {{ memberId: {} }}) {{
-                currentStreak
-                maxStreak
-            }}
-        }}"#,
-        member.member_id
-    );
-
-    debug!("Sending mutation {}", mutation);
-    let response = client
-        .post(&request_url)
-        .json(&serde_json::json!({ "query": mutation }))
-        .send()
-        .await
-        .context("Failed to succesfully post query to Root")?;
-
-    if !response.status().is_success() {
-        return Err(anyhow!(
-            "Server responded with an error: {:?}",
-            response.status()
-        ));
-    }
+/// Links a member to a Discord account, or unlinks them when `discord_id` is [`None`].
+pub async fn set_discord_id(member_id: i32, discord_id: Option<&str>) -> anyhow::Result<()> {
+    guarded("root.set_discord_id", async {
+        let request_url = std::env::var("ROOT_URL").context("ROOT_URL was not found in the ENV")?;
+
+        let client = reqwest::Client::new();
+        let mutation = r#"
+            mutation($memberId: Int!, $discordId: String) {
+                setDiscordId(input: { memberId: $memberId, discordId: $discordId }) {
+                    memberId
+                    discordId
+                }
+            }"#;
+
+        debug!("Sending mutation {}", mutation);
+        let response = run_id::tag(client.post(&request_url))
+            .json(&serde_json::json!({
+                "query": mutation,
+                "variables": { "memberId": member_id, "discordId": discord_id },
+            }))
+            .send()
+            .await
+            .context("Failed to succesfully post query to Root")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Server responded with an error: {:?}",
+                response.status()
+            ));
+        }
 
-    let response_json: serde_json::Value = response
-        .json()
-        .await
-        .context("Failed to parse response JSON")?;
-    debug!("Response: {}", response_json);
-
-    if let Some(data) = response_json
-        .get("data")
-        .and_then(|data| data.get("resetStreak"))
-    {
-        let current_streak =
-            data.get("currentStreak")
-                .and_then(|v| v.as_i64())
-                .ok_or_else(|| anyhow!("current_streak was parsed as None"))? as i32;
-        let max_streak =
-            data.get("maxStreak")
-                .and_then(|v| v.as_i64())
-                .ok_or_else(|| anyhow!("max_streak was parsed as None"))? as i32;
+        let response_json: Value = response
+            .json()
+            .await
+            .context("Failed to parse response JSON")?;
+        debug!("Response: {}", response_json);
+
+        response_json
+            .get("data")
+            .and_then(|data| data.get("setDiscordId"))
+            .filter(|member| !member.is_null())
+            .ok_or_else(|| anyhow!("Failed to access data from {}", response_json))?;
+        Ok(())
+    })
+    .await
+}
 
-        if member.streak.is_empty() {
-            member.streak.push(Streak {
-                current_streak,
-                max_streak,
-            });
-        } else {
-            for streak in &mut member.streak {
-                streak.current_streak = current_streak;
-                streak.max_streak = max_streak;
-            }
+/// Overwrites a member's attendance on `date`, for when the biometric system recorded it
+/// wrongly. `time_in` is `HH:MM:SS` and only set for members marked present.
+pub async fn correct_attendance(
+    member_id: i32,
+    date: NaiveDate,
+    is_present: bool,
+    time_in: Option<&str>,
+) -> anyhow::Result<()> {
+    guarded("root.correct_attendance", async {
+        let request_url = std::env::var("ROOT_URL").context("ROOT_URL was not found in the ENV")?;
+
+        let client = reqwest::Client::new();
+        let mutation = r#"
+            mutation($memberId: Int!, $date: NaiveDate!, $isPresent: Boolean!, $timeIn: String) {
+                correctAttendance(input: {
+                    memberId: $memberId, date: $date, isPresent: $isPresent, timeIn: $timeIn
+                }) {
+                    isPresent
+                    timeIn
+                }
+            }"#;
+
+        debug!("Sending mutation {}", mutation);
+        let response = run_id::tag(client.post(&request_url))
+            .json(&serde_json::json!({
+                "query": mutation,
+                "variables": {
+                    "memberId": member_id,
+                    "date": date,
+                    "isPresent": is_present,
+                    "timeIn": time_in,
+                },
+            }))
+            .send()
+            .await
+            .context("Failed to succesfully post query to Root")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Server responded with an error: {:?}",
+                response.status()
+            ));
         }
-    } else {
-        return Err(anyhow!("Failed to access data from {}", response_json));
-    }
 
-    Ok(())
+        let response_json: Value = response
+            .json()
+            .await
+            .context("Failed to parse response JSON")?;
+        debug!("Response: {}", response_json);
+
+        response_json
+            .get("data")
+            .and_then(|data| data.get("correctAttendance"))
+            .filter(|record| !record.is_null())
+            .ok_or_else(|| anyhow!("Failed to access data from {}", response_json))?;
+        Ok(())
+    })
+    .await
 }
 
 pub async fn fetch_attendance() -> anyhow::Result<Vec<AttendanceRecord>> {
-    let request_url =
-        std::env::var("ROOT_URL").context("ROOT_URL environment variable not found")?;
-
-    debug!("Fetching attendance data from {}", request_url);
-
-    let client = reqwest::Client::new();
-    let today = Local::now().format("%Y-%m-%d").to_string();
-    let query = format!(
-        r#"
-        query {{
-            attendanceByDate(date: "{}") {{
-                name,
-                year,
-                isPresent,
-                timeIn,
-            }}
-        }}"#,
-        today
-    );
-
-    let response = client
-        .post(&request_url)
-        .json(&serde_json::json!({ "query": query }))
-        .send()
-        .await
-        .context("Failed to send GraphQL request")?;
-    debug!("Response status: {:?}", response.status());
-
-    let json: Value = response
-        .json()
-        .await
-        .context("Failed to parse response as JSON")?;
-
-    let attendance_array = json["data"]["attendanceByDate"]
-        .as_array()
-        .context("Missing or invalid 'data.attendanceByDate' array in response")?;
-
-    let attendance: Vec<AttendanceRecord> = attendance_array
-        .iter()
-        .map(|entry| {
-            serde_json::from_value(entry.clone()).context("Failed to parse attendance record")
-        })
-        .collect::<anyhow::Result<Vec<_>>>()?;
-
-    debug!(
-        "Successfully fetched {} attendance records",
-        attendance.len()
-    );
-    Ok(attendance)
+    guarded("root.fetch_attendance", async {
+        let request_url =
+            std::env::var("ROOT_URL").context("ROOT_URL environment variable not found")?;
+
+        debug!("Fetching attendance data from {}", request_url);
+
+        let client = reqwest::Client::new();
+        let today = local_today().format("%Y-%m-%d").to_string();
+        let query = format!(
+            r#"
+            query {{
+                attendanceByDate(date: "{}") {{
+                    name,
+                    year,
+                    isPresent,
+                    timeIn,
+                    timeOut,
+                }}
+            }}"#,
+            today
+        );
+
+        let response = run_id::tag(client.post(&request_url))
+            .json(&serde_json::json!({ "query": query }))
+            .send()
+            .await
+            .context("Failed to send GraphQL request")?;
+        debug!("Response status: {:?}", response.status());
+
+        let json: Value = response
+            .json()
+            .await
+            .context("Failed to parse response as JSON")?;
+
+        let attendance_array = json["data"]["attendanceByDate"]
+            .as_array()
+            .context("Missing or invalid 'data.attendanceByDate' array in response")?;
+
+        let attendance: Vec<AttendanceRecord> = attendance_array
+            .iter()
+            .map(|entry| {
+                serde_json::from_value(entry.clone()).context("Failed to parse attendance record")
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        debug!(
+            "Successfully fetched {} attendance records",
+            attendance.len()
+        );
+        Ok(attendance)
+    })
+    .await
 }
 
 pub async fn fetch_streaks() -> anyhow::Result<Vec<StreakWithMemberId>> {
-    let request_url = std::env::var("ROOT_URL").context("ROOT_URL not found in ENV")?;
-
-    let client = reqwest::Client::new();
-    let query = r#"
-        {
-          streaks {
-            memberId
-            currentStreak
-            maxStreak
-          }
+    guarded("root.fetch_streaks", async {
+        let request_url = std::env::var("ROOT_URL").context("ROOT_URL not found in ENV")?;
+
+        let client = reqwest::Client::new();
+        let query = r#"
+            {
+              streaks {
+                memberId
+                currentStreak
+                maxStreak
+              }
+            }
+        "#;
+
+        debug!("Sending query {}", query);
+        let response = run_id::tag(client.post(request_url))
+            .json(&serde_json::json!({"query": query}))
+            .send()
+            .await
+            .context("Failed to successfully post request")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Server responded with an error: {:?}",
+                response.status()
+            ));
         }
-    "#;
 
-    debug!("Sending query {}", query);
-    let response = client
-        .post(request_url)
-        .json(&serde_json::json!({"query": query}))
-        .send()
-        .await
-        .context("Failed to successfully post request")?;
+        let response_json: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to serialize response")?;
+
+        debug!("Response: {}", response_json);
+        let streaks = response_json
+            .get("data")
+            .and_then(|data| data.get("streaks"))
+            .and_then(|streaks| {
+                serde_json::from_value::<Vec<StreakWithMemberId>>(streaks.clone()).ok()
+            })
+            .context("Failed to parse streaks data")?;
+
+        Ok(streaks)
+    })
+    .await
+}
 
-    if !response.status().is_success() {
-        return Err(anyhow!(
-            "Server responded with an error: {:?}",
-            response.status()
-        ));
-    }
+/// Stores the bot's summary of a day's status updates in Root, for the club website.
+pub async fn push_status_update_stats(stats: &StatusUpdateStats) -> anyhow::Result<()> {
+    push_stats(
+        "root.push_status_update_stats",
+        "recordStatusUpdateSummary",
+        "StatusUpdateSummaryInput",
+        stats,
+    )
+    .await
+}
+
+/// Stores the bot's summary of a day's lab attendance in Root, for the club website.
+pub async fn push_attendance_stats(stats: &AttendanceStats) -> anyhow::Result<()> {
+    push_stats(
+        "root.push_attendance_stats",
+        "recordAttendanceSummary",
+        "AttendanceSummaryInput",
+        stats,
+    )
+    .await
+}
 
-    let response_json: serde_json::Value = response
-        .json()
-        .await
-        .context("Failed to serialize response")?;
+async fn push_stats(
+    endpoint: &str,
+    mutation_name: &str,
+    input_type: &str,
+    input: &impl Serialize,
+) -> anyhow::Result<()> {
+    guarded(endpoint, async {
+        let request_url = std::env::var("ROOT_URL").context("ROOT_URL was not found in the ENV")?;
+
+        let client = reqwest::Client::new();
+        let mutation = format!(
+            r#"
+            mutation($input: {}!) {{
+                {}(input: $input) {{
+                    date
+                }}
+            }}"#,
+            input_type, mutation_name
+        );
+
+        debug!("Sending mutation {}", mutation);
+        let response = run_id::tag(client.post(&request_url))
+            .json(&serde_json::json!({
+                "query": mutation,
+                "variables": { "input": input },
+            }))
+            .send()
+            .await
+            .context("Failed to succesfully post query to Root")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Server responded with an error: {:?}",
+                response.status()
+            ));
+        }
+
+        let response_json: Value = response
+            .json()
+            .await
+            .context("Failed to parse response JSON")?;
+        debug!("Response: {}", response_json);
 
-    debug!("Response: {}", response_json);
-    let streaks = response_json
-        .get("data")
-        .and_then(|data| data.get("streaks"))
-        .and_then(|streaks| serde_json::from_value::<Vec<StreakWithMemberId>>(streaks.clone()).ok())
-        .context("Failed to parse streaks data")?;
+        if let Some(errors) = response_json.get("errors") {
+            return Err(anyhow!("{} was rejected: {}", mutation_name, errors));
+        }
+        Ok(())
+    })
+    .await
+}
 
-    Ok(streaks)
+/// Sends `query` to Root as is and returns the whole response, errors included, for
+/// debugging from Discord with `$gql`.
+pub async fn raw_query(query: &str) -> anyhow::Result<Value> {
+    guarded("root.raw_query", async {
+        let request_url = std::env::var("ROOT_URL").context("ROOT_URL was not found in the ENV")?;
+
+        let client = reqwest::Client::new();
+        debug!("Sending raw query {}", query);
+        let response = run_id::tag(client.post(&request_url))
+            .json(&serde_json::json!({ "query": query }))
+            .send()
+            .await
+            .context("Failed to succesfully post query to Root")?;
+
+        // GraphQL errors come back with a 4xx status and are worth showing too.
+        response
+            .json()
+            .await
+            .context("Failed to parse response JSON")
+    })
+    .await
 }
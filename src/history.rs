@@ -0,0 +1,324 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graphql::models::{AttendanceRecord, AttendanceStats, StatusUpdateStats},
+    storage::Storage,
+};
+
+const STATUS_UPDATE_HISTORY_KEY: &str = "status_update.history";
+const ATTENDANCE_HISTORY_KEY: &str = "attendance.history";
+const RESOURCE_HISTORY_KEY: &str = "resources.history";
+/// Number of days of history that are kept around, older entries are dropped.
+const HISTORY_RETENTION_DAYS: usize = 400;
+
+/// The outcome of a single member in a status update check.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MemberUpdateResult {
+    pub member_id: i32,
+    pub name: String,
+    pub discord_id: String,
+    pub group_id: i32,
+    pub sent_update: bool,
+    /// Whether the update only arrived during the grace period after the deadline.
+    #[serde(default)]
+    pub late_update: bool,
+    /// Whether a streak shield was used up instead of resetting their streak.
+    #[serde(default)]
+    pub shielded: bool,
+    pub current_streak: i32,
+    pub max_streak: i32,
+}
+
+/// Results of the status update check for a single day.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusUpdateDay {
+    pub date: NaiveDate,
+    /// Updates sent after this instant (plus the grace period) do not count for this day.
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// False when a mentor held back the streak resets of the defaulters.
+    #[serde(default = "resets_applied_default")]
+    pub resets_applied: bool,
+    /// Whether weekend rules made updates optional, so nobody's streak was reset.
+    #[serde(default)]
+    pub updates_optional: bool,
+    pub members: Vec<MemberUpdateResult>,
+}
+
+fn resets_applied_default() -> bool {
+    true
+}
+
+impl StatusUpdateDay {
+    pub fn senders(&self) -> impl Iterator<Item = &MemberUpdateResult> {
+        self.members.iter().filter(|member| member.sent_update)
+    }
+
+    pub fn average_streak(&self) -> f64 {
+        if self.members.is_empty() {
+            return 0.0;
+        }
+        let total: i32 = self.members.iter().map(|m| m.current_streak).sum();
+        total as f64 / self.members.len() as f64
+    }
+
+    pub fn member(&self, discord_id: &str) -> Option<&MemberUpdateResult> {
+        self.members.iter().find(|m| m.discord_id == discord_id)
+    }
+
+    pub fn stats(&self) -> StatusUpdateStats {
+        let update_count = self.senders().count() as i32;
+        StatusUpdateStats {
+            date: self.date,
+            member_count: self.members.len() as i32,
+            update_count,
+            defaulter_count: self.members.len() as i32 - update_count,
+            late_count: self.members.iter().filter(|m| m.late_update).count() as i32,
+        }
+    }
+}
+
+/// Attendance as reported by Root for a single day.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttendanceDay {
+    pub date: NaiveDate,
+    pub records: Vec<AttendanceRecord>,
+}
+
+impl AttendanceDay {
+    pub fn attendance_percentage(&self) -> f64 {
+        if self.records.is_empty() {
+            return 0.0;
+        }
+        let present = self.records.iter().filter(|r| r.is_present).count();
+        present as f64 / self.records.len() as f64 * 100.0
+    }
+
+    pub fn stats(&self) -> AttendanceStats {
+        let present_count = self.records.iter().filter(|r| r.is_present).count() as i32;
+        AttendanceStats {
+            date: self.date,
+            present_count,
+            absent_count: self.records.len() as i32 - present_count,
+            attendance_percentage: self.attendance_percentage(),
+        }
+    }
+}
+
+/// Which members shared a learning resource during the week ending on `week_ending`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResourceWeek {
+    pub week_ending: NaiveDate,
+    pub shared: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+/// Stores `day`, replacing any earlier result for the same date.
+pub async fn record_status_update_day(
+    storage: &Storage,
+    day: StatusUpdateDay,
+) -> anyhow::Result<()> {
+    storage
+        .update(
+            STATUS_UPDATE_HISTORY_KEY,
+            |history: &mut Vec<StatusUpdateDay>| upsert_day(history, day, |d| d.date),
+        )
+        .await
+}
+
+pub async fn status_update_day(
+    storage: &Storage,
+    date: NaiveDate,
+) -> anyhow::Result<Option<StatusUpdateDay>> {
+    let history: Vec<StatusUpdateDay> = storage.get(STATUS_UPDATE_HISTORY_KEY).await?;
+    Ok(history.into_iter().find(|day| day.date == date))
+}
+
+/// The latest stored day before `date`, which is not necessarily the day before it.
+pub async fn previous_status_update_day(
+    storage: &Storage,
+    date: NaiveDate,
+) -> anyhow::Result<Option<StatusUpdateDay>> {
+    let history: Vec<StatusUpdateDay> = storage.get(STATUS_UPDATE_HISTORY_KEY).await?;
+    Ok(history
+        .into_iter()
+        .filter(|day| day.date < date)
+        .max_by_key(|day| day.date))
+}
+
+/// Applies `f` to the stored day at `date`, returning `None` if there is no such day.
+pub async fn update_status_update_day<R>(
+    storage: &Storage,
+    date: NaiveDate,
+    f: impl FnOnce(&mut StatusUpdateDay) -> R,
+) -> anyhow::Result<Option<R>> {
+    storage
+        .update(
+            STATUS_UPDATE_HISTORY_KEY,
+            |history: &mut Vec<StatusUpdateDay>| {
+                history.iter_mut().find(|day| day.date == date).map(f)
+            },
+        )
+        .await
+}
+
+/// Applies `f` to the stored result of `discord_id` on `date`, returning `None` if there
+/// is no such result.
+pub async fn update_member_result<R>(
+    storage: &Storage,
+    date: NaiveDate,
+    discord_id: &str,
+    f: impl FnOnce(&mut MemberUpdateResult) -> R,
+) -> anyhow::Result<Option<R>> {
+    storage
+        .update(
+            STATUS_UPDATE_HISTORY_KEY,
+            |history: &mut Vec<StatusUpdateDay>| {
+                history
+                    .iter_mut()
+                    .find(|day| day.date == date)
+                    .and_then(|day| day.members.iter_mut().find(|m| m.discord_id == discord_id))
+                    .map(f)
+            },
+        )
+        .await
+}
+
+/// Returns up to `count` of the most recent days, oldest first.
+pub async fn recent_status_update_days(
+    storage: &Storage,
+    count: usize,
+) -> anyhow::Result<Vec<StatusUpdateDay>> {
+    let history: Vec<StatusUpdateDay> = storage.get(STATUS_UPDATE_HISTORY_KEY).await?;
+    Ok(tail(history, count))
+}
+
+/// Stores `day`, replacing any earlier attendance for the same date.
+pub async fn record_attendance_day(storage: &Storage, day: AttendanceDay) -> anyhow::Result<()> {
+    storage
+        .update(
+            ATTENDANCE_HISTORY_KEY,
+            |history: &mut Vec<AttendanceDay>| upsert_day(history, day, |d| d.date),
+        )
+        .await
+}
+
+/// Applies `f` to the stored attendance of the member named `name` on `date`, returning
+/// `None` if there is no such record.
+pub async fn update_attendance_record<R>(
+    storage: &Storage,
+    date: NaiveDate,
+    name: &str,
+    f: impl FnOnce(&mut AttendanceRecord) -> R,
+) -> anyhow::Result<Option<R>> {
+    storage
+        .update(
+            ATTENDANCE_HISTORY_KEY,
+            |history: &mut Vec<AttendanceDay>| {
+                history
+                    .iter_mut()
+                    .find(|day| day.date == date)
+                    .and_then(|day| day.records.iter_mut().find(|r| r.name == name))
+                    .map(f)
+            },
+        )
+        .await
+}
+
+pub async fn attendance_day(
+    storage: &Storage,
+    date: NaiveDate,
+) -> anyhow::Result<Option<AttendanceDay>> {
+    let history: Vec<AttendanceDay> = storage.get(ATTENDANCE_HISTORY_KEY).await?;
+    Ok(history.into_iter().find(|day| day.date == date))
+}
+
+/// Returns up to `count` of the most recent days, oldest first.
+pub async fn recent_attendance_days(
+    storage: &Storage,
+    count: usize,
+) -> anyhow::Result<Vec<AttendanceDay>> {
+    let history: Vec<AttendanceDay> = storage.get(ATTENDANCE_HISTORY_KEY).await?;
+    Ok(tail(history, count))
+}
+
+pub async fn record_resource_week(storage: &Storage, week: ResourceWeek) -> anyhow::Result<()> {
+    storage
+        .update(RESOURCE_HISTORY_KEY, |history: &mut Vec<ResourceWeek>| {
+            upsert_day(history, week, |w| w.week_ending)
+        })
+        .await
+}
+
+pub async fn latest_resource_week(storage: &Storage) -> anyhow::Result<Option<ResourceWeek>> {
+    let history: Vec<ResourceWeek> = storage.get(RESOURCE_HISTORY_KEY).await?;
+    Ok(history.into_iter().last())
+}
+
+/// Removes every trace of a member from the stored history. Attendance and resource
+/// records only carry names, so those are matched by `name`.
+pub async fn forget_member(storage: &Storage, discord_id: &str, name: &str) -> anyhow::Result<()> {
+    storage
+        .update(
+            STATUS_UPDATE_HISTORY_KEY,
+            |history: &mut Vec<StatusUpdateDay>| {
+                for day in history {
+                    day.members.retain(|m| m.discord_id != discord_id);
+                }
+            },
+        )
+        .await?;
+    storage
+        .update(
+            ATTENDANCE_HISTORY_KEY,
+            |history: &mut Vec<AttendanceDay>| {
+                for day in history {
+                    day.records.retain(|r| r.name != name);
+                }
+            },
+        )
+        .await?;
+    storage
+        .update(RESOURCE_HISTORY_KEY, |history: &mut Vec<ResourceWeek>| {
+            for week in history {
+                week.shared.retain(|n| n != name);
+                week.missing.retain(|n| n != name);
+            }
+        })
+        .await
+}
+
+fn upsert_day<T>(history: &mut Vec<T>, day: T, date_of: impl Fn(&T) -> NaiveDate) {
+    let date = date_of(&day);
+    history.retain(|existing| date_of(existing) != date);
+    history.push(day);
+    history.sort_by_key(|existing| date_of(existing));
+
+    if history.len() > HISTORY_RETENTION_DAYS {
+        let excess = history.len() - HISTORY_RETENTION_DAYS;
+        history.drain(..excess);
+    }
+}
+
+fn tail<T>(mut history: Vec<T>, count: usize) -> Vec<T> {
+    let start = history.len().saturating_sub(count);
+    history.split_off(start)
+}
@@ -0,0 +1,317 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{anyhow, Context as _};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ComponentInteraction, Context as SerenityContext, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use tracing::{error, info};
+
+use crate::{storage::Storage, utils::time::format_date, Data};
+
+/// Custom ID prefix of the import review buttons, routed here by [`crate::interactions`].
+pub const HOLIDAYS_COMPONENT: &str = "holidays";
+/// Holidays in effect, by date.
+const OBSERVED_KEY: &str = "holidays.observed";
+/// Imported holidays waiting for an admin to review them.
+const PENDING_KEY: &str = "holidays.pending";
+/// Imported dates an admin rejected, so they aren't proposed again.
+const REJECTED_KEY: &str = "holidays.rejected";
+/// Multi-day events longer than this are assumed not to be holidays.
+const MAX_HOLIDAY_DAYS: i64 = 7;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+}
+
+/// An imported holiday, reviewed together with the rest of its `batch`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PendingHoliday {
+    batch: u64,
+    holiday: Holiday,
+}
+
+pub async fn observed(storage: &Storage) -> anyhow::Result<BTreeMap<NaiveDate, String>> {
+    storage.get(OBSERVED_KEY).await
+}
+
+/// Whether `date` is an observed holiday, on which member checks are skipped.
+pub async fn is_holiday(storage: &Storage, date: NaiveDate) -> anyhow::Result<bool> {
+    Ok(observed(storage).await?.contains_key(&date))
+}
+
+/// Adds a holiday by hand, replacing any holiday on the same date.
+pub async fn observe(storage: &Storage, holiday: Holiday) -> anyhow::Result<()> {
+    storage
+        .update(
+            OBSERVED_KEY,
+            |observed: &mut BTreeMap<NaiveDate, String>| {
+                observed.insert(holiday.date, holiday.name);
+            },
+        )
+        .await
+}
+
+/// Removes the holiday on `date`, returning its name if there was one.
+pub async fn unobserve(storage: &Storage, date: NaiveDate) -> anyhow::Result<Option<String>> {
+    storage
+        .update(
+            OBSERVED_KEY,
+            |observed: &mut BTreeMap<NaiveDate, String>| observed.remove(&date),
+        )
+        .await
+}
+
+/// Downloads the calendar at `url` and returns its all-day events from `from` on.
+pub async fn fetch_ics(url: &str, from: NaiveDate) -> anyhow::Result<Vec<Holiday>> {
+    let response = reqwest::get(url)
+        .await
+        .context("Failed to fetch the holiday calendar")?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Holiday calendar responded with an error: {:?}",
+            response.status()
+        ));
+    }
+    let calendar = response
+        .text()
+        .await
+        .context("Failed to read the holiday calendar")?;
+    let mut holidays = parse_ics(&calendar);
+    holidays.retain(|holiday| holiday.date >= from);
+    Ok(holidays)
+}
+
+/// Reads the all-day events of an iCalendar file, one holiday per day they cover.
+pub fn parse_ics(calendar: &str) -> Vec<Holiday> {
+    // Long lines are folded onto continuation lines starting with whitespace.
+    let mut lines: Vec<String> = Vec::new();
+    for line in calendar.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(continuation) if !lines.is_empty() => lines
+                .last_mut()
+                .expect("Checked above")
+                .push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut holidays = Vec::new();
+    let (mut start, mut end, mut name) = (None, None, None);
+    for line in &lines {
+        let Some((property, value)) = line.split_once(':') else {
+            continue;
+        };
+        let property = property.split(';').next().unwrap_or_default();
+        match property {
+            "BEGIN" if value == "VEVENT" => (start, end, name) = (None, None, None),
+            "DTSTART" => start = parse_ics_date(value),
+            "DTEND" => end = parse_ics_date(value),
+            "SUMMARY" => name = Some(unescape_ics(value)),
+            "END" if value == "VEVENT" => {
+                let (Some(start), Some(name)) = (start, name.take()) else {
+                    continue;
+                };
+                // All-day events end on the day after their last day.
+                let days = end
+                    .map_or(1, |end| (end - start).num_days())
+                    .clamp(1, MAX_HOLIDAY_DAYS);
+                for offset in 0..days {
+                    holidays.push(Holiday {
+                        date: start + Duration::days(offset),
+                        name: name.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    holidays
+}
+
+fn parse_ics_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+fn unescape_ics(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// Queues the imported holidays that are new, returning them as `batch`. Dates already
+/// observed, pending or rejected are left out.
+pub async fn queue_for_review(
+    storage: &Storage,
+    batch: u64,
+    imported: Vec<Holiday>,
+) -> anyhow::Result<Vec<Holiday>> {
+    let observed = observed(storage).await?;
+    let rejected: HashSet<NaiveDate> = storage.get(REJECTED_KEY).await?;
+    storage
+        .update(PENDING_KEY, |pending: &mut Vec<PendingHoliday>| {
+            let mut queued = Vec::new();
+            for holiday in imported {
+                let known = observed.contains_key(&holiday.date)
+                    || rejected.contains(&holiday.date)
+                    || pending.iter().any(|p| p.holiday.date == holiday.date)
+                    || queued.iter().any(|q: &Holiday| q.date == holiday.date);
+                if !known {
+                    queued.push(holiday);
+                }
+            }
+            pending.extend(queued.iter().map(|holiday| PendingHoliday {
+                batch,
+                holiday: holiday.clone(),
+            }));
+            queued
+        })
+        .await
+}
+
+pub fn review_buttons(batch: u64) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        review_button("approve", batch, "Observe these", ButtonStyle::Success),
+        review_button("reject", batch, "Ignore these", ButtonStyle::Secondary),
+    ])
+}
+
+fn review_button(action: &str, batch: u64, label: &str, style: ButtonStyle) -> CreateButton {
+    CreateButton::new(format!("{}:{}:{}", HOLIDAYS_COMPONENT, action, batch))
+        .label(label)
+        .style(style)
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    arg: &str,
+    data: &Data,
+) {
+    let Ok(batch) = arg.parse() else {
+        return;
+    };
+    let result = match action {
+        "approve" | "reject" => {
+            review_batch(ctx, component, batch, action == "approve", data).await
+        }
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        error!(
+            "Failed to handle holidays interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+async fn review_batch(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    batch: u64,
+    approved: bool,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let is_admin = component
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild());
+    if !is_admin {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Only admins can review holidays.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    }
+
+    let reviewed = data
+        .storage
+        .update(PENDING_KEY, |pending: &mut Vec<PendingHoliday>| {
+            let (reviewed, rest): (Vec<_>, Vec<_>) = std::mem::take(pending)
+                .into_iter()
+                .partition(|p| p.batch == batch);
+            *pending = rest;
+            reviewed
+        })
+        .await?;
+    if approved {
+        data.storage
+            .update(
+                OBSERVED_KEY,
+                |observed: &mut BTreeMap<NaiveDate, String>| {
+                    for pending in &reviewed {
+                        observed.insert(pending.holiday.date, pending.holiday.name.clone());
+                    }
+                },
+            )
+            .await?;
+    } else {
+        data.storage
+            .update(REJECTED_KEY, |rejected: &mut HashSet<NaiveDate>| {
+                rejected.extend(reviewed.iter().map(|p| p.holiday.date));
+            })
+            .await?;
+    }
+
+    let outcome = if approved { "Observed" } else { "Ignored" };
+    info!(
+        "{} holidays {} by {}",
+        reviewed.len(),
+        outcome.to_lowercase(),
+        component.user.name
+    );
+    let content = if reviewed.is_empty() {
+        String::from("These holidays were already reviewed.")
+    } else {
+        let dates: Vec<String> = reviewed
+            .iter()
+            .map(|p| format_date(p.holiday.date))
+            .collect();
+        format!(
+            "{} {} holidays ({}), reviewed by {}.",
+            outcome,
+            reviewed.len(),
+            dates.join(", "),
+            component.user.name
+        )
+    };
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+    Ok(())
+}
@@ -15,22 +15,367 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    num::NonZeroU64,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, MessageId, RoleId};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+use crate::{config::IdsConfig, settings::Settings};
+
+// Every channel and role the bot has a fixed use for resolves through the registry below:
+// the built-in amFOSS IDs, overridden by the `[ids]` config section, overridden in turn by
+// the channels and roles message picked with `$setup`, which are stored in the `ids`
+// settings namespace. The startup check validates the result against the live server.
+
+/// Settings namespace of the picked IDs.
+pub const SETTINGS_NAMESPACE: &str = "ids";
+/// Key of the picked roles message.
+pub const ROLES_MESSAGE_KEY: &str = "roles_message";
+
 /// Points to the Embed in the #roles channel.
-pub const ROLES_MESSAGE_ID: u64 = 1298636092886749294;
-
-// Role IDs
-pub const ARCHIVE_ROLE_ID: u64 = 1208457364274028574;
-pub const MOBILE_ROLE_ID: u64 = 1298553701094395936;
-pub const SYSTEMS_ROLE_ID: u64 = 1298553801191718944;
-pub const AI_ROLE_ID: u64 = 1298553753523453952;
-pub const RESEARCH_ROLE_ID: u64 = 1298553855474270219;
-pub const DEVOPS_ROLE_ID: u64 = 1298553883169132554;
-pub const WEB_ROLE_ID: u64 = 1298553910167994428;
-
-// Channel IDs
-pub const GROUP_ONE_CHANNEL_ID: u64 = 1225098248293716008;
-pub const GROUP_TWO_CHANNEL_ID: u64 = 1225098298935738489;
-pub const GROUP_THREE_CHANNEL_ID: u64 = 1225098353378070710;
-pub const GROUP_FOUR_CHANNEL_ID: u64 = 1225098407216156712;
-pub const STATUS_UPDATE_CHANNEL_ID: u64 = 764575524127244318;
-pub const THE_LAB_CHANNEL_ID: u64 = 1208438766893670451;
+const ROLES_MESSAGE_ID: u64 = 1298636092886749294;
+
+/// A channel the bot has a fixed use for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    /// Where the status update report and other club-wide posts go.
+    StatusUpdate,
+    Lab,
+    #[serde(rename = "group_1")]
+    Group1,
+    #[serde(rename = "group_2")]
+    Group2,
+    #[serde(rename = "group_3")]
+    Group3,
+    #[serde(rename = "group_4")]
+    Group4,
+}
+
+impl ChannelKind {
+    pub const ALL: [ChannelKind; 6] = [
+        ChannelKind::StatusUpdate,
+        ChannelKind::Lab,
+        ChannelKind::Group1,
+        ChannelKind::Group2,
+        ChannelKind::Group3,
+        ChannelKind::Group4,
+    ];
+    /// The status update channels of groups one to four.
+    pub const GROUPS: [ChannelKind; 4] = [
+        ChannelKind::Group1,
+        ChannelKind::Group2,
+        ChannelKind::Group3,
+        ChannelKind::Group4,
+    ];
+
+    /// Name of the channel in the `[ids.channels]` config.
+    pub fn key(self) -> &'static str {
+        match self {
+            ChannelKind::StatusUpdate => "status_update",
+            ChannelKind::Lab => "lab",
+            ChannelKind::Group1 => "group_1",
+            ChannelKind::Group2 => "group_2",
+            ChannelKind::Group3 => "group_3",
+            ChannelKind::Group4 => "group_4",
+        }
+    }
+
+    /// Key of the channel in the `ids` settings namespace, where `$setup` stores it.
+    pub fn settings_key(self) -> &'static str {
+        match self {
+            ChannelKind::StatusUpdate => "status_update_channel",
+            ChannelKind::Lab => "lab_channel",
+            ChannelKind::Group1 => "group_1_channel",
+            ChannelKind::Group2 => "group_2_channel",
+            ChannelKind::Group3 => "group_3_channel",
+            ChannelKind::Group4 => "group_4_channel",
+        }
+    }
+
+    /// The group number of a group's status update channel.
+    pub fn group(self) -> Option<u64> {
+        ChannelKind::GROUPS
+            .iter()
+            .position(|kind| *kind == self)
+            .map(|index| index as u64 + 1)
+    }
+
+    fn builtin(self) -> u64 {
+        match self {
+            ChannelKind::StatusUpdate => 764575524127244318,
+            ChannelKind::Lab => 1208438766893670451,
+            ChannelKind::Group1 => 1225098248293716008,
+            ChannelKind::Group2 => 1225098298935738489,
+            ChannelKind::Group3 => 1225098353378070710,
+            ChannelKind::Group4 => 1225098407216156712,
+        }
+    }
+}
+
+impl fmt::Display for ChannelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.group() {
+            Some(group) => write!(f, "group {} channel", group),
+            None => write!(f, "{} channel", self.key().replace('_', " ")),
+        }
+    }
+}
+
+/// A role the bot hands out through the roles message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleKind {
+    Archive,
+    Mobile,
+    Systems,
+    Ai,
+    Research,
+    Devops,
+    Web,
+}
+
+impl RoleKind {
+    pub const ALL: [RoleKind; 7] = [
+        RoleKind::Archive,
+        RoleKind::Mobile,
+        RoleKind::Systems,
+        RoleKind::Ai,
+        RoleKind::Research,
+        RoleKind::Devops,
+        RoleKind::Web,
+    ];
+
+    /// Name of the role in the `[ids.roles]` config.
+    pub fn key(self) -> &'static str {
+        match self {
+            RoleKind::Archive => "archive",
+            RoleKind::Mobile => "mobile",
+            RoleKind::Systems => "systems",
+            RoleKind::Ai => "ai",
+            RoleKind::Research => "research",
+            RoleKind::Devops => "devops",
+            RoleKind::Web => "web",
+        }
+    }
+
+    fn builtin(self) -> u64 {
+        match self {
+            RoleKind::Archive => 1208457364274028574,
+            RoleKind::Mobile => 1298553701094395936,
+            RoleKind::Systems => 1298553801191718944,
+            RoleKind::Ai => 1298553753523453952,
+            RoleKind::Research => 1298553855474270219,
+            RoleKind::Devops => 1298553883169132554,
+            RoleKind::Web => 1298553910167994428,
+        }
+    }
+}
+
+impl fmt::Display for RoleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} role", self.key())
+    }
+}
+
+/// The resolved ID of every channel and role kind.
+#[derive(Clone, Debug)]
+pub struct IdRegistry {
+    channels: BTreeMap<ChannelKind, ChannelId>,
+    roles: BTreeMap<RoleKind, RoleId>,
+    roles_message: MessageId,
+}
+
+impl IdRegistry {
+    /// The built-in IDs with `config` and then the `$setup` picks applied.
+    fn resolve(config: &IdsConfig, picked: &HashMap<String, NonZeroU64>) -> Self {
+        let channels = ChannelKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let id = picked
+                    .get(kind.settings_key())
+                    .or(config.channels.get(&kind))
+                    .map_or(ChannelId::new(kind.builtin()), |id| ChannelId::from(*id));
+                (kind, id)
+            })
+            .collect();
+        let roles = RoleKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let id = config
+                    .roles
+                    .get(&kind)
+                    .map_or(RoleId::new(kind.builtin()), |id| RoleId::from(*id));
+                (kind, id)
+            })
+            .collect();
+        let roles_message = picked
+            .get(ROLES_MESSAGE_KEY)
+            .copied()
+            .or(config.roles_message_id)
+            .map_or(MessageId::new(ROLES_MESSAGE_ID), MessageId::from);
+        Self {
+            channels,
+            roles,
+            roles_message,
+        }
+    }
+
+    pub fn channel(&self, kind: ChannelKind) -> ChannelId {
+        self.channels[&kind]
+    }
+
+    pub fn role(&self, kind: RoleKind) -> RoleId {
+        self.roles[&kind]
+    }
+
+    /// The message members react to for roles.
+    pub fn roles_message(&self) -> MessageId {
+        self.roles_message
+    }
+
+    pub fn channels(&self) -> impl Iterator<Item = (ChannelKind, ChannelId)> + '_ {
+        self.channels.iter().map(|(kind, id)| (*kind, *id))
+    }
+
+    pub fn roles(&self) -> impl Iterator<Item = (RoleKind, RoleId)> + '_ {
+        self.roles.iter().map(|(kind, id)| (*kind, *id))
+    }
+}
+
+/// Where the registry's IDs come from, kept to re-resolve it when either changes.
+struct Sources {
+    config: IdsConfig,
+    picked: HashMap<String, NonZeroU64>,
+    registry: IdRegistry,
+}
+
+static SOURCES: LazyLock<RwLock<Sources>> = LazyLock::new(|| {
+    let config = IdsConfig::default();
+    let picked = HashMap::new();
+    let registry = IdRegistry::resolve(&config, &picked);
+    RwLock::new(Sources {
+        config,
+        picked,
+        registry,
+    })
+});
+
+fn update(f: impl FnOnce(&mut Sources)) {
+    let mut sources = SOURCES.write().expect("ID registry lock poisoned");
+    f(&mut sources);
+    sources.registry = IdRegistry::resolve(&sources.config, &sources.picked);
+}
+
+/// The current IDs.
+pub fn registry() -> IdRegistry {
+    SOURCES
+        .read()
+        .expect("ID registry lock poisoned")
+        .registry
+        .clone()
+}
+
+pub fn channel(kind: ChannelKind) -> ChannelId {
+    SOURCES
+        .read()
+        .expect("ID registry lock poisoned")
+        .registry
+        .channel(kind)
+}
+
+pub fn role(kind: RoleKind) -> RoleId {
+    SOURCES
+        .read()
+        .expect("ID registry lock poisoned")
+        .registry
+        .role(kind)
+}
+
+/// The message members react to for roles.
+pub fn roles_message_id() -> MessageId {
+    SOURCES
+        .read()
+        .expect("ID registry lock poisoned")
+        .registry
+        .roles_message()
+}
+
+/// The status update channels by group number.
+pub fn group_channel_ids() -> [(u64, u64); 4] {
+    let registry = registry();
+    ChannelKind::GROUPS.map(|kind| {
+        let group = kind.group().expect("Group channel kinds have a group");
+        (group, registry.channel(kind).get())
+    })
+}
+
+/// Applies the `[ids]` config, on startup and after `$reload_config`.
+pub fn configure(config: &IdsConfig) {
+    update(|sources| sources.config = config.clone());
+}
+
+/// Loads the picked IDs and keeps them current as the settings change.
+pub async fn watch(settings: Arc<Settings>) -> anyhow::Result<()> {
+    let mut changes = settings.subscribe();
+    reload(&settings).await?;
+
+    tokio::spawn(async move {
+        loop {
+            match changes.recv().await {
+                Ok(change) if change.namespace == SETTINGS_NAMESPACE => {
+                    let id = change
+                        .value
+                        .as_ref()
+                        .and_then(|value| picked_id(&change.key, value));
+                    update(|sources| {
+                        match id {
+                            Some(id) => sources.picked.insert(change.key, id),
+                            None => sources.picked.remove(&change.key),
+                        };
+                    })
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => {
+                    if let Err(e) = reload(&settings).await {
+                        warn!("Failed to reload the picked IDs: {:?}", e);
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn reload(settings: &Settings) -> anyhow::Result<()> {
+    let namespaces = settings.all().await?;
+    let picked = namespaces
+        .get(SETTINGS_NAMESPACE)
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), picked_id(key, value)?)))
+        .collect();
+    update(|sources| sources.picked = picked);
+    Ok(())
+}
+
+/// The ID stored under `key`, or `None` with a warning if it isn't a valid ID. Discord
+/// IDs are never zero.
+fn picked_id(key: &str, value: &serde_json::Value) -> Option<NonZeroU64> {
+    let id = value.as_u64().and_then(NonZeroU64::new);
+    if id.is_none() {
+        warn!(
+            "Ignoring {}.{} = {}, not a valid ID",
+            SETTINGS_NAMESPACE, key, value
+        );
+    }
+    id
+}
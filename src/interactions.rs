@@ -0,0 +1,254 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::NaiveDate;
+use serenity::all::{
+    ButtonStyle, ComponentInteraction, Context as SerenityContext, CreateActionRow, CreateButton,
+    CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use tracing::{debug, error};
+
+use crate::{
+    announcements::{self, ANNOUNCEMENT_COMPONENT},
+    appeals::{self, appeal_button, APPEAL_COMPONENT},
+    history::{attendance_day, recent_status_update_days, status_update_day},
+    holidays::{self, HOLIDAYS_COMPONENT},
+    inventory::{self, INVENTORY_COMPONENT},
+    onboarding::{self, ONBOARDING_COMPONENT},
+    reset_approvals::{self, RESET_APPROVAL_COMPONENT},
+    role_drift::{self, ROLE_DRIFT_COMPONENT},
+    sessions::{self, SESSION_COMPONENT},
+    spotlight::{self, SPOTLIGHT_COMPONENT},
+    Data,
+};
+
+const STATUS_REPORT: &str = "status_report";
+const ATTENDANCE_REPORT: &str = "attendance_report";
+
+/// Number of days shown by the "Show streak history" button.
+const STREAK_HISTORY_DAYS: usize = 7;
+
+/// Buttons attached to the status update report for `date`.
+pub fn status_report_buttons(date: NaiveDate) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![
+        report_button(STATUS_REPORT, "nice_list", date, "Show nice list"),
+        report_button(STATUS_REPORT, "groups", date, "Show per-group breakdown"),
+        report_button(STATUS_REPORT, "history", date, "Show streak history"),
+        appeal_button(date, None),
+    ])
+}
+
+/// Buttons attached to the lab attendance report for `date`.
+pub fn attendance_report_buttons(date: NaiveDate) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![report_button(
+        ATTENDANCE_REPORT,
+        "present",
+        date,
+        "Show present members",
+    )])
+}
+
+fn report_button(report: &str, action: &str, date: NaiveDate, label: &str) -> CreateButton {
+    CreateButton::new(format!("{}:{}:{}", report, action, date))
+        .label(label)
+        .style(ButtonStyle::Secondary)
+}
+
+/// Routes component interactions (button clicks, select menus) to their handlers
+/// based on the prefix of their custom ID.
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    data: &Data,
+) {
+    debug!(
+        "Handling component interaction {}",
+        component.data.custom_id
+    );
+    let mut parts = component.data.custom_id.splitn(3, ':');
+    let (Some(report), Some(action), Some(arg)) = (parts.next(), parts.next(), parts.next()) else {
+        return;
+    };
+
+    let embed = match report {
+        STATUS_REPORT => status_report_drilldown(action, arg, data).await,
+        ATTENDANCE_REPORT => attendance_report_drilldown(action, arg, data).await,
+        SESSION_COMPONENT => {
+            return sessions::handle_component(ctx, component, action, arg, data).await
+        }
+        ONBOARDING_COMPONENT => {
+            return onboarding::handle_component(ctx, component, action, arg, data).await
+        }
+        APPEAL_COMPONENT => {
+            return appeals::handle_component(ctx, component, action, arg, data).await
+        }
+        INVENTORY_COMPONENT => {
+            return inventory::handle_component(ctx, component, action, arg, data).await
+        }
+        SPOTLIGHT_COMPONENT => {
+            return spotlight::handle_component(ctx, component, action, arg, data).await
+        }
+        HOLIDAYS_COMPONENT => {
+            return holidays::handle_component(ctx, component, action, arg, data).await
+        }
+        ANNOUNCEMENT_COMPONENT => {
+            return announcements::handle_component(ctx, component, action, arg, data).await
+        }
+        ROLE_DRIFT_COMPONENT => {
+            return role_drift::handle_component(ctx, component, action, arg, data).await
+        }
+        RESET_APPROVAL_COMPONENT => {
+            return reset_approvals::handle_component(ctx, component, action, arg, data).await
+        }
+        _ => return,
+    };
+
+    let embed = embed.unwrap_or_else(|e| {
+        error!(
+            "Failed to build drill-down for {}: {:?}",
+            component.data.custom_id, e
+        );
+        CreateEmbed::new().description("Something went wrong while fetching this report.")
+    });
+
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .ephemeral(true),
+    );
+    if let Err(e) = component.create_response(&ctx.http, response).await {
+        error!("Failed to respond to component interaction: {}", e);
+    }
+}
+
+async fn status_report_drilldown(
+    action: &str,
+    date: &str,
+    data: &Data,
+) -> anyhow::Result<CreateEmbed> {
+    let date: NaiveDate = date.parse()?;
+    let Some(day) = status_update_day(&data.storage, date).await? else {
+        return Ok(missing_day_embed(date));
+    };
+
+    let embed = match action {
+        "nice_list" => {
+            let mut description = String::new();
+            for member in day.senders() {
+                description.push_str(&format!("- {}\n", member.name));
+            }
+            if description.is_empty() {
+                description.push_str("Nobody sent an update.");
+            }
+            CreateEmbed::new()
+                .title(format!("Nice List - {}", date))
+                .description(description)
+        }
+        "groups" => {
+            let mut groups: Vec<i32> = day.members.iter().map(|m| m.group_id).collect();
+            groups.sort_unstable();
+            groups.dedup();
+
+            let mut description = String::new();
+            for group in groups {
+                let members: Vec<_> = day.members.iter().filter(|m| m.group_id == group).collect();
+                let sent = members.iter().filter(|m| m.sent_update).count();
+                description.push_str(&format!(
+                    "## Group {} ({}/{})\n",
+                    group,
+                    sent,
+                    members.len()
+                ));
+                for member in members {
+                    let status = if member.sent_update {
+                        ":white_check_mark:"
+                    } else {
+                        ":x:"
+                    };
+                    description.push_str(&format!("- {} | {}\n", member.name, status));
+                }
+            }
+            CreateEmbed::new()
+                .title(format!("Per-Group Breakdown - {}", date))
+                .description(description)
+        }
+        "history" => {
+            let days = recent_status_update_days(&data.storage, STREAK_HISTORY_DAYS).await?;
+            let mut description = String::new();
+            for day in days.iter().filter(|d| d.date <= date) {
+                description.push_str(&format!(
+                    "- **{}**: {}/{} updates, average streak {:.1}\n",
+                    day.date,
+                    day.senders().count(),
+                    day.members.len(),
+                    day.average_streak()
+                ));
+            }
+            CreateEmbed::new()
+                .title("Streak History")
+                .description(description)
+        }
+        _ => return Err(anyhow::anyhow!("Unknown status report action {}", action)),
+    };
+
+    Ok(embed)
+}
+
+async fn attendance_report_drilldown(
+    action: &str,
+    date: &str,
+    data: &Data,
+) -> anyhow::Result<CreateEmbed> {
+    let date: NaiveDate = date.parse()?;
+    let Some(day) = attendance_day(&data.storage, date).await? else {
+        return Ok(missing_day_embed(date));
+    };
+
+    match action {
+        "present" => {
+            let mut description = String::new();
+            for year in 1..=3 {
+                let names: Vec<&str> = day
+                    .records
+                    .iter()
+                    .filter(|r| r.is_present && r.year == year)
+                    .map(|r| r.name.as_str())
+                    .collect();
+                if !names.is_empty() {
+                    description.push_str(&format!("### Year {}\n", year));
+                    for name in names {
+                        description.push_str(&format!("- {}\n", name));
+                    }
+                }
+            }
+            if description.is_empty() {
+                description.push_str("Nobody was present.");
+            }
+            Ok(CreateEmbed::new()
+                .title(format!("Present Members - {}", date))
+                .description(description))
+        }
+        _ => Err(anyhow::anyhow!(
+            "Unknown attendance report action {}",
+            action
+        )),
+    }
+}
+
+fn missing_day_embed(date: NaiveDate) -> CreateEmbed {
+    CreateEmbed::new().description(format!("No stored results for {}.", date))
+}
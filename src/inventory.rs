@@ -0,0 +1,331 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ChannelId, ComponentInteraction, Context as SerenityContext, CreateActionRow,
+    CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+    Permissions, UserId,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::InventoryItem,
+    preferences::{allows, Notification},
+    storage::Storage,
+    utils::permissions::clicker_has,
+    Data,
+};
+
+/// Custom ID prefix of the checkout approval buttons, routed here by [`crate::interactions`].
+pub const INVENTORY_COMPONENT: &str = "inventory";
+const CHECKOUTS_KEY: &str = "inventory.checkouts";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CheckoutStatus {
+    /// Waiting for a mentor to approve, only for items that need approval.
+    Pending,
+    Out,
+    Returned,
+    Rejected,
+}
+
+/// A member borrowing one unit of an item, from the request until it's returned.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkout {
+    pub id: u64,
+    pub item: String,
+    pub user_id: u64,
+    pub requested_at: DateTime<Utc>,
+    pub status: CheckoutStatus,
+    pub approved_by: Option<u64>,
+}
+
+impl Checkout {
+    /// Whether the checkout holds a unit of the item, or will once approved.
+    fn is_outstanding(&self) -> bool {
+        matches!(self.status, CheckoutStatus::Pending | CheckoutStatus::Out)
+    }
+}
+
+pub enum CheckoutOutcome {
+    Out,
+    /// Posted for approval, [`None`] if there is nowhere to post it.
+    Pending(Option<u64>),
+    Unavailable,
+    AlreadyHolding,
+}
+
+pub async fn checkouts(storage: &Storage) -> anyhow::Result<Vec<Checkout>> {
+    storage.get(CHECKOUTS_KEY).await
+}
+
+/// How many units of each item are out or waiting for approval.
+pub async fn outstanding_counts(storage: &Storage) -> anyhow::Result<BTreeMap<String, u32>> {
+    let mut counts = BTreeMap::new();
+    for checkout in checkouts(storage).await? {
+        if checkout.is_outstanding() {
+            *counts.entry(checkout.item).or_default() += 1;
+        }
+    }
+    Ok(counts)
+}
+
+/// Items each member has out or waiting for approval, keyed by Discord ID.
+pub async fn outstanding_by_member(
+    storage: &Storage,
+) -> anyhow::Result<BTreeMap<u64, Vec<Checkout>>> {
+    let mut by_member: BTreeMap<u64, Vec<Checkout>> = BTreeMap::new();
+    for checkout in checkouts(storage).await? {
+        if checkout.is_outstanding() {
+            by_member
+                .entry(checkout.user_id)
+                .or_default()
+                .push(checkout);
+        }
+    }
+    Ok(by_member)
+}
+
+/// Records a checkout of `item` for `user_id`. Items that need approval are posted to
+/// the approval channel and only count as out once a mentor approves.
+pub async fn request_checkout(
+    ctx: &SerenityContext,
+    data: &Data,
+    item: &InventoryItem,
+    user_id: UserId,
+) -> anyhow::Result<CheckoutOutcome> {
+    let status = if item.requires_approval {
+        CheckoutStatus::Pending
+    } else {
+        CheckoutStatus::Out
+    };
+    let checkout = data
+        .storage
+        .update(CHECKOUTS_KEY, |checkouts: &mut Vec<Checkout>| {
+            let outstanding: Vec<&Checkout> = checkouts
+                .iter()
+                .filter(|c| c.item == item.name && c.is_outstanding())
+                .collect();
+            if outstanding.iter().any(|c| c.user_id == user_id.get()) {
+                return Err(CheckoutOutcome::AlreadyHolding);
+            }
+            if outstanding.len() as u32 >= item.quantity {
+                return Err(CheckoutOutcome::Unavailable);
+            }
+            let checkout = Checkout {
+                id: checkouts.iter().map(|c| c.id).max().unwrap_or(0) + 1,
+                item: item.name.clone(),
+                user_id: user_id.get(),
+                requested_at: Utc::now(),
+                status,
+                approved_by: None,
+            };
+            checkouts.push(checkout.clone());
+            Ok(checkout)
+        })
+        .await?;
+    let checkout = match checkout {
+        Ok(checkout) => checkout,
+        Err(outcome) => return Ok(outcome),
+    };
+    info!(
+        "Checkout #{} of {} requested by {}",
+        checkout.id, checkout.item, user_id
+    );
+    if checkout.status == CheckoutStatus::Out {
+        return Ok(CheckoutOutcome::Out);
+    }
+
+    let config = data.config.read().await.clone();
+    let Some(channel_id) = config
+        .inventory
+        .approval_channel_id
+        .or(config.bot.ops_channel_id)
+    else {
+        warn!(
+            "No approval channel configured, checkout #{} is pending",
+            checkout.id
+        );
+        return Ok(CheckoutOutcome::Pending(None));
+    };
+    let buttons = CreateActionRow::Buttons(vec![
+        inventory_button("approve", checkout.id, "Approve", ButtonStyle::Success),
+        inventory_button("reject", checkout.id, "Reject", ButtonStyle::Danger),
+    ]);
+    let message = CreateMessage::new()
+        .content(format!(
+            "<@{}> wants to check out **{}** (checkout #{}).",
+            checkout.user_id, checkout.item, checkout.id
+        ))
+        .components(vec![buttons]);
+    if let Err(e) = ChannelId::new(channel_id)
+        .send_message(&ctx.http, message)
+        .await
+    {
+        // Nobody could approve it, so it mustn't hold a unit either.
+        data.storage
+            .update(CHECKOUTS_KEY, |checkouts: &mut Vec<Checkout>| {
+                checkouts.retain(|c| c.id != checkout.id)
+            })
+            .await?;
+        return Err(e.into());
+    }
+
+    Ok(CheckoutOutcome::Pending(Some(channel_id)))
+}
+
+/// Marks the member's checkout of `item` as returned, or withdraws it if it was still
+/// waiting for approval. Returns `false` if they didn't have it.
+pub async fn return_item(storage: &Storage, user_id: u64, item: &str) -> anyhow::Result<bool> {
+    storage
+        .update(CHECKOUTS_KEY, |checkouts: &mut Vec<Checkout>| {
+            let checkout = checkouts
+                .iter_mut()
+                .find(|c| c.user_id == user_id && c.item == item && c.is_outstanding());
+            match checkout {
+                Some(checkout) => {
+                    checkout.status = CheckoutStatus::Returned;
+                    true
+                }
+                None => false,
+            }
+        })
+        .await
+}
+
+/// Drops the member's returned and rejected checkouts. Items they still hold are kept
+/// so the hardware can be tracked down.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(CHECKOUTS_KEY, |checkouts: &mut Vec<Checkout>| {
+            checkouts.retain(|c| c.user_id != user_id || c.is_outstanding())
+        })
+        .await
+}
+
+fn inventory_button(action: &str, id: u64, label: &str, style: ButtonStyle) -> CreateButton {
+    CreateButton::new(format!("{}:{}:{}", INVENTORY_COMPONENT, action, id))
+        .label(label)
+        .style(style)
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    arg: &str,
+    data: &Data,
+) {
+    let Ok(id) = arg.parse() else {
+        return;
+    };
+    let result = match action {
+        "approve" => review_checkout(ctx, component, id, true, data).await,
+        "reject" => review_checkout(ctx, component, id, false, data).await,
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        error!(
+            "Failed to handle inventory interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+async fn review_checkout(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    id: u64,
+    approved: bool,
+    data: &Data,
+) -> anyhow::Result<()> {
+    if !clicker_has(component, Permissions::MANAGE_GUILD) {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Only mentors can review checkouts.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    }
+
+    let reviewer = component.user.id.get();
+    let checkout = data
+        .storage
+        .update(CHECKOUTS_KEY, |checkouts: &mut Vec<Checkout>| {
+            let checkout = checkouts
+                .iter_mut()
+                .find(|c| c.id == id && c.status == CheckoutStatus::Pending)?;
+            if approved {
+                checkout.status = CheckoutStatus::Out;
+                checkout.approved_by = Some(reviewer);
+            } else {
+                checkout.status = CheckoutStatus::Rejected;
+            }
+            Some(checkout.clone())
+        })
+        .await?;
+    let Some(checkout) = checkout else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("This checkout was already reviewed or withdrawn.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    };
+
+    let outcome = if approved { "Approved" } else { "Rejected" };
+    info!("Checkout #{} {} by {}", id, outcome, component.user.name);
+    let response = CreateInteractionResponseMessage::new()
+        .content(format!(
+            "<@{}>'s checkout of **{}**: {} by {}.",
+            checkout.user_id,
+            checkout.item,
+            outcome.to_lowercase(),
+            component.user.name
+        ))
+        .components(vec![]);
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(response),
+        )
+        .await?;
+
+    if !allows(&data.storage, checkout.user_id, Notification::Decisions).await {
+        return Ok(());
+    }
+    let dm = CreateMessage::new().content(format!(
+        "Your checkout of {} was {}.",
+        checkout.item,
+        outcome.to_lowercase()
+    ));
+    if let Err(e) = UserId::new(checkout.user_id)
+        .direct_message(&ctx.http, dm)
+        .await
+    {
+        warn!("Failed to DM the member of checkout #{}: {}", id, e);
+    }
+
+    Ok(())
+}
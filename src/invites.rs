@@ -0,0 +1,217 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    Context as SerenityContext, GuildId, InviteCreateEvent, InviteDeleteEvent, Member,
+};
+use tracing::{debug, info, warn};
+
+use crate::{storage::Storage, Data};
+
+/// Last known use count of every invite, per guild.
+const INVITES_KEY: &str = "invites.uses";
+const JOINS_KEY: &str = "invites.joins";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TrackedInvite {
+    inviter_id: Option<u64>,
+    uses: u64,
+}
+
+/// A member joining, attributed to the invite they used when it could be told apart.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Join {
+    pub guild_id: u64,
+    pub user_id: u64,
+    pub code: Option<String>,
+    pub inviter_id: Option<u64>,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// Takes a fresh snapshot of the guild's invites, called when the guild becomes available
+/// so joins can be attributed from the start.
+pub async fn snapshot_invites(ctx: &SerenityContext, data: &Data, guild_id: GuildId) {
+    match fetch_invites(ctx, guild_id).await {
+        Ok(invites) => {
+            debug!("Tracking {} invites of guild {}", invites.len(), guild_id);
+            if let Err(e) = data
+                .storage
+                .update(
+                    INVITES_KEY,
+                    |all: &mut HashMap<u64, HashMap<String, TrackedInvite>>| {
+                        all.insert(guild_id.get(), invites);
+                    },
+                )
+                .await
+            {
+                warn!("Failed to store invites of guild {}: {:?}", guild_id, e);
+            }
+        }
+        Err(e) => warn!("Failed to fetch invites of guild {}: {}", guild_id, e),
+    }
+}
+
+pub async fn record_invite_created(data: &Data, event: &InviteCreateEvent) {
+    let Some(guild_id) = event.guild_id else {
+        return;
+    };
+    let invite = TrackedInvite {
+        inviter_id: event.inviter.as_ref().map(|user| user.id.get()),
+        uses: event.uses,
+    };
+    if let Err(e) = data
+        .storage
+        .update(
+            INVITES_KEY,
+            |all: &mut HashMap<u64, HashMap<String, TrackedInvite>>| {
+                all.entry(guild_id.get())
+                    .or_default()
+                    .insert(event.code.clone(), invite);
+            },
+        )
+        .await
+    {
+        warn!("Failed to track invite {}: {:?}", event.code, e);
+    }
+}
+
+pub async fn record_invite_deleted(data: &Data, event: &InviteDeleteEvent) {
+    let Some(guild_id) = event.guild_id else {
+        return;
+    };
+    if let Err(e) = data
+        .storage
+        .update(
+            INVITES_KEY,
+            |all: &mut HashMap<u64, HashMap<String, TrackedInvite>>| {
+                if let Some(invites) = all.get_mut(&guild_id.get()) {
+                    invites.remove(&event.code);
+                }
+            },
+        )
+        .await
+    {
+        warn!("Failed to untrack invite {}: {:?}", event.code, e);
+    }
+}
+
+/// Attributes a new member to the invite whose use count went up since the last snapshot.
+/// Joins through the vanity URL, or several joins at once, are recorded without an invite.
+pub async fn record_join(ctx: &SerenityContext, data: &Data, member: &Member) {
+    if member.user.bot {
+        return;
+    }
+    let current = match fetch_invites(ctx, member.guild_id).await {
+        Ok(invites) => invites,
+        Err(e) => {
+            warn!(
+                "Failed to fetch invites for {}'s join: {}",
+                member.user.name, e
+            );
+            HashMap::new()
+        }
+    };
+
+    let guild_id = member.guild_id.get();
+    let result = data
+        .storage
+        .update(
+            INVITES_KEY,
+            |all: &mut HashMap<u64, HashMap<String, TrackedInvite>>| {
+                let previous = all.remove(&guild_id).unwrap_or_default();
+                let used: Vec<(&String, &TrackedInvite)> = current
+                    .iter()
+                    .filter(|(code, invite)| {
+                        invite.uses > previous.get(*code).map_or(0, |p| p.uses)
+                    })
+                    .collect();
+                let attribution = match used.as_slice() {
+                    [(code, invite)] => Some(((*code).clone(), invite.inviter_id)),
+                    _ => None,
+                };
+                all.insert(guild_id, current.clone());
+                attribution
+            },
+        )
+        .await;
+    let attribution = match result {
+        Ok(attribution) => attribution,
+        Err(e) => {
+            warn!("Failed to compare invites: {:?}", e);
+            None
+        }
+    };
+
+    let join = Join {
+        guild_id,
+        user_id: member.user.id.get(),
+        code: attribution.as_ref().map(|(code, _)| code.clone()),
+        inviter_id: attribution.and_then(|(_, inviter)| inviter),
+        joined_at: Utc::now(),
+    };
+    info!(
+        "{} joined through invite {}",
+        member.user.name,
+        join.code.as_deref().unwrap_or("unknown")
+    );
+    if let Err(e) = data
+        .storage
+        .update(JOINS_KEY, |joins: &mut Vec<Join>| joins.push(join))
+        .await
+    {
+        warn!("Failed to record {}'s join: {:?}", member.user.name, e);
+    }
+}
+
+pub async fn joins_since(storage: &Storage, since: DateTime<Utc>) -> anyhow::Result<Vec<Join>> {
+    let joins: Vec<Join> = storage.get(JOINS_KEY).await?;
+    Ok(joins.into_iter().filter(|j| j.joined_at >= since).collect())
+}
+
+/// Drops the member's own joins and removes them as the inviter of others.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(JOINS_KEY, |joins: &mut Vec<Join>| {
+            joins.retain(|j| j.user_id != user_id);
+            for join in joins.iter_mut().filter(|j| j.inviter_id == Some(user_id)) {
+                join.inviter_id = None;
+            }
+        })
+        .await
+}
+
+async fn fetch_invites(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+) -> serenity::Result<HashMap<String, TrackedInvite>> {
+    Ok(guild_id
+        .invites(&ctx.http)
+        .await?
+        .into_iter()
+        .map(|invite| {
+            let tracked = TrackedInvite {
+                inviter_id: invite.inviter.map(|user| user.id.get()),
+                uses: invite.uses,
+            };
+            (invite.code, tracked)
+        })
+        .collect())
+}
@@ -0,0 +1,87 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::Storage;
+
+const KUDOS_KEY: &str = "kudos.given";
+/// Colour of the kudos feed and the monthly tally.
+pub const KUDOS_COLOR: u32 = 0xe91e63;
+/// Kudos older than this are dropped, the monthly tally only needs the last month.
+const RETENTION_DAYS: i64 = 62;
+
+/// Appreciation one member gave another with `$kudos`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Kudos {
+    pub from: u64,
+    pub to: u64,
+    pub reason: String,
+    pub given_at: DateTime<Utc>,
+}
+
+/// Records `kudos` unless its giver already gave `daily_limit` in the last day. Returns
+/// whether it was recorded.
+pub async fn give(storage: &Storage, kudos: Kudos, daily_limit: usize) -> anyhow::Result<bool> {
+    storage
+        .update(KUDOS_KEY, |given: &mut Vec<Kudos>| {
+            let cutoff = Utc::now() - Duration::days(RETENTION_DAYS);
+            given.retain(|k| k.given_at >= cutoff);
+
+            let day_ago = kudos.given_at - Duration::days(1);
+            let given_today = given
+                .iter()
+                .filter(|k| k.from == kudos.from && k.given_at > day_ago)
+                .count();
+            if given_today >= daily_limit {
+                return false;
+            }
+            given.push(kudos.clone());
+            true
+        })
+        .await
+}
+
+/// How many kudos each member received between `from` and `to`, most appreciated first.
+pub async fn tally(
+    storage: &Storage,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> anyhow::Result<Vec<(u64, usize)>> {
+    let given: Vec<Kudos> = storage.get(KUDOS_KEY).await?;
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for kudos in given
+        .iter()
+        .filter(|k| k.given_at >= from && k.given_at < to)
+    {
+        *counts.entry(kudos.to).or_default() += 1;
+    }
+    let mut ranking: Vec<(u64, usize)> = counts.into_iter().collect();
+    ranking.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    Ok(ranking)
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(KUDOS_KEY, |given: &mut Vec<Kudos>| {
+            given.retain(|k| k.from != user_id && k.to != user_id)
+        })
+        .await
+}
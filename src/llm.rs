@@ -0,0 +1,68 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::{anyhow, Context as _};
+use serde_json::json;
+use tracing::debug;
+
+use crate::config::LlmConfig;
+
+/// Sends a single prompt to the configured OpenAI-compatible chat completions endpoint
+/// and returns the model's reply. The API key is read from `LLM_API_KEY`, if set.
+pub async fn complete(config: &LlmConfig, system: &str, prompt: &str) -> anyhow::Result<String> {
+    let endpoint = config
+        .endpoint
+        .as_ref()
+        .ok_or_else(|| anyhow!("No LLM endpoint configured"))?;
+
+    let client = reqwest::Client::new();
+    let mut request = client.post(endpoint).json(&json!({
+        "model": config.model,
+        "messages": [
+            { "role": "system", "content": system },
+            { "role": "user", "content": prompt },
+        ],
+    }));
+    if let Ok(api_key) = std::env::var("LLM_API_KEY") {
+        request = request.bearer_auth(api_key);
+    }
+
+    debug!(
+        "Sending prompt of {} characters to {}",
+        prompt.len(),
+        endpoint
+    );
+    let response = request
+        .send()
+        .await
+        .context("Failed to post prompt to LLM endpoint")?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "LLM endpoint responded with an error: {:?}",
+            response.status()
+        ));
+    }
+
+    let response_json: serde_json::Value = response
+        .json()
+        .await
+        .context("Failed to parse LLM response")?;
+    response_json["choices"][0]["message"]["content"]
+        .as_str()
+        .map(|content| content.trim().to_string())
+        .ok_or_else(|| anyhow!("Malformed LLM response: {}", response_json))
+}
@@ -15,41 +15,152 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
+/// Event-driven message counters for the group channels.
+mod activity;
+/// Announcements members can RSVP to, with the attendee list sent to the organizer.
+mod announcements;
+/// Defaulters' appeals against missed status updates, reviewed by mentors.
+mod appeals;
+/// Encrypted backups of the persistent storage.
+mod backup;
+/// Posting hours for channels, enforced through permission overwrites.
+mod channel_locks;
+/// Renders PNG charts for reports and commands.
+mod charts;
+/// Code-based lab check-ins for days when the attendance hardware is down.
+mod checkins;
+/// Monthly counts of which commands are run, by whom and how often they fail.
+mod command_usage;
 mod commands;
+/// Deployment configuration such as report theming, loaded from a TOML file.
+mod config;
+/// Primary/canary handshake that keeps two running versions from both sending.
+mod deployment;
+/// The error type of commands, categorized by the subsystem that failed.
+mod error;
+/// Discord scheduled events for recurring lab hours and meetings.
+mod events;
 mod graphql;
+/// Daily results of the report tasks, kept in [`storage::Storage`].
+mod history;
+/// Holidays on which member checks are skipped, imported from a public calendar.
+mod holidays;
+/// Channel and message IDs, picked with `$setup` or falling back to built-in defaults.
 mod ids;
+/// Routes button and select menu interactions to their handlers.
+mod interactions;
+/// Lab hardware members borrow, with mentor approval for expensive items.
+mod inventory;
+/// Attributes new members to the invite they joined through.
+mod invites;
+/// Appreciation members give each other with `$kudos`.
+mod kudos;
+/// Minimal client for an OpenAI-compatible chat completions endpoint.
+mod llm;
+/// Pushes daily KPIs to an external metrics sink such as a webhook or Prometheus Pushgateway.
+mod metrics;
+/// Versioned migrations of the stored data, run at startup.
+mod migrations;
+/// Button-driven rules quiz that grants newcomers the Member role.
+mod onboarding;
+/// Weekly on-call rotation of mentors, who get pinged when something needs attention.
+mod oncall;
+/// Uploads code shared in chat to a paste service.
+mod paste;
+/// Activity points members earn and spend on streak shields.
+mod points;
+/// Which kinds of DMs each member wants to receive.
+mod preferences;
+/// Erasure of a member's locally stored data on request.
+mod privacy;
+/// Holds back non-urgent messages during quiet hours and sends them in one batch.
+mod quiet_hours;
 mod reaction_roles;
+/// Streak resets held for a mentor's approval after the status update check.
+mod reset_approvals;
+/// Weekly audit of group roles that drifted from the members' groups on Root.
+mod role_drift;
+/// Restores a member's roles when they rejoin after leaving.
+mod role_snapshots;
+/// Correlation IDs for task runs, attached to their logs and requests to Root.
+mod run_id;
 /// This module is a simple cron equivalent. It spawns threads for the [`Task`]s that need to be completed.
 mod scheduler;
+/// Terms during which members are checked, with the checks paused in between.
+mod semester;
+/// Weekly talk proposals, approvals and RSVPs.
+mod sessions;
+/// Runtime tunables by namespace and key, changed with `$set`.
+mod settings;
+/// Per-shard connection health and disconnect alerts.
+mod shards;
+/// Weekly spotlight of the intros new members write when prompted.
+mod spotlight;
+/// Validates the environment at boot and reports misconfigured channels and roles.
+mod startup_checks;
+/// Persistent key-value storage backed by a JSON file.
+mod storage;
+/// Shared engine for streaks: extending, freezing, resetting and restoring them.
+mod streaks;
 /// A trait to define a job that needs to be executed regularly, for example checking for status updates daily.
 mod tasks;
+/// Tera templates for report bodies, overridable from the config.
+mod templates;
 mod utils;
+/// Keyword watchers that alert a role when members report something urgent.
+mod watchers;
+/// XP and levels earned through club activity.
+mod xp;
 
 use anyhow::Context as _;
+use ids::RoleKind;
 use poise::{Context as PoiseContext, Framework, FrameworkOptions, PrefixFrameworkOptions};
-use reaction_roles::{handle_reaction, populate_data_with_reaction_roles};
+use reaction_roles::{handle_reaction, populate_data_with_reaction_roles, EmojiKey};
+use scheduler::SchedulerState;
 use serenity::{
-    all::{ReactionType, RoleId, UserId},
+    all::{Interaction, UserId},
     client::{Context as SerenityContext, FullEvent},
-    model::gateway::GatewayIntents,
 };
-use tokio::sync::RwLock;
-use tracing::info;
-use tracing_subscriber::{fmt, layer::SubscriberExt, reload, EnvFilter, Registry};
+use shards::ShardHealth;
+use tokio::sync::{Notify, RwLock};
+use tracing::{error, info, warn};
+use tracing_subscriber::{
+    filter::filter_fn,
+    fmt,
+    layer::{Layer, SubscriberExt},
+    reload, EnvFilter, Registry,
+};
 
 use std::{
     collections::{HashMap, HashSet},
     fs::File,
     sync::Arc,
+    time::Instant,
 };
 
-pub type Error = Box<dyn std::error::Error + Send + Sync>;
+use config::{Config, DeploymentMode};
+use deployment::Deployment;
+use error::AmdError;
+use settings::Settings;
+use storage::Storage;
+use utils::time;
+
+pub type Error = AmdError;
 pub type Context<'a> = PoiseContext<'a, Data, Error>;
 pub type ReloadHandle = Arc<RwLock<reload::Handle<EnvFilter, Registry>>>;
 
+#[derive(Clone)]
 pub struct Data {
-    pub reaction_roles: HashMap<ReactionType, RoleId>,
+    pub reaction_roles: HashMap<EmojiKey, RoleKind>,
     pub log_reload_handle: ReloadHandle,
+    pub storage: Arc<Storage>,
+    pub config: Arc<RwLock<Config>>,
+    /// Notified when a task's schedule changes so the scheduler recomputes its next run.
+    pub schedule_changed: Arc<Notify>,
+    pub scheduler: Arc<SchedulerState>,
+    pub shard_health: Arc<ShardHealth>,
+    pub deployment: Arc<Deployment>,
+    pub settings: Arc<Settings>,
 }
 
 fn setup_tracing() -> anyhow::Result<ReloadHandle> {
@@ -73,26 +184,38 @@ fn setup_tracing() -> anyhow::Result<ReloadHandle> {
         },
     ));
 
+    // The filter applies to the logs only, Discord requests are timed whatever the level.
     if env != "production" {
         let subscriber = tracing_subscriber::registry()
-            .with(filter)
-            .with(fmt::layer().pretty().with_writer(std::io::stdout))
             .with(
                 fmt::layer()
                     .pretty()
-                    .with_ansi(false)
-                    .with_writer(File::create("amd.log").context("Failed to create subscriber")?),
+                    .with_writer(std::io::stdout)
+                    .and_then(fmt::layer().pretty().with_ansi(false).with_writer(
+                        File::create("amd.log").context("Failed to create subscriber")?,
+                    ))
+                    .with_filter(filter),
+            )
+            .with(
+                metrics::DiscordRequests
+                    .with_filter(filter_fn(metrics::DiscordRequests::is_request)),
             );
 
         tracing::subscriber::set_global_default(subscriber).context("Failed to set subscriber")?;
         Ok(Arc::new(RwLock::new(reload_handle)))
     } else {
-        let subscriber = tracing_subscriber::registry().with(filter).with(
-            fmt::layer()
-                .pretty()
-                .with_ansi(false)
-                .with_writer(File::create("amd.log").context("Failed to create subscriber")?),
-        );
+        let subscriber = tracing_subscriber::registry()
+            .with(
+                fmt::layer()
+                    .pretty()
+                    .with_ansi(false)
+                    .with_writer(File::create("amd.log").context("Failed to create subscriber")?)
+                    .with_filter(filter),
+            )
+            .with(
+                metrics::DiscordRequests
+                    .with_filter(filter_fn(metrics::DiscordRequests::is_request)),
+            );
 
         tracing::subscriber::set_global_default(subscriber).context("Failed to set subscriber")?;
         Ok(Arc::new(RwLock::new(reload_handle)))
@@ -102,14 +225,42 @@ fn setup_tracing() -> anyhow::Result<ReloadHandle> {
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     dotenv::dotenv().ok();
+    startup_checks::check_env()?;
     let reload_handle = setup_tracing().context("Failed to setup tracing")?;
 
     info!("Tracing initialized. Continuing main...");
+    let storage_path =
+        std::env::var("STORAGE_PATH").unwrap_or_else(|_| String::from("amd_state.json"));
+    let config = Config::load().context("Failed to load config")?;
+    let storage = Storage::open(storage_path).context("Failed to open storage")?;
+    // A canary shares the primary's storage and leaves writing it to the primary.
+    storage.set_read_only(config.deployment.mode == DeploymentMode::Canary);
+    migrations::run_migrations(&storage)
+        .await
+        .context("Failed to migrate storage")?;
+
+    let intents = config.features.intents();
+    info!("Requesting gateway intents {:?}", intents);
+
+    ids::configure(&config.ids);
+    time::configure(config.bot.timezone);
+    let deployment = Arc::new(Deployment::new(&config.deployment));
+    let storage = Arc::new(storage);
     let mut data = Data {
         reaction_roles: HashMap::new(),
         log_reload_handle: reload_handle,
+        settings: Arc::new(Settings::new(storage.clone())),
+        storage,
+        config: Arc::new(RwLock::new(config)),
+        schedule_changed: Arc::new(Notify::new()),
+        scheduler: Arc::new(SchedulerState::default()),
+        shard_health: Arc::new(ShardHealth::default()),
+        deployment,
     };
     populate_data_with_reaction_roles(&mut data);
+    ids::watch(data.settings.clone())
+        .await
+        .context("Failed to load the picked channel IDs")?;
 
     let discord_token =
         std::env::var("DISCORD_TOKEN").context("DISCORD_TOKEN was not found in the ENV")?;
@@ -125,8 +276,19 @@ async fn main() -> Result<(), Error> {
             event_handler: |ctx, event, framework, data| {
                 Box::pin(event_handler(ctx, event, framework, data))
             },
+            pre_command: |ctx| {
+                Box::pin(async move { ctx.set_invocation_data(Instant::now()).await })
+            },
+            post_command: |ctx| Box::pin(record_command(ctx, true)),
+            // A canary leaves commands to the primary while it's running.
+            command_check: Some(|ctx| {
+                Box::pin(async move { Ok(deployment::is_active(ctx.data())) })
+            }),
+            on_error: |error| Box::pin(on_error(error)),
             prefix_options: PrefixFrameworkOptions {
-                prefix: Some(String::from("$")),
+                stripped_dynamic_prefix: Some(|_ctx, msg, data| {
+                    Box::pin(async move { Ok(commands::prefix::strip_prefix(data, msg).await?) })
+                }),
                 ..Default::default()
             },
             owners: HashSet::from([owner_user_id]),
@@ -135,22 +297,21 @@ async fn main() -> Result<(), Error> {
         .setup(|ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
-                scheduler::run_scheduler(ctx.clone()).await;
+                deployment::start(&data).await;
+                tokio::spawn(startup_checks::report_health(ctx.clone(), data.clone()));
+                scheduler::run_scheduler(ctx.clone(), data.clone()).await;
                 Ok(data)
             })
         })
         .build();
 
-    let mut client = serenity::client::ClientBuilder::new(
-        discord_token,
-        GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT,
-    )
-    .framework(framework)
-    .await
-    .context("Failed to create the Serenity client")?;
+    let mut client = serenity::client::ClientBuilder::new(discord_token, intents)
+        .framework(framework)
+        .await
+        .context("Failed to create the Serenity client")?;
 
     client
-        .start()
+        .start_autosharded()
         .await
         .context("Failed to start the Serenity client")?;
 
@@ -159,19 +320,129 @@ async fn main() -> Result<(), Error> {
     Ok(())
 }
 
+/// Records the latency of a finished command in the metrics registry, and the run in
+/// the monthly usage counts.
+async fn record_command(ctx: Context<'_>, succeeded: bool) {
+    let Some(started) = ctx.invocation_data::<Instant>().await.map(|s| *s) else {
+        return;
+    };
+    let command = &ctx.command().qualified_name;
+    metrics::record_call(
+        &format!("command.{}", command),
+        started.elapsed(),
+        succeeded,
+    );
+    let user_id = ctx.author().id.get();
+    if let Err(e) = command_usage::record(&ctx.data().storage, command, user_id, succeeded).await {
+        warn!("Failed to record usage of {}: {:?}", command, e);
+    }
+}
+
+async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
+    match &error {
+        poise::FrameworkError::Command { error, ctx, .. } => {
+            record_command(*ctx, false).await;
+            metrics::record_command_error(error.category());
+            error!(
+                "Command {} failed ({}): {}",
+                ctx.command().qualified_name,
+                error.category(),
+                error
+            );
+            let reply = match error {
+                AmdError::Root(_) => {
+                    String::from("Root isn't responding right now, try again in a few minutes.")
+                }
+                _ => format!("Something went wrong: {}", error),
+            };
+            if let Err(e) = ctx.say(reply).await {
+                error!("Failed to report the command error: {}", e);
+            }
+            return;
+        }
+        // A canary standing by for the primary, nothing went wrong.
+        poise::FrameworkError::CommandCheckFailed { error: None, .. } => return,
+        _ => {}
+    }
+    if let Err(e) = poise::builtins::on_error(error).await {
+        error!("Failed to handle framework error: {}", e);
+    }
+}
+
 async fn event_handler(
     ctx: &SerenityContext,
     event: &FullEvent,
     _framework: poise::FrameworkContext<'_, Data, Error>,
     data: &Data,
 ) -> Result<(), Error> {
+    let features = data.config.read().await.features.clone();
+    if let FullEvent::Message { new_message } = event {
+        if features.message_scanning {
+            activity::record_message(data, new_message).await;
+        }
+    }
+    // A canary keeps counting activity but leaves everything else to the primary.
+    if !deployment::is_active(data) {
+        return Ok(());
+    }
+
     match event {
         FullEvent::ReactionAdd { add_reaction } => {
-            handle_reaction(ctx, add_reaction, data, true).await;
+            if features.reaction_roles {
+                handle_reaction(ctx, add_reaction, data, true).await;
+            }
+            if features.message_scanning {
+                tasks::duplicate_updates::handle_appeal_reaction(ctx, data, add_reaction).await;
+            }
         }
-        FullEvent::ReactionRemove { removed_reaction } => {
+        FullEvent::ReactionRemove { removed_reaction } if features.reaction_roles => {
             handle_reaction(ctx, removed_reaction, data, false).await;
         }
+        FullEvent::ShardStageUpdate { event } => {
+            shards::handle_stage_update(ctx, data, event).await;
+        }
+        FullEvent::Resume { .. } => {
+            info!("Shard {} resumed its session", ctx.shard_id);
+        }
+        FullEvent::Message { new_message } if features.message_scanning => {
+            tasks::status_update::handle_incoming_message(ctx, data, new_message).await;
+            xp::record_message(data, new_message).await;
+            watchers::check_message(ctx, data, new_message).await;
+        }
+        FullEvent::MessageUpdate { event, .. } if features.message_scanning => {
+            tasks::status_update::handle_edited_message(ctx, data, event).await;
+        }
+        FullEvent::GuildCreate { guild, .. } if features.member_tracking => {
+            invites::snapshot_invites(ctx, data, guild.id).await;
+        }
+        FullEvent::InviteCreate { data: event } if features.member_tracking => {
+            invites::record_invite_created(data, event).await;
+        }
+        FullEvent::InviteDelete { data: event } if features.member_tracking => {
+            invites::record_invite_deleted(data, event).await;
+        }
+        FullEvent::GuildMemberAddition { new_member } if features.member_tracking => {
+            invites::record_join(ctx, data, new_member).await;
+            role_snapshots::restore_roles(ctx, data, new_member).await;
+        }
+        FullEvent::GuildMemberRemoval {
+            guild_id,
+            user,
+            member_data_if_available,
+        } if features.member_tracking => {
+            role_snapshots::snapshot_roles(
+                data,
+                *guild_id,
+                user,
+                member_data_if_available.as_ref(),
+            )
+            .await;
+        }
+        FullEvent::InteractionCreate {
+            interaction: Interaction::Component(component),
+        } => {
+            interactions::handle_component(ctx, component, data).await;
+        }
         _ => {}
     }
 
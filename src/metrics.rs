@@ -0,0 +1,336 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context as _};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use tracing::{
+    debug,
+    field::{Field, Visit},
+    info_span,
+    span::{Attributes, Id},
+    warn, Instrument, Metadata, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use crate::settings::Settings;
+
+/// Latency samples kept per endpoint, older ones are dropped.
+const MAX_LATENCY_SAMPLES: usize = 500;
+
+#[derive(Default)]
+struct EndpointStats {
+    calls: u64,
+    errors: u64,
+    /// Unset for endpoints only timed by [`DiscordRequests`], which can't see failures.
+    tracks_errors: bool,
+    latencies: VecDeque<Duration>,
+}
+
+impl EndpointStats {
+    fn push_latency(&mut self, latency: Duration) {
+        if self.latencies.len() == MAX_LATENCY_SAMPLES {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+}
+
+#[derive(Default)]
+struct CacheStats {
+    hits: u64,
+    misses: u64,
+}
+
+/// In-process registry of call latencies and cache lookups, reset on restart.
+#[derive(Default)]
+struct Registry {
+    endpoints: HashMap<String, EndpointStats>,
+    caches: HashMap<&'static str, CacheStats>,
+    /// Failed commands by [`crate::error::AmdError::category`].
+    command_errors: HashMap<&'static str, u64>,
+}
+
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(Mutex::default);
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY.lock().expect("Metrics registry lock poisoned")
+}
+
+/// Runs `fut` in a span named after `endpoint` and records its latency and outcome.
+pub async fn timed<T, E>(endpoint: &str, fut: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+    let start = Instant::now();
+    let result = fut.instrument(info_span!("call", endpoint)).await;
+    record_call(endpoint, start.elapsed(), result.is_ok());
+    result
+}
+
+pub fn record_call(endpoint: &str, latency: Duration, succeeded: bool) {
+    let mut registry = registry();
+    let stats = registry.endpoints.entry(endpoint.to_string()).or_default();
+    stats.calls += 1;
+    stats.tracks_errors = true;
+    if !succeeded {
+        stats.errors += 1;
+    }
+    stats.push_latency(latency);
+}
+
+/// Records a call whose outcome isn't known.
+pub fn record_latency(endpoint: &str, latency: Duration) {
+    let mut registry = registry();
+    let stats = registry.endpoints.entry(endpoint.to_string()).or_default();
+    stats.calls += 1;
+    stats.push_latency(latency);
+}
+
+/// Times every request serenity makes to Discord from the span it opens around each one,
+/// under `discord.<route>`, e.g. `discord.ChannelMessages`.
+pub struct DiscordRequests;
+
+impl DiscordRequests {
+    /// Filter for the layer, so it only sees serenity's request spans.
+    pub fn is_request(metadata: &Metadata<'_>) -> bool {
+        metadata.is_span()
+            && metadata.target() == "serenity::http::client"
+            && metadata.name() == "request"
+    }
+}
+
+struct RequestTiming {
+    endpoint: String,
+    started: Instant,
+}
+
+impl<S> Layer<S> for DiscordRequests
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut route = RouteVisitor::default();
+        attrs.record(&mut route);
+        span.extensions_mut().insert(RequestTiming {
+            endpoint: format!("discord.{}", route.name.as_deref().unwrap_or("unknown")),
+            started: Instant::now(),
+        });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let extensions = span.extensions();
+        if let Some(timing) = extensions.get::<RequestTiming>() {
+            record_latency(&timing.endpoint, timing.started.elapsed());
+        }
+    }
+}
+
+/// Picks the route's name out of the `req` field, which is only available as `Debug`.
+#[derive(Default)]
+struct RouteVisitor {
+    name: Option<String>,
+}
+
+impl Visit for RouteVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() != "req" {
+            return;
+        }
+        let request = format!("{:?}", value);
+        self.name = route_name(&request);
+    }
+}
+
+fn route_name(request: &str) -> Option<String> {
+    let (_, rest) = request.split_once("route: ")?;
+    let name: String = rest
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+pub fn record_cache_lookup(cache: &'static str, hit: bool) {
+    let mut registry = registry();
+    let stats = registry.caches.entry(cache).or_default();
+    if hit {
+        stats.hits += 1;
+    } else {
+        stats.misses += 1;
+    }
+}
+
+pub fn record_command_error(category: &'static str) {
+    *registry().command_errors.entry(category).or_default() += 1;
+}
+
+pub struct EndpointSummary {
+    pub endpoint: String,
+    pub calls: u64,
+    /// [`None`] if failures of this endpoint aren't tracked.
+    pub errors: Option<u64>,
+    pub p50: Duration,
+    pub p95: Duration,
+}
+
+/// Per-endpoint call counts and latency percentiles over the recent samples.
+pub fn endpoint_summaries() -> Vec<EndpointSummary> {
+    let registry = registry();
+    let mut summaries: Vec<_> = registry
+        .endpoints
+        .iter()
+        .map(|(endpoint, stats)| {
+            let mut latencies: Vec<_> = stats.latencies.iter().copied().collect();
+            latencies.sort();
+            EndpointSummary {
+                endpoint: endpoint.clone(),
+                calls: stats.calls,
+                errors: stats.tracks_errors.then_some(stats.errors),
+                p50: percentile(&latencies, 50),
+                p95: percentile(&latencies, 95),
+            }
+        })
+        .collect();
+    summaries.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+    summaries
+}
+
+/// Hits and misses of every cache.
+pub fn cache_summaries() -> Vec<(&'static str, u64, u64)> {
+    let registry = registry();
+    let mut summaries: Vec<_> = registry
+        .caches
+        .iter()
+        .map(|(cache, stats)| (*cache, stats.hits, stats.misses))
+        .collect();
+    summaries.sort();
+    summaries
+}
+
+/// Failed commands per error category.
+pub fn command_error_summaries() -> Vec<(&'static str, u64)> {
+    let mut summaries: Vec<_> = registry()
+        .command_errors
+        .iter()
+        .map(|(category, count)| (*category, *count))
+        .collect();
+    summaries.sort();
+    summaries
+}
+
+fn percentile(sorted: &[Duration], percentile: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    sorted[(sorted.len() - 1) * percentile / 100]
+}
+
+/// A single daily KPI value pushed to the external metrics sink.
+pub struct Kpi {
+    pub name: &'static str,
+    pub value: f64,
+}
+
+impl Kpi {
+    pub fn new(name: &'static str, value: f64) -> Self {
+        Self { name, value }
+    }
+}
+
+/// Pushes `kpis` to the sink configured through `METRICS_PUSH_URL`.
+///
+/// The `metrics.push_format` setting, or `METRICS_PUSH_FORMAT` if it isn't set,
+/// selects the payload: `json` (default) posts a single JSON object suitable for
+/// generic webhooks, `prometheus` posts the text exposition format to a Pushgateway
+/// under the `amd` job. Failures are only logged so that a flaky metrics sink never
+/// fails a report.
+pub async fn push_kpis(settings: &Settings, kpis: &[Kpi]) {
+    let Some(url) = std::env::var("METRICS_PUSH_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+    else {
+        debug!("METRICS_PUSH_URL not set, skipping KPI push");
+        return;
+    };
+
+    let default_format =
+        std::env::var("METRICS_PUSH_FORMAT").unwrap_or_else(|_| "json".to_string());
+    let format = settings
+        .get_or("metrics", "push_format", default_format)
+        .await;
+    if let Err(e) = try_push_kpis(&url, &format, kpis).await {
+        warn!("Failed to push KPIs: {:?}", e);
+    }
+}
+
+async fn try_push_kpis(url: &str, format: &str, kpis: &[Kpi]) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+
+    let request = match format {
+        "json" => client.post(url).json(&json_payload(kpis)),
+        "prometheus" => client
+            .post(format!("{}/metrics/job/amd", url.trim_end_matches('/')))
+            .body(prometheus_payload(kpis)),
+        other => return Err(anyhow!("Unknown KPI push format: {}", other)),
+    };
+
+    debug!("Pushing {} KPIs to {}", kpis.len(), url);
+    let response = request
+        .send()
+        .await
+        .context("Failed to post KPIs to metrics sink")?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Metrics sink responded with an error: {:?}",
+            response.status()
+        ));
+    }
+
+    Ok(())
+}
+
+fn json_payload(kpis: &[Kpi]) -> Value {
+    let metrics: Map<String, Value> = kpis
+        .iter()
+        .map(|kpi| (kpi.name.to_string(), json!(kpi.value)))
+        .collect();
+
+    json!({
+        "source": "amd",
+        "timestamp": Utc::now().to_rfc3339(),
+        "metrics": metrics,
+    })
+}
+
+fn prometheus_payload(kpis: &[Kpi]) -> String {
+    kpis.iter()
+        .map(|kpi| format!("# TYPE amd_{0} gauge\namd_{0} {1}\n", kpi.name, kpi.value))
+        .collect()
+}
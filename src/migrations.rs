@@ -0,0 +1,162 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::{bail, Context as _};
+use chrono::NaiveDate;
+use serde_json::{Map, Value};
+use tracing::info;
+
+use crate::storage::Storage;
+
+const SCHEMA_VERSION_KEY: &str = "storage.schema_version";
+
+/// A versioned change to the layout of the stored data. Migrations run in order at
+/// startup and each one only ever runs once per store.
+pub struct Migration {
+    pub version: u64,
+    pub description: &'static str,
+    apply: fn(&mut Map<String, Value>) -> anyhow::Result<()>,
+}
+
+/// Every migration, ordered by version. Append new ones at the end and never edit or
+/// remove one that has been released.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Baseline layout",
+        apply: |_| Ok(()),
+    },
+    Migration {
+        version: 2,
+        description: "Seed the alternate-format authors that used to be hardcoded",
+        apply: |values| {
+            values
+                .entry("status_update.exempt_format")
+                .or_insert_with(|| {
+                    serde_json::json!(["767636699077410837", "1265880467047976970"])
+                });
+            Ok(())
+        },
+    },
+    Migration {
+        version: 3,
+        description: "Date status update checks by the day they judge, not the day they ran",
+        apply: |values| {
+            for key in [
+                "status_update.history",
+                "appeals.queue",
+                "update_quality.scores",
+                "duplicate_updates.flags",
+            ] {
+                if let Some(Value::Array(entries)) = values.get_mut(key) {
+                    for entry in entries {
+                        if let Some(date) = entry.get_mut("date") {
+                            *date = previous_day(date)?;
+                        }
+                    }
+                }
+            }
+            if let Some(Value::Object(days)) = values
+                .get_mut("reports.messages")
+                .and_then(|reports| reports.get_mut("status_update"))
+            {
+                let shifted = std::mem::take(days)
+                    .into_iter()
+                    .map(|(date, messages)| {
+                        let date = previous_day(&Value::String(date))?;
+                        Ok((date.as_str().unwrap_or_default().to_string(), messages))
+                    })
+                    .collect::<anyhow::Result<_>>()?;
+                *days = shifted;
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        version: 4,
+        description: "Date the duplicate update fingerprints",
+        apply: |values| {
+            // Undated fingerprints can't be told apart from a rerun's, start over.
+            values.remove("duplicate_updates.fingerprints");
+            Ok(())
+        },
+    },
+    Migration {
+        version: 5,
+        description: "Drop the birthday and contest notification opt-outs",
+        apply: |values| {
+            if let Some(Value::Object(members)) = values.get_mut("preferences.muted") {
+                for muted in members.values_mut() {
+                    if let Value::Array(kinds) = muted {
+                        kinds.retain(|kind| {
+                            !matches!(kind.as_str(), Some("birthday_pings" | "contest_alerts"))
+                        });
+                    }
+                }
+                members.retain(|_, muted| muted.as_array().is_none_or(|kinds| !kinds.is_empty()));
+            }
+            Ok(())
+        },
+    },
+];
+
+/// The date before a stored `YYYY-MM-DD` date.
+fn previous_day(date: &Value) -> anyhow::Result<Value> {
+    let date: NaiveDate = serde_json::from_value(date.clone()).context("Invalid stored date")?;
+    let previous = date.pred_opt().context("Stored date out of range")?;
+    Ok(serde_json::to_value(previous)?)
+}
+
+pub fn latest_version() -> u64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub async fn schema_version(storage: &Storage) -> anyhow::Result<u64> {
+    storage.get(SCHEMA_VERSION_KEY).await
+}
+
+/// Brings the store up to [`latest_version`]. All pending migrations are applied
+/// together, so a failure leaves the store untouched.
+pub async fn run_migrations(storage: &Storage) -> anyhow::Result<()> {
+    storage.transform(migrate).await
+}
+
+/// Applies the pending migrations to `values`, the raw values of a store.
+pub fn migrate(values: &mut Map<String, Value>) -> anyhow::Result<()> {
+    let current = values
+        .get(SCHEMA_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if current > latest_version() {
+        bail!(
+            "Storage schema version {} is newer than this build supports ({})",
+            current,
+            latest_version()
+        );
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        info!(
+            "Applying storage migration {}: {}",
+            migration.version, migration.description
+        );
+        (migration.apply)(values)
+            .with_context(|| format!("Storage migration {} failed", migration.version))?;
+        values.insert(SCHEMA_VERSION_KEY.to_string(), migration.version.into());
+    }
+    Ok(())
+}
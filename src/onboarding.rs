@@ -0,0 +1,309 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ComponentInteraction, Context as SerenityContext, CreateActionRow, CreateButton,
+    CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+    RoleId,
+};
+use tracing::{error, info, warn};
+
+use crate::{storage::Storage, Data};
+
+/// Custom ID prefix of the quiz buttons, routed here by [`crate::interactions`].
+pub const ONBOARDING_COMPONENT: &str = "onboarding";
+const QUESTIONS_KEY: &str = "onboarding.questions";
+const RESULTS_KEY: &str = "onboarding.results";
+/// Quizzes in progress by user, so the score can't be tampered with from the client.
+const ATTEMPTS_KEY: &str = "onboarding.attempts";
+
+/// A multiple choice question on the rules or code of conduct.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuizQuestion {
+    pub question: String,
+    pub options: Vec<String>,
+    /// Index into `options`.
+    pub answer: usize,
+}
+
+/// Progress of a quiz being taken.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+struct QuizAttempt {
+    /// Index of the question waiting for an answer.
+    question: usize,
+    score: usize,
+}
+
+/// Outcome of one attempt at the quiz, kept for moderators.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuizResult {
+    pub user_id: u64,
+    pub score: usize,
+    pub total: usize,
+    pub passed: bool,
+    pub taken_at: DateTime<Utc>,
+}
+
+pub async fn questions(storage: &Storage) -> anyhow::Result<Vec<QuizQuestion>> {
+    storage.get(QUESTIONS_KEY).await
+}
+
+pub async fn add_question(storage: &Storage, question: QuizQuestion) -> anyhow::Result<usize> {
+    storage
+        .update(QUESTIONS_KEY, |questions: &mut Vec<QuizQuestion>| {
+            questions.push(question);
+            questions.len()
+        })
+        .await
+}
+
+/// Removes the question at `index`, returning it if it existed.
+pub async fn remove_question(
+    storage: &Storage,
+    index: usize,
+) -> anyhow::Result<Option<QuizQuestion>> {
+    storage
+        .update(QUESTIONS_KEY, |questions: &mut Vec<QuizQuestion>| {
+            (index < questions.len()).then(|| questions.remove(index))
+        })
+        .await
+}
+
+/// Every recorded attempt, oldest first.
+pub async fn results(storage: &Storage) -> anyhow::Result<Vec<QuizResult>> {
+    storage.get(RESULTS_KEY).await
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(ATTEMPTS_KEY, |attempts: &mut HashMap<u64, QuizAttempt>| {
+            attempts.remove(&user_id);
+        })
+        .await?;
+    storage
+        .update(RESULTS_KEY, |results: &mut Vec<QuizResult>| {
+            results.retain(|r| r.user_id != user_id)
+        })
+        .await
+}
+
+/// The button newcomers click to take the quiz.
+pub fn start_button() -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new(format!(
+        "{}:start:0",
+        ONBOARDING_COMPONENT
+    ))
+    .label("Start the quiz")
+    .style(ButtonStyle::Primary)])
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    arg: &str,
+    data: &Data,
+) {
+    let result = match action {
+        "start" => start_quiz(ctx, component, data).await,
+        "answer" => answer_question(ctx, component, arg, data).await,
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        error!(
+            "Failed to handle onboarding interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+/// Starts the quiz over, dropping any attempt the user left unfinished.
+async fn start_quiz(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let user_id = component.user.id.get();
+    data.storage
+        .update(ATTEMPTS_KEY, |attempts: &mut HashMap<u64, QuizAttempt>| {
+            attempts.insert(user_id, QuizAttempt::default());
+        })
+        .await?;
+    show_question(ctx, component, data, QuizAttempt::default(), true).await
+}
+
+/// The answer buttons carry `<question>:<option>`, the score is kept in storage. Clicks
+/// on a question that isn't the one waiting for an answer, e.g. from an old message or
+/// a second click, are ignored.
+async fn answer_question(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    arg: &str,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let mut parts = arg.split(':').map(str::parse::<usize>);
+    let (Some(Ok(index)), Some(Ok(option))) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+
+    let questions = questions(&data.storage).await?;
+    let correct = questions.get(index).is_some_and(|q| q.answer == option);
+    let user_id = component.user.id.get();
+    let attempt = data
+        .storage
+        .update(ATTEMPTS_KEY, |attempts: &mut HashMap<u64, QuizAttempt>| {
+            let attempt = attempts
+                .get_mut(&user_id)
+                .filter(|attempt| attempt.question == index)?;
+            attempt.question += 1;
+            attempt.score += usize::from(correct);
+            let attempt = *attempt;
+            if attempt.question >= questions.len() {
+                attempts.remove(&user_id);
+            }
+            Some(attempt)
+        })
+        .await?;
+
+    let Some(attempt) = attempt else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("This question was already answered, start the quiz again to retake it.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    };
+    show_question(ctx, component, data, attempt, false).await
+}
+
+async fn show_question(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    data: &Data,
+    attempt: QuizAttempt,
+    first: bool,
+) -> anyhow::Result<()> {
+    let index = attempt.question;
+    let questions = questions(&data.storage).await?;
+    let response = if questions.is_empty() {
+        CreateInteractionResponseMessage::new()
+            .content("The quiz hasn't been set up yet, please ask a moderator.")
+    } else if let Some(question) = questions.get(index) {
+        let buttons = question
+            .options
+            .iter()
+            .enumerate()
+            .map(|(option, label)| {
+                CreateButton::new(format!(
+                    "{}:answer:{}:{}",
+                    ONBOARDING_COMPONENT, index, option
+                ))
+                .label(label)
+                .style(ButtonStyle::Secondary)
+            })
+            .collect::<Vec<_>>();
+        let embed = CreateEmbed::new()
+            .title(format!("Question {} of {}", index + 1, questions.len()))
+            .description(&question.question)
+            .footer(CreateEmbedFooter::new("Pick the right answer below."));
+        CreateInteractionResponseMessage::new()
+            .embed(embed)
+            .components(
+                buttons
+                    .chunks(5)
+                    .map(|row| CreateActionRow::Buttons(row.to_vec()))
+                    .collect(),
+            )
+    } else {
+        let content = finish_quiz(ctx, component, data, attempt.score, questions.len()).await?;
+        CreateInteractionResponseMessage::new()
+            .content(content)
+            .embeds(vec![])
+            .components(vec![])
+    };
+
+    // The first question opens a private message, later ones replace it in place.
+    let response = if first {
+        CreateInteractionResponse::Message(response.ephemeral(true))
+    } else {
+        CreateInteractionResponse::UpdateMessage(response)
+    };
+    component.create_response(&ctx.http, response).await?;
+    Ok(())
+}
+
+/// Records the attempt and grants the Member role on a pass. Returns the message
+/// shown to the newcomer.
+async fn finish_quiz(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    data: &Data,
+    score: usize,
+    total: usize,
+) -> anyhow::Result<String> {
+    let config = data.config.read().await.onboarding.clone();
+    let passed = score * 100 >= total * config.pass_percentage as usize;
+    data.storage
+        .update(RESULTS_KEY, |results: &mut Vec<QuizResult>| {
+            results.push(QuizResult {
+                user_id: component.user.id.get(),
+                score,
+                total,
+                passed,
+                taken_at: Utc::now(),
+            })
+        })
+        .await?;
+    info!(
+        "{} scored {}/{} on the onboarding quiz",
+        component.user.name, score, total
+    );
+
+    if !passed {
+        return Ok(format!(
+            "You got {}/{} right, which isn't enough to pass. Have another look at the rules and try again!",
+            score, total
+        ));
+    }
+
+    let (Some(guild_id), Some(role_id)) = (component.guild_id, config.member_role_id) else {
+        warn!("No member role configured, can't grant it after the quiz");
+        return Ok(format!(
+            "You got {}/{} right and passed! A moderator will give you access shortly.",
+            score, total
+        ));
+    };
+    ctx.http
+        .add_member_role(
+            guild_id,
+            component.user.id,
+            RoleId::new(role_id),
+            Some("Passed the onboarding quiz"),
+        )
+        .await?;
+
+    Ok(format!(
+        "You got {}/{} right and passed, welcome aboard! The rest of the server is now unlocked.",
+        score, total
+    ))
+}
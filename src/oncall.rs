@@ -0,0 +1,66 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate};
+use serenity::all::{ChannelId, Context, CreateAllowedMentions, CreateMessage, UserId};
+use tracing::warn;
+
+use crate::{config::OnCallConfig, utils::time::local_today, Data};
+
+/// The mentor on duty during the week containing `date`. Weeks start on Monday and the
+/// rotation starts with the first mentor in the week of `rotation_start`.
+pub fn on_call(config: &OnCallConfig, date: NaiveDate) -> Option<u64> {
+    if config.mentor_ids.is_empty() {
+        return None;
+    }
+    let start = week_start(config.rotation_start.unwrap_or_default());
+    let weeks = (week_start(date) - start).num_weeks();
+    let index = weeks.rem_euclid(config.mentor_ids.len() as i64) as usize;
+    Some(config.mentor_ids[index])
+}
+
+/// The mentor on duty right now, in the bot's timezone.
+pub async fn on_call_now(data: &Data) -> Option<u64> {
+    let today = local_today();
+    on_call(&data.config.read().await.on_call, today)
+}
+
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - ChronoDuration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Posts `content` to the ops channel, pinging the on-call mentor if there is one.
+pub async fn escalate(ctx: &Context, data: &Data, content: &str) {
+    let Some(channel_id) = data.config.read().await.bot.ops_channel_id else {
+        return;
+    };
+
+    let message = match on_call_now(data).await {
+        Some(user_id) => CreateMessage::new()
+            .content(format!("<@{}> {}", user_id, content))
+            .allowed_mentions(CreateAllowedMentions::new().users(vec![UserId::new(user_id)])),
+        None => CreateMessage::new()
+            .content(content)
+            .allowed_mentions(CreateAllowedMentions::new()),
+    };
+    if let Err(e) = ChannelId::new(channel_id)
+        .send_message(&ctx.http, message)
+        .await
+    {
+        warn!("Failed to escalate to the on-call mentor: {}", e);
+    }
+}
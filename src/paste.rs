@@ -0,0 +1,149 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::{anyhow, Context as _};
+use tracing::debug;
+
+use crate::config::PasteConfig;
+
+/// A language the paste link can be highlighted as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Language {
+    pub name: &'static str,
+    pub extension: &'static str,
+}
+
+const LANGUAGES: &[(Language, &[&str])] = &[
+    (lang("Rust", "rs"), &["rust", "rs"]),
+    (lang("Python", "py"), &["python", "py"]),
+    (lang("JavaScript", "js"), &["javascript", "js", "jsx"]),
+    (lang("TypeScript", "ts"), &["typescript", "ts", "tsx"]),
+    (lang("C", "c"), &["c", "h"]),
+    (lang("C++", "cpp"), &["cpp", "c++", "cc", "hpp"]),
+    (lang("Java", "java"), &["java"]),
+    (lang("Kotlin", "kt"), &["kotlin", "kt"]),
+    (lang("Go", "go"), &["go", "golang"]),
+    (lang("Dart", "dart"), &["dart"]),
+    (lang("Shell", "sh"), &["sh", "bash", "shell", "zsh"]),
+    (lang("HTML", "html"), &["html", "htm"]),
+    (lang("CSS", "css"), &["css"]),
+    (lang("SQL", "sql"), &["sql"]),
+    (lang("JSON", "json"), &["json"]),
+    (lang("YAML", "yaml"), &["yaml", "yml"]),
+    (lang("TOML", "toml"), &["toml"]),
+];
+
+/// Lines that give a language away when a paste has no tag or file name.
+const HINTS: &[(&str, &str)] = &[
+    ("fn main()", "rs"),
+    ("use std::", "rs"),
+    ("let mut ", "rs"),
+    ("#include", "cpp"),
+    ("public static void main", "java"),
+    ("package main", "go"),
+    ("func main()", "go"),
+    ("void main()", "dart"),
+    ("def ", "py"),
+    ("import React", "js"),
+    ("console.log(", "js"),
+    ("#!/bin/bash", "sh"),
+    ("<!DOCTYPE html", "html"),
+    ("SELECT ", "sql"),
+];
+
+const fn lang(name: &'static str, extension: &'static str) -> Language {
+    Language { name, extension }
+}
+
+/// Finds the language from the code block's tag or the file name, falling back to
+/// guessing from the code itself.
+pub fn detect_language(tag: Option<&str>, filename: Option<&str>, code: &str) -> Option<Language> {
+    let by_alias = |alias: &str| {
+        let alias = alias.to_lowercase();
+        LANGUAGES
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&alias.as_str()))
+            .map(|(language, _)| *language)
+    };
+    tag.and_then(by_alias)
+        .or_else(|| {
+            filename
+                .and_then(|name| name.rsplit_once('.'))
+                .and_then(|(_, extension)| by_alias(extension))
+        })
+        .or_else(|| {
+            HINTS
+                .iter()
+                .find(|(hint, _)| code.contains(hint))
+                .and_then(|(_, extension)| by_alias(extension))
+        })
+}
+
+/// Splits a message's code block into its language tag and the code. Text without a code
+/// block is returned as is.
+pub fn parse_code_block(text: &str) -> (Option<&str>, &str) {
+    let text = text.trim();
+    let Some(inner) = text
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    else {
+        return (None, text);
+    };
+    match inner.split_once('\n') {
+        Some((tag, code)) if !tag.trim().is_empty() && !tag.contains(' ') => {
+            (Some(tag.trim()), code.trim_end())
+        }
+        _ => (None, inner.trim()),
+    }
+}
+
+/// Uploads `content` as the raw request body and returns the link the service replies
+/// with, which is what paste.rs and most self-hosted pastebins do. The API key is read
+/// from `PASTE_API_KEY`, if set.
+pub async fn upload(config: &PasteConfig, content: String) -> anyhow::Result<String> {
+    let endpoint = config
+        .endpoint
+        .as_ref()
+        .ok_or_else(|| anyhow!("No paste endpoint configured"))?;
+
+    let client = reqwest::Client::new();
+    let mut request = client.post(endpoint).body(content);
+    if let Ok(api_key) = std::env::var("PASTE_API_KEY") {
+        request = request.bearer_auth(api_key);
+    }
+
+    debug!("Uploading a paste to {}", endpoint);
+    let response = request
+        .send()
+        .await
+        .context("Failed to upload to the paste service")?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Paste service responded with an error: {:?}",
+            response.status()
+        ));
+    }
+    let link = response
+        .text()
+        .await
+        .context("Failed to read the paste service's reply")?;
+    let link = link.trim();
+    if !link.starts_with("http") {
+        return Err(anyhow!("Paste service replied without a link: {}", link));
+    }
+    Ok(link.to_string())
+}
@@ -0,0 +1,168 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::Storage;
+
+/// Every member's points and shields, keyed by Discord ID.
+const WALLETS_KEY: &str = "points.wallets";
+/// Who was already paid for each occasion, so a rerun doesn't pay them twice.
+const AWARDED_KEY: &str = "points.awarded";
+/// How long the payouts of an occasion are remembered.
+const AWARDED_RETENTION_DAYS: i64 = 30;
+
+/// The members paid for one occasion, e.g. a day's status updates.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Awarded {
+    occasion: String,
+    awarded_at: DateTime<Utc>,
+    user_ids: HashSet<u64>,
+}
+
+/// Activity points a member earned and the streak shields they bought with them.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Wallet {
+    pub points: u64,
+    /// Each one saves the streak once when the member misses a status update.
+    pub shields: u32,
+}
+
+pub enum Purchase {
+    Bought(Wallet),
+    NotEnoughPoints(Wallet),
+    AtLimit(Wallet),
+}
+
+pub async fn wallet(storage: &Storage, user_id: u64) -> anyhow::Result<Wallet> {
+    let wallets: HashMap<u64, Wallet> = storage.get(WALLETS_KEY).await?;
+    Ok(wallets.get(&user_id).copied().unwrap_or_default())
+}
+
+/// Gives each of `user_ids` `points` for `occasion`, e.g. `status_update:2026-01-31`.
+/// Members already paid for the same occasion are skipped, so reruns are harmless.
+pub async fn award(
+    storage: &Storage,
+    occasion: &str,
+    user_ids: impl IntoIterator<Item = u64>,
+    points: u64,
+) -> anyhow::Result<()> {
+    let user_ids: Vec<u64> = user_ids.into_iter().collect();
+    if points == 0 || user_ids.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now();
+    let user_ids = storage
+        .update(AWARDED_KEY, |log: &mut Vec<Awarded>| {
+            log.retain(|a| now - a.awarded_at < TimeDelta::days(AWARDED_RETENTION_DAYS));
+            let index = match log.iter().position(|a| a.occasion == occasion) {
+                Some(index) => index,
+                None => {
+                    log.push(Awarded {
+                        occasion: occasion.to_string(),
+                        awarded_at: now,
+                        user_ids: HashSet::new(),
+                    });
+                    log.len() - 1
+                }
+            };
+            user_ids
+                .into_iter()
+                .filter(|user_id| log[index].user_ids.insert(*user_id))
+                .collect::<Vec<u64>>()
+        })
+        .await?;
+    if user_ids.is_empty() {
+        return Ok(());
+    }
+    storage
+        .update(WALLETS_KEY, |wallets: &mut HashMap<u64, Wallet>| {
+            for user_id in &user_ids {
+                let wallet = wallets.entry(*user_id).or_default();
+                wallet.points = wallet.points.saturating_add(points);
+            }
+        })
+        .await
+}
+
+/// Trades `cost` points for a shield, unless the member can't afford it or already holds
+/// `max_shields`.
+pub async fn buy_shield(
+    storage: &Storage,
+    user_id: u64,
+    cost: u64,
+    max_shields: u32,
+) -> anyhow::Result<Purchase> {
+    storage
+        .update(WALLETS_KEY, |wallets: &mut HashMap<u64, Wallet>| {
+            let wallet = wallets.entry(user_id).or_default();
+            if wallet.shields >= max_shields {
+                return Purchase::AtLimit(*wallet);
+            }
+            if wallet.points < cost {
+                return Purchase::NotEnoughPoints(*wallet);
+            }
+            wallet.points -= cost;
+            wallet.shields += 1;
+            Purchase::Bought(*wallet)
+        })
+        .await
+}
+
+/// Spends one of the member's shields, returns `false` if they have none.
+pub async fn use_shield(storage: &Storage, user_id: u64) -> anyhow::Result<bool> {
+    storage
+        .update(
+            WALLETS_KEY,
+            |wallets: &mut HashMap<u64, Wallet>| match wallets.get_mut(&user_id) {
+                Some(wallet) if wallet.shields > 0 => {
+                    wallet.shields -= 1;
+                    true
+                }
+                _ => false,
+            },
+        )
+        .await
+}
+
+/// Gives back a shield that was used up by mistake, e.g. when a missed update turns out
+/// to have been sent after all.
+pub async fn return_shield(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(WALLETS_KEY, |wallets: &mut HashMap<u64, Wallet>| {
+            wallets.entry(user_id).or_default().shields += 1;
+        })
+        .await
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(WALLETS_KEY, |wallets: &mut HashMap<u64, Wallet>| {
+            wallets.remove(&user_id);
+        })
+        .await?;
+    storage
+        .update(AWARDED_KEY, |log: &mut Vec<Awarded>| {
+            for awarded in log {
+                awarded.user_ids.remove(&user_id);
+            }
+        })
+        .await
+}
@@ -0,0 +1,117 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::storage::Storage;
+
+/// Notification kinds each member turned off, keyed by Discord ID. Everything is on by
+/// default, so only opt-outs are stored.
+const MUTED_NOTIFICATIONS_KEY: &str = "preferences.muted";
+
+/// The kinds of DMs the bot sends, which members can turn off with `$prefs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Notification {
+    /// Talk reminders, nudges to come to the lab, the prompt to write a spotlight intro and
+    /// the attendee list of an announcement that's starting.
+    Reminders,
+    /// Explanations of rejected status updates and mentors' defaulter summaries.
+    DefaulterNotices,
+    /// Feedback on the clarity of status updates.
+    UpdateFeedback,
+    /// Outcomes of appeals, talk proposals and checkout requests.
+    Decisions,
+}
+
+impl Notification {
+    pub const ALL: [Notification; 4] = [
+        Notification::Reminders,
+        Notification::DefaulterNotices,
+        Notification::UpdateFeedback,
+        Notification::Decisions,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Notification::Reminders => "Reminders",
+            Notification::DefaulterNotices => "Defaulter notices",
+            Notification::UpdateFeedback => "Status update feedback",
+            Notification::Decisions => "Request decisions",
+        }
+    }
+
+    /// Stable identifier, used as the select menu value.
+    pub fn id(self) -> &'static str {
+        match self {
+            Notification::Reminders => "reminders",
+            Notification::DefaulterNotices => "defaulter_notices",
+            Notification::UpdateFeedback => "update_feedback",
+            Notification::Decisions => "decisions",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.id() == id)
+    }
+}
+
+pub async fn muted_notifications(
+    storage: &Storage,
+    user_id: u64,
+) -> anyhow::Result<HashSet<Notification>> {
+    let muted: HashMap<u64, HashSet<Notification>> = storage.get(MUTED_NOTIFICATIONS_KEY).await?;
+    Ok(muted.get(&user_id).cloned().unwrap_or_default())
+}
+
+pub async fn set_muted_notifications(
+    storage: &Storage,
+    user_id: u64,
+    muted: HashSet<Notification>,
+) -> anyhow::Result<()> {
+    storage
+        .update(
+            MUTED_NOTIFICATIONS_KEY,
+            |all: &mut HashMap<u64, HashSet<Notification>>| {
+                if muted.is_empty() {
+                    all.remove(&user_id);
+                } else {
+                    all.insert(user_id, muted);
+                }
+            },
+        )
+        .await
+}
+
+/// Whether `user_id` wants DMs of this kind. Every task sending a DM checks this first.
+/// If the preferences can't be read, the DM is sent.
+pub async fn allows(storage: &Storage, user_id: u64, kind: Notification) -> bool {
+    match muted_notifications(storage, user_id).await {
+        Ok(muted) => !muted.contains(&kind),
+        Err(e) => {
+            warn!("Failed to read notification preferences: {:?}", e);
+            true
+        }
+    }
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    set_muted_notifications(storage, user_id, HashSet::new()).await
+}
@@ -0,0 +1,137 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Context as SerenityContext, CreateMessage, UserId};
+use tracing::{info, warn};
+
+use crate::{
+    activity, announcements, appeals, checkins, command_usage, commands::subscriptions,
+    graphql::queries::fetch_members, history, inventory, invites, kudos, onboarding, points,
+    preferences, quiet_hours, role_drift, role_snapshots, sessions, spotlight, storage::Storage,
+    tasks, xp, Data,
+};
+
+/// Discord IDs of members who were erased, they are skipped by all future processing.
+const ERASED_MEMBERS_KEY: &str = "privacy.erased";
+const ERASURE_LOG_KEY: &str = "privacy.erasure_log";
+
+/// Record of an erasure, kept so it can be shown that the request was honoured.
+#[derive(Debug, Serialize, Deserialize)]
+struct ErasureLogEntry {
+    erased_at: DateTime<Utc>,
+    discord_id: String,
+    requested_by: String,
+}
+
+pub async fn erased_members(storage: &Storage) -> anyhow::Result<HashSet<String>> {
+    storage.get(ERASED_MEMBERS_KEY).await
+}
+
+pub async fn is_erased(storage: &Storage, discord_id: &str) -> anyhow::Result<bool> {
+    Ok(erased_members(storage).await?.contains(discord_id))
+}
+
+/// Names of erased members as Root knows them, for data keyed by name such as attendance.
+pub async fn erased_names(storage: &Storage) -> anyhow::Result<HashSet<String>> {
+    let erased = erased_members(storage).await?;
+    if erased.is_empty() {
+        return Ok(HashSet::new());
+    }
+    Ok(fetch_members()
+        .await?
+        .into_iter()
+        .filter(|member| erased.contains(&member.discord_id))
+        .map(|member| member.name)
+        .collect())
+}
+
+/// Purges everything stored about a member, adds them to the erased list so they are no
+/// longer processed, and logs the erasure to storage and the ops channel.
+pub async fn erase_member(
+    ctx: &SerenityContext,
+    data: &Data,
+    user_id: UserId,
+    name: &str,
+    requested_by: UserId,
+) -> anyhow::Result<()> {
+    let storage = &data.storage;
+    let discord_id = user_id.to_string();
+
+    history::forget_member(storage, &discord_id, name).await?;
+    tasks::status_update::forget_member(storage, user_id.get()).await?;
+    tasks::update_quality::forget_member(storage, &discord_id).await?;
+    tasks::practice::forget_member(storage, user_id.get()).await?;
+    tasks::duplicate_updates::forget_member(storage, user_id.get()).await?;
+    tasks::group_access::forget_member(storage, user_id.get()).await?;
+    tasks::attendance_awards::forget_member(storage, user_id.get()).await?;
+    tasks::lab_attendance::forget_member(storage, name).await?;
+    checkins::forget_member(storage, &discord_id).await?;
+    sessions::forget_member(storage, user_id.get()).await?;
+    announcements::forget_member(storage, user_id.get()).await?;
+    subscriptions::forget_member(storage, user_id.get()).await?;
+    onboarding::forget_member(storage, user_id.get()).await?;
+    activity::forget_member(storage, user_id.get()).await?;
+    preferences::forget_member(storage, user_id.get()).await?;
+    appeals::forget_member(storage, user_id.get()).await?;
+    invites::forget_member(storage, user_id.get()).await?;
+    points::forget_member(storage, user_id.get()).await?;
+    xp::forget_member(storage, user_id.get()).await?;
+    kudos::forget_member(storage, user_id.get()).await?;
+    inventory::forget_member(storage, user_id.get()).await?;
+    role_snapshots::forget_member(storage, user_id.get()).await?;
+    role_drift::forget_member(storage, user_id.get()).await?;
+    spotlight::forget_member(storage, user_id.get()).await?;
+    command_usage::forget_member(storage, user_id.get()).await?;
+    quiet_hours::forget_member(storage, user_id.get()).await?;
+    // Pending streak resets (`reset_approvals`) only hold a date, their defaulters are
+    // read from the history purged above.
+
+    storage
+        .update(ERASED_MEMBERS_KEY, |erased: &mut HashSet<String>| {
+            erased.insert(discord_id.clone())
+        })
+        .await?;
+    storage
+        .update(ERASURE_LOG_KEY, |log: &mut Vec<ErasureLogEntry>| {
+            log.push(ErasureLogEntry {
+                erased_at: Utc::now(),
+                discord_id: discord_id.clone(),
+                requested_by: requested_by.to_string(),
+            })
+        })
+        .await?;
+    info!("Erased stored data of member {}", discord_id);
+
+    if let Some(channel_id) = data.config.read().await.bot.ops_channel_id {
+        let log = CreateMessage::new().content(format!(
+            "Erased the stored data of member {} (requested by {}).",
+            discord_id, requested_by
+        ));
+        if let Err(e) = ChannelId::new(channel_id)
+            .send_message(&ctx.http, log)
+            .await
+        {
+            warn!("Failed to log erasure: {}", e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,322 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Context, CreateEmbed, CreateMessage, Embed, UserId};
+use tracing::{debug, warn};
+
+use crate::{
+    preferences::{allows, Notification},
+    storage::Storage,
+    utils::{
+        embed::EMBED_TOTAL_LIMIT,
+        long_message::{split_content, CONTENT_LIMIT},
+        time::local_now,
+    },
+    Data,
+};
+
+const QUEUE_KEY: &str = "quiet_hours.queue";
+/// Discord's limit on the number of embeds of a single message.
+const EMBEDS_PER_MESSAGE: usize = 10;
+/// Flushes a message is tried in before it's dropped, e.g. for a member with closed DMs.
+const MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Destination {
+    Channel(u64),
+    User(u64),
+}
+
+/// A non-urgent message, kept as plain data so it can wait in storage.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct QueuedMessage {
+    pub content: Option<String>,
+    pub embeds: Vec<Embed>,
+    /// Flushes that already failed to send it.
+    #[serde(default)]
+    pub attempts: u32,
+    /// The kind of DM this is, checked against the member's preferences again when sent.
+    #[serde(default)]
+    pub notification: Option<Notification>,
+}
+
+impl QueuedMessage {
+    pub fn text(content: impl Into<String>) -> Self {
+        Self {
+            content: Some(content.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn embed(embed: CreateEmbed) -> anyhow::Result<Self> {
+        // Builders only serialize, the model they describe deserializes from the same JSON.
+        let embed = serde_json::from_value(serde_json::to_value(embed)?)?;
+        Ok(Self {
+            embeds: vec![embed],
+            ..Default::default()
+        })
+    }
+
+    /// Marks the message as a DM of kind `notification`.
+    pub fn notification(mut self, notification: Notification) -> Self {
+        self.notification = Some(notification);
+        self
+    }
+}
+
+pub async fn is_quiet_now(data: &Data) -> bool {
+    let now = local_now().time();
+    data.config.read().await.quiet_hours.is_quiet(now)
+}
+
+/// Sends `message` right away, or queues it if it's currently quiet hours.
+pub async fn send_or_queue(
+    ctx: &Context,
+    data: &Data,
+    destination: Destination,
+    message: QueuedMessage,
+) -> anyhow::Result<()> {
+    if is_quiet_now(data).await {
+        debug!("Quiet hours, queueing a message for {:?}", destination);
+        return data
+            .storage
+            .update(
+                QUEUE_KEY,
+                |queue: &mut Vec<(Destination, QueuedMessage)>| {
+                    queue.push((destination, message));
+                },
+            )
+            .await;
+    }
+
+    send(ctx, destination, vec![message])
+        .await
+        .map_err(|(e, _)| e)
+}
+
+/// Sends everything queued during quiet hours, merging the messages for each destination
+/// into as few as possible. Messages that fail to send are queued again for the next
+/// flush, and dropped after [`MAX_ATTEMPTS`]. Does nothing while it's still quiet.
+pub async fn flush_queue(ctx: &Context, data: &Data) -> anyhow::Result<()> {
+    if is_quiet_now(data).await {
+        return Ok(());
+    }
+
+    let queue = take_queue(&data.storage).await?;
+    let mut batches: Vec<(Destination, Vec<QueuedMessage>)> = Vec::new();
+    let mut positions: HashMap<Destination, usize> = HashMap::new();
+    for (destination, message) in queue {
+        let position = *positions.entry(destination).or_insert_with(|| {
+            batches.push((destination, Vec::new()));
+            batches.len() - 1
+        });
+        batches[position].1.push(message);
+    }
+
+    let mut retries = Vec::new();
+    for (destination, mut messages) in batches {
+        if let Destination::User(user_id) = destination {
+            // Members may have turned these DMs off since they were queued.
+            let mut allowed = Vec::new();
+            for message in messages {
+                match message.notification {
+                    Some(kind) if !allows(&data.storage, user_id, kind).await => {}
+                    _ => allowed.push(message),
+                }
+            }
+            messages = allowed;
+        }
+        if messages.is_empty() {
+            continue;
+        }
+        debug!(
+            "Sending {} queued messages to {:?}",
+            messages.len(),
+            destination
+        );
+        let Err((e, unsent)) = send(ctx, destination, messages).await else {
+            continue;
+        };
+        warn!(
+            "Failed to send {} queued messages to {:?}: {:?}",
+            unsent.len(),
+            destination,
+            e
+        );
+        for mut message in unsent {
+            message.attempts += 1;
+            if message.attempts < MAX_ATTEMPTS {
+                retries.push((destination, message));
+            }
+        }
+    }
+    if !retries.is_empty() {
+        data.storage
+            .update(
+                QUEUE_KEY,
+                |queue: &mut Vec<(Destination, QueuedMessage)>| {
+                    // Ahead of anything queued meanwhile, to keep the order they were sent in.
+                    queue.splice(0..0, retries);
+                },
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Drops the DMs waiting for the member.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(
+            QUEUE_KEY,
+            |queue: &mut Vec<(Destination, QueuedMessage)>| {
+                queue.retain(|(destination, _)| !matches!(destination, Destination::User(id) if *id == user_id))
+            },
+        )
+        .await
+}
+
+async fn take_queue(storage: &Storage) -> anyhow::Result<Vec<(Destination, QueuedMessage)>> {
+    storage
+        .update(
+            QUEUE_KEY,
+            |queue: &mut Vec<(Destination, QueuedMessage)>| std::mem::take(queue),
+        )
+        .await
+}
+
+/// Merges `messages` into as few as Discord accepts, keeping each under the limits on
+/// content length, embed count and total embed size.
+fn pack(messages: Vec<QueuedMessage>) -> Vec<QueuedMessage> {
+    // Merged messages can't be told apart anymore, they share the most attempts and keep
+    // their kind only if they all had the same.
+    let attempts = messages
+        .iter()
+        .map(|m| m.attempts)
+        .max()
+        .unwrap_or_default();
+    let notification = messages
+        .first()
+        .and_then(|m| m.notification)
+        .filter(|kind| messages.iter().all(|m| m.notification == Some(*kind)));
+    let mut chunks: Vec<String> = Vec::new();
+    let mut embeds = Vec::new();
+    for message in messages {
+        for content in message
+            .content
+            .as_deref()
+            .map(split_content)
+            .unwrap_or_default()
+        {
+            match chunks.last_mut() {
+                Some(chunk)
+                    if chunk.chars().count() + content.chars().count() + 2 <= CONTENT_LIMIT =>
+                {
+                    chunk.push_str("\n\n");
+                    chunk.push_str(&content);
+                }
+                _ => chunks.push(content),
+            }
+        }
+        embeds.extend(message.embeds);
+    }
+
+    let mut packed: Vec<QueuedMessage> = chunks.into_iter().map(QueuedMessage::text).collect();
+    for message in &mut packed {
+        message.attempts = attempts;
+        message.notification = notification;
+    }
+    let mut size = 0;
+    for embed in embeds {
+        let length = embed_length(&embed);
+        match packed.last_mut() {
+            Some(last)
+                if !last.embeds.is_empty()
+                    && last.embeds.len() < EMBEDS_PER_MESSAGE
+                    && size + length <= EMBED_TOTAL_LIMIT =>
+            {
+                size += length;
+                last.embeds.push(embed);
+            }
+            _ => {
+                size = length;
+                packed.push(QueuedMessage {
+                    content: None,
+                    embeds: vec![embed],
+                    attempts,
+                    notification,
+                });
+            }
+        }
+    }
+    packed
+}
+
+/// Characters of `embed` that count towards Discord's total per message.
+fn embed_length(embed: &Embed) -> usize {
+    let count = |text: Option<&String>| text.map_or(0, |text| text.chars().count());
+    count(embed.title.as_ref())
+        + count(embed.description.as_ref())
+        + count(embed.footer.as_ref().map(|footer| &footer.text))
+        + count(embed.author.as_ref().map(|author| &author.name))
+        + embed
+            .fields
+            .iter()
+            .map(|field| field.name.chars().count() + field.value.chars().count())
+            .sum::<usize>()
+}
+
+/// Sends `messages` merged by [`pack`]. On failure, returns the error along with the
+/// messages that weren't sent.
+async fn send(
+    ctx: &Context,
+    destination: Destination,
+    messages: Vec<QueuedMessage>,
+) -> Result<(), (anyhow::Error, Vec<QueuedMessage>)> {
+    let mut outgoing = pack(messages).into_iter();
+    while let Some(message) = outgoing.next() {
+        let mut builder = CreateMessage::new().embeds(
+            message
+                .embeds
+                .iter()
+                .cloned()
+                .map(CreateEmbed::from)
+                .collect(),
+        );
+        if let Some(content) = &message.content {
+            builder = builder.content(content);
+        }
+        let result = match destination {
+            Destination::Channel(id) => ChannelId::new(id)
+                .send_message(&ctx.http, builder)
+                .await
+                .map(|_| ()),
+            Destination::User(id) => UserId::new(id)
+                .direct_message(&ctx.http, builder)
+                .await
+                .map(|_| ()),
+        };
+        if let Err(e) = result {
+            let unsent = std::iter::once(message).chain(outgoing).collect();
+            return Err((e.into(), unsent));
+        }
+    }
+    Ok(())
+}
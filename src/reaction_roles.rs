@@ -1,50 +1,96 @@
-use std::collections::HashMap;
-
-use serenity::all::{Context as SerenityContext, MessageId, Reaction, ReactionType, RoleId};
-use tracing::{debug, error};
+use serde::{Deserialize, Serialize};
+use serenity::all::{Context as SerenityContext, EmojiId, Reaction, ReactionType, RoleId};
+use tracing::{debug, error, warn};
 
 use crate::{
-    ids::{
-        AI_ROLE_ID, ARCHIVE_ROLE_ID, DEVOPS_ROLE_ID, MOBILE_ROLE_ID, RESEARCH_ROLE_ID,
-        ROLES_MESSAGE_ID, SYSTEMS_ROLE_ID, WEB_ROLE_ID,
-    },
+    ids::{self, roles_message_id, RoleKind},
+    storage::Storage,
     Data,
 };
 
+/// Reaction roles added at runtime with `$reactionroles add`.
+const CUSTOM_REACTION_ROLES_KEY: &str = "reaction_roles.custom";
+
+/// Identifies an emoji regardless of how Discord happened to describe it. Custom emoji are
+/// matched by ID alone, since their name and animated flag aren't always sent with reactions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EmojiKey {
+    Unicode(String),
+    Custom(EmojiId),
+}
+
+impl From<&ReactionType> for EmojiKey {
+    fn from(emoji: &ReactionType) -> Self {
+        match emoji {
+            ReactionType::Custom { id, .. } => EmojiKey::Custom(*id),
+            // Clients don't agree on sending the emoji variation selector.
+            ReactionType::Unicode(name) => EmojiKey::Unicode(name.replace('\u{fe0f}', "")),
+            _ => EmojiKey::Unicode(String::new()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CustomReactionRole {
+    pub emoji: ReactionType,
+    pub role_id: u64,
+}
+
 pub fn populate_data_with_reaction_roles(data: &mut Data) {
     let roles = [
-        (
-            ReactionType::Unicode("📁".to_string()),
-            RoleId::new(ARCHIVE_ROLE_ID),
-        ),
-        (
-            ReactionType::Unicode("📱".to_string()),
-            RoleId::new(MOBILE_ROLE_ID),
-        ),
-        (
-            ReactionType::Unicode("⚙️".to_string()),
-            RoleId::new(SYSTEMS_ROLE_ID),
-        ),
-        (
-            ReactionType::Unicode("🤖".to_string()),
-            RoleId::new(AI_ROLE_ID),
-        ),
-        (
-            ReactionType::Unicode("📜".to_string()),
-            RoleId::new(RESEARCH_ROLE_ID),
-        ),
-        (
-            ReactionType::Unicode("🚀".to_string()),
-            RoleId::new(DEVOPS_ROLE_ID),
-        ),
-        (
-            ReactionType::Unicode("🌐".to_string()),
-            RoleId::new(WEB_ROLE_ID),
-        ),
+        ("📁", RoleKind::Archive),
+        ("📱", RoleKind::Mobile),
+        ("⚙️", RoleKind::Systems),
+        ("🤖", RoleKind::Ai),
+        ("📜", RoleKind::Research),
+        ("🚀", RoleKind::Devops),
+        ("🌐", RoleKind::Web),
     ];
 
     data.reaction_roles
-        .extend::<HashMap<ReactionType, RoleId>>(roles.into());
+        .extend(roles.into_iter().map(|(emoji, kind)| {
+            (
+                EmojiKey::from(&ReactionType::Unicode(emoji.to_string())),
+                kind,
+            )
+        }));
+}
+
+pub async fn custom_reaction_roles(storage: &Storage) -> anyhow::Result<Vec<CustomReactionRole>> {
+    storage.get(CUSTOM_REACTION_ROLES_KEY).await
+}
+
+/// Maps `emoji` to `role_id`, replacing any role the emoji was already mapped to.
+pub async fn add_reaction_role(
+    storage: &Storage,
+    emoji: ReactionType,
+    role_id: u64,
+) -> anyhow::Result<()> {
+    storage
+        .update(
+            CUSTOM_REACTION_ROLES_KEY,
+            |roles: &mut Vec<CustomReactionRole>| {
+                let key = EmojiKey::from(&emoji);
+                roles.retain(|r| EmojiKey::from(&r.emoji) != key);
+                roles.push(CustomReactionRole { emoji, role_id });
+            },
+        )
+        .await
+}
+
+/// Removes the runtime mapping of `emoji`, returning whether there was one.
+pub async fn remove_reaction_role(storage: &Storage, emoji: &ReactionType) -> anyhow::Result<bool> {
+    let key = EmojiKey::from(emoji);
+    storage
+        .update(
+            CUSTOM_REACTION_ROLES_KEY,
+            |roles: &mut Vec<CustomReactionRole>| {
+                let before = roles.len();
+                roles.retain(|r| EmojiKey::from(&r.emoji) != key);
+                roles.len() != before
+            },
+        )
+        .await
 }
 
 pub async fn handle_reaction(
@@ -53,9 +99,12 @@ pub async fn handle_reaction(
     data: &Data,
     is_add: bool,
 ) {
-    if !is_relevant_reaction(reaction.message_id, &reaction.emoji, data) {
+    if reaction.message_id != roles_message_id() {
         return;
     }
+    let Some(role_id) = role_for(data, &reaction.emoji).await else {
+        return;
+    };
 
     debug!("Handling {:?} from {:?}.", reaction.emoji, reaction.user_id);
 
@@ -69,14 +118,11 @@ pub async fn handle_reaction(
     let Ok(member) = guild_id.member(ctx, user_id).await else {
         return;
     };
-    let Some(role_id) = data.reaction_roles.get(&reaction.emoji) else {
-        return;
-    };
 
     let result = if is_add {
-        member.add_role(&ctx.http, *role_id).await
+        member.add_role(&ctx.http, role_id).await
     } else {
-        member.remove_role(&ctx.http, *role_id).await
+        member.remove_role(&ctx.http, role_id).await
     };
 
     if let Err(e) = result {
@@ -87,6 +133,21 @@ pub async fn handle_reaction(
     }
 }
 
-fn is_relevant_reaction(message_id: MessageId, emoji: &ReactionType, data: &Data) -> bool {
-    message_id == MessageId::new(ROLES_MESSAGE_ID) && data.reaction_roles.contains_key(emoji)
+/// The role behind `emoji`, with built-in mappings taking precedence over runtime ones.
+async fn role_for(data: &Data, emoji: &ReactionType) -> Option<RoleId> {
+    let key = EmojiKey::from(emoji);
+    if let Some(kind) = data.reaction_roles.get(&key) {
+        return Some(ids::role(*kind));
+    }
+
+    match custom_reaction_roles(&data.storage).await {
+        Ok(roles) => roles
+            .into_iter()
+            .find(|r| EmojiKey::from(&r.emoji) == key)
+            .map(|r| RoleId::new(r.role_id)),
+        Err(e) => {
+            warn!("Failed to load custom reaction roles: {:?}", e);
+            None
+        }
+    }
 }
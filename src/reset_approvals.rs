@@ -0,0 +1,304 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ChannelId, ComponentInteraction, Context as SerenityContext, CreateActionRow,
+    CreateButton, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateMessage, EditMessage, Permissions,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::ReportKind,
+    graphql::models::{Member, Streak},
+    history::{status_update_day, update_member_result, update_status_update_day},
+    storage::Storage,
+    streaks::{self, DayOutcome, UpdateStreaks},
+    utils::{delivery::annotate_report, permissions::clicker_has, time::format_date},
+    Data,
+};
+
+/// Custom ID prefix of the reset approval buttons, routed here by [`crate::interactions`].
+pub const RESET_APPROVAL_COMPONENT: &str = "reset_approval";
+const PENDING_KEY: &str = "reset_approvals.pending";
+
+/// Streak resets of a status update check waiting for a mentor to approve or hold them
+/// back. The defaulters are read from the day's history when the resets are applied, so
+/// appeals approved in the meantime are respected.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingResets {
+    pub date: NaiveDate,
+    /// Resets are applied automatically once this passes.
+    pub expires_at: DateTime<Utc>,
+    pub channel_id: u64,
+    /// Unset if the approval request couldn't be posted.
+    pub message_id: Option<u64>,
+}
+
+/// Whether the resets of `date` are still waiting for a mentor.
+pub async fn is_pending(storage: &Storage, date: NaiveDate) -> anyhow::Result<bool> {
+    let pending: Vec<PendingResets> = storage.get(PENDING_KEY).await?;
+    Ok(pending.iter().any(|p| p.date == date))
+}
+
+/// Asks mentors in `channel_id` to approve the streak resets of `defaulters` on `date`.
+/// Resets are applied automatically after `timeout_minutes` by [`apply_expired`], even if
+/// the request couldn't be posted.
+pub async fn request_approval(
+    ctx: &SerenityContext,
+    data: &Data,
+    date: NaiveDate,
+    channel_id: u64,
+    timeout_minutes: u64,
+    defaulters: &[&Member],
+) -> anyhow::Result<()> {
+    let pending = PendingResets {
+        date,
+        expires_at: Utc::now() + TimeDelta::minutes(timeout_minutes as i64),
+        channel_id,
+        message_id: None,
+    };
+    data.storage
+        .update(PENDING_KEY, |all: &mut Vec<PendingResets>| {
+            all.retain(|p| p.date != date);
+            all.push(pending);
+        })
+        .await?;
+
+    let mut description = String::from("The following streaks are about to be reset:\n");
+    for member in defaulters {
+        description.push_str(&format!("- {} (Group {})\n", member.name, member.group_id));
+    }
+    description.push_str(&format!(
+        "\nResets are applied automatically in {} minutes unless held back.",
+        timeout_minutes
+    ));
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}:approve:{}", RESET_APPROVAL_COMPONENT, date))
+            .label("Approve")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}:deny:{}", RESET_APPROVAL_COMPONENT, date))
+            .label("Hold back")
+            .style(ButtonStyle::Danger),
+    ]);
+    let embed = CreateEmbed::new()
+        .title(format!("Pending Streak Resets - {}", format_date(date)))
+        .description(description);
+    let message = ChannelId::new(channel_id)
+        .send_message(
+            &ctx.http,
+            CreateMessage::new().embed(embed).components(vec![buttons]),
+        )
+        .await?;
+
+    data.storage
+        .update(PENDING_KEY, |all: &mut Vec<PendingResets>| {
+            if let Some(p) = all.iter_mut().find(|p| p.date == date) {
+                p.message_id = Some(message.id.get());
+            }
+        })
+        .await
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    arg: &str,
+    data: &Data,
+) {
+    let result = match action {
+        "approve" | "deny" => match arg.parse() {
+            Ok(date) => review(ctx, component, date, action == "approve", data).await,
+            Err(_) => return,
+        },
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        error!(
+            "Failed to handle reset approval interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+async fn review(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    date: NaiveDate,
+    approved: bool,
+    data: &Data,
+) -> anyhow::Result<()> {
+    if !clicker_has(component, Permissions::MANAGE_GUILD) {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Only mentors can approve or hold back streak resets.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    }
+
+    let Some(pending) = take(&data.storage, |p| p.date == date).await?.pop() else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("These resets were already decided.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    };
+
+    if approved {
+        if let Err(e) = apply(data, date).await {
+            // Let mentors retry rather than leaving the resets undecided.
+            restore(&data.storage, pending).await?;
+            let response = CreateInteractionResponseMessage::new()
+                .content("Failed to apply the resets, try again later.")
+                .ephemeral(true);
+            component
+                .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+                .await?;
+            return Err(e);
+        }
+    }
+
+    let outcome = if approved {
+        format!("Streak resets approved by {}.", component.user.name)
+    } else {
+        format!("Streak resets held back by {}.", component.user.name)
+    };
+    info!("{} ({})", outcome, date);
+    let response = CreateInteractionResponseMessage::new()
+        .content(&outcome)
+        .components(vec![]);
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(response),
+        )
+        .await?;
+    annotate(ctx, data, date, &outcome).await;
+    Ok(())
+}
+
+/// Applies the resets nobody decided on before they expired.
+pub async fn apply_expired(ctx: &SerenityContext, data: &Data) -> anyhow::Result<()> {
+    let now = Utc::now();
+    for pending in take(&data.storage, |p| p.expires_at <= now).await? {
+        if let Err(e) = apply(data, pending.date).await {
+            warn!(
+                "Failed to apply the streak resets of {}: {:?}",
+                pending.date, e
+            );
+            restore(&data.storage, pending).await?;
+            continue;
+        }
+
+        let outcome = "No response, streak resets were applied automatically.";
+        info!("{} ({})", outcome, pending.date);
+        if let Some(message_id) = pending.message_id {
+            let edit = EditMessage::new().content(outcome).components(vec![]);
+            if let Err(e) = ChannelId::new(pending.channel_id)
+                .edit_message(&ctx.http, message_id, edit)
+                .await
+            {
+                warn!("Failed to close the reset approval request: {}", e);
+            }
+        }
+        annotate(ctx, data, pending.date, outcome).await;
+    }
+    Ok(())
+}
+
+/// Removes and returns the pending resets matching `filter`, so only one decision is ever
+/// applied.
+async fn take(
+    storage: &Storage,
+    filter: impl Fn(&PendingResets) -> bool,
+) -> anyhow::Result<Vec<PendingResets>> {
+    storage
+        .update(PENDING_KEY, |all: &mut Vec<PendingResets>| {
+            let (taken, kept) = all.drain(..).partition(|p| filter(p));
+            *all = kept;
+            taken
+        })
+        .await
+}
+
+async fn restore(storage: &Storage, pending: PendingResets) -> anyhow::Result<()> {
+    storage
+        .update(PENDING_KEY, |all: &mut Vec<PendingResets>| {
+            all.push(pending)
+        })
+        .await
+}
+
+/// Resets the streaks of everyone who still stands as a defaulter on `date`, spending
+/// their streak shields first. One member failing in Root doesn't hold up the others, or
+/// get anyone reset twice on a retry.
+async fn apply(data: &Data, date: NaiveDate) -> anyhow::Result<()> {
+    let Some(day) = status_update_day(&data.storage, date).await? else {
+        return Ok(());
+    };
+    let policy = UpdateStreaks {
+        storage: &data.storage,
+        resets: true,
+        milestones: &[],
+    };
+    for result in day.members.iter().filter(|m| !m.sent_update) {
+        let mut member = Member {
+            member_id: result.member_id,
+            name: result.name.clone(),
+            discord_id: result.discord_id.clone(),
+            group_id: result.group_id,
+            streak: vec![Streak {
+                current_streak: result.current_streak,
+                max_streak: result.max_streak,
+            }],
+        };
+        let outcome = match streaks::record_day(&policy, &mut member, false).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                warn!("Failed to reset the streak of {}: {:?}", member.name, e);
+                continue;
+            }
+        };
+        let streak = member.streak.first().cloned();
+        update_member_result(&data.storage, date, &result.discord_id, |r| {
+            r.shielded = outcome == DayOutcome::Frozen;
+            if let Some(streak) = streak {
+                r.current_streak = streak.current_streak;
+                r.max_streak = streak.max_streak;
+            }
+        })
+        .await?;
+    }
+    update_status_update_day(&data.storage, date, |day| day.resets_applied = true).await?;
+    Ok(())
+}
+
+async fn annotate(ctx: &SerenityContext, data: &Data, date: NaiveDate, outcome: &str) {
+    if let Err(e) =
+        annotate_report(ctx, &data.storage, ReportKind::StatusUpdate, date, outcome).await
+    {
+        warn!("Failed to add the reset decision to the report: {:?}", e);
+    }
+}
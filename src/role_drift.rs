@@ -0,0 +1,262 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ComponentInteraction, Context as SerenityContext, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage, EditInteractionResponse, GuildId,
+    RoleId, UserId,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{config::GroupsConfig, graphql::models::Member, storage::Storage, Data};
+
+/// Custom ID prefix of the "Apply fixes" button, routed here by [`crate::interactions`].
+pub const ROLE_DRIFT_COMPONENT: &str = "role_drift";
+const REPORT_KEY: &str = "role_drift.report";
+
+/// A member whose group roles on Discord don't match their group on Root.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Drift {
+    pub user_id: u64,
+    pub name: String,
+    pub group_id: i32,
+    /// The role of their Root group, if they lack it.
+    pub missing_role: Option<u64>,
+    /// Roles of other groups they still have.
+    pub extra_roles: Vec<u64>,
+}
+
+/// The latest reconciliation report, kept so its button applies exactly what mentors saw.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub id: u64,
+    pub guild_id: u64,
+    pub drifts: Vec<Drift>,
+    pub applied_by: Option<u64>,
+}
+
+/// Compares the group roles of every linked member in `guild_id` with their Root group.
+/// Members who aren't in the server or whose group has no role are skipped.
+pub async fn find_drift(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    members: &[Member],
+    groups: &GroupsConfig,
+) -> Vec<Drift> {
+    let group_roles: HashSet<u64> = groups.roles.iter().map(|role| role.role_id).collect();
+    let mut drifts = Vec::new();
+
+    for member in members {
+        let Some(expected) = groups.role_for(member.group_id) else {
+            continue;
+        };
+        let Ok(user_id) = member.discord_id.parse::<u64>() else {
+            continue;
+        };
+        let guild_member = match guild_id.member(&ctx.http, UserId::new(user_id)).await {
+            Ok(guild_member) => guild_member,
+            Err(e) => {
+                debug!("Skipping {} in the role audit: {}", member.name, e);
+                continue;
+            }
+        };
+
+        let roles: HashSet<u64> = guild_member.roles.iter().map(|role| role.get()).collect();
+        let missing_role = (!roles.contains(&expected)).then_some(expected);
+        let mut extra_roles: Vec<u64> = roles
+            .intersection(&group_roles)
+            .copied()
+            .filter(|role| *role != expected)
+            .collect();
+        extra_roles.sort_unstable();
+        if missing_role.is_some() || !extra_roles.is_empty() {
+            drifts.push(Drift {
+                user_id,
+                name: member.name.clone(),
+                group_id: member.group_id,
+                missing_role,
+                extra_roles,
+            });
+        }
+    }
+
+    drifts
+}
+
+/// Stores `report` as the one the "Apply fixes" button acts on, replacing older reports.
+pub async fn save_report(storage: &Storage, report: &DriftReport) -> anyhow::Result<()> {
+    storage.set(REPORT_KEY, report).await
+}
+
+pub fn apply_button(report_id: u64) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new(format!(
+        "{}:apply:{}",
+        ROLE_DRIFT_COMPONENT, report_id
+    ))
+    .label("Apply fixes")
+    .style(ButtonStyle::Danger)])
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(REPORT_KEY, |report: &mut DriftReport| {
+            report.drifts.retain(|drift| drift.user_id != user_id)
+        })
+        .await
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    arg: &str,
+    data: &Data,
+) {
+    let Ok(id) = arg.parse() else {
+        return;
+    };
+    let result = match action {
+        "apply" => apply_fixes(ctx, component, id, data).await,
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        error!(
+            "Failed to handle role drift interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+async fn apply_fixes(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    id: u64,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let can_manage_roles = component
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_roles());
+    if !can_manage_roles {
+        return reply_ephemeral(
+            ctx,
+            component,
+            "Only mentors who can manage roles can apply fixes.",
+        )
+        .await;
+    }
+
+    let reviewer = component.user.id.get();
+    let report = data
+        .storage
+        .update(REPORT_KEY, |report: &mut DriftReport| {
+            if report.id != id || report.applied_by.is_some() {
+                return None;
+            }
+            report.applied_by = Some(reviewer);
+            Some(report.clone())
+        })
+        .await?;
+    let Some(report) = report else {
+        return reply_ephemeral(
+            ctx,
+            component,
+            "These fixes were already applied or a newer audit replaced them.",
+        )
+        .await;
+    };
+
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "Applying fixes, confirmed by {}...",
+                        component.user.name
+                    ))
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    let guild_id = GuildId::new(report.guild_id);
+    let mut failures = Vec::new();
+    for drift in &report.drifts {
+        if let Err(e) = fix_roles(ctx, guild_id, drift).await {
+            warn!("Failed to fix the group roles of {}: {}", drift.name, e);
+            failures.push(drift.name.as_str());
+        }
+    }
+    info!(
+        "{} applied {} group role fixes",
+        component.user.name,
+        report.drifts.len() - failures.len()
+    );
+
+    let mut summary = format!(
+        "Fixed the group roles of {} members, confirmed by {}.",
+        report.drifts.len() - failures.len(),
+        component.user.name
+    );
+    if !failures.is_empty() {
+        summary.push_str(&format!("\nCould not update: {}", failures.join(", ")));
+    }
+    component
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(summary))
+        .await?;
+    Ok(())
+}
+
+async fn fix_roles(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    drift: &Drift,
+) -> serenity::Result<()> {
+    const REASON: Option<&str> = Some("Matching the member's group on Root");
+    let user_id = UserId::new(drift.user_id);
+    if let Some(role) = drift.missing_role {
+        ctx.http
+            .add_member_role(guild_id, user_id, RoleId::new(role), REASON)
+            .await?;
+    }
+    for role in &drift.extra_roles {
+        ctx.http
+            .remove_member_role(guild_id, user_id, RoleId::new(*role), REASON)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn reply_ephemeral(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    content: &str,
+) -> anyhow::Result<()> {
+    let response = CreateInteractionResponseMessage::new()
+        .content(content)
+        .ephemeral(true);
+    component
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,127 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{Context as SerenityContext, GuildId, Member, RoleId, User};
+use tracing::{debug, info, warn};
+
+use crate::{storage::Storage, Data};
+
+const SNAPSHOTS_KEY: &str = "role_snapshots.members";
+
+/// The roles a member had when they left.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RoleSnapshot {
+    guild_id: u64,
+    role_ids: Vec<u64>,
+    left_at: DateTime<Utc>,
+}
+
+/// Remembers the roles of a member who left, if the cache still had them.
+pub async fn snapshot_roles(data: &Data, guild_id: GuildId, user: &User, member: Option<&Member>) {
+    if user.bot {
+        return;
+    }
+    let Some(member) = member else {
+        debug!(
+            "No cached roles for {}, nothing to restore later",
+            user.name
+        );
+        return;
+    };
+    let snapshot = RoleSnapshot {
+        guild_id: guild_id.get(),
+        role_ids: member.roles.iter().map(|role| role.get()).collect(),
+        left_at: Utc::now(),
+    };
+    if let Err(e) = data
+        .storage
+        .update(
+            SNAPSHOTS_KEY,
+            |snapshots: &mut HashMap<u64, RoleSnapshot>| {
+                snapshots.insert(user.id.get(), snapshot);
+            },
+        )
+        .await
+    {
+        warn!("Failed to snapshot {}'s roles: {:?}", user.name, e);
+    }
+}
+
+/// Gives a returning member back the whitelisted roles they had when they left.
+pub async fn restore_roles(ctx: &SerenityContext, data: &Data, member: &Member) {
+    let whitelist = data.config.read().await.roles.restore_on_rejoin.clone();
+    if whitelist.is_empty() {
+        return;
+    }
+
+    let user_id = member.user.id.get();
+    let snapshot = data
+        .storage
+        .update(
+            SNAPSHOTS_KEY,
+            |snapshots: &mut HashMap<u64, RoleSnapshot>| {
+                snapshots
+                    .remove(&user_id)
+                    .filter(|s| s.guild_id == member.guild_id.get())
+            },
+        )
+        .await;
+    let snapshot = match snapshot {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(
+                "Failed to load {}'s role snapshot: {:?}",
+                member.user.name, e
+            );
+            return;
+        }
+    };
+
+    let roles: Vec<RoleId> = snapshot
+        .role_ids
+        .into_iter()
+        .filter(|id| whitelist.contains(id))
+        .map(RoleId::new)
+        .collect();
+    if roles.is_empty() {
+        return;
+    }
+    match member.add_roles(&ctx.http, &roles).await {
+        Ok(()) => info!(
+            "Restored {} roles to {} on rejoin",
+            roles.len(),
+            member.user.name
+        ),
+        Err(e) => warn!("Failed to restore {}'s roles: {}", member.user.name, e),
+    }
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(
+            SNAPSHOTS_KEY,
+            |snapshots: &mut HashMap<u64, RoleSnapshot>| {
+                snapshots.remove(&user_id);
+            },
+        )
+        .await
+}
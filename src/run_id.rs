@@ -0,0 +1,52 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::future::Future;
+
+use rand::Rng;
+use reqwest::RequestBuilder;
+
+/// Header that carries the run ID on requests to Root, so its logs can be matched too.
+pub const HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    static RUN_ID: String;
+}
+
+/// A short random ID, unique enough to grep a day's logs for.
+pub fn generate() -> String {
+    format!("{:08x}", rand::thread_rng().gen::<u32>())
+}
+
+/// Runs `fut` with `id` as the current run ID.
+pub async fn scope<F: Future>(id: String, fut: F) -> F::Output {
+    RUN_ID.scope(id, fut).await
+}
+
+/// The ID of the task run this is part of, if any. Work spawned onto another tokio task
+/// doesn't inherit it.
+pub fn current() -> Option<String> {
+    RUN_ID.try_with(Clone::clone).ok()
+}
+
+/// Adds the current run ID to `request` as [`HEADER`].
+pub fn tag(request: RequestBuilder) -> RequestBuilder {
+    match current() {
+        Some(id) => request.header(HEADER, id),
+        None => request,
+    }
+}
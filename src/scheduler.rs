@@ -15,31 +15,436 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
-use crate::tasks::{get_tasks, Task};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
 
+use crate::{
+    deployment, holidays,
+    oncall::escalate,
+    run_id, semester,
+    storage::Storage,
+    tasks::{get_tasks, OverlapPolicy, RetryLater, Task},
+    utils::time::{local_today, timezone},
+    Data,
+};
+
+use chrono::{DateTime, NaiveTime, Timelike, Utc};
+use rand::Rng;
+use serde::Serialize;
 use serenity::client::Context as SerenityContext;
-use tokio::spawn;
-use tracing::{debug, error, trace};
+use tokio::{spawn, sync::Notify, task::JoinHandle, time::Duration};
+use tracing::{debug, error, info, info_span, trace, warn, Instrument};
+
+/// Report times set with `$schedule set`, keyed by task name.
+const SCHEDULE_OVERRIDES_KEY: &str = "scheduler.overrides";
+/// When each task last completed without an error, used to check dependencies.
+const LAST_SUCCESS_KEY: &str = "scheduler.last_success";
+/// How many times in a row a task is retried after a [`RetryLater`] before the run
+/// counts as failed.
+const MAX_RETRIES: u32 = 3;
 
-pub async fn run_scheduler(ctx: SerenityContext) {
+tokio::task_local! {
+    static RETRY_ATTEMPT: u32;
+}
+
+/// How many times the current run has been retried after a [`RetryLater`], 0 on its
+/// first attempt and outside task runs.
+pub fn retry_attempt() -> u32 {
+    RETRY_ATTEMPT.try_with(|attempt| *attempt).unwrap_or(0)
+}
+
+pub async fn run_scheduler(ctx: SerenityContext, data: Data) {
     trace!("Running scheduler");
-    let tasks = get_tasks();
+    let tasks = topological_order(get_tasks());
+    let state = data.scheduler.clone();
 
     for task in tasks {
         debug!("Spawing task {}", task.name());
-        spawn(schedule_task(ctx.clone(), task));
+        spawn(schedule_task(
+            ctx.clone(),
+            data.clone(),
+            state.clone(),
+            task,
+        ));
+    }
+}
+
+/// Orders tasks so every task comes after its dependencies. Tasks that are part of a
+/// dependency cycle can never run and are dropped.
+fn topological_order(tasks: Vec<Box<dyn Task>>) -> Vec<Box<dyn Task>> {
+    let names: HashSet<String> = tasks.iter().map(|task| task.name().to_string()).collect();
+    for task in &tasks {
+        for dependency in task.depends_on() {
+            if !names.contains(*dependency) {
+                warn!(
+                    "Task {} depends on unknown task {}",
+                    task.name(),
+                    dependency
+                );
+            }
+        }
+    }
+
+    let mut ordered: Vec<Box<dyn Task>> = Vec::new();
+    let mut remaining = tasks;
+    loop {
+        let (ready, blocked): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|task| {
+            task.depends_on().iter().all(|dependency| {
+                !names.contains(*dependency) || ordered.iter().any(|t| t.name() == *dependency)
+            })
+        });
+        remaining = blocked;
+        if ready.is_empty() {
+            break;
+        }
+        ordered.extend(ready);
+    }
+
+    for task in &remaining {
+        error!(
+            "Task {} is part of a dependency cycle, not scheduling it",
+            task.name()
+        );
+    }
+    ordered
+}
+
+/// Shared between the task loops so dependents can wait for their dependencies.
+#[derive(Default)]
+pub struct SchedulerState {
+    tasks: Mutex<HashMap<String, TaskState>>,
+    changed: Notify,
+    /// Tasks whose last run failed, so a task failing every run only escalates once.
+    failing: Mutex<HashSet<String>>,
+}
+
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct TaskState {
+    /// Time of the next run, without jitter.
+    pub slot: Option<DateTime<Utc>>,
+    pub running: bool,
+    /// When the task runs again after asking for a retry, dependents wait for it.
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Retries in a row so far.
+    pub retries: u32,
+}
+
+impl SchedulerState {
+    /// Current slot and running state of every scheduled task.
+    pub fn snapshot(&self) -> HashMap<String, TaskState> {
+        self.tasks
+            .lock()
+            .expect("Scheduler state lock poisoned")
+            .clone()
+    }
+
+    fn get(&self, task: &dyn Task) -> TaskState {
+        self.tasks
+            .lock()
+            .expect("Scheduler state lock poisoned")
+            .get(task.name())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn update(&self, task: &dyn Task, f: impl FnOnce(&mut TaskState)) {
+        let mut tasks = self.tasks.lock().expect("Scheduler state lock poisoned");
+        f(tasks.entry(task.name().to_string()).or_default());
+        drop(tasks);
+        self.changed.notify_waiters();
+    }
+
+    /// Records the outcome of a run, returning whether the task just started failing.
+    fn record_outcome(&self, task: &dyn Task, succeeded: bool) -> bool {
+        let mut failing = self.failing.lock().expect("Scheduler state lock poisoned");
+        if succeeded {
+            failing.remove(task.name());
+            false
+        } else {
+            failing.insert(task.name().to_string())
+        }
+    }
+
+    /// Waits until none of the task's dependencies are running or due in the same slot.
+    async fn wait_for_dependencies(&self, task: &dyn Task) {
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            let now = Utc::now();
+            let pending = {
+                let tasks = self.tasks.lock().expect("Scheduler state lock poisoned");
+                task.depends_on().iter().any(|dependency| {
+                    tasks.get(*dependency).is_some_and(|state| {
+                        state.running
+                            || state.retry_at.is_some()
+                            || state.slot.is_some_and(|slot| slot <= now)
+                    })
+                })
+            };
+            if !pending {
+                return;
+            }
+
+            debug!("Task {}: Waiting for dependencies", task.name());
+            changed.await;
+        }
     }
 }
 
-async fn schedule_task(ctx: SerenityContext, task: Box<dyn Task>) {
+async fn schedule_task(
+    ctx: SerenityContext,
+    data: Data,
+    state: Arc<SchedulerState>,
+    task: Box<dyn Task>,
+) {
+    let task: Arc<dyn Task> = Arc::from(task);
+    let mut in_flight: Option<JoinHandle<()>> = None;
+    // Holds a permit, so a retry asked for before the loop waits again isn't missed.
+    let retry_requested = Arc::new(Notify::new());
+
     loop {
-        let next_run_in = task.run_in();
+        let next_run_in = match state.get(task.as_ref()).retry_at {
+            Some(retry_at) => (retry_at - Utc::now()).to_std().unwrap_or_default(),
+            None => next_run_in(&data.storage, task.as_ref()).await,
+        };
+        let slot = Utc::now() + next_run_in;
+        state.update(task.as_ref(), |s| s.slot = Some(slot));
+
+        let next_run_in = next_run_in + jitter(&data).await;
         debug!("Task {}: Next run in {:?}", task.name(), next_run_in);
-        tokio::time::sleep(next_run_in).await;
+        tokio::select! {
+            _ = tokio::time::sleep(next_run_in) => {}
+            _ = data.schedule_changed.notified() => {
+                debug!("Task {}: Schedule changed, recomputing next run", task.name());
+                continue;
+            }
+            _ = retry_requested.notified() => {
+                debug!("Task {}: Retry requested, recomputing next run", task.name());
+                continue;
+            }
+        }
+
+        if let Some(previous) = in_flight.take_if(|previous| !previous.is_finished()) {
+            match task.overlap_policy() {
+                OverlapPolicy::Skip => {
+                    warn!(
+                        "Task {}: Previous run still in progress, skipping",
+                        task.name()
+                    );
+                    in_flight = Some(previous);
+                    continue;
+                }
+                OverlapPolicy::Queue => {
+                    warn!("Task {}: Waiting for previous run to finish", task.name());
+                    let _ = previous.await;
+                }
+            }
+        }
+
+        state.update(task.as_ref(), |s| s.running = true);
+        let (ctx, data, state, task) = (ctx.clone(), data.clone(), state.clone(), task.clone());
+        let retry_requested = retry_requested.clone();
+        in_flight = Some(spawn(async move {
+            run_task(ctx, &data, &state, task.as_ref()).await;
+            state.update(task.as_ref(), |s| s.running = false);
+            if state.get(task.as_ref()).retry_at.is_some() {
+                retry_requested.notify_one();
+            }
+        }));
+    }
+}
+
+async fn run_task(ctx: SerenityContext, data: &Data, state: &SchedulerState, task: &dyn Task) {
+    let attempt = state.get(task).retries;
+    state.update(task, |s| {
+        s.retry_at = None;
+        s.retries = 0;
+    });
+    state.wait_for_dependencies(task).await;
+    if !deployment::is_active(data) {
+        info!("Task {}: Dry run, deferring to the primary", task.name());
+        if let Err(e) = task.dry_run(ctx, data).await {
+            warn!("Task {}: Dry run failed: {:?}", task.name(), e);
+        }
+        return;
+    }
+    if task.reads_messages() && !data.config.read().await.features.message_scanning {
+        info!(
+            "Task {}: Skipped, message scanning is disabled",
+            task.name()
+        );
+        return;
+    }
+    if task.follows_semester() {
+        let (date, in_session) = {
+            let config = data.config.read().await;
+            let date = task.evaluated_date(&config, Utc::now());
+            (date, semester::in_session(&config.semester, date))
+        };
+        if !in_session {
+            info!(
+                "Task {}: Skipped, {} is outside the semester",
+                task.name(),
+                date
+            );
+            return;
+        }
+        match holidays::is_holiday(&data.storage, date).await {
+            Ok(true) => {
+                info!("Task {}: Skipped, {} is a holiday", task.name(), date);
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => warn!(
+                "Task {}: Could not check for holidays: {:?}",
+                task.name(),
+                e
+            ),
+        }
+    }
+    let unmet = match unmet_dependencies(&data.storage, task).await {
+        Ok(unmet) => unmet,
+        Err(e) => {
+            error!(
+                "Task {}: Could not check dependencies, error {}",
+                task.name(),
+                e
+            );
+            return;
+        }
+    };
+    if !unmet.is_empty() {
+        warn!(
+            "Task {}: Skipping, dependencies have not succeeded today: {}",
+            task.name(),
+            unmet.join(", ")
+        );
+        return;
+    }
 
-        debug!("Running task {}", task.name());
-        if let Err(e) = task.run(ctx.clone()).await {
-            error!("Could not run task {}, error {}", task.name(), e);
+    let id = run_id::generate();
+    debug!("Running task {} as run {}", task.name(), id);
+    let span = info_span!("task", name = task.name(), run_id = %id);
+    let run = RETRY_ATTEMPT.scope(attempt, task.run(ctx.clone(), data).instrument(span));
+    let result = run_id::scope(id.clone(), run).await;
+    let result = match result {
+        Err(e) => match e.downcast::<RetryLater>() {
+            Ok(retry) if attempt < MAX_RETRIES => {
+                info!("Task {}: {}", task.name(), retry);
+                state.update(task, |s| {
+                    s.retry_at = Some(Utc::now() + retry.after);
+                    s.retries = attempt + 1;
+                });
+                return;
+            }
+            Ok(retry) => Err(anyhow::anyhow!(
+                "Gave up after {} retries: {}",
+                MAX_RETRIES,
+                retry.reason
+            )),
+            Err(e) => Err(e),
+        },
+        Ok(()) => Ok(()),
+    };
+    if let Err(e) = result {
+        error!(
+            "Could not run task {} (run {}), error {}",
+            task.name(),
+            id,
+            e
+        );
+        if state.record_outcome(task, false) {
+            let content = format!("Task {} failed (run `{}`): {}", task.name(), id, e);
+            escalate(&ctx, data, &content).await;
         }
+        return;
+    }
+    state.record_outcome(task, true);
+
+    let result = data
+        .storage
+        .update(
+            LAST_SUCCESS_KEY,
+            |last_success: &mut HashMap<String, DateTime<Utc>>| {
+                last_success.insert(task.name().to_string(), Utc::now())
+            },
+        )
+        .await;
+    if let Err(e) = result {
+        warn!("Task {}: Failed to record success: {:?}", task.name(), e);
     }
 }
+
+/// Dependencies of `task` that haven't completed successfully today, in the bot's timezone.
+async fn unmet_dependencies(storage: &Storage, task: &dyn Task) -> anyhow::Result<Vec<String>> {
+    if task.depends_on().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let last_success: HashMap<String, DateTime<Utc>> = storage.get(LAST_SUCCESS_KEY).await?;
+    let today = local_today();
+    Ok(task
+        .depends_on()
+        .iter()
+        .filter(|dependency| {
+            last_success
+                .get(**dependency)
+                .is_none_or(|time| time.with_timezone(&timezone()).date_naive() != today)
+        })
+        .map(|dependency| dependency.to_string())
+        .collect())
+}
+
+async fn jitter(data: &Data) -> Duration {
+    let max_jitter_seconds = data.config.read().await.scheduler.max_jitter_seconds;
+    if max_jitter_seconds == 0 {
+        return Duration::ZERO;
+    }
+
+    Duration::from_secs(rand::thread_rng().gen_range(0..=max_jitter_seconds))
+}
+
+/// Time till the task's next run, honouring its override if one is set.
+pub async fn next_run_in(storage: &Storage, task: &dyn Task) -> Duration {
+    let overrides = match schedule_overrides(storage).await {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            warn!("Failed to read schedule overrides: {:?}", e);
+            HashMap::new()
+        }
+    };
+
+    overrides
+        .get(task.name())
+        .and_then(|time| task.run_in_at(time.hour(), time.minute()))
+        .unwrap_or_else(|| task.run_in())
+}
+
+pub async fn schedule_overrides(storage: &Storage) -> anyhow::Result<HashMap<String, NaiveTime>> {
+    storage.get(SCHEDULE_OVERRIDES_KEY).await
+}
+
+/// Sets or, with `None`, removes the override for `task_name` and wakes the scheduler so
+/// the change applies immediately.
+pub async fn set_schedule_override(
+    data: &Data,
+    task_name: &str,
+    time: Option<NaiveTime>,
+) -> anyhow::Result<()> {
+    data.storage
+        .update(
+            SCHEDULE_OVERRIDES_KEY,
+            |overrides: &mut HashMap<String, NaiveTime>| match time {
+                Some(time) => overrides.insert(task_name.to_string(), time),
+                None => overrides.remove(task_name),
+            },
+        )
+        .await?;
+    data.schedule_changed.notify_waiters();
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+
+use crate::{
+    config::{SemesterConfig, Term},
+    storage::Storage,
+};
+
+/// Term starts and ends that were already announced, as `start:<term>` and `end:<term>`.
+const ANNOUNCED_KEY: &str = "semester.announced";
+
+/// The term `date` falls in, if any.
+pub fn term_on(config: &SemesterConfig, date: NaiveDate) -> Option<&Term> {
+    config
+        .terms
+        .iter()
+        .find(|term| term.start <= date && date <= term.end)
+}
+
+/// The first term starting after `date`.
+pub fn next_term(config: &SemesterConfig, date: NaiveDate) -> Option<&Term> {
+    config
+        .terms
+        .iter()
+        .filter(|term| term.start > date)
+        .min_by_key(|term| term.start)
+}
+
+/// The last term that ended before `date`.
+pub fn previous_term(config: &SemesterConfig, date: NaiveDate) -> Option<&Term> {
+    config
+        .terms
+        .iter()
+        .filter(|term| term.end < date)
+        .max_by_key(|term| term.end)
+}
+
+/// Whether checks run on `date`. They always do when no terms are configured.
+pub fn in_session(config: &SemesterConfig, date: NaiveDate) -> bool {
+    config.terms.is_empty() || term_on(config, date).is_some()
+}
+
+/// Records the announcement `key`, returning false if it was already made.
+pub async fn mark_announced(storage: &Storage, key: String) -> anyhow::Result<bool> {
+    storage
+        .update(ANNOUNCED_KEY, |announced: &mut HashSet<String>| {
+            announced.insert(key)
+        })
+        .await
+}
@@ -0,0 +1,343 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ChannelId, ComponentInteraction, Context as SerenityContext, CreateActionRow,
+    CreateButton, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMessage,
+    Permissions, UserId,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    preferences::{allows, Notification},
+    storage::Storage,
+    utils::{
+        permissions::clicker_has,
+        time::{discord_timestamp, TimestampStyle},
+    },
+    Data,
+};
+
+/// Custom ID prefix of the session buttons, routed here by [`crate::interactions`].
+pub const SESSION_COMPONENT: &str = "session";
+const SESSIONS_KEY: &str = "sessions.talks";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SessionStatus {
+    Proposed,
+    Approved,
+    Rejected,
+}
+
+/// A talk proposed by a member, from the proposal through to the feedback poll.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub id: u64,
+    pub title: String,
+    pub description: String,
+    pub speaker_id: u64,
+    pub starts_at: DateTime<Utc>,
+    pub status: SessionStatus,
+    pub attendees: Vec<u64>,
+    pub reminder_sent: bool,
+    pub feedback_sent: bool,
+}
+
+impl Session {
+    pub fn embed(&self) -> CreateEmbed {
+        CreateEmbed::new()
+            .title(&self.title)
+            .description(&self.description)
+            .field("Speaker", format!("<@{}>", self.speaker_id), true)
+            .field(
+                "When",
+                discord_timestamp(self.starts_at, TimestampStyle::LongDateTime),
+                true,
+            )
+            .field("Attending", self.attendees.len().to_string(), true)
+            .footer(CreateEmbedFooter::new(format!("Session #{}", self.id)))
+    }
+}
+
+pub async fn sessions(storage: &Storage) -> anyhow::Result<Vec<Session>> {
+    storage.get(SESSIONS_KEY).await
+}
+
+/// Applies `f` to the session with `id`, returning `None` if there is no such session.
+pub async fn update_session<R>(
+    storage: &Storage,
+    id: u64,
+    f: impl FnOnce(&mut Session) -> R,
+) -> anyhow::Result<Option<R>> {
+    storage
+        .update(SESSIONS_KEY, |sessions: &mut Vec<Session>| {
+            sessions.iter_mut().find(|s| s.id == id).map(f)
+        })
+        .await
+}
+
+/// Drops the member's RSVPs and any talks they proposed.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(SESSIONS_KEY, |sessions: &mut Vec<Session>| {
+            sessions.retain(|s| s.speaker_id != user_id);
+            for session in sessions {
+                session.attendees.retain(|id| *id != user_id);
+            }
+        })
+        .await
+}
+
+/// Stores a new proposal and posts it to the approval channel for mentors.
+pub async fn propose_session(
+    ctx: &SerenityContext,
+    data: &Data,
+    title: String,
+    description: String,
+    speaker_id: UserId,
+    starts_at: DateTime<Utc>,
+) -> anyhow::Result<Session> {
+    let session = data
+        .storage
+        .update(SESSIONS_KEY, |sessions: &mut Vec<Session>| {
+            let session = Session {
+                id: sessions.iter().map(|s| s.id).max().unwrap_or(0) + 1,
+                title,
+                description,
+                speaker_id: speaker_id.get(),
+                starts_at,
+                status: SessionStatus::Proposed,
+                attendees: Vec::new(),
+                reminder_sent: false,
+                feedback_sent: false,
+            };
+            sessions.push(session.clone());
+            session
+        })
+        .await?;
+    info!("Session #{} proposed by {}", session.id, speaker_id);
+
+    let config = data.config.read().await.clone();
+    let Some(channel_id) = config
+        .sessions
+        .approval_channel_id
+        .or(config.bot.ops_channel_id)
+    else {
+        warn!(
+            "No approval channel configured, session #{} is pending",
+            session.id
+        );
+        return Ok(session);
+    };
+
+    let buttons = CreateActionRow::Buttons(vec![
+        session_button("approve", session.id, "Approve", ButtonStyle::Success),
+        session_button("reject", session.id, "Reject", ButtonStyle::Danger),
+    ]);
+    let message = CreateMessage::new()
+        .content("New talk proposal:")
+        .embed(session.embed())
+        .components(vec![buttons]);
+    ChannelId::new(channel_id)
+        .send_message(&ctx.http, message)
+        .await?;
+
+    Ok(session)
+}
+
+fn session_button(action: &str, id: u64, label: &str, style: ButtonStyle) -> CreateButton {
+    CreateButton::new(format!("{}:{}:{}", SESSION_COMPONENT, action, id))
+        .label(label)
+        .style(style)
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    arg: &str,
+    data: &Data,
+) {
+    let result = match arg.parse() {
+        Ok(id) => match action {
+            "approve" => review_proposal(ctx, component, id, true, data).await,
+            "reject" => review_proposal(ctx, component, id, false, data).await,
+            "rsvp" => toggle_rsvp(ctx, component, id, data).await,
+            _ => return,
+        },
+        Err(_) => return,
+    };
+
+    if let Err(e) = result {
+        error!(
+            "Failed to handle session interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+async fn review_proposal(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    id: u64,
+    approved: bool,
+    data: &Data,
+) -> anyhow::Result<()> {
+    if !clicker_has(component, Permissions::MANAGE_GUILD) {
+        let response = CreateInteractionResponseMessage::new()
+            .content("Only mentors can review session proposals.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    }
+
+    let status = if approved {
+        SessionStatus::Approved
+    } else {
+        SessionStatus::Rejected
+    };
+    let session = update_session(&data.storage, id, |session| {
+        if session.status != SessionStatus::Proposed {
+            return None;
+        }
+        session.status = status;
+        Some(session.clone())
+    })
+    .await?
+    .flatten();
+    let Some(session) = session else {
+        let response = CreateInteractionResponseMessage::new()
+            .content("This proposal was already reviewed.")
+            .ephemeral(true);
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await?;
+        return Ok(());
+    };
+
+    let outcome = if approved { "Approved" } else { "Rejected" };
+    info!("Session #{} {} by {}", id, outcome, component.user.name);
+    let response = CreateInteractionResponseMessage::new()
+        .content(format!("{} by {}.", outcome, component.user.name))
+        .components(vec![]);
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(response),
+        )
+        .await?;
+
+    if approved {
+        announce_session(ctx, data, &session).await?;
+    }
+    if !allows(&data.storage, session.speaker_id, Notification::Decisions).await {
+        return Ok(());
+    }
+    let dm = CreateMessage::new().content(format!(
+        "Your talk \"{}\" was {}.",
+        session.title,
+        outcome.to_lowercase()
+    ));
+    if let Err(e) = UserId::new(session.speaker_id)
+        .direct_message(&ctx.http, dm)
+        .await
+    {
+        warn!("Failed to DM speaker of session #{}: {}", id, e);
+    }
+
+    Ok(())
+}
+
+async fn announce_session(
+    ctx: &SerenityContext,
+    data: &Data,
+    session: &Session,
+) -> anyhow::Result<()> {
+    let Some(channel_id) = data.config.read().await.sessions.channel_id else {
+        warn!(
+            "No sessions channel configured, not announcing #{}",
+            session.id
+        );
+        return Ok(());
+    };
+
+    let rsvp = CreateActionRow::Buttons(vec![session_button(
+        "rsvp",
+        session.id,
+        "RSVP",
+        ButtonStyle::Primary,
+    )]);
+    let message = CreateMessage::new()
+        .content("Upcoming talk, RSVP to get a reminder:")
+        .embed(session.embed())
+        .components(vec![rsvp]);
+    ChannelId::new(channel_id)
+        .send_message(&ctx.http, message)
+        .await?;
+
+    Ok(())
+}
+
+async fn toggle_rsvp(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    id: u64,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let user_id = component.user.id.get();
+    let session = update_session(&data.storage, id, |session| {
+        if session.attendees.contains(&user_id) {
+            session.attendees.retain(|id| *id != user_id);
+        } else {
+            session.attendees.push(user_id);
+        }
+        session.clone()
+    })
+    .await?;
+    let Some(session) = session else {
+        return Ok(());
+    };
+
+    // Refresh the attendee count on the announcement, then confirm privately.
+    let response = CreateInteractionResponseMessage::new().embed(session.embed());
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(response),
+        )
+        .await?;
+    let reply = if session.attendees.contains(&user_id) {
+        "You're on the list, you'll get a reminder before the talk starts."
+    } else {
+        "You're no longer attending this talk."
+    };
+    component
+        .create_followup(
+            &ctx.http,
+            CreateInteractionResponseFollowup::new()
+                .content(reply)
+                .ephemeral(true),
+        )
+        .await?;
+
+    Ok(())
+}
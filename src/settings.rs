@@ -0,0 +1,241 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context as _};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use crate::storage::Storage;
+
+/// Every setting, by namespace and then key.
+const SETTINGS_KEY: &str = "settings";
+
+/// Every version of the settings, newest last.
+const SNAPSHOTS_KEY: &str = "settings.snapshots";
+/// How many versions are kept to roll back to.
+const MAX_SNAPSHOTS: usize = 50;
+
+/// How many unread changes a slow subscriber can fall behind before it misses some.
+const CHANGE_CAPACITY: usize = 32;
+
+pub type Namespaces = BTreeMap<String, BTreeMap<String, Value>>;
+
+/// A setting that was set, or removed if `value` is [`None`].
+#[derive(Clone, Debug)]
+pub struct SettingChange {
+    pub namespace: String,
+    pub key: String,
+    pub value: Option<Value>,
+}
+
+/// The settings as they were after a change.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub taken_at: DateTime<Utc>,
+    /// What changed, e.g. `set ids.lab_channel`.
+    pub change: String,
+    pub settings: Namespaces,
+}
+
+/// Runtime tunables that owners can change with `$set` without a redeploy. Each module
+/// keeps its settings in its own namespace and reads them with a fallback, so an unset
+/// setting means the module's default.
+pub struct Settings {
+    storage: Arc<Storage>,
+    changes: broadcast::Sender<SettingChange>,
+}
+
+impl Settings {
+    pub fn new(storage: Arc<Storage>) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_CAPACITY);
+        Self { storage, changes }
+    }
+
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> anyhow::Result<Option<T>> {
+        let namespaces: Namespaces = self.storage.get(SETTINGS_KEY).await?;
+        namespaces
+            .get(namespace)
+            .and_then(|settings| settings.get(key))
+            .map(|value| {
+                serde_json::from_value(value.clone())
+                    .with_context(|| format!("Setting {}.{} has the wrong type", namespace, key))
+            })
+            .transpose()
+    }
+
+    /// The setting, or `default` if it isn't set or can't be read.
+    pub async fn get_or<T: DeserializeOwned>(&self, namespace: &str, key: &str, default: T) -> T {
+        match self.get(namespace, key).await {
+            Ok(value) => value.unwrap_or(default),
+            Err(e) => {
+                warn!("Failed to read setting, using the default: {:?}", e);
+                default
+            }
+        }
+    }
+
+    pub async fn set<T: Serialize>(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &T,
+    ) -> anyhow::Result<()> {
+        let value = serde_json::to_value(value)
+            .with_context(|| format!("Failed to serialize setting {}.{}", namespace, key))?;
+        self.baseline().await?;
+        self.storage
+            .update(SETTINGS_KEY, |namespaces: &mut Namespaces| {
+                namespaces
+                    .entry(namespace.to_string())
+                    .or_default()
+                    .insert(key.to_string(), value.clone())
+            })
+            .await?;
+        self.notify(namespace, key, Some(value));
+        self.snapshot(format!("set {}.{}", namespace, key)).await
+    }
+
+    /// Removes the setting so its module falls back to the default. Returns whether it
+    /// was set.
+    pub async fn remove(&self, namespace: &str, key: &str) -> anyhow::Result<bool> {
+        self.baseline().await?;
+        let removed = self
+            .storage
+            .update(SETTINGS_KEY, |namespaces: &mut Namespaces| {
+                let Some(settings) = namespaces.get_mut(namespace) else {
+                    return false;
+                };
+                let removed = settings.remove(key).is_some();
+                if settings.is_empty() {
+                    namespaces.remove(namespace);
+                }
+                removed
+            })
+            .await?;
+        if removed {
+            self.notify(namespace, key, None);
+            self.snapshot(format!("removed {}.{}", namespace, key))
+                .await?;
+        }
+        Ok(removed)
+    }
+
+    /// Past versions of the settings, oldest first.
+    pub async fn history(&self) -> anyhow::Result<Vec<Snapshot>> {
+        self.storage.get(SNAPSHOTS_KEY).await
+    }
+
+    /// Restores the settings of `version`, which becomes a new version itself so the
+    /// rollback can be undone too. Subscribers are told about every setting that changed.
+    pub async fn rollback(&self, version: u32) -> anyhow::Result<()> {
+        let target = self
+            .history()
+            .await?
+            .into_iter()
+            .find(|snapshot| snapshot.version == version)
+            .ok_or_else(|| anyhow!("There is no version {} of the settings", version))?;
+
+        let previous = self
+            .storage
+            .update(SETTINGS_KEY, |namespaces: &mut Namespaces| {
+                std::mem::replace(namespaces, target.settings.clone())
+            })
+            .await?;
+        self.notify_differences(&previous, &target.settings);
+        self.snapshot(format!("rolled back to version {}", version))
+            .await
+    }
+
+    /// Records the settings from before versioning started, so the first change can be
+    /// rolled back too.
+    async fn baseline(&self) -> anyhow::Result<()> {
+        if self.history().await?.is_empty() {
+            self.snapshot(String::from("settings before the first recorded change"))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn snapshot(&self, change: String) -> anyhow::Result<()> {
+        let settings = self.all().await?;
+        self.storage
+            .update(SNAPSHOTS_KEY, |snapshots: &mut Vec<Snapshot>| {
+                let version = snapshots.last().map_or(1, |last| last.version + 1);
+                snapshots.push(Snapshot {
+                    version,
+                    taken_at: Utc::now(),
+                    change,
+                    settings,
+                });
+                if snapshots.len() > MAX_SNAPSHOTS {
+                    snapshots.drain(..snapshots.len() - MAX_SNAPSHOTS);
+                }
+            })
+            .await
+    }
+
+    pub async fn all(&self) -> anyhow::Result<Namespaces> {
+        self.storage.get(SETTINGS_KEY).await
+    }
+
+    /// Receives every change made after subscribing, for modules that cache a setting.
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingChange> {
+        self.changes.subscribe()
+    }
+
+    fn notify_differences(&self, before: &Namespaces, after: &Namespaces) {
+        let value = |namespaces: &Namespaces, namespace: &str, key: &str| {
+            namespaces
+                .get(namespace)
+                .and_then(|settings| settings.get(key))
+                .cloned()
+        };
+        let keys: BTreeSet<(&String, &String)> = before
+            .iter()
+            .chain(after)
+            .flat_map(|(namespace, settings)| settings.keys().map(move |key| (namespace, key)))
+            .collect();
+        for (namespace, key) in keys {
+            let new = value(after, namespace, key);
+            if value(before, namespace, key) != new {
+                self.notify(namespace, key, new);
+            }
+        }
+    }
+
+    fn notify(&self, namespace: &str, key: &str, value: Option<Value>) {
+        // Nobody listening is fine, the new value is read on next use anyway.
+        let _ = self.changes.send(SettingChange {
+            namespace: namespace.to_string(),
+            key: key.to_string(),
+            value,
+        });
+    }
+}
@@ -0,0 +1,120 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serenity::{
+    all::{Context, ShardId},
+    gateway::{ConnectionStage, ShardStageUpdateEvent},
+};
+use tracing::{info, warn};
+
+use crate::{oncall::escalate, Data};
+
+/// Tracks when each shard lost its connection, so a shard that stays disconnected
+/// beyond the configured threshold can be reported to the ops channel.
+#[derive(Default)]
+pub struct ShardHealth {
+    disconnected_since: Mutex<HashMap<ShardId, Instant>>,
+}
+
+impl ShardHealth {
+    /// Seconds each currently disconnected shard has been down for.
+    pub fn disconnected_seconds(&self) -> HashMap<u32, u64> {
+        self.lock()
+            .iter()
+            .map(|(shard_id, since)| (shard_id.0, since.elapsed().as_secs()))
+            .collect()
+    }
+
+    fn disconnected_since(&self, shard_id: ShardId) -> Option<Instant> {
+        self.lock().get(&shard_id).copied()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<ShardId, Instant>> {
+        self.disconnected_since
+            .lock()
+            .expect("Shard health lock poisoned")
+    }
+}
+
+pub async fn handle_stage_update(ctx: &Context, data: &Data, event: &ShardStageUpdateEvent) {
+    info!(
+        "Shard {} moved from {:?} to {:?}",
+        event.shard_id, event.old, event.new
+    );
+
+    if event.new == ConnectionStage::Connected {
+        let since = data.shard_health.lock().remove(&event.shard_id);
+        let threshold = alert_threshold(data).await;
+        if let Some(since) = since.filter(|since| since.elapsed() >= threshold) {
+            let minutes = since.elapsed().as_secs() / 60;
+            alert(
+                ctx,
+                data,
+                format!(
+                    "Shard {} reconnected after {} minutes.",
+                    event.shard_id, minutes
+                ),
+            )
+            .await;
+        }
+        return;
+    }
+
+    let since = Instant::now();
+    {
+        let mut disconnected = data.shard_health.lock();
+        if disconnected.contains_key(&event.shard_id) {
+            return;
+        }
+        disconnected.insert(event.shard_id, since);
+    }
+
+    // Only the first stage change after losing the connection starts a timer.
+    let (ctx, data, shard_id) = (ctx.clone(), data.clone(), event.shard_id);
+    tokio::spawn(async move {
+        let threshold = alert_threshold(&data).await;
+        tokio::time::sleep(threshold).await;
+        if data.shard_health.disconnected_since(shard_id) == Some(since) {
+            alert(
+                &ctx,
+                &data,
+                format!(
+                    "Shard {} has been disconnected for over {} minutes.",
+                    shard_id,
+                    threshold.as_secs() / 60
+                ),
+            )
+            .await;
+        }
+    });
+}
+
+async fn alert_threshold(data: &Data) -> Duration {
+    let minutes = data.config.read().await.bot.shard_alert_minutes;
+    Duration::from_secs(minutes * 60)
+}
+
+async fn alert(ctx: &Context, data: &Data, content: String) {
+    warn!("{}", content);
+    escalate(ctx, data, &content).await;
+}
@@ -0,0 +1,259 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use poise::Modal;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ButtonStyle, ComponentInteraction, Context as SerenityContext, CreateActionRow, CreateButton,
+    CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+    ModalInteractionCollector,
+};
+use tokio::time::Duration;
+use tracing::{error, info};
+
+use crate::{
+    invites::joins_since,
+    preferences::{allows, Notification},
+    storage::Storage,
+    utils::{broadcast::broadcast, embed::EMBED_TOTAL_LIMIT},
+    Data,
+};
+
+/// Custom ID prefix of the "Introduce yourself" button, routed here by [`crate::interactions`].
+pub const SPOTLIGHT_COMPONENT: &str = "spotlight";
+const INTROS_KEY: &str = "spotlight.intros";
+/// Members who were already asked for an intro.
+const PROMPTED_KEY: &str = "spotlight.prompted";
+const SPOTLIGHT_TITLE: &str = "New Members Spotlight";
+/// Intros are shown as fields with a blank name.
+const INTRO_FIELD_NAME: &str = "\u{200b}";
+/// Discord rejects embeds with more fields than this.
+const MAX_FIELDS: usize = 25;
+/// How long a member has to fill in the intro form after clicking the button.
+const INTRO_FORM_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, poise::Modal)]
+#[name = "Introduce yourself"]
+struct IntroModal {
+    #[name = "Tell us a bit about yourself"]
+    #[paragraph]
+    #[max_length = 500]
+    about: String,
+    #[name = "What are you into?"]
+    #[placeholder = "Web dev, competitive programming, robotics..."]
+    #[max_length = 100]
+    interests: Option<String>,
+}
+
+/// A new member's answer to the intro prompt, waiting for the next spotlight.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Intro {
+    pub user_id: u64,
+    pub about: String,
+    pub interests: Option<String>,
+    pub written_at: DateTime<Utc>,
+    pub featured: bool,
+}
+
+/// DMs everyone who joined in the past week and wasn't asked yet a button to write an
+/// intro for the spotlight.
+pub async fn prompt_new_members(ctx: &SerenityContext, data: &Data) -> anyhow::Result<()> {
+    let since = Utc::now() - ChronoDuration::days(7);
+    let joined: HashSet<u64> = joins_since(&data.storage, since)
+        .await?
+        .into_iter()
+        .map(|join| join.user_id)
+        .collect();
+    let to_prompt = data
+        .storage
+        .update(PROMPTED_KEY, |prompted: &mut HashSet<u64>| {
+            let new: Vec<u64> = joined.difference(prompted).copied().collect();
+            prompted.extend(&new);
+            new
+        })
+        .await?;
+
+    let button = CreateButton::new(format!("{}:open:intro", SPOTLIGHT_COMPONENT))
+        .label("Introduce yourself")
+        .style(ButtonStyle::Primary);
+    let mut messages = Vec::new();
+    for user_id in to_prompt {
+        if !allows(&data.storage, user_id, Notification::Reminders).await {
+            continue;
+        }
+        let dm = CreateMessage::new()
+            .content(
+                "Welcome to amFOSS! Every week we introduce the newest members to the club. \
+                 Write a few lines about yourself to be featured.",
+            )
+            .components(vec![CreateActionRow::Buttons(vec![button.clone()])]);
+        messages.push((user_id, dm));
+    }
+    broadcast(ctx, data, "Intro prompts", messages).await;
+    Ok(())
+}
+
+/// The intros that weren't featured yet, marking them as featured.
+pub async fn take_intros(storage: &Storage) -> anyhow::Result<Vec<Intro>> {
+    storage
+        .update(INTROS_KEY, |intros: &mut Vec<Intro>| {
+            let fresh: Vec<Intro> = intros.iter().filter(|i| !i.featured).cloned().collect();
+            for intro in intros.iter_mut() {
+                intro.featured = true;
+            }
+            fresh
+        })
+        .await
+}
+
+/// Puts intros back in line for the next spotlight, when posting this one failed.
+pub async fn restore_intros(storage: &Storage, user_ids: &[u64]) -> anyhow::Result<()> {
+    storage
+        .update(INTROS_KEY, |intros: &mut Vec<Intro>| {
+            for intro in intros.iter_mut().filter(|i| user_ids.contains(&i.user_id)) {
+                intro.featured = false;
+            }
+        })
+        .await
+}
+
+/// Splits the spotlight of `intros` into embeds that each fit in a message of their own,
+/// along with the user IDs featured in each.
+pub fn spotlight_embeds(intros: &[Intro]) -> Vec<(Vec<u64>, CreateEmbed)> {
+    let description = format!("Say hi to the {} newest members of the club!", intros.len());
+    let header = SPOTLIGHT_TITLE.chars().count() + description.chars().count();
+
+    let mut batches: Vec<Vec<(u64, String)>> = vec![Vec::new()];
+    let mut used = header;
+    for intro in intros {
+        let mut value = format!("<@{}>\n{}", intro.user_id, intro.about);
+        if let Some(interests) = &intro.interests {
+            value.push_str(&format!("\n*Into: {}*", interests));
+        }
+        let size = INTRO_FIELD_NAME.chars().count() + value.chars().count();
+        let batch = batches.last_mut().expect("Started with a batch");
+        if !batch.is_empty() && (batch.len() == MAX_FIELDS || used + size > EMBED_TOTAL_LIMIT) {
+            batches.push(Vec::new());
+            used = SPOTLIGHT_TITLE.chars().count();
+        }
+        used += size;
+        batches
+            .last_mut()
+            .expect("Started with a batch")
+            .push((intro.user_id, value));
+    }
+
+    batches
+        .into_iter()
+        .enumerate()
+        .map(|(index, batch)| {
+            let mut embed = CreateEmbed::new().title(SPOTLIGHT_TITLE);
+            if index == 0 {
+                embed = embed.description(&description);
+            }
+            let mut user_ids = Vec::new();
+            for (user_id, value) in batch {
+                user_ids.push(user_id);
+                embed = embed.field(INTRO_FIELD_NAME, value, false);
+            }
+            (user_ids, embed)
+        })
+        .collect()
+}
+
+/// Drops the member's intro and the record of prompting them.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(INTROS_KEY, |intros: &mut Vec<Intro>| {
+            intros.retain(|i| i.user_id != user_id)
+        })
+        .await?;
+    storage
+        .update(PROMPTED_KEY, |prompted: &mut HashSet<u64>| {
+            prompted.remove(&user_id);
+        })
+        .await
+}
+
+pub async fn handle_component(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    action: &str,
+    _arg: &str,
+    data: &Data,
+) {
+    let result = match action {
+        "open" => write_intro(ctx, component, data).await,
+        _ => return,
+    };
+
+    if let Err(e) = result {
+        error!(
+            "Failed to handle spotlight interaction {}: {:?}",
+            component.data.custom_id, e
+        );
+    }
+}
+
+async fn write_intro(
+    ctx: &SerenityContext,
+    component: &ComponentInteraction,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let modal_id = component.id.to_string();
+    component
+        .create_response(&ctx.http, IntroModal::create(None, modal_id.clone()))
+        .await?;
+    let Some(submission) = ModalInteractionCollector::new(&ctx.shard)
+        .filter(move |m| m.data.custom_id == modal_id)
+        .timeout(INTRO_FORM_TIMEOUT)
+        .await
+    else {
+        return Ok(());
+    };
+    let form = IntroModal::parse(submission.data.clone()).map_err(anyhow::Error::msg)?;
+
+    let user_id = component.user.id.get();
+    let intro = Intro {
+        user_id,
+        about: form.about,
+        interests: form
+            .interests
+            .filter(|interests| !interests.trim().is_empty()),
+        written_at: Utc::now(),
+        featured: false,
+    };
+    data.storage
+        .update(INTROS_KEY, |intros: &mut Vec<Intro>| {
+            // Rewriting an intro before it's featured replaces it.
+            intros.retain(|i| i.user_id != user_id || i.featured);
+            intros.push(intro);
+        })
+        .await?;
+    info!("{} wrote an intro for the spotlight", component.user.name);
+
+    let response = CreateInteractionResponseMessage::new()
+        .content("Thanks! You'll be in this week's new members spotlight.")
+        .ephemeral(true);
+    submission
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,389 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::{hash_map::Entry, HashMap};
+
+use anyhow::anyhow;
+use reqwest::Url;
+use serenity::all::{
+    Channel, ChannelId, Context, GuildId, Member, PartialGuild, Permissions, RoleId,
+};
+use serenity::{http::StatusCode, Error as SerenityError};
+use tracing::{info, warn};
+
+use crate::{
+    config::Config,
+    deployment,
+    ids::{self, ChannelKind},
+    utils::long_message::send_long,
+    Data,
+};
+
+/// What the bot needs in every channel it posts to.
+const POSTING: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES)
+    .union(Permissions::EMBED_LINKS);
+
+/// Checks every environment variable the bot can't start without, reporting all of the
+/// problems at once instead of failing on the first.
+pub fn check_env() -> anyhow::Result<()> {
+    let mut problems = Vec::new();
+    for name in ["AMD_RUST_ENV", "DISCORD_TOKEN"] {
+        if std::env::var(name).is_ok_and(|value| !value.is_empty()) {
+            continue;
+        }
+        problems.push(format!("{} is not set", name));
+    }
+    check_parsed(&mut problems, "ENABLE_DEBUG_LIBRARIES", true, |value| {
+        value.parse::<bool>().is_ok()
+    });
+    check_parsed(&mut problems, "OWNER_ID", true, |value| {
+        value.parse::<u64>().is_ok()
+    });
+    check_parsed(&mut problems, "ROOT_URL", true, |value| {
+        Url::parse(value).is_ok()
+    });
+    check_parsed(&mut problems, "METRICS_PUSH_URL", false, |value| {
+        value.is_empty() || Url::parse(value).is_ok()
+    });
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "The environment is misconfigured:\n- {}",
+        problems.join("\n- ")
+    ))
+}
+
+fn check_parsed(
+    problems: &mut Vec<String>,
+    name: &str,
+    required: bool,
+    is_valid: impl Fn(&str) -> bool,
+) {
+    match std::env::var(name) {
+        Ok(value) if is_valid(&value) => {}
+        Ok(value) => problems.push(format!("{} has an invalid value: {}", name, value)),
+        Err(_) if required => problems.push(format!("{} is not set", name)),
+        Err(_) => {}
+    }
+}
+
+/// Checks the configured channels and roles against Discord and posts what's wrong with
+/// them to the ops channel, or logs it if there's no ops channel.
+pub async fn report_health(ctx: Context, data: Data) {
+    if !deployment::is_active(&data) {
+        return;
+    }
+    let config = data.config.read().await.clone();
+
+    let mut problems = optional_env_problems(&config);
+    let mut guilds = HashMap::new();
+    let channels = configured_channels(&config);
+    for (setting, channel_id, needed) in &channels {
+        if let Err(problem) = check_channel(&ctx, &mut guilds, *channel_id, *needed).await {
+            problems.push(format!("`{}`: {}", setting, problem));
+        }
+    }
+    let roles = configured_roles(&config);
+    for (setting, role_id) in &roles {
+        if let Err(problem) = check_role(&guilds, *role_id) {
+            problems.push(format!("`{}`: {}", setting, problem));
+        }
+    }
+
+    let summary = if problems.is_empty() {
+        format!(
+            "Startup check passed: {} channels and {} roles look fine.",
+            channels.len(),
+            roles.len()
+        )
+    } else {
+        format!(
+            "Startup check found {} problems:\n- {}",
+            problems.len(),
+            problems.join("\n- ")
+        )
+    };
+    info!("{}", summary);
+
+    let Some(ops_channel_id) = config.bot.ops_channel_id else {
+        return;
+    };
+    let channel_id = ChannelId::new(ops_channel_id);
+    if let Err(e) = send_long(&ctx.http, channel_id, &summary, "startup_check.md").await {
+        warn!("Failed to post the startup check: {}", e);
+    }
+}
+
+/// Variables only some features need, missing while those features are configured.
+fn optional_env_problems(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    if config.backup.channel_id.is_some() && std::env::var("BACKUP_KEY").is_err() {
+        problems.push(String::from(
+            "`backup.channel_id` is set but BACKUP_KEY is not, backups will fail",
+        ));
+    }
+    if config.llm.endpoint.is_some() && std::env::var("LLM_API_KEY").is_err() {
+        problems.push(String::from(
+            "`llm.endpoint` is set but LLM_API_KEY is not, requests may be rejected",
+        ));
+    }
+    problems
+}
+
+/// A guild and the bot's member in it, fetched once per guild.
+struct GuildInfo {
+    guild: PartialGuild,
+    bot: Member,
+}
+
+async fn check_channel(
+    ctx: &Context,
+    guilds: &mut HashMap<GuildId, GuildInfo>,
+    channel_id: u64,
+    needed: Permissions,
+) -> Result<(), String> {
+    let channel = match ChannelId::new(channel_id).to_channel(&ctx.http).await {
+        Ok(Channel::Guild(channel)) => channel,
+        Ok(_) => return Err(format!("channel {} is not a server channel", channel_id)),
+        Err(SerenityError::Http(e)) if e.status_code() == Some(StatusCode::NOT_FOUND) => {
+            return Err(format!("channel {} no longer exists", channel_id))
+        }
+        Err(e) => return Err(format!("channel {} can't be fetched: {}", channel_id, e)),
+    };
+
+    let info = match guilds.entry(channel.guild_id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let bot_id = ctx.cache.current_user().id;
+            let guild = channel
+                .guild_id
+                .to_partial_guild(&ctx.http)
+                .await
+                .map_err(|e| format!("server of channel {} can't be fetched: {}", channel_id, e))?;
+            let bot = channel
+                .guild_id
+                .member(&ctx.http, bot_id)
+                .await
+                .map_err(|e| format!("bot member can't be fetched: {}", e))?;
+            entry.insert(GuildInfo { guild, bot })
+        }
+    };
+
+    let missing = needed - info.guild.user_permissions_in(&channel, &info.bot);
+    if !missing.is_empty() {
+        return Err(format!("missing {} in <#{}>", missing, channel_id));
+    }
+    Ok(())
+}
+
+fn check_role(guilds: &HashMap<GuildId, GuildInfo>, role_id: u64) -> Result<(), String> {
+    let role_id = RoleId::new(role_id);
+    let Some(info) = guilds
+        .values()
+        .find(|info| info.guild.roles.contains_key(&role_id))
+    else {
+        return Err(format!("role {} no longer exists", role_id));
+    };
+
+    let bot_roles: Vec<_> = info
+        .bot
+        .roles
+        .iter()
+        .chain([&info.guild.id.everyone_role()])
+        .filter_map(|id| info.guild.roles.get(id))
+        .collect();
+    let permissions = bot_roles
+        .iter()
+        .fold(Permissions::empty(), |permissions, role| {
+            permissions | role.permissions
+        });
+    if !permissions.intersects(Permissions::MANAGE_ROLES | Permissions::ADMINISTRATOR) {
+        return Err(String::from("the bot can't manage roles"));
+    }
+    let bot_position = bot_roles
+        .iter()
+        .map(|role| role.position)
+        .max()
+        .unwrap_or(0);
+    let role = &info.guild.roles[&role_id];
+    if role.position >= bot_position {
+        return Err(format!(
+            "role {} is above the bot's highest role, it can't be assigned",
+            role.name
+        ));
+    }
+    Ok(())
+}
+
+/// Every channel the bot posts to or manages, with the setting it comes from and the
+/// permissions it needs there.
+fn configured_channels(config: &Config) -> Vec<(String, u64, Permissions)> {
+    let registry = ids::registry();
+    let mut channels: Vec<(String, u64, Permissions)> = registry
+        .channels()
+        .map(|(kind, channel_id)| {
+            let needed = match kind.group() {
+                Some(_) => Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY,
+                None => POSTING,
+            };
+            (
+                format!("ids.channels.{}", kind.key()),
+                channel_id.get(),
+                needed,
+            )
+        })
+        .collect();
+
+    let optional = [
+        ("bot.ops_channel_id", config.bot.ops_channel_id),
+        (
+            "status_update.reset_approval_channel_id",
+            config.status_update.reset_approval_channel_id,
+        ),
+        (
+            "status_update.appeal_channel_id",
+            config.status_update.appeal_channel_id,
+        ),
+        (
+            "status_update.private_defaulters_channel_id",
+            config.status_update.private_defaulters_channel_id,
+        ),
+        ("feeds.channel_id", config.feeds.channel_id),
+        ("resources.channel_id", config.resources.channel_id),
+        ("practice.channel_id", config.practice.channel_id),
+        ("groups.audit_channel_id", config.groups.audit_channel_id),
+        ("sessions.channel_id", config.sessions.channel_id),
+        (
+            "sessions.approval_channel_id",
+            config.sessions.approval_channel_id,
+        ),
+        (
+            "events.reminder_channel_id",
+            config.events.reminder_channel_id,
+        ),
+        ("backup.channel_id", config.backup.channel_id),
+        (
+            "invites.report_channel_id",
+            config.invites.report_channel_id,
+        ),
+        ("xp.announce_channel_id", config.xp.announce_channel_id),
+        ("kudos.channel_id", config.kudos.channel_id),
+        (
+            "inventory.approval_channel_id",
+            config.inventory.approval_channel_id,
+        ),
+        (
+            "semester.announce_channel_id",
+            config.semester.announce_channel_id,
+        ),
+        ("spotlight.channel_id", config.spotlight.channel_id),
+        (
+            "holidays.review_channel_id",
+            config.holidays.review_channel_id,
+        ),
+    ];
+    for (setting, channel_id) in optional {
+        if let Some(channel_id) = channel_id {
+            channels.push((setting.into(), channel_id, POSTING));
+        }
+    }
+
+    if config.channel_topics.enabled {
+        for kind in [ChannelKind::StatusUpdate, ChannelKind::Lab] {
+            let channel_id = registry.channel(kind).get();
+            channels.push((
+                "channel_topics.enabled".into(),
+                channel_id,
+                Permissions::VIEW_CHANNEL | Permissions::MANAGE_CHANNELS,
+            ));
+        }
+    }
+    if config.reports.discussion_threads {
+        for kind in [ChannelKind::StatusUpdate, ChannelKind::Lab] {
+            channels.push((
+                "reports.discussion_threads".into(),
+                registry.channel(kind).get(),
+                Permissions::VIEW_CHANNEL | Permissions::CREATE_PUBLIC_THREADS,
+            ));
+        }
+    }
+    if let Some(channel_id) = config.attendance.occupancy_channel_id {
+        channels.push((
+            "attendance.occupancy_channel_id".into(),
+            channel_id,
+            Permissions::VIEW_CHANNEL | Permissions::MANAGE_CHANNELS,
+        ));
+    }
+    for delivery in &config.reports.deliveries {
+        channels.push(("reports.deliveries".into(), delivery.channel_id, POSTING));
+    }
+    for watcher in &config.watchers {
+        if let Some(channel_id) = watcher.alert_channel_id {
+            channels.push((format!("watchers.{}", watcher.name), channel_id, POSTING));
+        }
+    }
+    for policy in &config.retention {
+        channels.push((
+            "retention".into(),
+            policy.channel_id,
+            Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY,
+        ));
+    }
+    for channel_id in &config.llm.summarize_channel_ids {
+        channels.push((
+            "llm.summarize_channel_ids".into(),
+            *channel_id,
+            Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY,
+        ));
+    }
+    for lock in &config.channel_locks {
+        channels.push((
+            "channel_locks".into(),
+            lock.channel_id,
+            Permissions::VIEW_CHANNEL | Permissions::MANAGE_ROLES,
+        ));
+    }
+    channels
+}
+
+/// Every role the bot assigns, with the setting it comes from.
+fn configured_roles(config: &Config) -> Vec<(String, u64)> {
+    let mut roles: Vec<(String, u64)> = ids::registry()
+        .roles()
+        .map(|(kind, role_id)| (format!("ids.roles.{}", kind.key()), role_id.get()))
+        .collect();
+
+    if let Some(role_id) = config.attendance.regular_role_id {
+        roles.push(("attendance.regular_role_id".into(), role_id));
+    }
+    if let Some(role_id) = config.onboarding.member_role_id {
+        roles.push(("onboarding.member_role_id".into(), role_id));
+    }
+    for role in &config.groups.roles {
+        roles.push(("groups.roles".into(), role.role_id));
+    }
+    for role in &config.xp.level_roles {
+        roles.push(("xp.level_roles".into(), role.role_id));
+    }
+    for role_id in &config.roles.restore_on_rejoin {
+        roles.push(("roles.restore_on_rejoin".into(), *role_id));
+    }
+    roles
+}
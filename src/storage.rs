@@ -0,0 +1,199 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::Context as _;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::RwLock;
+use tracing::debug;
+
+use crate::error::StorageError;
+
+/// A small JSON file backed key-value store for state that must survive restarts.
+///
+/// Every key holds an arbitrary serializable value. Modules should namespace their
+/// keys (`status_update.history`, `attendance.history`, ...) to avoid collisions.
+/// The whole file is rewritten on every write which is fine for the amount of data
+/// the bot keeps around.
+pub struct Storage {
+    path: PathBuf,
+    values: RwLock<Map<String, Value>>,
+    /// While set, writes only change the values in memory. A canary standing by shares
+    /// the primary's file and must not write to it.
+    read_only: AtomicBool,
+}
+
+impl Storage {
+    /// Opens the store at `path`, creating an empty one if the file does not exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let values = if path.exists() {
+            let contents = std::fs::read_to_string(&path).with_context(|| {
+                StorageError(format!("Failed to read storage file {}", path.display()))
+            })?;
+            serde_json::from_str(&contents).with_context(|| {
+                StorageError(format!("Failed to parse storage file {}", path.display()))
+            })?
+        } else {
+            Map::new()
+        };
+
+        debug!(
+            "Opened storage at {} with {} keys",
+            path.display(),
+            values.len()
+        );
+        Ok(Self {
+            path,
+            values: RwLock::new(values),
+            read_only: AtomicBool::new(false),
+        })
+    }
+
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::Relaxed);
+    }
+
+    /// Replaces the values in memory with the file's, dropping writes that were never
+    /// persisted. Used by a canary taking over the primary's file.
+    pub async fn reload(&self) -> anyhow::Result<()> {
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| {
+                StorageError(format!(
+                    "Failed to read storage file {}",
+                    self.path.display()
+                ))
+            })?;
+        let reloaded = serde_json::from_str(&contents).with_context(|| {
+            StorageError(format!(
+                "Failed to parse storage file {}",
+                self.path.display()
+            ))
+        })?;
+        *self.values.write().await = reloaded;
+        Ok(())
+    }
+
+    /// Returns the value stored under `key`, or `T::default()` if nothing is stored yet.
+    pub async fn get<T: DeserializeOwned + Default>(&self, key: &str) -> anyhow::Result<T> {
+        let values = self.values.read().await;
+        match values.get(key) {
+            Some(value) => serde_json::from_value(value.clone()).with_context(|| {
+                StorageError(format!("Failed to deserialize storage key {}", key))
+            }),
+            None => Ok(T::default()),
+        }
+    }
+
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T) -> anyhow::Result<()> {
+        let mut values = self.values.write().await;
+        let value = serde_json::to_value(value)
+            .with_context(|| StorageError(format!("Failed to serialize storage key {}", key)))?;
+        values.insert(key.to_string(), value);
+        self.persist(&values)
+    }
+
+    /// Atomically reads, modifies and writes back the value under `key`.
+    pub async fn update<T, R, F>(&self, key: &str, f: F) -> anyhow::Result<R>
+    where
+        T: Serialize + DeserializeOwned + Default,
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut values = self.values.write().await;
+        let mut current: T = match values.get(key) {
+            Some(value) => serde_json::from_value(value.clone()).with_context(|| {
+                StorageError(format!("Failed to deserialize storage key {}", key))
+            })?,
+            None => T::default(),
+        };
+
+        let result = f(&mut current);
+        let value = serde_json::to_value(&current)
+            .with_context(|| StorageError(format!("Failed to serialize storage key {}", key)))?;
+        values.insert(key.to_string(), value);
+        self.persist(&values)?;
+
+        Ok(result)
+    }
+
+    /// Applies `f` to the raw values and persists the result. Nothing is changed if `f`
+    /// fails, used by [`crate::migrations`] to rewrite stored data.
+    pub async fn transform<R>(
+        &self,
+        f: impl FnOnce(&mut Map<String, Value>) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        let mut values = self.values.write().await;
+        let mut updated = values.clone();
+        let result = f(&mut updated)?;
+        self.persist(&updated)?;
+        *values = updated;
+
+        Ok(result)
+    }
+
+    /// Serializes the whole store, used for backups.
+    pub async fn export(&self) -> anyhow::Result<Vec<u8>> {
+        let values = self.values.read().await;
+        serde_json::to_vec(&*values).context(StorageError("Failed to serialize storage".into()))
+    }
+
+    /// Replaces the whole store with `imported`, parsed from what [`Storage::export`]
+    /// produced.
+    pub async fn import(&self, imported: Map<String, Value>) -> anyhow::Result<()> {
+        let mut values = self.values.write().await;
+        *values = imported;
+        self.persist(&values)
+    }
+
+    /// Number of entries held under every key, or 1 for plain values.
+    pub async fn key_sizes(&self) -> Map<String, Value> {
+        let values = self.values.read().await;
+        values
+            .iter()
+            .map(|(key, value)| {
+                let size = match value {
+                    Value::Array(array) => array.len(),
+                    Value::Object(object) => object.len(),
+                    _ => 1,
+                };
+                (key.clone(), Value::from(size))
+            })
+            .collect()
+    }
+
+    fn persist(&self, values: &Map<String, Value>) -> anyhow::Result<()> {
+        if self.read_only.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let contents = serde_json::to_string_pretty(values)
+            .context(StorageError("Failed to serialize storage".into()))?;
+        // Write to a temporary file first so a crash mid-write never corrupts the store.
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, contents)
+            .with_context(|| StorageError(format!("Failed to write {}", tmp_path.display())))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| StorageError(format!("Failed to replace {}", self.path.display())))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::async_trait;
+
+use crate::graphql::models::Member;
+use crate::graphql::queries::{increment_streak, reset_streak, set_streak};
+use crate::points;
+use crate::storage::Storage;
+
+/// How a kind of streak is kept, and whether a missed day breaks it. The engine below
+/// decides what a day does to a streak, the policy stores the result.
+#[async_trait]
+pub trait StreakPolicy: Sync {
+    /// Whether missing a day breaks the streak, false e.g. when a mentor held resets back.
+    fn resets_on_miss(&self) -> bool;
+
+    /// Streak lengths worth celebrating when a member reaches them.
+    fn milestones(&self) -> &[i32] {
+        &[]
+    }
+
+    /// Spends a freeze that keeps `member`'s streak through a miss, `false` if they have
+    /// none left.
+    async fn freeze(&self, member: &Member) -> anyhow::Result<bool>;
+
+    /// Hands back a freeze spent on a miss that was overturned.
+    async fn unfreeze(&self, member: &Member) -> anyhow::Result<()>;
+
+    async fn increment(&self, member: &mut Member) -> anyhow::Result<()>;
+
+    async fn reset(&self, member: &mut Member) -> anyhow::Result<()>;
+
+    async fn set(&self, member: &mut Member, current: i32, max: i32) -> anyhow::Result<()>;
+}
+
+/// What a day did to a member's streak.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DayOutcome {
+    /// Kept up, with the milestone the new streak reached, if any.
+    Extended {
+        milestone: Option<i32>,
+    },
+    /// Missed, but the streak was kept because misses don't count that day.
+    Held,
+    /// Missed, and a freeze was spent instead of resetting the streak.
+    Frozen,
+    Reset,
+}
+
+/// Applies a day to `member`'s streak, `kept_up` being whether they did what the streak
+/// asks of them.
+pub async fn record_day<P: StreakPolicy>(
+    policy: &P,
+    member: &mut Member,
+    kept_up: bool,
+) -> anyhow::Result<DayOutcome> {
+    if kept_up {
+        policy.increment(member).await?;
+        let current = member.streak.first().map(|s| s.current_streak);
+        return Ok(DayOutcome::Extended {
+            milestone: current.and_then(|current| milestone(policy.milestones(), current)),
+        });
+    }
+    if !policy.resets_on_miss() {
+        return Ok(DayOutcome::Held);
+    }
+    if policy.freeze(member).await? {
+        return Ok(DayOutcome::Frozen);
+    }
+    policy.reset(member).await?;
+    Ok(DayOutcome::Reset)
+}
+
+/// `streak` if it is one of `milestones`.
+pub fn milestone(milestones: &[i32], streak: i32) -> Option<i32> {
+    milestones.contains(&streak).then_some(streak)
+}
+
+/// The streak a member has after keeping up on a day that follows a streak of `before`.
+pub fn extended(before: i32) -> i32 {
+    before.max(0) + 1
+}
+
+/// Turns a missed day back into a kept one, setting the streak to `current` and handing
+/// back the freeze the miss cost, if any. Returns the new max streak.
+pub async fn overturn_miss<P: StreakPolicy>(
+    policy: &P,
+    member: &mut Member,
+    current: i32,
+    max: i32,
+    frozen: bool,
+) -> anyhow::Result<i32> {
+    let max = max.max(current);
+    policy.set(member, current, max).await?;
+    if frozen {
+        policy.unfreeze(member).await?;
+    }
+    Ok(max)
+}
+
+/// Status update streaks, kept in Root, with streak shields as freezes.
+pub struct UpdateStreaks<'a> {
+    pub storage: &'a Storage,
+    pub resets: bool,
+    pub milestones: &'a [i32],
+}
+
+#[async_trait]
+impl StreakPolicy for UpdateStreaks<'_> {
+    fn resets_on_miss(&self) -> bool {
+        self.resets
+    }
+
+    fn milestones(&self) -> &[i32] {
+        self.milestones
+    }
+
+    async fn freeze(&self, member: &Member) -> anyhow::Result<bool> {
+        match member.discord_id.parse() {
+            Ok(user_id) => points::use_shield(self.storage, user_id).await,
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn unfreeze(&self, member: &Member) -> anyhow::Result<()> {
+        match member.discord_id.parse() {
+            Ok(user_id) => points::return_shield(self.storage, user_id).await,
+            Err(_) => Ok(()),
+        }
+    }
+
+    async fn increment(&self, member: &mut Member) -> anyhow::Result<()> {
+        increment_streak(member).await
+    }
+
+    async fn reset(&self, member: &mut Member) -> anyhow::Result<()> {
+        reset_streak(member).await
+    }
+
+    async fn set(&self, member: &mut Member, current: i32, max: i32) -> anyhow::Result<()> {
+        set_streak(member, current, max).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::graphql::models::Streak;
+
+    /// Keeps streaks on the member itself, with a fixed number of freezes.
+    struct MockPolicy {
+        resets: bool,
+        freezes: AtomicU32,
+        milestones: Vec<i32>,
+    }
+
+    impl MockPolicy {
+        fn new(resets: bool, freezes: u32) -> Self {
+            Self {
+                resets,
+                freezes: AtomicU32::new(freezes),
+                milestones: vec![7],
+            }
+        }
+    }
+
+    #[async_trait]
+    impl StreakPolicy for MockPolicy {
+        fn resets_on_miss(&self) -> bool {
+            self.resets
+        }
+
+        fn milestones(&self) -> &[i32] {
+            &self.milestones
+        }
+
+        async fn freeze(&self, _member: &Member) -> anyhow::Result<bool> {
+            let spent = self
+                .freezes
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+            Ok(spent.is_ok())
+        }
+
+        async fn unfreeze(&self, _member: &Member) -> anyhow::Result<()> {
+            self.freezes.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn increment(&self, member: &mut Member) -> anyhow::Result<()> {
+            let streak = &mut member.streak[0];
+            streak.current_streak = extended(streak.current_streak);
+            streak.max_streak = streak.max_streak.max(streak.current_streak);
+            Ok(())
+        }
+
+        async fn reset(&self, member: &mut Member) -> anyhow::Result<()> {
+            member.streak[0].current_streak = 0;
+            Ok(())
+        }
+
+        async fn set(&self, member: &mut Member, current: i32, max: i32) -> anyhow::Result<()> {
+            member.streak[0] = Streak {
+                current_streak: current,
+                max_streak: max,
+            };
+            Ok(())
+        }
+    }
+
+    fn member(current_streak: i32, max_streak: i32) -> Member {
+        Member {
+            member_id: 1,
+            name: String::from("Test"),
+            discord_id: String::from("1"),
+            group_id: 1,
+            streak: vec![Streak {
+                current_streak,
+                max_streak,
+            }],
+        }
+    }
+
+    fn current(member: &Member) -> i32 {
+        member.streak[0].current_streak
+    }
+
+    #[tokio::test]
+    async fn keeping_up_extends_the_streak() {
+        let policy = MockPolicy::new(true, 0);
+        let mut member = member(3, 5);
+        let outcome = record_day(&policy, &mut member, true).await.unwrap();
+        assert_eq!(outcome, DayOutcome::Extended { milestone: None });
+        assert_eq!(current(&member), 4);
+    }
+
+    #[tokio::test]
+    async fn reaching_a_milestone_is_reported() {
+        let policy = MockPolicy::new(true, 0);
+        let mut member = member(6, 6);
+        let outcome = record_day(&policy, &mut member, true).await.unwrap();
+        assert_eq!(outcome, DayOutcome::Extended { milestone: Some(7) });
+        assert_eq!(member.streak[0].max_streak, 7);
+    }
+
+    #[tokio::test]
+    async fn missing_without_resets_holds_the_streak() {
+        let policy = MockPolicy::new(false, 1);
+        let mut member = member(3, 5);
+        let outcome = record_day(&policy, &mut member, false).await.unwrap();
+        assert_eq!(outcome, DayOutcome::Held);
+        assert_eq!(current(&member), 3);
+        assert_eq!(policy.freezes.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn missing_spends_a_freeze_first() {
+        let policy = MockPolicy::new(true, 1);
+        let mut member = member(3, 5);
+        let outcome = record_day(&policy, &mut member, false).await.unwrap();
+        assert_eq!(outcome, DayOutcome::Frozen);
+        assert_eq!(current(&member), 3);
+        assert_eq!(policy.freezes.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn missing_without_a_freeze_resets_the_streak() {
+        let policy = MockPolicy::new(true, 0);
+        let mut member = member(3, 5);
+        let outcome = record_day(&policy, &mut member, false).await.unwrap();
+        assert_eq!(outcome, DayOutcome::Reset);
+        assert_eq!(current(&member), 0);
+    }
+
+    #[tokio::test]
+    async fn overturning_a_reset_restores_the_streak() {
+        let policy = MockPolicy::new(true, 0);
+        let mut member = member(0, 5);
+        let max = overturn_miss(&policy, &mut member, extended(3), 5, false)
+            .await
+            .unwrap();
+        assert_eq!(max, 5);
+        assert_eq!(current(&member), 4);
+        assert_eq!(policy.freezes.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn overturning_a_frozen_miss_hands_the_freeze_back() {
+        let policy = MockPolicy::new(true, 0);
+        let mut member = member(5, 5);
+        let max = overturn_miss(&policy, &mut member, extended(5), 5, true)
+            .await
+            .unwrap();
+        assert_eq!(max, 6);
+        assert_eq!(member.streak[0].max_streak, 6);
+        assert_eq!(policy.freezes.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn extending_a_missed_streak_starts_over() {
+        assert_eq!(extended(-1), 1);
+        assert_eq!(extended(0), 1);
+        assert_eq!(extended(4), 5);
+    }
+}
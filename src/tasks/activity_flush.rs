@@ -0,0 +1,46 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::Context;
+use serenity::async_trait;
+use tokio::time::Duration;
+
+use super::Task;
+use crate::{activity::flush_activity, Data};
+
+/// Stores the group channel activity counted since the last flush.
+pub struct GroupActivityFlush;
+
+#[async_trait]
+impl Task for GroupActivityFlush {
+    fn name(&self) -> &str {
+        "Group Activity Flush"
+    }
+
+    fn run_in(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    async fn run(&self, _ctx: Context, data: &Data) -> anyhow::Result<()> {
+        flush_activity(&data.storage).await
+    }
+
+    // A canary counts activity too, so it's there when it takes over.
+    async fn dry_run(&self, _ctx: Context, data: &Data) -> anyhow::Result<()> {
+        flush_activity(&data.storage).await
+    }
+}
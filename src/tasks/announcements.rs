@@ -0,0 +1,59 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::Utc;
+use serenity::all::Context;
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::warn;
+
+use super::Task;
+use crate::{
+    announcements::{announcements, close_rsvps},
+    Data,
+};
+
+/// Closes RSVPs on announcements whose event started and sends organizers the attendees.
+pub struct AnnouncementRsvps;
+
+#[async_trait]
+impl Task for AnnouncementRsvps {
+    fn name(&self) -> &str {
+        "Announcement RSVPs"
+    }
+
+    fn run_in(&self) -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let now = Utc::now();
+        for announcement in announcements(&data.storage).await? {
+            if announcement.closed || announcement.starts_at > now {
+                continue;
+            }
+            // One organizer with closed DMs shouldn't hold up the others.
+            if let Err(e) = close_rsvps(&ctx, data, announcement.id).await {
+                warn!(
+                    "Failed to send the attendees of announcement #{}: {:?}",
+                    announcement.id, e
+                );
+            }
+        }
+        Ok(())
+    }
+}
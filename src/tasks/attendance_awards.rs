@@ -0,0 +1,234 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use chrono::{Datelike, NaiveDate};
+use serenity::all::{Context, CreateMessage, GuildId, RoleId, UserId};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+use super::{status_update::tracked_members, Task};
+use crate::{
+    history::{recent_attendance_days, AttendanceDay},
+    ids::{self, ChannelKind},
+    privacy::erased_names,
+    storage::Storage,
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::{format_month, local_today, time_until},
+    },
+    Data,
+};
+
+/// Members currently holding the "Lab Regular" role, so it can be taken back next month.
+const REGULARS_KEY: &str = "attendance_awards.regulars";
+const LEADERBOARD_SIZE: usize = 10;
+const MOST_IMPROVED_SIZE: usize = 5;
+
+/// On the 1st, posts last month's attendance leaderboard and most improved members, and
+/// hands the "Lab Regular" role to the top members.
+pub struct AttendanceAwards;
+
+#[async_trait]
+impl Task for AttendanceAwards {
+    fn name(&self) -> &str {
+        "Attendance Awards"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(10, 15)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let today = local_today();
+        if today.day() != 1 {
+            return Ok(());
+        }
+        post_attendance_awards(ctx, data, today).await
+    }
+}
+
+async fn post_attendance_awards(ctx: Context, data: &Data, today: NaiveDate) -> anyhow::Result<()> {
+    let last_day = today - chrono::Duration::days(1);
+    let first_day = last_day.with_day(1).expect("Valid date");
+    let previous_first_day = (first_day - chrono::Duration::days(1))
+        .with_day(1)
+        .expect("Valid date");
+
+    let days = recent_attendance_days(&data.storage, 62).await?;
+    let in_range = |from: NaiveDate, to: NaiveDate| {
+        days.iter()
+            .filter(move |day| day.date >= from && day.date <= to)
+    };
+    let mut this_month = attendance_percentages(in_range(first_day, last_day));
+    let erased = erased_names(&data.storage).await?;
+    this_month.retain(|name, _| !erased.contains(name));
+    let previous_month = attendance_percentages(in_range(
+        previous_first_day,
+        first_day - chrono::Duration::days(1),
+    ));
+
+    let mut leaderboard: Vec<(&str, f64)> = this_month
+        .iter()
+        .map(|(name, percentage)| (name.as_str(), *percentage))
+        .collect();
+    leaderboard.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+    leaderboard.truncate(LEADERBOARD_SIZE);
+
+    let mut improved: Vec<(&str, f64)> = this_month
+        .iter()
+        .filter_map(|(name, percentage)| {
+            let before = previous_month.get(name)?;
+            Some((name.as_str(), percentage - before))
+        })
+        .filter(|(_, change)| *change > 0.0)
+        .collect();
+    improved.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+    improved.truncate(MOST_IMPROVED_SIZE);
+
+    let mut description = String::new();
+    if leaderboard.is_empty() {
+        description.push_str("No attendance was recorded last month.");
+    } else {
+        description.push_str("# Top Attendance\n");
+        for (rank, (name, percentage)) in leaderboard.iter().enumerate() {
+            description.push_str(&format!("{}. {} - {:.0}%\n", rank + 1, name, percentage));
+        }
+    }
+    if !improved.is_empty() {
+        description.push_str("# Most Improved\n");
+        for (name, change) in &improved {
+            description.push_str(&format!("- {} (+{:.0} points)\n", name, change));
+        }
+    }
+
+    let config = data.config.read().await.clone();
+    let embed = report_embed(
+        &ctx,
+        &config.theme.embed,
+        format!("Attendance Awards - {}", format_month(first_day)),
+        config.theme.attendance.high_attendance_color,
+    )
+    .description(description);
+    check_permissions(&ctx, ids::channel(ChannelKind::Lab).get(), POST_EMBEDS)?;
+    ids::channel(ChannelKind::Lab)
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await
+        .context("Failed to send attendance awards")?;
+
+    if let Some(role_id) = config.attendance.regular_role_id {
+        let top: Vec<&str> = leaderboard
+            .iter()
+            .take(config.attendance.regular_count)
+            .map(|(name, _)| *name)
+            .collect();
+        rotate_regular_role(&ctx, data, RoleId::new(role_id), &top).await?;
+    }
+
+    Ok(())
+}
+
+/// Attendance percentage of each member over `days`, leaving out days the lab was closed.
+fn attendance_percentages<'a>(
+    days: impl Iterator<Item = &'a AttendanceDay>,
+) -> HashMap<String, f64> {
+    // name -> (days present, days recorded)
+    let mut tally: HashMap<&str, (usize, usize)> = HashMap::new();
+    for day in days.filter(|day| day.records.iter().any(|r| r.is_present)) {
+        for record in &day.records {
+            let entry = tally.entry(&record.name).or_default();
+            entry.0 += usize::from(record.is_present);
+            entry.1 += 1;
+        }
+    }
+    tally
+        .into_iter()
+        .map(|(name, (present, total))| (name.to_string(), present as f64 / total as f64 * 100.0))
+        .collect()
+}
+
+/// Gives `role_id` to the members named in `top` and takes it from last month's holders.
+/// Attendance records only carry names, so they are matched to Discord IDs through Root.
+async fn rotate_regular_role(
+    ctx: &Context,
+    data: &Data,
+    role_id: RoleId,
+    top: &[&str],
+) -> anyhow::Result<()> {
+    let guild_id: GuildId = ids::channel(ChannelKind::Lab)
+        .to_channel(&ctx.http)
+        .await?
+        .guild()
+        .context("The lab channel isn't in a server")?
+        .guild_id;
+    let members = tracked_members(data).await?;
+    let regulars: Vec<u64> = members
+        .iter()
+        .filter(|m| top.contains(&m.name.as_str()))
+        .filter_map(|m| m.discord_id.parse().ok())
+        .collect();
+    let previous: Vec<u64> = data.storage.get(REGULARS_KEY).await?;
+
+    for user_id in previous.iter().filter(|id| !regulars.contains(id)) {
+        if let Err(e) = ctx
+            .http
+            .remove_member_role(
+                guild_id,
+                UserId::new(*user_id),
+                role_id,
+                Some("No longer a top attendee"),
+            )
+            .await
+        {
+            warn!("Failed to take the regular role from {}: {}", user_id, e);
+        }
+    }
+    for user_id in regulars.iter().filter(|id| !previous.contains(id)) {
+        if let Err(e) = ctx
+            .http
+            .add_member_role(
+                guild_id,
+                UserId::new(*user_id),
+                role_id,
+                Some("Top attendee of the month"),
+            )
+            .await
+        {
+            warn!("Failed to give the regular role to {}: {}", user_id, e);
+        }
+    }
+
+    info!("{} members are Lab Regulars this month", regulars.len());
+    data.storage.set(REGULARS_KEY, &regulars).await
+}
+
+/// Forgets that the member holds the "Lab Regular" role, it's no longer taken back.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(REGULARS_KEY, |regulars: &mut Vec<u64>| {
+            regulars.retain(|id| *id != user_id)
+        })
+        .await
+}
@@ -0,0 +1,160 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveTime;
+use serenity::all::{Context, CreateMessage};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::debug;
+
+use super::{lab_attendance::fetch_and_remember_attendance, status_update::tracked_members, Task};
+use crate::{
+    checkins::manual_checkins,
+    history::recent_attendance_days,
+    preferences::{allows, Notification},
+    quiet_hours::{is_quiet_now, send_or_queue, Destination, QueuedMessage},
+    utils::{
+        broadcast::broadcast,
+        time::{local_today, time_until},
+    },
+    Data,
+};
+
+/// How many past days of attendance are used to learn a member's habits.
+const LOOKBACK_DAYS: usize = 14;
+/// Members need at least this many days of history before they are nudged.
+const MIN_HISTORY_DAYS: usize = 5;
+/// Share of the past days a member must have arrived by [`TYPICAL_ARRIVAL`].
+const ON_TIME_RATIO: f64 = 0.7;
+const TYPICAL_ARRIVAL: (u32, u32) = (17, 0);
+const NUDGE: &str = "Hey! You're usually in the lab by 5 PM but haven't checked in yet \
+     today. If you're on your way, the attendance report goes out at 6 PM.";
+
+/// At 5:15 PM, DMs members who usually arrive by 5 PM but haven't checked in yet,
+/// so they have a chance to make it before the 6 PM report.
+pub struct AttendanceNudge;
+
+#[async_trait]
+impl Task for AttendanceNudge {
+    fn name(&self) -> &str {
+        "Attendance Nudge"
+    }
+
+    fn follows_semester(&self) -> bool {
+        true
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(17, 15)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let regulars = usual_early_arrivals(data).await?;
+        if regulars.is_empty() {
+            return Ok(());
+        }
+
+        let today = local_today();
+        let checked_in: Vec<String> = manual_checkins(&data.storage, today)
+            .await?
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        let attendance = fetch_and_remember_attendance(&data.storage).await?;
+        let members = tracked_members(data).await?;
+
+        let mut nudged = Vec::new();
+        for record in attendance {
+            if record.is_present || !regulars.contains(&record.name) {
+                continue;
+            }
+            if checked_in.contains(&record.name) {
+                continue;
+            }
+            let Some(member) = members.iter().find(|m| m.name == record.name) else {
+                continue;
+            };
+            let Ok(user_id) = member.discord_id.parse::<u64>() else {
+                continue;
+            };
+            if !allows(&data.storage, user_id, Notification::Reminders).await {
+                continue;
+            }
+
+            debug!("Nudging {} about attendance", member.name);
+            nudged.push(user_id);
+        }
+
+        if is_quiet_now(data).await {
+            for user_id in nudged {
+                let dm = QueuedMessage::text(NUDGE).notification(Notification::Reminders);
+                send_or_queue(&ctx, data, Destination::User(user_id), dm).await?;
+            }
+        } else {
+            let messages = nudged
+                .into_iter()
+                .map(|user_id| (user_id, CreateMessage::new().content(NUDGE)))
+                .collect();
+            broadcast(&ctx, data, "Attendance nudges", messages).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Members who arrived by [`TYPICAL_ARRIVAL`] on most of the recent days they have
+/// history for.
+async fn usual_early_arrivals(data: &Data) -> anyhow::Result<HashSet<String>> {
+    let (hour, minute) = TYPICAL_ARRIVAL;
+    let cutoff = NaiveTime::from_hms_opt(hour, minute, 0).expect("Valid time");
+    let days = recent_attendance_days(&data.storage, LOOKBACK_DAYS).await?;
+
+    let mut tallies: HashMap<String, (usize, usize)> = HashMap::new();
+    for day in &days {
+        for record in &day.records {
+            let on_time = record.is_present
+                && record
+                    .time_in
+                    .as_deref()
+                    .and_then(parse_time_in)
+                    .is_some_and(|time| time <= cutoff);
+            let tally = tallies.entry(record.name.clone()).or_default();
+            tally.0 += usize::from(on_time);
+            tally.1 += 1;
+        }
+    }
+
+    Ok(tallies
+        .into_iter()
+        .filter(|(_, (on_time, total))| {
+            *total >= MIN_HISTORY_DAYS && *on_time as f64 / *total as f64 >= ON_TIME_RATIO
+        })
+        .map(|(name, _)| name)
+        .collect())
+}
+
+/// Root reports arrival times as `HH:MM:SS` with optional fractional seconds.
+fn parse_time_in(time: &str) -> Option<NaiveTime> {
+    let time = time.split('.').next()?;
+    NaiveTime::parse_from_str(time, "%H:%M:%S").ok()
+}
@@ -0,0 +1,48 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use serenity::all::Context;
+use serenity::async_trait;
+use tokio::time::Duration;
+
+use super::Task;
+use crate::{backup::upload_backup, utils::time::time_until, Data};
+
+/// Uploads an encrypted backup of the bot's storage every night at 3 AM.
+pub struct NightlyBackup;
+
+#[async_trait]
+impl Task for NightlyBackup {
+    fn name(&self) -> &str {
+        "Nightly Backup"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(3, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        if data.config.read().await.backup.channel_id.is_none() {
+            return Ok(());
+        }
+        upload_backup(&ctx, data).await
+    }
+}
@@ -0,0 +1,117 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{Duration as ChronoDuration, NaiveDate};
+use serenity::all::{ChannelId, Context, EditChannel, Permissions};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::debug;
+
+use super::Task;
+use crate::{
+    history::{recent_attendance_days, recent_status_update_days},
+    ids::{self, ChannelKind},
+    utils::{
+        permissions::check_permissions,
+        time::{local_today, time_until},
+    },
+    Data,
+};
+
+/// Keeps the latest figures in the topics of the status update and lab channels each
+/// morning, so they're visible without scrolling back to the reports.
+pub struct ChannelTopics;
+
+#[async_trait]
+impl Task for ChannelTopics {
+    fn name(&self) -> &str {
+        "Channel Topics"
+    }
+
+    fn follows_semester(&self) -> bool {
+        true
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(7, 00)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    fn depends_on(&self) -> &[&'static str] {
+        &["Status Update Check"]
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        if !data.config.read().await.channel_topics.enabled {
+            return Ok(());
+        }
+        let today = local_today();
+
+        // A check covers the updates sent the day before it runs.
+        if let Some(day) = recent_status_update_days(&data.storage, 1).await?.pop() {
+            let top_streak = day
+                .members
+                .iter()
+                .map(|m| m.current_streak)
+                .max()
+                .unwrap_or(0)
+                .max(0);
+            let topic = format!(
+                "{}: {}/{} updates | Current top streak: {} days",
+                day_label(day.date - ChronoDuration::days(1), today),
+                day.senders().count(),
+                day.members.len(),
+                top_streak
+            );
+            set_topic(&ctx, ids::channel(ChannelKind::StatusUpdate).get(), &topic).await?;
+        }
+
+        if let Some(day) = recent_attendance_days(&data.storage, 1).await?.pop() {
+            let present = day.records.iter().filter(|r| r.is_present).count();
+            let topic = format!(
+                "{}: {}/{} in the lab ({:.0}%)",
+                day_label(day.date, today),
+                present,
+                day.records.len(),
+                day.attendance_percentage()
+            );
+            set_topic(&ctx, ids::channel(ChannelKind::Lab).get(), &topic).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn day_label(date: NaiveDate, today: NaiveDate) -> String {
+    if date == today - ChronoDuration::days(1) {
+        String::from("Yesterday")
+    } else {
+        date.format("%a, %b %d").to_string()
+    }
+}
+
+async fn set_topic(ctx: &Context, channel_id: u64, topic: &str) -> anyhow::Result<()> {
+    check_permissions(ctx, channel_id, Permissions::MANAGE_CHANNELS)?;
+    debug!("Setting the topic of {} to {}", channel_id, topic);
+    ChannelId::new(channel_id)
+        .edit(&ctx.http, EditChannel::new().topic(topic))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,159 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serenity::all::{ChannelId, Context, GetMessages, MessageId, Permissions};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{info, warn};
+
+use super::Task;
+use crate::{
+    config::RetentionConfig, metrics::timed, storage::Storage,
+    utils::permissions::check_permissions, utils::scan::PAGE_SIZE, utils::time::time_until, Data,
+};
+
+/// Pages of history read per channel and night, a long backlog is worked off over
+/// several nights instead of one long run.
+const MAX_PAGES: usize = 20;
+/// Pause between two deletes. Old messages can't be bulk deleted, so each is a request.
+const DELETE_INTERVAL: Duration = Duration::from_millis(500);
+/// Per channel, the oldest message the last run read before stopping at [`MAX_PAGES`],
+/// where the next run carries on.
+const CURSORS_KEY: &str = "cleanup.cursors";
+/// Milliseconds between the Unix epoch and Discord's, the start of its snowflake IDs.
+const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+
+/// Nightly deletes the bot's own messages that outlived their channel's retention policy.
+pub struct MessageCleanup;
+
+#[async_trait]
+impl Task for MessageCleanup {
+    fn name(&self) -> &str {
+        "Message Cleanup"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(2, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let policies = data.config.read().await.retention.clone();
+        let mut failed = Vec::new();
+        for policy in &policies {
+            match clean_channel(&ctx, &data.storage, policy).await {
+                Ok(0) => {}
+                Ok(deleted) => info!(
+                    "Deleted {} old bot messages in channel {}",
+                    deleted, policy.channel_id
+                ),
+                Err(e) => {
+                    warn!("Failed to clean channel {}: {:?}", policy.channel_id, e);
+                    failed.push(format!("<#{}>", policy.channel_id));
+                }
+            }
+        }
+
+        if failed.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow!("Couldn't clean up {}", failed.join(", ")))
+    }
+}
+
+/// Deletes the bot's messages in the policy's channel older than its retention period,
+/// returning how many were deleted. Messages that fail to delete are skipped, they're
+/// tried again once paging has gone through the whole history.
+async fn clean_channel(
+    ctx: &Context,
+    storage: &Storage,
+    policy: &RetentionConfig,
+) -> anyhow::Result<usize> {
+    check_permissions(
+        ctx,
+        policy.channel_id,
+        Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY,
+    )?;
+    let bot_id = ctx.cache.current_user().id;
+    let channel = ChannelId::new(policy.channel_id);
+    let cutoff = snowflake_at(Utc::now() - chrono::Duration::days(policy.days.get().into()));
+
+    // Paging back from the cutoff skips every message that's still being kept. A backlog
+    // longer than a night's pages is picked up where the last run stopped.
+    let cursors: HashMap<u64, u64> = storage.get(CURSORS_KEY).await?;
+    let start = cursors
+        .get(&policy.channel_id)
+        .map(|&id| MessageId::new(id))
+        .filter(|&cursor| cursor < cutoff)
+        .unwrap_or(cutoff);
+    let mut builder = GetMessages::new().before(start).limit(PAGE_SIZE);
+    let mut cursor = None;
+    let mut deleted = 0;
+    let mut failed = 0;
+    for _ in 0..MAX_PAGES {
+        let messages = timed("discord.get_messages", channel.messages(&ctx.http, builder)).await?;
+        let Some(oldest) = messages.last() else {
+            cursor = None;
+            break;
+        };
+        builder = GetMessages::new().before(oldest.id).limit(PAGE_SIZE);
+        cursor = (messages.len() == PAGE_SIZE as usize).then_some(oldest.id);
+
+        for message in messages.iter().filter(|m| m.author.id == bot_id) {
+            if deleted + failed > 0 {
+                tokio::time::sleep(DELETE_INTERVAL).await;
+            }
+            match message.delete(&ctx.http).await {
+                Ok(()) => deleted += 1,
+                Err(e) => {
+                    warn!(
+                        "Failed to delete message {} in channel {}: {}",
+                        message.id, policy.channel_id, e
+                    );
+                    failed += 1;
+                }
+            }
+        }
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    storage
+        .update(
+            CURSORS_KEY,
+            |cursors: &mut HashMap<u64, u64>| match cursor {
+                Some(cursor) => cursors.insert(policy.channel_id, cursor.get()),
+                None => cursors.remove(&policy.channel_id),
+            },
+        )
+        .await?;
+    Ok(deleted)
+}
+
+/// The smallest message ID Discord could have assigned at `time`.
+fn snowflake_at(time: DateTime<Utc>) -> MessageId {
+    let since_epoch = (time.timestamp_millis() - DISCORD_EPOCH_MS).max(1);
+    MessageId::new((since_epoch as u64) << 22)
+}
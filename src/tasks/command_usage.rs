@@ -0,0 +1,150 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use chrono::Datelike;
+use serenity::all::{ChannelId, Context, CreateAllowedMentions, CreateMessage};
+use serenity::async_trait;
+use tokio::time::Duration;
+
+use super::Task;
+use crate::{
+    command_usage::{usage_in, CommandStats},
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::{format_month, local_today, time_until},
+    },
+    Data,
+};
+
+const USAGE_COLOR: u32 = 0x6366f1;
+/// How many commands and users each ranking shows.
+const TOP_COUNT: usize = 10;
+/// Commands run fewer times than this are left out of the failure ratios, one failed run
+/// out of two says little.
+const MIN_CALLS_FOR_RATIO: u64 = 5;
+
+/// On the 1st, posts last month's command usage to the ops channel, to show which
+/// features members actually use.
+pub struct CommandUsageReport;
+
+#[async_trait]
+impl Task for CommandUsageReport {
+    fn name(&self) -> &str {
+        "Command Usage Report"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(10, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let today = local_today();
+        if today.day() != 1 {
+            return Ok(());
+        }
+        let config = data.config.read().await.clone();
+        let Some(channel_id) = config.bot.ops_channel_id else {
+            return Ok(());
+        };
+
+        let last_month = today - chrono::Duration::days(1);
+        let usage = usage_in(&data.storage, last_month).await?;
+        let mut commands: Vec<(&String, &CommandStats)> = usage.commands.iter().collect();
+        let total: u64 = commands.iter().map(|(_, stats)| stats.calls).sum();
+        let failed: u64 = commands.iter().map(|(_, stats)| stats.failures).sum();
+
+        let embed = report_embed(
+            &ctx,
+            &config.theme.embed,
+            format!("Command Usage - {}", format_month(last_month)),
+            USAGE_COLOR,
+        );
+        let embed = if total == 0 {
+            embed.description("No commands were run last month.")
+        } else {
+            commands.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then(a.0.cmp(b.0)));
+            let most_used = commands
+                .iter()
+                .take(TOP_COUNT)
+                .map(|(name, stats)| format!("`{}` - {} runs", name, stats.calls))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            let mut users: Vec<(&u64, &u64)> = usage.users.iter().collect();
+            users.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            let most_active = users
+                .iter()
+                .take(TOP_COUNT)
+                .map(|(user_id, calls)| format!("<@{}> - {} runs", user_id, calls))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            commands.retain(|(_, stats)| stats.calls >= MIN_CALLS_FOR_RATIO && stats.failures > 0);
+            commands.sort_by(|a, b| b.1.failure_ratio().total_cmp(&a.1.failure_ratio()));
+            let failing = commands
+                .iter()
+                .take(TOP_COUNT)
+                .map(|(name, stats)| {
+                    format!(
+                        "`{}` - {:.0}% of {} runs",
+                        name,
+                        stats.failure_ratio() * 100.0,
+                        stats.calls
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            embed
+                .description(format!(
+                    "{} commands were run by {} members, {} of them failed.",
+                    total,
+                    usage.users.len(),
+                    failed
+                ))
+                .field("Most used", most_used, false)
+                .field("Most active", or_none(most_active), false)
+                .field("Most failing", or_none(failing), false)
+        };
+
+        check_permissions(&ctx, channel_id, POST_EMBEDS)?;
+        ChannelId::new(channel_id)
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .embed(embed)
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
+            .await
+            .context("Failed to send the command usage report")?;
+        Ok(())
+    }
+}
+
+fn or_none(list: String) -> String {
+    if list.is_empty() {
+        String::from("None")
+    } else {
+        list
+    }
+}
@@ -0,0 +1,148 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use chrono::Datelike;
+use serenity::all::{Context, CreateMessage};
+use serenity::async_trait;
+use tokio::time::Duration;
+
+use super::{update_quality::scores_between, Task};
+use crate::{
+    history::recent_status_update_days,
+    ids::{self, ChannelKind},
+    privacy::erased_members,
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::{format_month, local_today, time_until},
+    },
+    Data,
+};
+
+const AWARD_COUNT: usize = 5;
+
+/// Announces the most consistent members of the previous month on the 1st.
+pub struct ConsistencyAwards;
+
+#[async_trait]
+impl Task for ConsistencyAwards {
+    fn name(&self) -> &str {
+        "Consistency Awards"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(10, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let today = local_today();
+        if today.day() != 1 {
+            return Ok(());
+        }
+        post_consistency_awards(ctx, data, today).await
+    }
+}
+
+/// Ranks members by updates sent last month, breaking ties with their average update
+/// quality score when LLM feedback is enabled.
+async fn post_consistency_awards(
+    ctx: Context,
+    data: &Data,
+    today: chrono::NaiveDate,
+) -> anyhow::Result<()> {
+    let last_day = today - chrono::Duration::days(1);
+    let first_day = last_day.with_day(1).expect("Valid date");
+
+    let days: Vec<_> = recent_status_update_days(&data.storage, 31)
+        .await?
+        .into_iter()
+        .filter(|day| day.date >= first_day && day.date <= last_day)
+        .collect();
+    let scores = scores_between(&data.storage, first_day, last_day).await?;
+    let erased = erased_members(&data.storage).await?;
+
+    // discord_id -> (name, updates sent, total quality score, scored updates)
+    let mut tally: HashMap<&str, (&str, usize, u32, u32)> = HashMap::new();
+    for member in days
+        .iter()
+        .flat_map(|day| day.senders())
+        .filter(|member| !erased.contains(&member.discord_id))
+    {
+        tally
+            .entry(&member.discord_id)
+            .or_insert((&member.name, 0, 0, 0))
+            .1 += 1;
+    }
+    for score in &scores {
+        if let Some(entry) = tally.get_mut(score.discord_id.as_str()) {
+            entry.2 += score.score as u32;
+            entry.3 += 1;
+        }
+    }
+
+    let mut ranking: Vec<_> = tally
+        .into_values()
+        .map(|(name, sent, total, scored)| {
+            let average = if scored > 0 {
+                total as f64 / scored as f64
+            } else {
+                0.0
+            };
+            (name, sent, average)
+        })
+        .collect();
+    ranking.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)));
+
+    let mut description = String::new();
+    for (rank, (name, sent, average)) in ranking.iter().take(AWARD_COUNT).enumerate() {
+        description.push_str(&format!("{}. {} - {} updates", rank + 1, name, sent));
+        if *average > 0.0 {
+            description.push_str(&format!(", average quality {:.1}/10", average));
+        }
+        description.push('\n');
+    }
+    if description.is_empty() {
+        description.push_str("No status updates were recorded last month.");
+    }
+
+    let theme = data.config.read().await.theme.clone();
+    let embed = report_embed(
+        &ctx,
+        &theme.embed,
+        format!("Consistency Awards - {}", format_month(first_day)),
+        theme.status_update.color,
+    )
+    .description(description);
+    check_permissions(
+        &ctx,
+        ids::channel(ChannelKind::StatusUpdate).get(),
+        POST_EMBEDS,
+    )?;
+    ids::channel(ChannelKind::StatusUpdate)
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await
+        .context("Failed to send consistency awards")?;
+
+    Ok(())
+}
@@ -0,0 +1,256 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Context, CreateMessage, MessageId, Reaction, ReactionType};
+use tracing::{debug, info, warn};
+
+use super::status_update::ReceivedUpdate;
+use crate::{storage::Storage, Data};
+
+/// Similarity fingerprints of each member's updates from their last two checked days,
+/// oldest first, keyed by Discord ID.
+const FINGERPRINTS_KEY: &str = "duplicate_updates.fingerprints";
+const FLAGS_KEY: &str = "duplicate_updates.flags";
+/// Flags older than this are dropped, appeals only make sense for recent reports.
+const FLAG_RETENTION_DAYS: i64 = 30;
+/// Reaction members add to their flagged update to appeal the flag.
+pub const APPEAL_EMOJI: char = '🙋';
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct Fingerprint {
+    date: NaiveDate,
+    hash: u64,
+}
+
+/// An update that was nearly identical to the member's previous one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DuplicateFlag {
+    pub date: NaiveDate,
+    pub message_id: u64,
+    pub channel_id: u64,
+    pub author_id: u64,
+    pub author_name: String,
+    pub similarity: f64,
+    pub appealed: bool,
+}
+
+/// Compares every member's latest update with their previous day's and flags those at
+/// least `threshold` similar. The flagged messages get an [`APPEAL_EMOJI`] reaction the
+/// author can click if the flag is a false positive.
+pub async fn find_duplicates(
+    ctx: &Context,
+    storage: &Storage,
+    threshold: f64,
+    date: NaiveDate,
+    updates: &[ReceivedUpdate],
+) -> anyhow::Result<Vec<DuplicateFlag>> {
+    // Updates are sorted oldest first, so later ones replace earlier ones.
+    let latest: HashMap<u64, &ReceivedUpdate> = updates.iter().map(|u| (u.author_id, u)).collect();
+
+    let flags = storage
+        .update(
+            FINGERPRINTS_KEY,
+            |fingerprints: &mut HashMap<u64, Vec<Fingerprint>>| {
+                let mut flags = Vec::new();
+                for (author_id, update) in &latest {
+                    let hash = simhash(&update.content);
+                    let history = fingerprints.entry(*author_id).or_default();
+                    // A rerun of the same day replaces that day's fingerprint rather than
+                    // comparing the update with itself.
+                    history.retain(|f| f.date < date);
+                    let previous = history.last().copied();
+                    history.push(Fingerprint { date, hash });
+                    if history.len() > 2 {
+                        history.remove(0);
+                    }
+                    if let Some(previous) = previous {
+                        let similarity = similarity(previous.hash, hash);
+                        if similarity >= threshold {
+                            flags.push(DuplicateFlag {
+                                date,
+                                message_id: update.message_id,
+                                channel_id: update.channel_id,
+                                author_id: *author_id,
+                                author_name: update.author_name.clone(),
+                                similarity,
+                                appealed: false,
+                            });
+                        }
+                    }
+                }
+                flags
+            },
+        )
+        .await?;
+
+    let oldest = date - chrono::Duration::days(FLAG_RETENTION_DAYS);
+    storage
+        .update(FLAGS_KEY, |stored: &mut Vec<DuplicateFlag>| {
+            stored.retain(|f| f.date >= oldest && f.date != date);
+            stored.extend(flags.iter().cloned());
+        })
+        .await?;
+
+    for flag in &flags {
+        debug!(
+            "Update from {} is {:.0}% similar to their previous one",
+            flag.author_name,
+            flag.similarity * 100.0
+        );
+        if let Err(e) = ChannelId::new(flag.channel_id)
+            .create_reaction(
+                &ctx.http,
+                MessageId::new(flag.message_id),
+                ReactionType::Unicode(APPEAL_EMOJI.to_string()),
+            )
+            .await
+        {
+            warn!(
+                "Failed to add appeal reaction for {}: {}",
+                flag.author_name, e
+            );
+        }
+    }
+
+    Ok(flags)
+}
+
+/// The updates flagged on `date`, including appealed ones.
+pub async fn flags_for(storage: &Storage, date: NaiveDate) -> anyhow::Result<Vec<DuplicateFlag>> {
+    let flags: Vec<DuplicateFlag> = storage.get(FLAGS_KEY).await?;
+    Ok(flags.into_iter().filter(|f| f.date == date).collect())
+}
+
+/// Records an appeal when the author of a flagged update reacts with [`APPEAL_EMOJI`],
+/// and asks mentors in the ops channel to take a look.
+pub async fn handle_appeal_reaction(ctx: &Context, data: &Data, reaction: &Reaction) {
+    if reaction.emoji != ReactionType::Unicode(APPEAL_EMOJI.to_string()) {
+        return;
+    }
+    let Some(user_id) = reaction.user_id else {
+        return;
+    };
+
+    let appealed = data
+        .storage
+        .update(FLAGS_KEY, |flags: &mut Vec<DuplicateFlag>| {
+            let flag = flags.iter_mut().find(|f| {
+                f.message_id == reaction.message_id.get() && f.author_id == user_id.get()
+            })?;
+            if flag.appealed {
+                return None;
+            }
+            flag.appealed = true;
+            Some(flag.clone())
+        })
+        .await;
+    let flag = match appealed {
+        Ok(Some(flag)) => flag,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Failed to record appeal: {:?}", e);
+            return;
+        }
+    };
+    info!("{} appealed their copy-paste flag", flag.author_name);
+
+    let Some(channel_id) = data.config.read().await.bot.ops_channel_id else {
+        return;
+    };
+    let link = reaction
+        .message_id
+        .link(reaction.channel_id, reaction.guild_id);
+    let message = CreateMessage::new().content(format!(
+        "{} appealed the copy-paste flag on their update from {}: {}",
+        flag.author_name, flag.date, link
+    ));
+    if let Err(e) = ChannelId::new(channel_id)
+        .send_message(&ctx.http, message)
+        .await
+    {
+        warn!("Failed to forward appeal: {}", e);
+    }
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(
+            FINGERPRINTS_KEY,
+            |fingerprints: &mut HashMap<u64, Vec<Fingerprint>>| fingerprints.remove(&user_id),
+        )
+        .await?;
+    storage
+        .update(FLAGS_KEY, |flags: &mut Vec<DuplicateFlag>| {
+            flags.retain(|f| f.author_id != user_id)
+        })
+        .await
+}
+
+/// 64-bit SimHash over the word bigrams of `text`. Similar texts get fingerprints that
+/// differ in few bits, so only the fingerprint needs to be kept between days.
+fn simhash(text: &str) -> u64 {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut weights = [0i64; 64];
+    let features: Vec<String> = if words.len() < 2 {
+        words
+    } else {
+        words.windows(2).map(|pair| pair.join(" ")).collect()
+    };
+    for feature in features {
+        let hash = fnv1a(feature.as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// Share of matching bits between two fingerprints, from 0 to 1.
+fn similarity(a: u64, b: u64) -> f64 {
+    1.0 - (a ^ b).count_ones() as f64 / 64.0
+}
+
+/// FNV-1a, used instead of the std hasher since fingerprints are persisted and must
+/// stay stable across Rust versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
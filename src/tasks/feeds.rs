@@ -0,0 +1,182 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context as _};
+use feed_rs::model::Entry;
+use serenity::all::{ChannelId, Context, CreateEmbed};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::Task;
+use crate::{
+    quiet_hours::{send_or_queue, Destination, QueuedMessage},
+    storage::Storage,
+    Data,
+};
+
+const SEEN_ENTRIES_KEY: &str = "feeds.seen";
+/// Number of entry IDs remembered per feed, enough to cover any feed's backlog.
+const SEEN_ENTRIES_LIMIT: usize = 200;
+const SUMMARY_LENGTH: usize = 300;
+
+/// Polls the configured RSS/Atom feeds and posts new articles to the reading channel.
+pub struct FeedAnnouncements;
+
+#[async_trait]
+impl Task for FeedAnnouncements {
+    fn name(&self) -> &str {
+        "Feed Announcements"
+    }
+
+    fn run_in(&self) -> Duration {
+        Duration::from_secs(30 * 60)
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        poll_feeds(ctx, data).await
+    }
+}
+
+async fn poll_feeds(ctx: Context, data: &Data) -> anyhow::Result<()> {
+    let config = data.config.read().await.feeds.clone();
+    let Some(channel_id) = config.channel_id else {
+        debug!("No feed channel configured, skipping");
+        return Ok(());
+    };
+
+    for url in &config.urls {
+        if let Err(e) = poll_feed(&ctx, data, ChannelId::new(channel_id), url).await {
+            warn!("Failed to poll feed {}: {:?}", url, e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn poll_feed(
+    ctx: &Context,
+    data: &Data,
+    channel_id: ChannelId,
+    url: &str,
+) -> anyhow::Result<()> {
+    let response = reqwest::get(url).await.context("Failed to fetch feed")?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Feed responded with an error: {:?}",
+            response.status()
+        ));
+    }
+    let body = response.bytes().await.context("Failed to read feed")?;
+    let feed = feed_rs::parser::parse(body.as_ref()).context("Failed to parse feed")?;
+
+    let seen: HashMap<String, Vec<String>> = data.storage.get(SEEN_ENTRIES_KEY).await?;
+    let seen_ids = seen.get(url);
+    let new_entries: Vec<&Entry> = feed
+        .entries
+        .iter()
+        .filter(|entry| seen_ids.is_none_or(|ids| !ids.contains(&entry.id)))
+        .collect();
+
+    // Don't flood the channel with a feed's whole history the first time we see it.
+    if seen_ids.is_none() {
+        info!(
+            "Started tracking feed {} with {} entries",
+            url,
+            new_entries.len()
+        );
+        return mark_seen(&data.storage, url, &new_entries).await;
+    }
+
+    // Feeds list the newest entry first, post in chronological order instead. Each entry
+    // is marked seen once posted, so a failure only retries the ones left.
+    for entry in new_entries.iter().rev() {
+        let destination = Destination::Channel(channel_id.get());
+        send_or_queue(
+            ctx,
+            data,
+            destination,
+            QueuedMessage::embed(entry_embed(entry))?,
+        )
+        .await
+        .context("Failed to post feed entry")?;
+        mark_seen(&data.storage, url, &[entry]).await?;
+    }
+
+    Ok(())
+}
+
+async fn mark_seen(storage: &Storage, url: &str, entries: &[&Entry]) -> anyhow::Result<()> {
+    storage
+        .update(
+            SEEN_ENTRIES_KEY,
+            |seen: &mut HashMap<String, Vec<String>>| {
+                let seen_ids = seen.entry(url.to_string()).or_default();
+                seen_ids.extend(entries.iter().map(|entry| entry.id.clone()));
+                let excess = seen_ids.len().saturating_sub(SEEN_ENTRIES_LIMIT);
+                seen_ids.drain(..excess);
+            },
+        )
+        .await
+}
+
+fn entry_embed(entry: &Entry) -> CreateEmbed {
+    let title = entry
+        .title
+        .as_ref()
+        .map(|title| title.content.clone())
+        .unwrap_or_else(|| String::from("New post"));
+    let summary = entry
+        .summary
+        .as_ref()
+        .map(|summary| truncate(&strip_html(&summary.content), SUMMARY_LENGTH))
+        .unwrap_or_default();
+
+    let mut embed = CreateEmbed::new().title(title).description(summary);
+    if let Some(link) = entry.links.first() {
+        embed = embed.url(&link.href);
+    }
+    if let Some(published) = entry.published.or(entry.updated) {
+        embed = embed.timestamp(published);
+    }
+
+    embed
+}
+
+fn strip_html(content: &str) -> String {
+    let mut text = String::with_capacity(content.len());
+    let mut in_tag = false;
+    for c in content.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(max_chars).collect();
+    format!("{}…", truncated.trim_end())
+}
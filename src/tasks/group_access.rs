@@ -0,0 +1,208 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Context as _};
+use serenity::all::{
+    ChannelId, ChannelType, Context, CreateAllowedMentions, CreateMessage, GuildId, Permissions,
+    UserId,
+};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{debug, info};
+
+use super::{status_update::tracked_members, Task};
+use crate::{
+    ids::group_channel_ids,
+    storage::Storage,
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::time_until,
+    },
+    Data,
+};
+
+/// Members who couldn't reach their group channel at the last check.
+const STUCK_KEY: &str = "group_access.stuck";
+const ACCESS_COLOR: u32 = 0xef4444;
+/// Room left in the report's description for members, under Discord's 4096.
+const REPORT_LENGTH: usize = 4000;
+/// What a member needs in their group channel to send their status update.
+const NEEDED: Permissions = Permissions::VIEW_CHANNEL.union(Permissions::SEND_MESSAGES);
+/// In a group forum, members reply in their own post instead.
+const NEEDED_IN_FORUM: Permissions =
+    Permissions::VIEW_CHANNEL.union(Permissions::SEND_MESSAGES_IN_THREADS);
+
+/// A member whose permissions keep them from posting in their group channel.
+struct Stuck {
+    user_id: u64,
+    name: String,
+    group: u64,
+    channel_id: u64,
+    missing: Permissions,
+}
+
+/// Every evening, works out each member's permissions in their group channel and reports
+/// those who can't post there, since they'd be marked as defaulters through no fault of
+/// their own. Only changes to the list are reported.
+pub struct GroupChannelAccess;
+
+#[async_trait]
+impl Task for GroupChannelAccess {
+    fn name(&self) -> &str {
+        "Group Channel Access"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(20, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    fn follows_semester(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let config = data.config.read().await.clone();
+        let Some(report_channel_id) = config.groups.audit_channel_id.or(config.bot.ops_channel_id)
+        else {
+            return Ok(());
+        };
+
+        let stuck = find_stuck(&ctx, data).await?;
+        let ids: BTreeSet<u64> = stuck.iter().map(|s| s.user_id).collect();
+        let previous: BTreeSet<u64> = data.storage.get(STUCK_KEY).await?;
+        if ids == previous {
+            debug!("Group channel access unchanged");
+            return Ok(());
+        }
+        data.storage.set(STUCK_KEY, &ids).await?;
+        info!("{} members can't post in their group channel", stuck.len());
+
+        let description = if stuck.is_empty() {
+            String::from("Everyone can post in their group channel again.")
+        } else {
+            let mut description = format!(
+                "{} members can't post in their group channel, so their status updates \
+                 can't be counted:\n",
+                stuck.len()
+            );
+            for (listed, member) in stuck.iter().enumerate() {
+                let line = format!(
+                    "- <@{}> ({}, Group {}): missing {} in <#{}>\n",
+                    member.user_id, member.name, member.group, member.missing, member.channel_id
+                );
+                if description.len() + line.len() > REPORT_LENGTH {
+                    description.push_str(&format!("…and {} more\n", stuck.len() - listed));
+                    break;
+                }
+                description.push_str(&line);
+            }
+            description
+        };
+        let embed = report_embed(
+            &ctx,
+            &config.theme.embed,
+            "Group Channel Access",
+            ACCESS_COLOR,
+        )
+        .description(description);
+
+        check_permissions(&ctx, report_channel_id, POST_EMBEDS)?;
+        ChannelId::new(report_channel_id)
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .embed(embed)
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
+            .await
+            .context("Failed to send the group channel access report")?;
+        Ok(())
+    }
+}
+
+async fn find_stuck(ctx: &Context, data: &Data) -> anyhow::Result<Vec<Stuck>> {
+    let channels = group_channel_ids();
+    let guild_id: GuildId = ChannelId::new(channels[0].1)
+        .to_channel(&ctx.http)
+        .await?
+        .guild()
+        .context("The group channels aren't in a server")?
+        .guild_id;
+
+    let mut stuck = Vec::new();
+    for member in tracked_members(data).await? {
+        let Some(&(group, channel_id)) = channels
+            .iter()
+            .find(|(group, _)| *group == member.group_id as u64)
+        else {
+            continue;
+        };
+        let Ok(user_id) = member.discord_id.parse::<u64>() else {
+            continue;
+        };
+        let guild_member = match guild_id.member(&ctx.http, UserId::new(user_id)).await {
+            Ok(guild_member) => guild_member,
+            Err(e) => {
+                debug!("Skipping {} in the access check: {}", member.name, e);
+                continue;
+            }
+        };
+
+        let (needed, permissions) = {
+            let guild = ctx
+                .cache
+                .guild(guild_id)
+                .ok_or_else(|| anyhow!("Server {} isn't cached", guild_id))?;
+            let channel = guild
+                .channels
+                .get(&ChannelId::new(channel_id))
+                .ok_or_else(|| anyhow!("Group {} channel isn't cached", group))?;
+            let needed = if channel.kind == ChannelType::Forum {
+                NEEDED_IN_FORUM
+            } else {
+                NEEDED
+            };
+            (needed, guild.user_permissions_in(channel, &guild_member))
+        };
+        let missing = needed - permissions;
+        if !missing.is_empty() {
+            stuck.push(Stuck {
+                user_id,
+                name: member.name,
+                group,
+                channel_id,
+                missing,
+            });
+        }
+    }
+    Ok(stuck)
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(STUCK_KEY, |stuck: &mut BTreeSet<u64>| {
+            stuck.remove(&user_id);
+        })
+        .await
+}
@@ -0,0 +1,112 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use chrono::{Utc, Weekday};
+use serenity::all::{ChannelId, Context, CreateMessage};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::info;
+
+use super::Task;
+use crate::{
+    holidays::{fetch_ics, queue_for_review, review_buttons},
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::{format_date, local_today, time_until_weekday},
+    },
+    Data,
+};
+
+const REVIEW_COLOR: u32 = 0x0ea5e9;
+/// Holidays further ahead than this are imported by a later run.
+const IMPORT_DAYS: i64 = 120;
+
+/// Imports upcoming public holidays every Monday and asks an admin to review them.
+pub struct HolidayImport;
+
+#[async_trait]
+impl Task for HolidayImport {
+    fn name(&self) -> &str {
+        "Holiday Import"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until_weekday(Weekday::Mon, 9, 30)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until_weekday(Weekday::Mon, hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let config = data.config.read().await.clone();
+        let Some(url) = config.holidays.ics_url.as_deref() else {
+            return Ok(());
+        };
+        let Some(channel_id) = config
+            .holidays
+            .review_channel_id
+            .or(config.bot.ops_channel_id)
+        else {
+            return Ok(());
+        };
+
+        let today = local_today();
+        let mut imported = fetch_ics(url, today).await?;
+        imported.retain(|holiday| (holiday.date - today).num_days() <= IMPORT_DAYS);
+        let batch = Utc::now().timestamp() as u64;
+        let mut queued = queue_for_review(&data.storage, batch, imported).await?;
+        info!("Imported {} new holidays for review", queued.len());
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        queued.sort_by_key(|holiday| holiday.date);
+        let mut description = String::from(
+            "These public holidays were imported. Member checks are skipped on the ones \
+             you observe.\n",
+        );
+        for holiday in &queued {
+            description.push_str(&format!(
+                "- **{}**: {}\n",
+                format_date(holiday.date),
+                holiday.name
+            ));
+        }
+        let embed = report_embed(
+            &ctx,
+            &config.theme.embed,
+            "Holidays to Review",
+            REVIEW_COLOR,
+        )
+        .description(description);
+
+        check_permissions(&ctx, channel_id, POST_EMBEDS)?;
+        ChannelId::new(channel_id)
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .embed(embed)
+                    .components(vec![review_buttons(batch)]),
+            )
+            .await
+            .context("Failed to post the holidays for review")?;
+        Ok(())
+    }
+}
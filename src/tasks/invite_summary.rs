@@ -0,0 +1,101 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+use chrono::{Utc, Weekday};
+use serenity::all::{ChannelId, Context, CreateMessage};
+use serenity::async_trait;
+use tokio::time::Duration;
+
+use super::Task;
+use crate::{
+    invites::joins_since,
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::time_until_weekday,
+    },
+    Data,
+};
+
+/// Tells the outreach team every Monday how many people joined through each invite.
+pub struct InviteSummary;
+
+#[async_trait]
+impl Task for InviteSummary {
+    fn name(&self) -> &str {
+        "Invite Summary"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until_weekday(Weekday::Mon, 10, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until_weekday(Weekday::Mon, hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let config = data.config.read().await.clone();
+        let Some(channel_id) = config.invites.report_channel_id else {
+            return Ok(());
+        };
+
+        let joins = joins_since(&data.storage, Utc::now() - chrono::Duration::days(7)).await?;
+        // code -> (inviter, joins)
+        let mut per_invite: HashMap<&str, (Option<u64>, usize)> = HashMap::new();
+        let mut unattributed = 0;
+        for join in &joins {
+            match &join.code {
+                Some(code) => per_invite.entry(code).or_insert((join.inviter_id, 0)).1 += 1,
+                None => unattributed += 1,
+            }
+        }
+        let mut invites: Vec<_> = per_invite.into_iter().collect();
+        invites.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then(a.0.cmp(b.0)));
+
+        let mut description = format!("{} members joined this week.\n", joins.len());
+        for (code, (inviter_id, count)) in invites {
+            let inviter = inviter_id
+                .map(|id| format!(" by <@{}>", id))
+                .unwrap_or_default();
+            description.push_str(&format!("- `{}`{}: {} joins\n", code, inviter, count));
+        }
+        if unattributed > 0 {
+            description.push_str(&format!(
+                "- {} joins couldn't be attributed to an invite\n",
+                unattributed
+            ));
+        }
+
+        let embed = report_embed(
+            &ctx,
+            &config.theme.embed,
+            "Weekly Invite Summary",
+            config.theme.status_update.color,
+        )
+        .description(description);
+        check_permissions(&ctx, channel_id, POST_EMBEDS)?;
+        ChannelId::new(channel_id)
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await
+            .context("Failed to send invite summary")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use chrono::{Datelike, NaiveDate};
+use serenity::all::{ChannelId, Context, CreateAllowedMentions, CreateMessage};
+use serenity::async_trait;
+use tokio::time::Duration;
+
+use super::Task;
+use crate::{
+    kudos::{tally, KUDOS_COLOR},
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::{format_month, local_today, time_until, timezone},
+    },
+    Data,
+};
+
+/// On the 1st, announces the members who received the most kudos last month.
+pub struct KudosTally;
+
+#[async_trait]
+impl Task for KudosTally {
+    fn name(&self) -> &str {
+        "Kudos Tally"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(10, 30)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let today = local_today();
+        if today.day() != 1 {
+            return Ok(());
+        }
+        post_kudos_tally(ctx, data, today).await
+    }
+}
+
+async fn post_kudos_tally(ctx: Context, data: &Data, today: NaiveDate) -> anyhow::Result<()> {
+    let config = data.config.read().await.clone();
+    let Some(channel_id) = config.kudos.channel_id else {
+        return Ok(());
+    };
+    let first_day = (today - chrono::Duration::days(1))
+        .with_day(1)
+        .expect("Valid date");
+    let start_of = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|time| time.and_local_timezone(timezone()).single())
+            .map(|time| time.to_utc())
+            .context("Invalid start of month")
+    };
+    let ranking = tally(&data.storage, start_of(first_day)?, start_of(today)?).await?;
+
+    let description = if ranking.is_empty() {
+        String::from("No kudos were given last month.")
+    } else {
+        let total: usize = ranking.iter().map(|(_, count)| count).sum();
+        let mut description = format!("{} kudos were given last month.\n", total);
+        for (rank, (user_id, count)) in ranking.iter().take(config.kudos.top_count).enumerate() {
+            description.push_str(&format!("{}. <@{}> - {} kudos\n", rank + 1, user_id, count));
+        }
+        description
+    };
+    let embed = report_embed(
+        &ctx,
+        &config.theme.embed,
+        format!("Most Appreciated - {}", format_month(first_day)),
+        KUDOS_COLOR,
+    )
+    .description(description);
+
+    check_permissions(&ctx, channel_id, POST_EMBEDS)?;
+    ChannelId::new(channel_id)
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .embed(embed)
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await
+        .context("Failed to send the kudos tally")?;
+    Ok(())
+}
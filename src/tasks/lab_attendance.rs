@@ -15,55 +15,401 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
-use super::Task;
+use super::{defer_while_root_unavailable, RetryLater, Task};
 use anyhow::Context as _;
-use chrono::{DateTime, Datelike, Local, NaiveTime, ParseError, TimeZone, Timelike, Utc};
-use serenity::all::{
-    ChannelId, Colour, Context as SerenityContext, CreateEmbed, CreateEmbedAuthor, CreateMessage,
-};
+use chrono::{DateTime, NaiveDate, NaiveTime, ParseError, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serenity::all::{Context as SerenityContext, CreateEmbed, CreateMessage};
 use serenity::async_trait;
-use std::collections::HashMap;
-use tracing::{debug, trace};
+use std::collections::{HashMap, HashSet};
+use tokio::time::Duration;
+use tracing::{debug, info, trace, warn};
 
 use crate::{
-    graphql::{models::AttendanceRecord, queries::fetch_attendance},
-    ids::THE_LAB_CHANNEL_ID,
-    utils::time::{get_five_forty_five_pm_timestamp, time_until},
+    checkins::{manual_checkins, merge_manual_checkins},
+    config::{ReportDetail, ReportKind, ThemeConfig},
+    graphql::{
+        models::AttendanceRecord,
+        queries::{fetch_attendance, fetch_members, push_attendance_stats},
+    },
+    history::{record_attendance_day, AttendanceDay},
+    ids::{self, ChannelKind},
+    interactions::attendance_report_buttons,
+    metrics::{push_kpis, Kpi},
+    points,
+    privacy::erased_names,
+    scheduler::retry_attempt,
+    settings::Settings,
+    storage::Storage,
+    utils::{
+        delivery::deliver_report,
+        embed::report_embed,
+        time::{
+            format_date, get_five_forty_five_pm_timestamp, local_now, local_to_utc, local_today,
+            time_until, timezone,
+        },
+    },
+    xp, Data,
 };
 
-const TITLE_URL: &str = "https://www.amfoss.in/";
-const AUTHOR_URL: &str = "https://github.com/amfoss/amd";
+/// The last attendance Root returned, reported as stale when Root can't be reached.
+const LAST_KNOWN_KEY: &str = "attendance.last_known";
+/// How long after failing to fetch the attendance the report is retried.
+const STALE_RETRY: Duration = Duration::from_secs(30 * 60);
 
 pub struct PresenseReport;
 
+#[derive(Serialize, Deserialize)]
+struct LastKnownAttendance {
+    fetched_at: DateTime<Utc>,
+    records: Vec<AttendanceRecord>,
+}
+
+/// A day's attendance split into the lists the report shows.
+struct AttendanceSummary {
+    date: NaiveDate,
+    total_count: usize,
+    absent_list: Vec<AttendanceRecord>,
+    late_list: Vec<AttendanceRecord>,
+}
+
+impl AttendanceSummary {
+    fn lab_closed(&self) -> bool {
+        self.absent_list.len() == self.total_count
+    }
+}
+
+/// Additional sections of the attendance report, empty ones are left out.
+struct AttendanceNotes {
+    manual_checkins: Vec<String>,
+    discrepancies: Vec<String>,
+}
+
 #[async_trait]
 impl Task for PresenseReport {
     fn name(&self) -> &str {
         "Lab Attendance Check"
     }
 
+    fn follows_semester(&self) -> bool {
+        true
+    }
+
     fn run_in(&self) -> tokio::time::Duration {
         time_until(18, 00)
     }
 
-    async fn run(&self, ctx: SerenityContext) -> anyhow::Result<()> {
-        check_lab_attendance(ctx).await
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<tokio::time::Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: SerenityContext, data: &Data) -> anyhow::Result<()> {
+        let today = local_today();
+        if data.config.read().await.weekend.attendance_skipped(today) {
+            trace!("Skipping the attendance report, weekend rules are in effect");
+            return Ok(());
+        }
+        if let Err(e) = defer_while_root_unavailable(
+            &ctx,
+            ids::channel(ChannelKind::Lab).get(),
+            "attendance report",
+        )
+        .await
+        {
+            if let Some(retry) = e.downcast_ref::<RetryLater>() {
+                fall_back_to_stale_report(&ctx, data, retry.after).await;
+            }
+            return Err(e);
+        }
+        let attendance = match fetch_and_remember_attendance(&data.storage).await {
+            Ok(attendance) => attendance,
+            Err(e) => {
+                fall_back_to_stale_report(&ctx, data, STALE_RETRY).await;
+                return Err(RetryLater {
+                    after: STALE_RETRY,
+                    reason: format!("Failed to fetch attendance from Root: {:#}", e),
+                }
+                .into());
+            }
+        };
+        check_lab_attendance(ctx, data, attendance).await
+    }
+
+    async fn dry_run(&self, _ctx: SerenityContext, data: &Data) -> anyhow::Result<()> {
+        let mut attendance = fetch_and_remember_attendance(&data.storage).await?;
+        let today = local_today();
+        merge_manual_checkins(
+            &mut attendance,
+            &manual_checkins(&data.storage, today).await?,
+        );
+        let summary = summarize_attendance(today, &attendance);
+        info!(
+            "Dry run: {} members, {} absent and {} late",
+            summary.total_count,
+            summary.absent_list.len(),
+            summary.late_list.len()
+        );
+        Ok(())
     }
 }
 
-pub async fn check_lab_attendance(ctx: SerenityContext) -> anyhow::Result<()> {
-    trace!("Starting lab attendance check");
-    let attendance = fetch_attendance()
+/// Fetches today's attendance from Root and remembers it as the last known attendance.
+/// Erased members are left out of the copy, their names can't be looked up while Root
+/// is down and the copy is read.
+pub async fn fetch_and_remember_attendance(
+    storage: &Storage,
+) -> anyhow::Result<Vec<AttendanceRecord>> {
+    let records = fetch_attendance().await?;
+    let erased = match erased_names(storage).await {
+        Ok(erased) => erased,
+        Err(e) => {
+            warn!(
+                "Failed to look up erased members, not remembering the attendance: {:?}",
+                e
+            );
+            return Ok(records);
+        }
+    };
+    let last_known = LastKnownAttendance {
+        fetched_at: Utc::now(),
+        records: records
+            .iter()
+            .filter(|record| !erased.contains(&record.name))
+            .cloned()
+            .collect(),
+    };
+    if let Err(e) = storage.set(LAST_KNOWN_KEY, &last_known).await {
+        warn!("Failed to remember the attendance: {:?}", e);
+    }
+    Ok(records)
+}
+
+/// Drops the member from the last known attendance, which is keyed by name.
+pub async fn forget_member(storage: &Storage, name: &str) -> anyhow::Result<()> {
+    storage
+        .update(
+            LAST_KNOWN_KEY,
+            |last_known: &mut Option<LastKnownAttendance>| {
+                if let Some(last_known) = last_known {
+                    last_known.records.retain(|record| record.name != name);
+                }
+            },
+        )
         .await
-        .context("Failed to fetch attendance from Root")?;
+}
+
+/// Sends the stale report when Root can't be reached, on the first attempt only, retries
+/// just try for fresh data.
+async fn fall_back_to_stale_report(ctx: &SerenityContext, data: &Data, retry_in: Duration) {
+    if retry_attempt() > 0 {
+        return;
+    }
+    if let Err(e) = send_stale_report(ctx, data, retry_in).await {
+        warn!("Failed to send the stale attendance report: {:?}", e);
+    }
+}
+
+/// Posts the report built from the attendance last fetched today, labelled as stale.
+/// Nothing is recorded or awarded for it, that's left to the retry with fresh data.
+async fn send_stale_report(
+    ctx: &SerenityContext,
+    data: &Data,
+    retry_in: Duration,
+) -> anyhow::Result<()> {
+    let now = local_now();
+    let last_known: Option<LastKnownAttendance> = data.storage.get(LAST_KNOWN_KEY).await?;
+    let Some(mut last_known) = last_known.filter(|last_known| {
+        last_known
+            .fetched_at
+            .with_timezone(&timezone())
+            .date_naive()
+            == now.date_naive()
+    }) else {
+        debug!("No attendance was fetched today, skipping the stale report");
+        return Ok(());
+    };
+
+    let checkins = manual_checkins(&data.storage, now.date_naive()).await?;
+    let manual_list = merge_manual_checkins(&mut last_known.records, &checkins);
+    let summary = summarize_attendance(now.date_naive(), &last_known.records);
+    let notes = AttendanceNotes {
+        manual_checkins: manual_list,
+        discrepancies: Vec::new(),
+    };
+    let theme = data.config.read().await.theme.clone();
+    let (embed, stats) = attendance_report_embed(ctx, &theme, &summary);
+    let label = format!(
+        "⚠️ Root is unreachable, this is stale data from {}. The report will be retried in {} minutes.\n\n",
+        last_known
+            .fetched_at
+            .with_timezone(&timezone())
+            .format("%H:%M"),
+        retry_in.as_secs().div_ceil(60)
+    );
+    let description = format!(
+        "{}{}",
+        label,
+        format_report(&summary, &notes, stats.clone())
+    );
+    let stats = label + &stats;
+
+    deliver_report(
+        ctx,
+        data,
+        ReportKind::Attendance,
+        summary.date,
+        ids::channel(ChannelKind::Lab).get(),
+        |delivery| match delivery.detail {
+            ReportDetail::Full => {
+                Some(CreateMessage::new().embed(embed.clone().description(&description)))
+            }
+            ReportDetail::Stats => {
+                Some(CreateMessage::new().embed(embed.clone().description(&stats)))
+            }
+            // Like the fresh report, there are no group excerpts to send.
+            ReportDetail::Group => None,
+        },
+    )
+    .await
+    .context("Failed to send the stale attendance report")
+}
+
+pub async fn check_lab_attendance(
+    ctx: SerenityContext,
+    data: &Data,
+    mut attendance: Vec<AttendanceRecord>,
+) -> anyhow::Result<()> {
+    trace!("Starting lab attendance check");
 
-    let time = Local::now().with_timezone(&chrono_tz::Asia::Kolkata);
+    let erased = erased_names(&data.storage).await?;
+    attendance.retain(|record| !erased.contains(&record.name));
+    let time = local_now();
+    let checkins = manual_checkins(&data.storage, time.date_naive()).await?;
+    let manual_list = merge_manual_checkins(&mut attendance, &checkins);
+    let day = AttendanceDay {
+        date: time.date_naive(),
+        records: attendance.clone(),
+    };
+    let stats = day.stats();
+    if let Err(e) = record_attendance_day(&data.storage, day).await {
+        warn!("Failed to record the attendance day: {:?}", e);
+    }
+
+    let config = data.config.read().await.clone();
+    match present_members(&attendance).await {
+        Ok(present) => {
+            let points = config.points.per_attendance;
+            let occasion = format!("attendance:{}", time.date_naive());
+            let present_ids = present.iter().copied();
+            if let Err(e) = points::award(&data.storage, &occasion, present_ids, points).await {
+                warn!("Failed to award attendance points: {:?}", e);
+            }
+            xp::award(&ctx, data, present, config.xp.per_attendance).await;
+        }
+        Err(e) => warn!("Failed to match attendance to members: {:?}", e),
+    }
+    if config.reports.push_summaries {
+        if let Err(e) = push_attendance_stats(&stats).await {
+            warn!("Failed to push the attendance summary to Root: {:?}", e);
+        }
+    }
+
+    let presence_source_url = config.attendance.presence_source_url;
+    let discrepancies = match presence_source_url {
+        Some(url) => match fetch_secondary_presence(&url).await {
+            Ok(seen) => find_discrepancies(&attendance, &seen),
+            Err(e) => {
+                warn!("Skipping presence cross-check: {:?}", e);
+                Vec::new()
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let summary = summarize_attendance(time.date_naive(), &attendance);
+    if config.features.webhooks {
+        push_attendance_kpis(
+            &data.settings,
+            summary.total_count,
+            summary.absent_list.len(),
+            summary.late_list.len(),
+        )
+        .await;
+    }
+
+    if summary.lab_closed() {
+        send_lab_closed_message(ctx, data, summary.date).await?;
+    } else {
+        send_attendance_report(
+            ctx,
+            data,
+            &summary,
+            &AttendanceNotes {
+                manual_checkins: manual_list,
+                discrepancies,
+            },
+        )
+        .await?;
+    }
+
+    trace!("Completed lab attendance check");
+    Ok(())
+}
+
+/// Discord IDs of the members present. Attendance is recorded by name, so members are
+/// matched to their Discord account through Root.
+async fn present_members(attendance: &[AttendanceRecord]) -> anyhow::Result<Vec<u64>> {
+    let present: HashSet<&str> = attendance
+        .iter()
+        .filter(|record| record.is_present)
+        .map(|record| record.name.as_str())
+        .collect();
+    Ok(fetch_members()
+        .await?
+        .into_iter()
+        .filter(|member| present.contains(member.name.as_str()))
+        .filter_map(|member| member.discord_id.parse().ok())
+        .collect())
+}
+
+/// Rebuilds the report of a past `day` from its stored attendance, for when the original
+/// message was deleted or rendered wrongly. Discrepancies with the secondary presence
+/// source aren't stored, so they are left out.
+pub async fn replay_report(
+    ctx: &SerenityContext,
+    data: &Data,
+    day: &AttendanceDay,
+) -> anyhow::Result<CreateMessage> {
+    let theme = data.config.read().await.theme.clone();
+    let summary = summarize_attendance(day.date, &day.records);
+    if summary.lab_closed() {
+        return Ok(CreateMessage::new().embed(lab_closed_embed(ctx, &theme, day.date)));
+    }
+
+    let notes = AttendanceNotes {
+        manual_checkins: manual_checkins(&data.storage, day.date)
+            .await?
+            .into_iter()
+            .map(|c| c.name)
+            .collect(),
+        discrepancies: Vec::new(),
+    };
+    let (embed, stats) = attendance_report_embed(ctx, &theme, &summary);
+    Ok(CreateMessage::new()
+        .embed(embed.description(format_report(&summary, &notes, stats)))
+        .components(vec![attendance_report_buttons(day.date)]))
+}
+
+/// Splits `records` into the absent and late members shown in the report.
+fn summarize_attendance(date: NaiveDate, records: &[AttendanceRecord]) -> AttendanceSummary {
+    let time = local_now();
     let threshold_time = get_five_forty_five_pm_timestamp(time);
 
     let mut absent_list = Vec::new();
     let mut late_list = Vec::new();
 
-    for record in &attendance {
+    for record in records {
         debug!("Checking attendance for member: {}", record.name);
         if !record.is_present || record.time_in.is_none() {
             absent_list.push(record.clone());
@@ -78,101 +424,208 @@ pub async fn check_lab_attendance(ctx: SerenityContext) -> anyhow::Result<()> {
         }
     }
 
-    if absent_list.len() == attendance.len() {
-        send_lab_closed_message(ctx).await?;
-    } else {
-        send_attendance_report(ctx, absent_list, late_list, attendance.len()).await?;
+    AttendanceSummary {
+        date,
+        total_count: records.len(),
+        absent_list,
+        late_list,
     }
+}
 
-    trace!("Completed lab attendance check");
-    Ok(())
+/// Names of the members the secondary presence source (e.g. the lab's Wi-Fi controller)
+/// saw today. The source is expected to return a JSON array of member names.
+async fn fetch_secondary_presence(url: &str) -> anyhow::Result<HashSet<String>> {
+    let names: Vec<String> = reqwest::get(url)
+        .await
+        .context("Failed to reach the presence source")?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Failed to parse the presence source response")?;
+    Ok(names.into_iter().map(|name| name.to_lowercase()).collect())
 }
 
-async fn send_lab_closed_message(ctx: SerenityContext) -> anyhow::Result<()> {
-    let today_date = Utc::now().format("%B %d, %Y").to_string();
+/// Members whose Root attendance disagrees with the secondary presence source.
+fn find_discrepancies(attendance: &[AttendanceRecord], seen: &HashSet<String>) -> Vec<String> {
+    attendance
+        .iter()
+        .filter_map(|record| {
+            let was_seen = seen.contains(&record.name.to_lowercase());
+            match (record.is_present, was_seen) {
+                (false, true) => Some(format!(
+                    "{}: marked absent but seen on the lab network",
+                    record.name
+                )),
+                (true, false) => Some(format!(
+                    "{}: marked present but not seen on the lab network",
+                    record.name
+                )),
+                _ => None,
+            }
+        })
+        .collect()
+}
 
-    let bot_user = ctx.http.get_current_user().await?;
-    let bot_avatar_url = bot_user
-        .avatar_url()
-        .unwrap_or_else(|| bot_user.default_avatar_url());
+async fn push_attendance_kpis(
+    settings: &Settings,
+    total_count: usize,
+    absent_count: usize,
+    late_count: usize,
+) {
+    let present = total_count - absent_count;
+    let attendance_percentage = if total_count > 0 {
+        (present as f64 / total_count as f64) * 100.0
+    } else {
+        0.0
+    };
 
-    let embed = CreateEmbed::new()
-        .title(format!("Presense Report - {}", today_date))
-        .url(TITLE_URL)
-        .author(
-            CreateEmbedAuthor::new("amD")
-                .url(AUTHOR_URL)
-                .icon_url(bot_avatar_url),
-        )
-        .color(Colour::RED)
-        .description("Uh-oh, seems like the lab is closed today! 🏖️ Everyone is absent!")
-        .timestamp(Utc::now());
+    push_kpis(
+        settings,
+        &[
+            Kpi::new("attendance_percentage", attendance_percentage),
+            Kpi::new("attendance_present", present as f64),
+            Kpi::new("attendance_absent", absent_count as f64),
+            Kpi::new("attendance_late", late_count as f64),
+        ],
+    )
+    .await;
+}
 
-    ChannelId::new(THE_LAB_CHANNEL_ID)
-        .send_message(&ctx.http, CreateMessage::new().embed(embed))
-        .await
-        .context("Failed to send lab closed message")?;
+async fn send_lab_closed_message(
+    ctx: SerenityContext,
+    data: &Data,
+    date: NaiveDate,
+) -> anyhow::Result<()> {
+    let theme = data.config.read().await.theme.clone();
+    let embed = lab_closed_embed(&ctx, &theme, date);
+
+    // There are no names to hide or split by group, every destination gets the same message.
+    deliver_report(
+        &ctx,
+        data,
+        ReportKind::Attendance,
+        date,
+        ids::channel(ChannelKind::Lab).get(),
+        |_| Some(CreateMessage::new().embed(embed.clone())),
+    )
+    .await
+    .context("Failed to send lab closed message")?;
 
     Ok(())
 }
 
+fn lab_closed_embed(ctx: &SerenityContext, theme: &ThemeConfig, date: NaiveDate) -> CreateEmbed {
+    let attendance_theme = &theme.attendance;
+    report_embed(
+        ctx,
+        &theme.embed,
+        format!("{} - {}", attendance_theme.title, format_date(date)),
+        attendance_theme.lab_closed_color,
+    )
+    .description(&attendance_theme.lab_closed_message)
+}
+
 async fn send_attendance_report(
     ctx: SerenityContext,
-    absent_list: Vec<AttendanceRecord>,
-    late_list: Vec<AttendanceRecord>,
-    total_count: usize,
+    data: &Data,
+    summary: &AttendanceSummary,
+    notes: &AttendanceNotes,
 ) -> anyhow::Result<()> {
-    let today_date = Utc::now().format("%B %d, %Y").to_string();
+    let theme = data.config.read().await.theme.clone();
+    let (embed, stats) = attendance_report_embed(&ctx, &theme, summary);
+    let description = format_report(summary, notes, stats.clone());
+    let date = summary.date;
+
+    deliver_report(
+        &ctx,
+        data,
+        ReportKind::Attendance,
+        date,
+        ids::channel(ChannelKind::Lab).get(),
+        |delivery| match delivery.detail {
+            ReportDetail::Full => Some(
+                CreateMessage::new()
+                    .embed(embed.clone().description(&description))
+                    .components(vec![attendance_report_buttons(date)]),
+            ),
+            ReportDetail::Stats => {
+                Some(CreateMessage::new().embed(embed.clone().description(&stats)))
+            }
+            ReportDetail::Group => {
+                warn!(
+                "Group excerpts aren't available for the attendance report, skipping channel {}",
+                delivery.channel_id
+            );
+                None
+            }
+        },
+    )
+    .await
+    .context("Failed to send attendance report")?;
 
-    let present = total_count - absent_list.len();
+    Ok(())
+}
+
+/// The report embed without a description, and the stats section every variant starts with.
+fn attendance_report_embed(
+    ctx: &SerenityContext,
+    theme: &ThemeConfig,
+    summary: &AttendanceSummary,
+) -> (CreateEmbed, String) {
+    let total_count = summary.total_count;
+    let present = total_count - summary.absent_list.len();
     let attendance_percentage = if total_count > 0 {
         (present as f32 / total_count as f32) * 100.0
     } else {
         0.0
     };
 
-    let bot_user = ctx.http.get_current_user().await?;
-    let bot_avatar_url = bot_user
-        .avatar_url()
-        .unwrap_or_else(|| bot_user.default_avatar_url());
-
+    let attendance_theme = &theme.attendance;
     let embed_color = if attendance_percentage > 75.0 {
-        Colour::DARK_GREEN
+        attendance_theme.high_attendance_color
     } else if attendance_percentage > 50.0 {
-        Colour::GOLD
+        attendance_theme.medium_attendance_color
     } else {
-        Colour::RED
+        attendance_theme.low_attendance_color
     };
 
-    let mut description = format!(
-        "# Stats\n- Present: {} ({}%)\n- Absent: {}\n- Late: {}\n\n",
+    let stats = format!(
+        "# {}\n- Present: {} ({}%)\n- Absent: {}\n- Late: {}\n\n",
+        attendance_theme.stats_header,
         present,
         attendance_percentage.round() as i32,
-        absent_list.len(),
-        late_list.len()
+        summary.absent_list.len(),
+        summary.late_list.len()
     );
 
-    description.push_str(&format_attendance_list("Absent", &absent_list));
-    description.push_str(&format_attendance_list("Late", &late_list));
-
-    let embed = CreateEmbed::new()
-        .title(format!("Presense Report - {}", today_date))
-        .url(TITLE_URL)
-        .author(
-            CreateEmbedAuthor::new("amD")
-                .url(AUTHOR_URL)
-                .icon_url(bot_avatar_url),
-        )
-        .color(embed_color)
-        .description(description)
-        .timestamp(Utc::now());
-
-    ChannelId::new(THE_LAB_CHANNEL_ID)
-        .send_message(&ctx.http, CreateMessage::new().embed(embed))
-        .await
-        .context("Failed to send attendance report")?;
+    let embed = report_embed(
+        ctx,
+        &theme.embed,
+        format!("{} - {}", attendance_theme.title, format_date(summary.date)),
+        embed_color,
+    );
+    (embed, stats)
+}
 
-    Ok(())
+/// The description of the full report: `stats` followed by the lists and notes.
+fn format_report(summary: &AttendanceSummary, notes: &AttendanceNotes, stats: String) -> String {
+    let mut description = stats;
+
+    description.push_str(&format_attendance_list("Absent", &summary.absent_list));
+    description.push_str(&format_attendance_list("Late", &summary.late_list));
+    if !notes.manual_checkins.is_empty() {
+        description.push_str(&format!(
+            "# Manual check-ins\n{}\n",
+            notes.manual_checkins.join(", ")
+        ));
+    }
+    if !notes.discrepancies.is_empty() {
+        description.push_str("# Discrepancies\n");
+        for discrepancy in &notes.discrepancies {
+            description.push_str(&format!("- {}\n", discrepancy));
+        }
+    }
+    description
 }
 
 fn format_attendance_list(title: &str, list: &[AttendanceRecord]) -> String {
@@ -209,22 +662,9 @@ fn format_attendance_list(title: &str, list: &[AttendanceRecord]) -> String {
     result
 }
 
-fn parse_time(time_str: &str) -> Result<DateTime<Local>, ParseError> {
+fn parse_time(time_str: &str) -> Result<DateTime<Tz>, ParseError> {
     let time_only = time_str.split('.').next().unwrap();
     let naive_time = NaiveTime::parse_from_str(time_only, "%H:%M:%S")?;
-    let now = Local::now();
-
-    let result = Local
-        .with_ymd_and_hms(
-            now.year(),
-            now.month(),
-            now.day(),
-            naive_time.hour(),
-            naive_time.minute(),
-            naive_time.second(),
-        )
-        .single()
-        .expect("Valid datetime must be created");
-
-    Ok(result)
+    let timezone = timezone();
+    Ok(local_to_utc(local_today(), naive_time, timezone).with_timezone(&timezone))
 }
@@ -15,15 +15,91 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
-mod lab_attendance;
-mod status_update;
+mod activity_flush;
+mod announcements;
+pub mod attendance_awards;
+mod attendance_nudge;
+mod backup;
+mod channel_locks;
+mod channel_topics;
+mod cleanup;
+mod command_usage;
+mod consistency_awards;
+pub mod duplicate_updates;
+mod events;
+mod feeds;
+pub mod group_access;
+mod holidays;
+mod invite_summary;
+mod kudos_tally;
+pub mod lab_attendance;
+pub mod occupancy;
+pub mod practice;
+mod presence;
+mod quiet_hours;
+mod reset_approvals;
+mod resource_sharing;
+mod role_drift;
+mod semester;
+mod sessions;
+mod spotlight;
+pub mod status_update;
+pub mod summaries;
+pub mod update_quality;
+mod weekly_summary;
+mod xp_flush;
 
+use activity_flush::GroupActivityFlush;
+use announcements::AnnouncementRsvps;
 use anyhow::Result;
 use async_trait::async_trait;
+use attendance_awards::AttendanceAwards;
+use attendance_nudge::AttendanceNudge;
+use backup::NightlyBackup;
+use channel_locks::ChannelLockSchedule;
+use channel_topics::ChannelTopics;
+use chrono::{DateTime, NaiveDate, Utc};
+use cleanup::MessageCleanup;
+use command_usage::CommandUsageReport;
+use consistency_awards::ConsistencyAwards;
+use events::ScheduledEventSync;
+use feeds::FeedAnnouncements;
+use group_access::GroupChannelAccess;
+use holidays::HolidayImport;
+use invite_summary::InviteSummary;
+use kudos_tally::KudosTally;
 use lab_attendance::PresenseReport;
+use occupancy::LabOccupancy;
+use practice::PracticeProblemPoster;
+use presence::PresenceRotation;
+use quiet_hours::QuietHoursFlush;
+use reset_approvals::ResetApprovalTimeouts;
+use resource_sharing::ResourceSharingCheck;
+use role_drift::RoleDriftAudit;
+use semester::SemesterAnnouncements;
+use serenity::all::{ChannelId, CreateMessage};
 use serenity::client::Context;
-use status_update::StatusUpdateCheck;
+use sessions::SessionReminders;
+use spotlight::NewMemberSpotlight;
+use status_update::{StatusUpdateCheck, StatusUpdatePreview};
+use std::fmt;
+use summaries::NightlySummaries;
 use tokio::time::Duration;
+use tracing::warn;
+use weekly_summary::WeeklySummary;
+use xp_flush::MessageXpFlush;
+
+use crate::{
+    config::Config, graphql::breaker::root_unavailable_for, scheduler::retry_attempt, Data,
+};
+
+/// What the scheduler does when a task is due while its previous run is still in flight.
+pub enum OverlapPolicy {
+    /// Skip this run, the task runs again at its next slot.
+    Skip,
+    /// Wait for the previous run to finish, then run.
+    Queue,
+}
 
 /// A [`Task`] is any job that needs to be executed on a regular basis.
 /// A task has a function [`Task::run_in`] that returns the time till the
@@ -32,11 +108,127 @@ use tokio::time::Duration;
 pub trait Task: Send + Sync {
     fn name(&self) -> &str;
     fn run_in(&self) -> Duration;
-    async fn run(&self, ctx: Context) -> Result<()>;
+    /// Time till the next run if the task were scheduled at `hour:minute` instead of its
+    /// default time. Tasks that run on an interval return [`None`] and can't be rescheduled.
+    fn run_in_at(&self, _hour: u32, _minute: u32) -> Option<Duration> {
+        None
+    }
+    /// Names of tasks that must have succeeded today before this task runs. When they
+    /// share a slot, the scheduler runs the dependencies first.
+    fn depends_on(&self) -> &[&'static str] {
+        &[]
+    }
+    /// Whether the task reads message content, it is skipped while message scanning is
+    /// disabled.
+    fn reads_messages(&self) -> bool {
+        false
+    }
+    /// Whether the task checks members, it is paused outside the configured semesters and
+    /// on observed holidays.
+    fn follows_semester(&self) -> bool {
+        false
+    }
+    /// The day a run starting at `now` reports on, which decides whether it falls in the
+    /// semester. Checks that run the morning after the day they judge override this.
+    fn evaluated_date(&self, config: &Config, now: DateTime<Utc>) -> NaiveDate {
+        now.with_timezone(&config.bot.timezone).date_naive()
+    }
+    fn overlap_policy(&self) -> OverlapPolicy {
+        OverlapPolicy::Skip
+    }
+    async fn run(&self, ctx: Context, data: &Data) -> Result<()>;
+    /// What a canary standing by runs in place of [`Task::run`]: the computation without
+    /// sending anything or changing Root, to show the new version works before it takes
+    /// over. Its storage writes are never persisted.
+    async fn dry_run(&self, _ctx: Context, _data: &Data) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Returned from [`Task::run`] to have the scheduler run the task again after `after`
+/// instead of at its next slot. A task retried too often counts as failed.
+#[derive(Debug)]
+pub struct RetryLater {
+    pub after: Duration,
+    pub reason: String,
+}
+
+impl fmt::Display for RetryLater {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, retrying in {} seconds",
+            self.reason,
+            self.after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for RetryLater {}
+
+/// Holds a report back while Root's circuit breaker is open, telling `channel_id` that it
+/// was deferred. The scheduler runs the task again once the breaker lets calls through.
+pub async fn defer_while_root_unavailable(
+    ctx: &Context,
+    channel_id: u64,
+    report: &str,
+) -> Result<()> {
+    let Some(retry_in) = root_unavailable_for() else {
+        return Ok(());
+    };
+    if retry_attempt() == 0 {
+        warn!("Root unavailable, deferring the {}", report);
+        let message =
+            CreateMessage::new().content(format!("Root unavailable, {} deferred.", report));
+        if let Err(e) = ChannelId::new(channel_id)
+            .send_message(&ctx.http, message)
+            .await
+        {
+            warn!("Failed to announce deferred {}: {}", report, e);
+        }
+    }
+    Err(RetryLater {
+        after: retry_in,
+        reason: format!("Root unavailable, {} deferred", report),
+    }
+    .into())
 }
 
 /// Analogous to [`crate::commands::get_commands`], every task that is defined
 /// must be included in the returned vector in order for it to be scheduled.
 pub fn get_tasks() -> Vec<Box<dyn Task>> {
-    vec![Box::new(StatusUpdateCheck), Box::new(PresenseReport)]
+    vec![
+        Box::new(StatusUpdatePreview),
+        Box::new(StatusUpdateCheck),
+        Box::new(ResetApprovalTimeouts),
+        Box::new(AttendanceNudge),
+        Box::new(PresenseReport),
+        Box::new(ResourceSharingCheck),
+        Box::new(WeeklySummary),
+        Box::new(FeedAnnouncements),
+        Box::new(PracticeProblemPoster),
+        Box::new(PresenceRotation::default()),
+        Box::new(NightlySummaries),
+        Box::new(ConsistencyAwards),
+        Box::new(AttendanceAwards),
+        Box::new(SessionReminders),
+        Box::new(AnnouncementRsvps),
+        Box::new(NightlyBackup),
+        Box::new(ScheduledEventSync),
+        Box::new(LabOccupancy::default()),
+        Box::new(QuietHoursFlush),
+        Box::new(MessageXpFlush),
+        Box::new(GroupActivityFlush),
+        Box::new(InviteSummary),
+        Box::new(ChannelLockSchedule),
+        Box::new(KudosTally),
+        Box::new(MessageCleanup),
+        Box::new(CommandUsageReport),
+        Box::new(ChannelTopics),
+        Box::new(SemesterAnnouncements),
+        Box::new(RoleDriftAudit),
+        Box::new(NewMemberSpotlight),
+        Box::new(HolidayImport),
+        Box::new(GroupChannelAccess),
+    ]
 }
@@ -0,0 +1,111 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::sync::Mutex;
+
+use chrono::{NaiveTime, TimeDelta};
+use serenity::all::{ChannelId, Context, EditChannel};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::debug;
+
+use super::{lab_attendance::fetch_and_remember_attendance, Task};
+use crate::{
+    checkins::{manual_checkins, merge_manual_checkins},
+    graphql::models::AttendanceRecord,
+    storage::Storage,
+    utils::time::local_now,
+    Data,
+};
+
+/// Members the attendance hardware hasn't seen for this long have left the lab.
+const LEFT_AFTER: TimeDelta = TimeDelta::minutes(20);
+
+/// Shows how many members are in the lab in the name of a locked channel. Discord only
+/// allows two renames per channel every ten minutes, so this runs no more often than that
+/// and skips the rename when the count hasn't changed.
+#[derive(Default)]
+pub struct LabOccupancy {
+    last_name: Mutex<Option<String>>,
+}
+
+#[async_trait]
+impl Task for LabOccupancy {
+    fn name(&self) -> &str {
+        "Lab Occupancy"
+    }
+
+    fn run_in(&self) -> Duration {
+        Duration::from_secs(10 * 60)
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let config = data.config.read().await.attendance.clone();
+        let Some(channel_id) = config.occupancy_channel_id else {
+            return Ok(());
+        };
+
+        let now = local_now().time();
+        let name = if now < config.lab_opens_at || now >= config.lab_closes_at {
+            String::from("🔴 Lab: closed")
+        } else {
+            match members_inside(&data.storage).await?.len() {
+                0 => String::from("🔴 Lab: empty"),
+                inside => format!("🟢 Lab: {} inside", inside),
+            }
+        };
+        if self
+            .last_name
+            .lock()
+            .expect("Occupancy lock poisoned")
+            .as_ref()
+            == Some(&name)
+        {
+            return Ok(());
+        }
+
+        debug!("Renaming occupancy channel to {}", name);
+        ChannelId::new(channel_id)
+            .edit(&ctx.http, EditChannel::new().name(&name))
+            .await?;
+        *self.last_name.lock().expect("Occupancy lock poisoned") = Some(name);
+        Ok(())
+    }
+}
+
+/// Members in the lab right now: checked in today, on the attendance hardware or
+/// manually, and not gone unseen for [`LEFT_AFTER`].
+pub async fn members_inside(storage: &Storage) -> anyhow::Result<Vec<AttendanceRecord>> {
+    let mut attendance = fetch_and_remember_attendance(storage).await?;
+    let now = local_now();
+    merge_manual_checkins(
+        &mut attendance,
+        &manual_checkins(storage, now.date_naive()).await?,
+    );
+    attendance.retain(|record| {
+        // Manual check-ins and fresh arrivals have no last-seen time yet.
+        let last_seen = record.time_out.as_deref().and_then(parse_root_time);
+        record.is_present && last_seen.is_none_or(|seen| now.time() - seen <= LEFT_AFTER)
+    });
+    Ok(attendance)
+}
+
+/// Root reports times in the bot's timezone as `HH:MM:SS` with optional fractional seconds.
+fn parse_root_time(time: &str) -> Option<NaiveTime> {
+    let time = time.split('.').next()?;
+    NaiveTime::parse_from_str(time, "%H:%M:%S").ok()
+}
@@ -0,0 +1,266 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Context as _};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    AutoArchiveDuration, ChannelId, Context, CreateEmbed, CreateMessage, CreateThread, Permissions,
+};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{debug, warn};
+
+use super::Task;
+use crate::{
+    config::{PracticeConfig, PracticeProblem},
+    privacy::erased_members,
+    storage::Storage,
+    utils::{
+        permissions::{check_permissions, POST_EMBEDS},
+        scan::scan_channels,
+        time::{format_day, format_month, local_today, time_until},
+    },
+    Data,
+};
+
+const PRACTICE_DAYS_KEY: &str = "practice.days";
+const CODEFORCES_PROBLEMS_URL: &str = "https://codeforces.com/api/problemset.problems";
+const LEADERBOARD_SIZE: usize = 10;
+
+/// A posted problem and who replied in its solutions thread.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PracticeDay {
+    pub date: NaiveDate,
+    pub title: String,
+    pub thread_id: u64,
+    pub participants: Vec<u64>,
+}
+
+/// Posts a daily practice problem with a thread for solutions.
+pub struct PracticeProblemPoster;
+
+#[async_trait]
+impl Task for PracticeProblemPoster {
+    fn name(&self) -> &str {
+        "Practice Problem"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(9, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        post_practice_problem(ctx, data).await
+    }
+}
+
+async fn post_practice_problem(ctx: Context, data: &Data) -> anyhow::Result<()> {
+    let config = data.config.read().await.practice.clone();
+    let Some(channel_id) = config.channel_id.map(ChannelId::new) else {
+        debug!("No practice channel configured, skipping");
+        return Ok(());
+    };
+    check_permissions(
+        &ctx,
+        channel_id.get(),
+        POST_EMBEDS | Permissions::CREATE_PUBLIC_THREADS,
+    )?;
+    let today = local_today();
+
+    if let Err(e) = tally_previous_day(&ctx, &data.storage).await {
+        warn!("Failed to tally practice participants: {:?}", e);
+    }
+    if today.day() == 1 {
+        let last_month = today - chrono::Duration::days(1);
+        let leaderboard =
+            monthly_leaderboard(&data.storage, last_month.year(), last_month.month()).await?;
+        let embed = leaderboard_embed(last_month, &leaderboard);
+        channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+    }
+
+    let problem = pick_problem(&config, today).await?;
+    let embed = CreateEmbed::new()
+        .title(format!("Problem of the Day: {}", problem.title))
+        .url(&problem.url)
+        .description("Share your approach and solutions in the thread below!");
+    let message = channel_id
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await
+        .context("Failed to post practice problem")?;
+    let thread = channel_id
+        .create_thread_from_message(
+            &ctx.http,
+            message.id,
+            CreateThread::new(format!("Solutions — {}", format_day(today)))
+                .auto_archive_duration(AutoArchiveDuration::OneDay),
+        )
+        .await
+        .context("Failed to create solutions thread")?;
+
+    let day = PracticeDay {
+        date: today,
+        title: problem.title,
+        thread_id: thread.id.get(),
+        participants: Vec::new(),
+    };
+    data.storage
+        .update(PRACTICE_DAYS_KEY, |days: &mut Vec<PracticeDay>| {
+            days.push(day)
+        })
+        .await
+}
+
+/// Records everyone who posted in the most recent solutions thread.
+async fn tally_previous_day(ctx: &Context, storage: &Storage) -> anyhow::Result<()> {
+    let days: Vec<PracticeDay> = storage.get(PRACTICE_DAYS_KEY).await?;
+    let Some(previous) = days.last() else {
+        return Ok(());
+    };
+
+    let since = previous
+        .date
+        .and_hms_opt(0, 0, 0)
+        .expect("Valid timestamp")
+        .and_utc();
+    let messages = scan_channels(ctx, &[ChannelId::new(previous.thread_id)], since, |m| {
+        !m.author.bot
+    })
+    .await?;
+    let erased = erased_members(storage).await?;
+    let participants: HashSet<u64> = messages
+        .iter()
+        .filter(|m| !erased.contains(&m.author.id.to_string()))
+        .map(|m| m.author.id.get())
+        .collect();
+
+    let thread_id = previous.thread_id;
+    storage
+        .update(PRACTICE_DAYS_KEY, |days: &mut Vec<PracticeDay>| {
+            if let Some(day) = days.iter_mut().find(|day| day.thread_id == thread_id) {
+                day.participants = participants.into_iter().collect();
+            }
+        })
+        .await
+}
+
+/// Drops the member from the participants of every practice day.
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(PRACTICE_DAYS_KEY, |days: &mut Vec<PracticeDay>| {
+            for day in days {
+                day.participants.retain(|id| *id != user_id);
+            }
+        })
+        .await
+}
+
+/// Number of problems each user participated in during the given month, most first.
+pub async fn monthly_leaderboard(
+    storage: &Storage,
+    year: i32,
+    month: u32,
+) -> anyhow::Result<Vec<(u64, usize)>> {
+    let days: Vec<PracticeDay> = storage.get(PRACTICE_DAYS_KEY).await?;
+    let mut tally: HashMap<u64, usize> = HashMap::new();
+    for day in days
+        .iter()
+        .filter(|day| day.date.year() == year && day.date.month() == month)
+    {
+        for participant in &day.participants {
+            *tally.entry(*participant).or_default() += 1;
+        }
+    }
+
+    let mut leaderboard: Vec<_> = tally.into_iter().collect();
+    leaderboard.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    leaderboard.truncate(LEADERBOARD_SIZE);
+    Ok(leaderboard)
+}
+
+pub fn leaderboard_embed(month: NaiveDate, leaderboard: &[(u64, usize)]) -> CreateEmbed {
+    let mut description = String::new();
+    for (rank, (user_id, count)) in leaderboard.iter().enumerate() {
+        description.push_str(&format!(
+            "{}. <@{}> - {} problems\n",
+            rank + 1,
+            user_id,
+            count
+        ));
+    }
+    if description.is_empty() {
+        description.push_str("Nobody has participated yet.");
+    }
+
+    CreateEmbed::new()
+        .title(format!("Practice Leaderboard - {}", format_month(month)))
+        .description(description)
+}
+
+/// Rotates through the configured pool, falling back to Codeforces when it's empty.
+async fn pick_problem(
+    config: &PracticeConfig,
+    today: NaiveDate,
+) -> anyhow::Result<PracticeProblem> {
+    let day_index = today.num_days_from_ce() as usize;
+    if !config.problems.is_empty() {
+        return Ok(config.problems[day_index % config.problems.len()].clone());
+    }
+
+    let response: serde_json::Value = reqwest::get(CODEFORCES_PROBLEMS_URL)
+        .await
+        .context("Failed to fetch Codeforces problems")?
+        .json()
+        .await
+        .context("Failed to parse Codeforces response")?;
+    let problems: Vec<&serde_json::Value> = response["result"]["problems"]
+        .as_array()
+        .context("Missing 'result.problems' in Codeforces response")?
+        .iter()
+        .filter(|problem| {
+            problem["rating"].as_u64().is_some_and(|rating| {
+                rating >= config.codeforces_min_rating && rating <= config.codeforces_max_rating
+            })
+        })
+        .collect();
+    if problems.is_empty() {
+        return Err(anyhow!(
+            "No Codeforces problems in the configured rating range"
+        ));
+    }
+
+    let problem = problems[day_index % problems.len()];
+    Ok(PracticeProblem {
+        title: problem["name"]
+            .as_str()
+            .unwrap_or("Codeforces Problem")
+            .to_string(),
+        url: format!(
+            "https://codeforces.com/problemset/problem/{}/{}",
+            problem["contestId"],
+            problem["index"].as_str().unwrap_or_default()
+        ),
+    })
+}
@@ -0,0 +1,78 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serenity::all::{ActivityData, Context};
+use serenity::async_trait;
+use tokio::time::Duration;
+
+use super::Task;
+use crate::{
+    history::{recent_attendance_days, recent_status_update_days},
+    utils::time::local_now,
+    Data,
+};
+
+/// Rotates the bot's activity through a few live stats every few minutes.
+#[derive(Default)]
+pub struct PresenceRotation {
+    index: AtomicUsize,
+}
+
+#[async_trait]
+impl Task for PresenceRotation {
+    fn name(&self) -> &str {
+        "Presence Rotation"
+    }
+
+    fn run_in(&self) -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let activities = build_activities(data).await?;
+        let index = self.index.fetch_add(1, Ordering::Relaxed) % activities.len();
+        ctx.set_activity(activities.into_iter().nth(index));
+        Ok(())
+    }
+}
+
+async fn build_activities(data: &Data) -> anyhow::Result<Vec<ActivityData>> {
+    let mut activities = vec![ActivityData::custom(format!(
+        "Next report at 5:00 AM {}",
+        local_now().format("%Z")
+    ))];
+
+    if let Some(day) = recent_status_update_days(&data.storage, 1).await?.pop() {
+        let active_streaks = day.members.iter().filter(|m| m.current_streak > 0).count();
+        activities.push(ActivityData::watching(format!(
+            "{} streaks 🔥",
+            active_streaks
+        )));
+    }
+
+    if let Some(day) = recent_attendance_days(&data.storage, 1).await?.pop() {
+        activities.push(ActivityData::watching(format!(
+            "the lab: {:.0}% attendance on {}",
+            day.attendance_percentage(),
+            day.date.format("%b %d")
+        )));
+    }
+
+    Ok(activities)
+}
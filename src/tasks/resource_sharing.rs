@@ -0,0 +1,111 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashSet;
+
+use chrono::{Utc, Weekday};
+use serenity::all::{ChannelId, Context, Message};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{debug, info};
+
+use super::Task;
+use crate::{
+    graphql::queries::fetch_members,
+    history::{record_resource_week, ResourceWeek},
+    utils::{
+        scan::scan_channels,
+        time::{local_today, time_until_weekday},
+    },
+    Data,
+};
+
+/// Checks that every member shared at least one learning resource during the past week.
+/// Runs just before the weekly summary, which reports the stored result.
+pub struct ResourceSharingCheck;
+
+#[async_trait]
+impl Task for ResourceSharingCheck {
+    fn name(&self) -> &str {
+        "Resource Sharing Check"
+    }
+
+    fn reads_messages(&self) -> bool {
+        true
+    }
+
+    fn follows_semester(&self) -> bool {
+        true
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until_weekday(Weekday::Sun, 18, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until_weekday(Weekday::Sun, hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        resource_sharing_check(ctx, data).await
+    }
+}
+
+async fn resource_sharing_check(ctx: Context, data: &Data) -> anyhow::Result<()> {
+    let Some(channel_id) = data.config.read().await.resources.channel_id else {
+        debug!("No resources channel configured, skipping");
+        return Ok(());
+    };
+
+    let since = Utc::now() - chrono::Duration::days(7);
+    let resources = scan_channels(
+        &ctx,
+        &[ChannelId::new(channel_id)],
+        since,
+        is_valid_resource,
+    )
+    .await?;
+    let sharers: HashSet<String> = resources
+        .iter()
+        .map(|message| message.author.id.to_string())
+        .collect();
+
+    let members = fetch_members().await?;
+    let (shared, missing): (Vec<_>, Vec<_>) = members
+        .into_iter()
+        .partition(|member| sharers.contains(&member.discord_id));
+    info!(
+        "{} members shared resources this week, {} did not",
+        shared.len(),
+        missing.len()
+    );
+
+    let week = ResourceWeek {
+        week_ending: local_today(),
+        shared: shared.into_iter().map(|member| member.name).collect(),
+        missing: missing.into_iter().map(|member| member.name).collect(),
+    };
+    record_resource_week(&data.storage, week).await
+}
+
+/// A resource is any message carrying a link or an attachment.
+fn is_valid_resource(msg: &Message) -> bool {
+    !msg.author.bot
+        && (msg.content.contains("http://")
+            || msg.content.contains("https://")
+            || !msg.attachments.is_empty())
+}
@@ -0,0 +1,122 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use chrono::{Utc, Weekday};
+use serenity::all::{ChannelId, Context, CreateAllowedMentions, CreateMessage};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::info;
+
+use super::{status_update::tracked_members, Task};
+use crate::{
+    role_drift::{apply_button, find_drift, save_report, DriftReport},
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::time_until_weekday,
+    },
+    Data,
+};
+
+const DRIFT_COLOR: u32 = 0xf59e0b;
+/// Longest list of drifted members that fits in the embed description.
+const DESCRIPTION_LENGTH: usize = 4000;
+
+/// Every Monday, flags members whose group role on Discord doesn't match their Root group.
+pub struct RoleDriftAudit;
+
+#[async_trait]
+impl Task for RoleDriftAudit {
+    fn name(&self) -> &str {
+        "Role Drift Audit"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until_weekday(Weekday::Mon, 11, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until_weekday(Weekday::Mon, hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let config = data.config.read().await.clone();
+        let Some(channel_id) = config.groups.audit_channel_id.or(config.bot.ops_channel_id) else {
+            return Ok(());
+        };
+        if config.groups.roles.is_empty() {
+            return Ok(());
+        }
+
+        let guild_id = ChannelId::new(channel_id)
+            .to_channel(&ctx.http)
+            .await?
+            .guild()
+            .context("The role audit channel isn't in a server")?
+            .guild_id;
+        let members = tracked_members(data).await?;
+        let drifts = find_drift(&ctx, guild_id, &members, &config.groups).await;
+        info!("Role audit found {} drifted members", drifts.len());
+        if drifts.is_empty() {
+            return Ok(());
+        }
+
+        let mut description = format!(
+            "{} members have group roles that don't match Root.\n",
+            drifts.len()
+        );
+        for (shown, drift) in drifts.iter().enumerate() {
+            let mut line = format!("- <@{}> (Group {}):", drift.user_id, drift.group_id);
+            if let Some(role) = drift.missing_role {
+                line.push_str(&format!(" missing <@&{}>", role));
+            }
+            for role in &drift.extra_roles {
+                line.push_str(&format!(" extra <@&{}>", role));
+            }
+            line.push('\n');
+            if description.len() + line.len() > DESCRIPTION_LENGTH {
+                description.push_str(&format!("…and {} more\n", drifts.len() - shown));
+                break;
+            }
+            description.push_str(&line);
+        }
+
+        let report = DriftReport {
+            id: Utc::now().timestamp() as u64,
+            guild_id: guild_id.get(),
+            drifts,
+            applied_by: None,
+        };
+        save_report(&data.storage, &report).await?;
+
+        let embed = report_embed(&ctx, &config.theme.embed, "Group Role Audit", DRIFT_COLOR)
+            .description(description);
+        check_permissions(&ctx, channel_id, POST_EMBEDS)?;
+        ChannelId::new(channel_id)
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .embed(embed)
+                    .components(vec![apply_button(report.id)])
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
+            .await
+            .context("Failed to send the role audit")?;
+        Ok(())
+    }
+}
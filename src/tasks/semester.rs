@@ -0,0 +1,109 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{Duration as ChronoDuration, NaiveDate};
+use serenity::all::{ChannelId, Context, CreateMessage, Permissions};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::info;
+
+use super::Task;
+use crate::{
+    config::SemesterConfig,
+    ids::{self, ChannelKind},
+    semester::{mark_announced, next_term, previous_term, term_on},
+    utils::{
+        permissions::check_permissions,
+        time::{format_day, local_today, time_until},
+    },
+    Data,
+};
+
+/// A start or end missed while the bot was down is still announced this many days late.
+const ANNOUNCE_WITHIN_DAYS: i64 = 7;
+
+/// Announces when a term starts and the checks resume, and when it ends and they pause.
+pub struct SemesterAnnouncements;
+
+#[async_trait]
+impl Task for SemesterAnnouncements {
+    fn name(&self) -> &str {
+        "Semester Announcements"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(8, 00)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let config = data.config.read().await.semester.clone();
+        let today = local_today();
+        let Some((key, content)) = announcement(&config, today) else {
+            return Ok(());
+        };
+        if !mark_announced(&data.storage, key).await? {
+            return Ok(());
+        }
+
+        info!("Announcing: {}", content);
+        let channel_id = config
+            .announce_channel_id
+            .unwrap_or(ids::channel(ChannelKind::StatusUpdate).get());
+        check_permissions(&ctx, channel_id, Permissions::SEND_MESSAGES)?;
+        ChannelId::new(channel_id)
+            .send_message(&ctx.http, CreateMessage::new().content(content))
+            .await?;
+        Ok(())
+    }
+}
+
+/// The announcement due on `today` and the key it is recorded under, if any.
+fn announcement(config: &SemesterConfig, today: NaiveDate) -> Option<(String, String)> {
+    let recent = |date: NaiveDate| date <= today && today - date < days(ANNOUNCE_WITHIN_DAYS);
+
+    if let Some(term) = term_on(config, today).filter(|term| recent(term.start)) {
+        let content = format!(
+            "{} has started! Status update and attendance checks are back on, running \
+             until {}.",
+            term.name,
+            format_day(term.end)
+        );
+        return Some((format!("start:{}", term.name), content));
+    }
+
+    let term = previous_term(config, today).filter(|term| recent(term.end + days(1)))?;
+    if term_on(config, today).is_some() {
+        return None;
+    }
+    let resumes = match next_term(config, today) {
+        Some(next) => format!("until {} starts on {}", next.name, format_day(next.start)),
+        None => String::from("until the next term"),
+    };
+    let content = format!(
+        "{} is over, enjoy the break! Status update and attendance checks are paused {}.",
+        term.name, resumes
+    );
+    Some((format!("end:{}", term.name), content))
+}
+
+fn days(days: i64) -> ChronoDuration {
+    ChronoDuration::days(days)
+}
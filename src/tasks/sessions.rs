@@ -0,0 +1,141 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{Duration as ChronoDuration, Utc};
+use serenity::all::{ChannelId, Context, CreateMessage, CreatePoll, CreatePollAnswer, Permissions};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::debug;
+
+use super::Task;
+use crate::{
+    points,
+    preferences::{allows, Notification},
+    sessions::{sessions, update_session, Session, SessionStatus},
+    utils::{
+        broadcast::broadcast,
+        permissions::check_permissions,
+        time::{discord_timestamp, TimestampStyle},
+    },
+    Data,
+};
+
+/// How long the post-session feedback poll stays open.
+const FEEDBACK_POLL_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Reminds attendees before approved talks and posts a feedback poll afterwards.
+pub struct SessionReminders;
+
+#[async_trait]
+impl Task for SessionReminders {
+    fn name(&self) -> &str {
+        "Session Reminders"
+    }
+
+    fn run_in(&self) -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let (config, per_session) = {
+            let config = data.config.read().await;
+            (config.sessions.clone(), config.points.per_session)
+        };
+        let now = Utc::now();
+
+        for session in sessions(&data.storage).await? {
+            if session.status != SessionStatus::Approved {
+                continue;
+            }
+            let ends_at = session.starts_at + ChronoDuration::minutes(config.duration_minutes);
+            let remind_at = session.starts_at - ChronoDuration::minutes(config.reminder_minutes);
+
+            if !session.reminder_sent && now >= remind_at && now < ends_at {
+                send_reminders(&ctx, data, &session).await;
+                update_session(&data.storage, session.id, |s| s.reminder_sent = true).await?;
+            }
+
+            if !session.feedback_sent && now >= ends_at {
+                if let Some(channel_id) = config.channel_id {
+                    post_feedback_poll(&ctx, channel_id, &session).await?;
+                }
+                update_session(&data.storage, session.id, |s| s.feedback_sent = true).await?;
+                let participants = session.attendees.iter().copied();
+                points::award(
+                    &data.storage,
+                    &format!("session:{}", session.id),
+                    participants.chain([session.speaker_id]),
+                    per_session,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn send_reminders(ctx: &Context, data: &Data, session: &Session) {
+    debug!(
+        "Reminding {} attendees of session #{}",
+        session.attendees.len(),
+        session.id
+    );
+    let content = format!(
+        "Reminder: \"{}\" starts {}.",
+        session.title,
+        discord_timestamp(session.starts_at, TimestampStyle::Relative)
+    );
+    let recipients = session
+        .attendees
+        .iter()
+        .chain(std::iter::once(&session.speaker_id));
+    let mut messages = Vec::new();
+    for user_id in recipients {
+        if !allows(&data.storage, *user_id, Notification::Reminders).await {
+            continue;
+        }
+        messages.push((*user_id, CreateMessage::new().content(&content)));
+    }
+    let label = format!("Reminders for session #{}", session.id);
+    broadcast(ctx, data, &label, messages).await;
+}
+
+async fn post_feedback_poll(
+    ctx: &Context,
+    channel_id: u64,
+    session: &Session,
+) -> anyhow::Result<()> {
+    let answers = ["Loved it", "It was good", "It was okay", "Not for me"]
+        .into_iter()
+        .map(|text| CreatePollAnswer::new().text(text))
+        .collect();
+    let poll = CreatePoll::new()
+        .question(format!("How was \"{}\"?", session.title))
+        .answers(answers)
+        .duration(FEEDBACK_POLL_DURATION);
+    check_permissions(
+        ctx,
+        channel_id,
+        Permissions::SEND_MESSAGES | Permissions::SEND_POLLS,
+    )?;
+    ChannelId::new(channel_id)
+        .send_message(&ctx.http, CreateMessage::new().poll(poll))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use chrono::{Datelike, Weekday};
+use serenity::all::{ChannelId, Context, CreateAllowedMentions, CreateMessage};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::info;
+
+use super::Task;
+use crate::{
+    spotlight::{prompt_new_members, restore_intros, spotlight_embeds, take_intros},
+    utils::{
+        permissions::{check_permissions, POST_EMBEDS},
+        time::{local_now, time_until},
+    },
+    Data,
+};
+
+/// Asks newcomers for an intro every evening and posts the week's intros on Fridays.
+pub struct NewMemberSpotlight;
+
+#[async_trait]
+impl Task for NewMemberSpotlight {
+    fn name(&self) -> &str {
+        "New Member Spotlight"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(17, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let config = data.config.read().await.clone();
+        let Some(channel_id) = config.spotlight.channel_id else {
+            return Ok(());
+        };
+        prompt_new_members(&ctx, data).await?;
+
+        let today = local_now();
+        if today.weekday() != Weekday::Fri {
+            return Ok(());
+        }
+        let intros = take_intros(&data.storage).await?;
+        if intros.is_empty() {
+            return Ok(());
+        }
+        if let Err(e) = check_permissions(&ctx, channel_id, POST_EMBEDS) {
+            let featured: Vec<u64> = intros.iter().map(|intro| intro.user_id).collect();
+            restore_intros(&data.storage, &featured).await?;
+            return Err(e);
+        }
+
+        // Each embed goes in a message of its own to stay under Discord's size limits.
+        let mut embeds = spotlight_embeds(&intros).into_iter();
+        while let Some((featured, embed)) = embeds.next() {
+            let message = CreateMessage::new()
+                .embed(embed)
+                .allowed_mentions(CreateAllowedMentions::new());
+            if let Err(e) = ChannelId::new(channel_id)
+                .send_message(&ctx.http, message)
+                .await
+            {
+                let unsent: Vec<u64> = featured
+                    .into_iter()
+                    .chain(embeds.flat_map(|(user_ids, _)| user_ids))
+                    .collect();
+                restore_intros(&data.storage, &unsent).await?;
+                return Err(e).context("Failed to post the new members spotlight");
+            }
+        }
+        info!("Featured {} new members in the spotlight", intros.len());
+        Ok(())
+    }
+}
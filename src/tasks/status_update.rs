@@ -15,22 +15,54 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
 use serenity::all::{
-    CacheHttp, ChannelId, Context, CreateEmbed, CreateMessage, GetMessages, Message,
+    CacheHttp, ChannelId, Context, CreateAllowedMentions, CreateEmbed, CreateMessage, GuildId,
+    Mentionable, Message, MessageUpdateEvent, RoleId, UserId,
 };
 use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{debug, info, warn};
 
-use super::Task;
-use crate::graphql::models::{Member, StreakWithMemberId};
-use crate::graphql::queries::{fetch_members, fetch_streaks, increment_streak, reset_streak};
-use crate::ids::{
-    GROUP_FOUR_CHANNEL_ID, GROUP_ONE_CHANNEL_ID, GROUP_THREE_CHANNEL_ID, GROUP_TWO_CHANNEL_ID,
-    STATUS_UPDATE_CHANNEL_ID,
+use super::{
+    defer_while_root_unavailable,
+    duplicate_updates::{find_duplicates, flags_for, DuplicateFlag},
+    update_quality::review_updates,
+    OverlapPolicy, Task,
 };
-use crate::utils::time::time_until;
+use crate::appeals::{notify_defaulters, DeliveryReceipt};
+use crate::config::{
+    Config, GroupMentors, ReportDetail, ReportKind, StatusUpdateConfig, StatusUpdateTheme,
+};
+use crate::graphql::models::{Member, Streak, StreakWithMemberId};
+use crate::graphql::queries::{fetch_members, fetch_streaks, push_status_update_stats};
+use crate::history::{
+    previous_status_update_day, recent_status_update_days, record_status_update_day,
+    MemberUpdateResult, StatusUpdateDay,
+};
+use crate::ids::{self, group_channel_ids, ChannelKind};
+use crate::interactions::status_report_buttons;
+use crate::metrics::{push_kpis, record_cache_lookup, Kpi};
+use crate::points;
+use crate::preferences::{allows, Notification};
+use crate::privacy::{erased_members, is_erased};
+use crate::reset_approvals;
+use crate::settings::Settings;
+use crate::storage::Storage;
+use crate::streaks::{self, DayOutcome, UpdateStreaks};
+use crate::templates;
+use crate::utils::broadcast::broadcast;
+use crate::utils::delivery::deliver_report;
+use crate::utils::embed::report_embed;
+use crate::utils::long_message::long_message;
+use crate::utils::permissions::{check_permissions, POST_EMBEDS};
+use crate::utils::scan::{expand_forums, scan_channels};
+use crate::utils::time::{format_date, time_until};
+use crate::xp;
+use crate::Data;
 
 /// Checks for status updates daily at 5 AM.
 pub struct StatusUpdateCheck;
@@ -41,118 +73,972 @@ impl Task for StatusUpdateCheck {
         "Status Update Check"
     }
 
-    fn run_in(&self) -> tokio::time::Duration {
+    fn reads_messages(&self) -> bool {
+        true
+    }
+
+    fn follows_semester(&self) -> bool {
+        true
+    }
+
+    fn evaluated_date(&self, config: &Config, now: DateTime<Utc>) -> NaiveDate {
+        evaluated_date(config, now)
+    }
+
+    fn run_in(&self) -> Duration {
         time_until(5, 00)
     }
 
-    async fn run(&self, ctx: Context) -> anyhow::Result<()> {
-        status_update_check(ctx).await
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    // A skipped check would leave a whole day of streaks untouched.
+    fn overlap_policy(&self) -> OverlapPolicy {
+        OverlapPolicy::Queue
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        // Deferring up front rather than retrying, a check that failed half way may
+        // already have updated some streaks.
+        defer_while_root_unavailable(
+            &ctx,
+            ids::channel(ChannelKind::StatusUpdate).get(),
+            "status update report",
+        )
+        .await?;
+        status_update_check(ctx, data).await
+    }
+
+    async fn dry_run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let updates = get_updates(&ctx, data).await?;
+        let members = tracked_members(data).await?;
+        let (naughty_list, nice_list) = categorize_members(&members, &updates);
+        info!(
+            "Dry run: {} members sent their update, {} would be defaulters",
+            nice_list.len(),
+            naughty_list.values().map(Vec::len).sum::<usize>()
+        );
+        Ok(())
+    }
+}
+
+/// Posts the would-be defaulters to the ops channel 15 minutes before the check, so
+/// mentors can catch false negatives before any streaks are reset.
+pub struct StatusUpdatePreview;
+
+#[async_trait]
+impl Task for StatusUpdatePreview {
+    fn name(&self) -> &str {
+        "Status Update Preview"
+    }
+
+    fn reads_messages(&self) -> bool {
+        true
+    }
+
+    fn follows_semester(&self) -> bool {
+        true
+    }
+
+    fn evaluated_date(&self, config: &Config, now: DateTime<Utc>) -> NaiveDate {
+        evaluated_date(config, now)
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(4, 45)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        status_update_preview(ctx, data).await
     }
 }
 
 type GroupedMember = HashMap<u64, Vec<Member>>;
 
+/// Valid updates recorded as they arrive, merged with a scan of the channels by the check.
+const RECEIVED_UPDATES_KEY: &str = "status_update.received";
+
+/// A valid status update, either recorded from the gateway or found by scanning a channel.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReceivedUpdate {
+    pub message_id: u64,
+    pub channel_id: u64,
+    pub author_id: u64,
+    pub author_name: String,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ReceivedUpdate {
+    fn from_message(message: &Message) -> Self {
+        Self {
+            message_id: message.id.get(),
+            channel_id: message.channel_id.get(),
+            author_id: message.author.id.get(),
+            author_name: message.author.name.clone(),
+            content: message.content.clone(),
+            timestamp: DateTime::from_timestamp(message.timestamp.unix_timestamp(), 0)
+                .unwrap_or_default(),
+        }
+    }
+}
+
 struct ReportConfig {
-    time_valid_from: DateTime<chrono_tz::Tz>,
+    time_valid_from: DateTime<Utc>,
+    /// Local time the window opens at, for explaining rejections.
+    opens_at: NaiveTime,
     keywords: Vec<&'static str>,
-    special_authors: Vec<&'static str>,
 }
 
-const AMAN_SHAFEEQ: &str = "767636699077410837";
-const CHANDRA_MOULI: &str = "1265880467047976970";
+/// When each member was last told their message doesn't count, by the opening of the
+/// window it was sent in, so they're only told once a day.
+const REJECTION_WARNINGS_KEY: &str = "status_update.rejection_warnings";
+
+/// Discord IDs of members allowed to use an alternate format, where signing off with
+/// "regards" is enough. Managed with `$report exempt_format`.
+const EXEMPT_FORMAT_KEY: &str = "status_update.exempt_format";
+
+pub async fn format_exempt_authors(storage: &Storage) -> anyhow::Result<HashSet<String>> {
+    storage.get(EXEMPT_FORMAT_KEY).await
+}
+
+/// Adds or removes a member from the alternate-format list, returns `false` if they
+/// already were or weren't on it.
+pub async fn set_format_exempt(
+    storage: &Storage,
+    discord_id: String,
+    exempt: bool,
+) -> anyhow::Result<bool> {
+    storage
+        .update(EXEMPT_FORMAT_KEY, |authors: &mut HashSet<String>| {
+            if exempt {
+                authors.insert(discord_id)
+            } else {
+                authors.remove(&discord_id)
+            }
+        })
+        .await
+}
+
+async fn status_update_check(ctx: Context, data: &Data) -> anyhow::Result<()> {
+    let deadline = Utc::now();
+    let (grace_period_minutes, date) = {
+        let config = data.config.read().await;
+        (
+            config.status_update.grace_period_minutes,
+            evaluated_date(&config, deadline),
+        )
+    };
 
-async fn status_update_check(ctx: Context) -> anyhow::Result<()> {
-    let updates = get_updates(&ctx).await?;
-    let members = fetch_members().await?;
+    let mut updates = get_updates(&ctx, data).await?;
+    let members = tracked_members(data).await?;
+
+    let (naughty_list, _) = categorize_members(&members, &updates);
+    if grace_period_minutes > 0 && !naughty_list.is_empty() {
+        debug!(
+            "Waiting {} minutes for late updates before finalizing",
+            grace_period_minutes
+        );
+        tokio::time::sleep(Duration::from_secs(grace_period_minutes * 60)).await;
+        updates = get_updates(&ctx, data).await?;
+    }
+    let late_senders = get_late_senders(&updates, deadline);
 
     // naughty_list -> members who did not send updates
-    let (mut naughty_list, mut nice_list) = categorize_members(&members, updates);
-    update_streaks_for_members(&mut naughty_list, &mut nice_list).await?;
+    let (mut naughty_list, mut nice_list) = categorize_members(&members, &updates);
+    let config = data.config.read().await.clone();
+    let updates_optional = config.weekend.updates_optional(date);
+    // Resets waiting for a mentor are applied by `reset_approvals` once decided, so the
+    // report goes out without waiting on them.
+    let approval_channel_id = config
+        .status_update
+        .reset_approval_channel_id
+        .filter(|_| !updates_optional && !naughty_list.is_empty());
+    let resets_applied = !updates_optional && approval_channel_id.is_none();
+    let (shielded, milestones) = update_streaks_for_members(
+        &data.storage,
+        &config.status_update,
+        &mut naughty_list,
+        &mut nice_list,
+        resets_applied,
+    )
+    .await?;
+    let resets = StreakResets {
+        applied: resets_applied,
+        pending: approval_channel_id.is_some(),
+        updates_optional,
+        shielded,
+    };
+    let senders: Vec<u64> = nice_list
+        .iter()
+        .filter_map(|m| m.discord_id.parse().ok())
+        .collect();
+    if let Err(e) = points::award(
+        &data.storage,
+        &format!("status_update:{}", date),
+        senders.iter().copied(),
+        config.points.per_update,
+    )
+    .await
+    {
+        warn!("Failed to award status update points: {:?}", e);
+    }
+    xp::award(&ctx, data, senders, config.xp.per_update).await;
+    if config.features.webhooks {
+        push_status_update_kpis(&data.settings, &naughty_list, &nice_list).await;
+    }
+
+    let day = build_status_update_day(
+        date,
+        deadline,
+        &naughty_list,
+        &nice_list,
+        &late_senders,
+        &resets,
+    );
+    let diff = previous_status_update_day(&data.storage, date)
+        .await?
+        .map(|previous| diff_days(&previous, &day));
+    let stats = day.stats();
+    // The streaks are already updated, losing the history shouldn't also lose the report.
+    if let Err(e) = record_status_update_day(&data.storage, day).await {
+        warn!("Failed to record the status update day: {:?}", e);
+    }
+    if let Some(channel_id) = approval_channel_id {
+        let defaulters: Vec<&Member> = naughty_list.values().flatten().collect();
+        if let Err(e) = reset_approvals::request_approval(
+            &ctx,
+            data,
+            date,
+            channel_id,
+            config.status_update.reset_approval_timeout_minutes,
+            &defaulters,
+        )
+        .await
+        {
+            warn!(
+                "Failed to ask mentors to approve the streak resets: {:?}",
+                e
+            );
+        }
+    }
+    if config.reports.push_summaries {
+        if let Err(e) = push_status_update_stats(&stats).await {
+            warn!("Failed to push the status update summary to Root: {:?}", e);
+        }
+    }
+
+    let duplicates = if config.status_update.duplicate_threshold > 0.0 {
+        find_duplicates(
+            &ctx,
+            &data.storage,
+            config.status_update.duplicate_threshold,
+            date,
+            &updates,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to look for copy-pasted updates: {:?}", e);
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+
+    let late_list: Vec<Member> = nice_list
+        .iter()
+        .filter(|member| late_senders.contains(&member.discord_id))
+        .cloned()
+        .collect();
+    let stats_embed = generate_stats_embed(&ctx, &config, &nice_list, &naughty_list, &late_list);
+    let group_embed = |group| {
+        generate_group_embed(
+            &ctx,
+            &config,
+            group,
+            &nice_list,
+            &naughty_list,
+            &late_list,
+            &resets,
+        )
+    };
+    let streaks = fetch_streaks().await?;
+    let notes = ReportNotes {
+        late_list: late_list.clone(),
+        milestones,
+        duplicates,
+        diff,
+    };
+    let full_embed = |channel_id| {
+        generate_embed(
+            &ctx,
+            &config,
+            get_leaderboard_stats(&members, &streaks),
+            &naughty_list,
+            &notes,
+            &resets,
+            config.status_update.names_defaulters_in(channel_id),
+        )
+    };
+
+    deliver_report(
+        &ctx,
+        data,
+        ReportKind::StatusUpdate,
+        date,
+        ids::channel(ChannelKind::StatusUpdate).get(),
+        |delivery| match delivery.detail {
+            ReportDetail::Full => {
+                let mut message = CreateMessage::new()
+                    .embed(full_embed(delivery.channel_id))
+                    .components(vec![status_report_buttons(date)]);
+                message =
+                    ping_mentors(message, &config.status_update, &resets, naughty_list.keys());
+                if delivery.recognition {
+                    message =
+                        message.embed(generate_recognition_embed(&ctx, &config, &nice_list, None));
+                }
+                Some(message)
+            }
+            ReportDetail::Stats => Some(CreateMessage::new().embed(stats_embed.clone())),
+            ReportDetail::Group => {
+                let Some(group) = delivery.group else {
+                    warn!(
+                        "Group delivery to channel {} has no group set",
+                        delivery.channel_id
+                    );
+                    return None;
+                };
+                let mut message = CreateMessage::new().embed(group_embed(group));
+                if naughty_list.contains_key(&group) {
+                    message = ping_mentors(message, &config.status_update, &resets, [&group]);
+                }
+                if delivery.recognition {
+                    message = message.embed(generate_recognition_embed(
+                        &ctx,
+                        &config,
+                        &nice_list,
+                        Some(group),
+                    ));
+                }
+                Some(message)
+            }
+        },
+    )
+    .await?;
+    if let Some(channel_id) = config.status_update.private_defaulters_channel_id {
+        let message = CreateMessage::new()
+            .embed(full_embed(channel_id))
+            .components(vec![status_report_buttons(date)]);
+        let message = ping_mentors(message, &config.status_update, &resets, naughty_list.keys());
+        send_private_report(&ctx, &config, channel_id, message).await?;
+    }
+
+    // Missing an optional update is nothing to warn anyone about.
+    if !updates_optional {
+        let defaulters: Vec<&Member> = naughty_list.values().flatten().collect();
+        let receipts = notify_defaulters(&ctx, data, date, &defaulters).await;
+        notify_group_mentors(&ctx, data, &config.status_update, &naughty_list, &receipts).await;
+    }
+
+    if config.llm.update_feedback && config.llm.endpoint.is_some() {
+        review_updates(&ctx, data, &config.llm, date, &updates).await;
+    }
+
+    Ok(())
+}
+
+async fn status_update_preview(ctx: Context, data: &Data) -> anyhow::Result<()> {
+    let config = data.config.read().await.clone();
+    let Some(channel_id) = config.bot.ops_channel_id else {
+        return Ok(());
+    };
+    check_permissions(&ctx, channel_id, POST_EMBEDS)?;
+
+    let updates = get_updates(&ctx, data).await?;
+    let members = tracked_members(data).await?;
+    let (naughty_list, _) = categorize_members(&members, &updates);
+
+    let mut description = String::new();
+    if naughty_list.is_empty() {
+        description.push_str("Everyone has sent their update so far.\n");
+    } else {
+        description.push_str("These members would be marked as defaulters if the check ran now:\n");
+        let mut groups: Vec<_> = naughty_list.iter().collect();
+        groups.sort_by_key(|(group, _)| **group);
+        for (group, missed_members) in groups {
+            description.push_str(&format!("## Group {}\n", group));
+            for member in missed_members {
+                description.push_str(&format!("- {}\n", member.name));
+            }
+        }
+    }
 
-    let embed = generate_embed(members, naughty_list).await?;
-    let msg = CreateMessage::new().embed(embed);
+    let embed = report_embed(
+        &ctx,
+        &config.theme.embed,
+        "Status Update Preview",
+        config.theme.status_update.color,
+    )
+    .description(description);
+    ChannelId::new(channel_id)
+        .send_message(ctx.http(), CreateMessage::new().embed(embed))
+        .await?;
 
-    let status_update_channel = ChannelId::new(STATUS_UPDATE_CHANNEL_ID);
-    status_update_channel.send_message(ctx.http(), msg).await?;
+    Ok(())
+}
+
+/// Posts the report naming the defaulters to the mentors-only channel, unless a full
+/// delivery already went there.
+async fn send_private_report(
+    ctx: &Context,
+    config: &Config,
+    channel_id: u64,
+    message: CreateMessage,
+) -> anyhow::Result<()> {
+    let delivered = config
+        .reports
+        .deliveries_for(
+            ReportKind::StatusUpdate,
+            ids::channel(ChannelKind::StatusUpdate).get(),
+        )
+        .iter()
+        .any(|delivery| delivery.channel_id == channel_id && delivery.detail == ReportDetail::Full);
+    if delivered {
+        return Ok(());
+    }
 
+    check_permissions(ctx, channel_id, POST_EMBEDS)?;
+    ChannelId::new(channel_id)
+        .send_message(&ctx.http, message)
+        .await?;
     Ok(())
 }
 
-async fn get_updates(ctx: &Context) -> anyhow::Result<Vec<Message>> {
-    let channel_ids = get_channel_ids();
-    let mut updates = Vec::new();
+/// DMs every configured mentor a summary of only their own group's defaulters, naming
+/// those whose defaulter notice couldn't be delivered.
+async fn notify_group_mentors(
+    ctx: &Context,
+    data: &Data,
+    config: &StatusUpdateConfig,
+    naughty_list: &GroupedMember,
+    receipts: &BTreeMap<u64, DeliveryReceipt>,
+) {
+    let mut messages = Vec::new();
+    for (group, missed_members) in naughty_list {
+        let Some(mentors) = config.mentors_for(*group) else {
+            continue;
+        };
+
+        let mut summary = format!("Defaulters from Group {} today:\n", group);
+        let mut unreachable = Vec::new();
+        for member in missed_members {
+            summary.push_str(&format!("- {}\n", member.name));
+            let receipt = member
+                .discord_id
+                .parse::<u64>()
+                .ok()
+                .and_then(|user_id| receipts.get(&user_id));
+            if let Some(receipt) = receipt.filter(|r| r.unreachable()) {
+                let note = match receipt.last_delivered {
+                    Some(date) => format!("last reached {}", format_date(date)),
+                    None => String::from("never received a warning"),
+                };
+                unreachable.push(format!("- {} ({})\n", member.name, note));
+            }
+        }
+        if !unreachable.is_empty() {
+            summary.push_str("\nCouldn't be DMed, their DMs are likely closed:\n");
+            summary.push_str(&unreachable.concat());
+        }
+
+        for user_id in &mentors.user_ids {
+            if !allows(&data.storage, *user_id, Notification::DefaulterNotices).await {
+                continue;
+            }
+            messages.push((*user_id, long_message(&summary, "defaulters.md")));
+        }
+    }
+    broadcast(ctx, data, "Mentor defaulter summaries", messages).await;
+}
+
+/// Root members minus those who asked to be erased.
+pub async fn tracked_members(data: &Data) -> anyhow::Result<Vec<Member>> {
+    let erased = erased_members(&data.storage).await?;
+    let mut members = fetch_members().await?;
+    members.retain(|m| !erased.contains(&m.discord_id));
+    Ok(members)
+}
+
+/// Result of re-validating a single member's update after the check already ran.
+pub enum RecheckOutcome {
+    /// There is no stored result for this member in the latest check.
+    NotTracked,
+    AlreadyCounted,
+    NoValidUpdate,
+    Restored {
+        current_streak: i32,
+    },
+}
+
+/// Re-validates `user_id`'s update for the latest check, for example after they edited
+/// a message that was missing a keyword, and reverses the streak reset if it now passes.
+pub async fn recheck_member_update(
+    ctx: &Context,
+    data: &Data,
+    user_id: UserId,
+) -> anyhow::Result<RecheckOutcome> {
+    let days = recent_status_update_days(&data.storage, 2).await?;
+    let Some(mut day) = days.last().cloned() else {
+        return Ok(RecheckOutcome::NotTracked);
+    };
+    let discord_id = user_id.to_string();
+    let Some(index) = day.members.iter().position(|m| m.discord_id == discord_id) else {
+        return Ok(RecheckOutcome::NotTracked);
+    };
+    if day.members[index].sent_update {
+        return Ok(RecheckOutcome::AlreadyCounted);
+    }
+
+    let grace_period_minutes = data.config.read().await.status_update.grace_period_minutes;
+    let cutoff = day
+        .deadline
+        .map(|deadline| deadline + chrono::Duration::minutes(grace_period_minutes as i64));
+    // Scan rather than read the recorded updates, the message may have been edited since.
+    let has_valid_update = scan_updates(ctx, data).await?.iter().any(|update| {
+        update.author_id == user_id.get() && cutoff.is_none_or(|cutoff| update.timestamp <= cutoff)
+    });
+    if !has_valid_update {
+        return Ok(RecheckOutcome::NoValidUpdate);
+    }
+
+    // The streak before the reset is the one stored for the previous check.
+    let previous_streak = days
+        .iter()
+        .rev()
+        .nth(1)
+        .and_then(|previous| previous.members.iter().find(|m| m.discord_id == discord_id))
+        .map(|m| m.current_streak)
+        .unwrap_or(0);
+    let result = &mut day.members[index];
+    let current_streak = streaks::extended(previous_streak);
+
+    let mut member = Member {
+        member_id: result.member_id,
+        name: result.name.clone(),
+        discord_id: result.discord_id.clone(),
+        group_id: result.group_id,
+        streak: Vec::new(),
+    };
+    let policy = UpdateStreaks {
+        storage: &data.storage,
+        resets: true,
+        milestones: &[],
+    };
+    let max_streak = streaks::overturn_miss(
+        &policy,
+        &mut member,
+        current_streak,
+        result.max_streak,
+        result.shielded,
+    )
+    .await?;
+    let (per_update, xp_per_update) = {
+        let config = data.config.read().await;
+        (config.points.per_update, config.xp.per_update)
+    };
+    let occasion = format!("status_update:{}", day.date);
+    if let Err(e) = points::award(&data.storage, &occasion, [user_id.get()], per_update).await {
+        warn!("Failed to award status update points: {:?}", e);
+    }
+    xp::award(ctx, data, [user_id.get()], xp_per_update).await;
+
+    result.sent_update = true;
+    result.shielded = false;
+    result.current_streak = current_streak;
+    result.max_streak = max_streak;
+    record_status_update_day(&data.storage, day).await?;
+
+    Ok(RecheckOutcome::Restored { current_streak })
+}
+
+/// Validates messages in the group channels as they arrive. Valid updates are recorded
+/// and get a ✅. A member's first message of the day that doesn't count gets a ❌ and a DM
+/// explaining what's missing, later ones are left alone.
+pub async fn handle_incoming_message(ctx: &Context, data: &Data, message: &Message) {
+    if message.author.bot || !in_group_channel(ctx, message.channel_id, message.guild_id).await {
+        return;
+    }
+    match is_erased(&data.storage, &message.author.id.to_string()).await {
+        Ok(false) => {}
+        Ok(true) => return,
+        Err(e) => {
+            warn!("Failed to check erasure list: {:?}", e);
+            return;
+        }
+    }
+
+    let exempt_authors = match format_exempt_authors(&data.storage).await {
+        Ok(authors) => authors,
+        Err(e) => {
+            warn!("Failed to load format exemptions: {:?}", e);
+            HashSet::new()
+        }
+    };
+    let report_config = get_report_config(data).await;
+    let missing = missing_requirements(message, &report_config, &exempt_authors);
+    if !missing.is_empty() {
+        reject_update(ctx, &data.storage, message, &report_config, &missing).await;
+        return;
+    }
+
+    if let Err(e) = record_update(&data.storage, message, &report_config).await {
+        warn!(
+            "Failed to record update from {}: {:?}",
+            message.author.name, e
+        );
+        return;
+    }
+
+    if let Err(e) = message.react(ctx.http(), '✅').await {
+        warn!(
+            "Failed to react to update from {}: {}",
+            message.author.name, e
+        );
+    }
+}
+
+/// Re-validates an edited message in a group channel, so fixing a rejected update makes
+/// it count and editing a valid one into something else stops it from counting. Unlike
+/// new messages, edits that don't count aren't explained over DM again.
+pub async fn handle_edited_message(ctx: &Context, data: &Data, event: &MessageUpdateEvent) {
+    if !in_group_channel(ctx, event.channel_id, event.guild_id).await {
+        return;
+    }
+    let message = match event.channel_id.message(ctx, event.id).await {
+        Ok(message) => message,
+        Err(e) => {
+            warn!("Failed to fetch edited message {}: {}", event.id, e);
+            return;
+        }
+    };
+    if message.author.bot {
+        return;
+    }
+    match is_erased(&data.storage, &message.author.id.to_string()).await {
+        Ok(false) => {}
+        Ok(true) => return,
+        Err(e) => {
+            warn!("Failed to check erasure list: {:?}", e);
+            return;
+        }
+    }
+    let report_config = get_report_config(data).await;
+    if message.timestamp.to_utc() < report_config.time_valid_from {
+        return;
+    }
+
+    let exempt_authors = match format_exempt_authors(&data.storage).await {
+        Ok(authors) => authors,
+        Err(e) => {
+            warn!("Failed to load format exemptions: {:?}", e);
+            HashSet::new()
+        }
+    };
+    let valid = missing_requirements(&message, &report_config, &exempt_authors).is_empty();
+    let result = if valid {
+        record_update(&data.storage, &message, &report_config).await
+    } else {
+        let message_id = message.id.get();
+        data.storage
+            .update(RECEIVED_UPDATES_KEY, |stored: &mut Vec<ReceivedUpdate>| {
+                stored.retain(|u| u.message_id != message_id)
+            })
+            .await
+    };
+    if let Err(e) = result {
+        warn!(
+            "Failed to record edited update from {}: {:?}",
+            message.author.name, e
+        );
+        return;
+    }
+
+    let (add, remove) = if valid {
+        ('✅', '❌')
+    } else {
+        ('❌', '✅')
+    };
+    let bot_id = ctx.cache.current_user().id;
+    if let Err(e) = message
+        .channel_id
+        .delete_reaction(ctx.http(), message.id, Some(bot_id), remove)
+        .await
+    {
+        debug!("No reaction to remove from {}: {}", message.id, e);
+    }
+    if let Err(e) = message.react(ctx.http(), add).await {
+        warn!(
+            "Failed to react to edited message from {}: {}",
+            message.author.name, e
+        );
+    }
+}
+
+/// Stores `message` as a valid update of the current window, replacing an earlier
+/// version of it.
+async fn record_update(
+    storage: &Storage,
+    message: &Message,
+    report_config: &ReportConfig,
+) -> anyhow::Result<()> {
+    let update = ReceivedUpdate::from_message(message);
+    let since = report_config.time_valid_from;
+    storage
+        .update(RECEIVED_UPDATES_KEY, |stored: &mut Vec<ReceivedUpdate>| {
+            // Only the current window is ever read back.
+            stored.retain(|u| u.timestamp >= since && u.message_id != update.message_id);
+            stored.push(update);
+        })
+        .await
+}
+
+async fn reject_update(
+    ctx: &Context,
+    storage: &Storage,
+    message: &Message,
+    report_config: &ReportConfig,
+    missing: &[String],
+) {
+    let author_id = message.author.id.get();
+    let window = report_config.time_valid_from;
+    let first_today = storage
+        .update(
+            REJECTION_WARNINGS_KEY,
+            |warned: &mut HashMap<u64, DateTime<Utc>>| {
+                warned.insert(author_id, window) != Some(window)
+            },
+        )
+        .await;
+    match first_today {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            warn!("Failed to record rejection warning: {:?}", e);
+            return;
+        }
+    }
+
+    if let Err(e) = message.react(ctx.http(), '❌').await {
+        warn!(
+            "Failed to react to message from {}: {}",
+            message.author.name, e
+        );
+    }
+
+    if !allows(storage, author_id, Notification::DefaulterNotices).await {
+        return;
+    }
+    let mut explanation =
+        String::from("Your message in the group channel doesn't count as a status update:\n");
+    for reason in missing {
+        explanation.push_str(&format!("- {}\n", reason));
+    }
+    let dm = CreateMessage::new().content(explanation);
+    if let Err(e) = message.author.direct_message(ctx.http(), dm).await {
+        warn!(
+            "Failed to DM {} about their update: {}",
+            message.author.name, e
+        );
+    }
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    storage
+        .update(RECEIVED_UPDATES_KEY, |stored: &mut Vec<ReceivedUpdate>| {
+            stored.retain(|u| u.author_id != user_id)
+        })
+        .await?;
+    storage
+        .update(
+            REJECTION_WARNINGS_KEY,
+            |warned: &mut HashMap<u64, DateTime<Utc>>| {
+                warned.remove(&user_id);
+            },
+        )
+        .await?;
+    set_format_exempt(storage, user_id.to_string(), false).await?;
+    Ok(())
+}
 
-    let get_messages_builder = GetMessages::new().limit(100);
-    for channel in channel_ids {
-        let messages = channel.messages(ctx.http(), get_messages_builder).await?;
-        let valid_updates = messages.into_iter().filter(is_valid_status_update);
-        updates.extend(valid_updates);
+/// Returns the valid updates of the current window, oldest first. The recorded updates
+/// may be incomplete, e.g. if the bot was restarted overnight or missed a gateway event,
+/// so the channels are always scanned as well. Scanned messages win over recorded ones,
+/// they reflect any edits since.
+async fn get_updates(ctx: &Context, data: &Data) -> anyhow::Result<Vec<ReceivedUpdate>> {
+    let since = get_report_config(data).await.time_valid_from;
+    let mut updates: HashMap<u64, ReceivedUpdate> = data
+        .storage
+        .get::<Vec<ReceivedUpdate>>(RECEIVED_UPDATES_KEY)
+        .await?
+        .into_iter()
+        .filter(|u| u.timestamp >= since)
+        .map(|u| (u.message_id, u))
+        .collect();
+    for update in scan_updates(ctx, data).await? {
+        updates.insert(update.message_id, update);
     }
 
+    let mut updates: Vec<ReceivedUpdate> = updates.into_values().collect();
+    updates.sort_by_key(|u| u.timestamp);
     Ok(updates)
 }
 
-// TODO: Replace hardcoded set with configurable list
+async fn scan_updates(ctx: &Context, data: &Data) -> anyhow::Result<Vec<ReceivedUpdate>> {
+    let report_config = get_report_config(data).await;
+    let since = report_config.time_valid_from;
+    let exempt_authors = format_exempt_authors(&data.storage).await?;
+    let channels = expand_forums(ctx, &get_channel_ids(), since).await?;
+    let messages = scan_channels(ctx, &channels, since, |message| {
+        missing_requirements(message, &report_config, &exempt_authors).is_empty()
+    })
+    .await?;
+    Ok(messages.iter().map(ReceivedUpdate::from_message).collect())
+}
+
 fn get_channel_ids() -> Vec<ChannelId> {
-    vec![
-        ChannelId::new(GROUP_ONE_CHANNEL_ID),
-        ChannelId::new(GROUP_TWO_CHANNEL_ID),
-        ChannelId::new(GROUP_THREE_CHANNEL_ID),
-        ChannelId::new(GROUP_FOUR_CHANNEL_ID),
-    ]
+    group_channel_ids()
+        .into_iter()
+        .map(|(_, channel_id)| ChannelId::new(channel_id))
+        .collect()
+}
+
+/// Whether `message` was sent in a group channel, or in a post of a group forum channel
+/// where each member replies to their own post daily.
+async fn in_group_channel(ctx: &Context, channel_id: ChannelId, guild_id: Option<GuildId>) -> bool {
+    let channels = get_channel_ids();
+    if channels.contains(&channel_id) {
+        return true;
+    }
+    let Some(guild_id) = guild_id else {
+        return false;
+    };
+
+    let cached = ctx.cache.guild(guild_id).and_then(|guild| {
+        if guild.channels.contains_key(&channel_id) {
+            // A regular channel, not a post.
+            return Some(None);
+        }
+        guild
+            .threads
+            .iter()
+            .find(|thread| thread.id == channel_id)
+            .map(|thread| thread.parent_id)
+    });
+    record_cache_lookup("discord.channels", cached.is_some());
+    let parent_id = match cached {
+        Some(parent_id) => parent_id,
+        None => match channel_id.to_channel(&ctx.http).await {
+            Ok(channel) => channel.guild().and_then(|thread| thread.parent_id),
+            Err(e) => {
+                warn!("Failed to look up channel {}: {}", channel_id, e);
+                None
+            }
+        },
+    };
+    parent_id.is_some_and(|parent_id| channels.contains(&parent_id))
 }
 
-fn is_valid_status_update(msg: &Message) -> bool {
-    let report_config = get_report_config();
+/// Lists why `msg` doesn't count as a status update, empty if it is valid.
+fn missing_requirements(
+    msg: &Message,
+    report_config: &ReportConfig,
+    exempt_authors: &HashSet<String>,
+) -> Vec<String> {
     let content = msg.content.to_lowercase();
+    let mut missing = Vec::new();
 
     let is_within_timeframe = DateTime::<Utc>::from_timestamp(msg.timestamp.timestamp(), 0)
         .expect("Valid timestamp")
-        .with_timezone(&chrono_tz::Asia::Kolkata)
         >= report_config.time_valid_from;
+    if !is_within_timeframe {
+        missing.push(format!(
+            "it was sent before today's window opened at {}",
+            report_config.opens_at.format("%-I:%M %p")
+        ));
+    }
 
-    let has_required_keywords = report_config
-        .keywords
-        .iter()
-        .all(|keyword| content.contains(keyword));
-    let is_special_author = report_config
-        .special_authors
-        .contains(&msg.author.id.to_string().as_str());
-    let is_valid_content =
-        has_required_keywords || (is_special_author && content.contains("regards"));
-
-    is_within_timeframe && is_valid_content
-}
-
-// TODO: Parts of this could also be removed from code like channel_ids
-fn get_report_config() -> ReportConfig {
-    let now = chrono::Utc::now().with_timezone(&chrono_tz::Asia::Kolkata);
-    let yesterday = now.date_naive() - chrono::Duration::days(1);
-    let time_valid_from = yesterday
-        .and_hms_opt(20, 0, 0)
-        .expect("Valid timestamp")
-        .and_local_timezone(chrono_tz::Asia::Kolkata)
-        .earliest()
-        .expect("Valid timezone conversion");
+    if exempt_authors.contains(&msg.author.id.to_string()) && content.contains("regards") {
+        return missing;
+    }
+    for keyword in &report_config.keywords {
+        if !content.contains(keyword) {
+            missing.push(format!("it doesn't contain \"{}\"", keyword));
+        }
+    }
+
+    missing
+}
+
+/// The day a check judges: the day the update window it reads opened on, usually the day
+/// before the check runs.
+pub fn evaluated_date(config: &Config, now: DateTime<Utc>) -> NaiveDate {
+    let timezone = config.bot.timezone;
+    config
+        .status_update
+        .window(timezone)
+        .opened(now)
+        .with_timezone(&timezone)
+        .date_naive()
+}
+
+async fn get_report_config(data: &Data) -> ReportConfig {
+    let window = {
+        let config = data.config.read().await;
+        config.status_update.window(config.bot.timezone)
+    };
 
     ReportConfig {
-        time_valid_from,
+        time_valid_from: window.opened(Utc::now()),
+        opens_at: window.opens_at,
         keywords: vec!["namah shivaya", "regards"],
-        special_authors: vec![AMAN_SHAFEEQ, CHANDRA_MOULI],
     }
 }
 
+/// Returns the Discord IDs of members whose first valid update was sent after `deadline`.
+fn get_late_senders(updates: &[ReceivedUpdate], deadline: DateTime<Utc>) -> HashSet<String> {
+    let mut first_update: HashMap<String, i64> = HashMap::new();
+    for update in updates {
+        let timestamp = update.timestamp.timestamp();
+        first_update
+            .entry(update.author_id.to_string())
+            .and_modify(|first| *first = (*first).min(timestamp))
+            .or_insert(timestamp);
+    }
+
+    first_update
+        .into_iter()
+        .filter(|(_, timestamp)| *timestamp > deadline.timestamp())
+        .map(|(author, _)| author)
+        .collect()
+}
+
 fn categorize_members(
     members: &Vec<Member>,
-    updates: Vec<Message>,
+    updates: &[ReceivedUpdate],
 ) -> (GroupedMember, Vec<Member>) {
     let mut nice_list = vec![];
     let mut naughty_list = HashMap::new();
 
     let mut sent_updates: HashSet<String> = HashSet::new();
 
-    for message in updates.iter() {
-        sent_updates.insert(message.author.id.to_string());
+    for update in updates.iter() {
+        sent_updates.insert(update.author_id.to_string());
     }
 
     for member in members {
@@ -170,105 +1056,703 @@ fn categorize_members(
     (naughty_list, nice_list)
 }
 
+/// Returns the member IDs of defaulters who had a streak shield, which was used up
+/// instead of resetting their streak, and the senders whose streak reached a milestone.
 async fn update_streaks_for_members(
+    storage: &Storage,
+    config: &StatusUpdateConfig,
     naughty_list: &mut GroupedMember,
     nice_list: &mut Vec<Member>,
-) -> anyhow::Result<()> {
+    reset_defaulters: bool,
+) -> anyhow::Result<(HashSet<i32>, Vec<MilestoneContext>)> {
+    let policy = UpdateStreaks {
+        storage,
+        resets: reset_defaulters,
+        milestones: &config.streak_milestones,
+    };
+    let mut milestones = Vec::new();
     for member in nice_list {
-        increment_streak(member).await?;
+        if let DayOutcome::Extended {
+            milestone: Some(streak),
+        } = streaks::record_day(&policy, member, true).await?
+        {
+            milestones.push(MilestoneContext {
+                name: member.name.clone(),
+                streak,
+            });
+        }
     }
 
+    let mut shielded = HashSet::new();
     for members in naughty_list.values_mut() {
         for member in members {
-            reset_streak(member).await?;
+            if streaks::record_day(&policy, member, false).await? == DayOutcome::Frozen {
+                info!("Used a streak shield of {}", member.name);
+                shielded.insert(member.member_id);
+            }
         }
     }
 
-    Ok(())
+    Ok((shielded, milestones))
 }
 
-async fn generate_embed(
-    members: Vec<Member>,
-    naughty_list: GroupedMember,
-) -> anyhow::Result<CreateEmbed> {
-    let (all_time_high, all_time_high_members, current_highest, current_highest_members) =
-        get_leaderboard_stats(members).await?;
-    let mut description = String::new();
+fn build_status_update_day(
+    date: chrono::NaiveDate,
+    deadline: DateTime<Utc>,
+    naughty_list: &GroupedMember,
+    nice_list: &[Member],
+    late_senders: &HashSet<String>,
+    resets: &StreakResets,
+) -> StatusUpdateDay {
+    let to_result = |member: &Member, sent_update: bool| {
+        let (current_streak, max_streak) = member
+            .streak
+            .first()
+            .map(|streak| (streak.current_streak, streak.max_streak))
+            .unwrap_or_default();
+        MemberUpdateResult {
+            member_id: member.member_id,
+            name: member.name.clone(),
+            discord_id: member.discord_id.clone(),
+            group_id: member.group_id,
+            sent_update,
+            late_update: late_senders.contains(&member.discord_id),
+            shielded: resets.shielded.contains(&member.member_id),
+            current_streak,
+            max_streak,
+        }
+    };
 
-    description.push_str("# Leaderboard Updates\n");
+    let members = nice_list
+        .iter()
+        .map(|member| to_result(member, true))
+        .chain(
+            naughty_list
+                .values()
+                .flatten()
+                .map(|member| to_result(member, false)),
+        )
+        .collect();
 
-    description.push_str(&format!(
-        "## All-Time High Streak: {} days\n",
-        all_time_high
-    ));
-    description.push_str(&format_members(&all_time_high_members));
+    StatusUpdateDay {
+        date,
+        deadline: Some(deadline),
+        resets_applied: resets.applied,
+        updates_optional: resets.updates_optional,
+        members,
+    }
+}
 
-    description.push_str(&format!(
-        "## Current Highest Streak: {} days\n",
-        current_highest
+async fn push_status_update_kpis(
+    settings: &Settings,
+    naughty_list: &GroupedMember,
+    nice_list: &[Member],
+) {
+    let defaulters: Vec<&Member> = naughty_list.values().flatten().collect();
+    let streaks: Vec<i32> = nice_list
+        .iter()
+        .chain(defaulters.iter().copied())
+        .filter_map(|member| member.streak.first())
+        .map(|streak| streak.current_streak)
+        .collect();
+    let average_streak = if streaks.is_empty() {
+        0.0
+    } else {
+        streaks.iter().sum::<i32>() as f64 / streaks.len() as f64
+    };
+
+    push_kpis(
+        settings,
+        &[
+            Kpi::new("status_update_defaulters", defaulters.len() as f64),
+            Kpi::new("status_update_senders", nice_list.len() as f64),
+            Kpi::new("average_current_streak", average_streak),
+        ],
+    )
+    .await;
+}
+
+/// Rebuilds the full report of a past `day` from its stored results, for when the
+/// original message was deleted or rendered wrongly.
+pub async fn replay_report(
+    ctx: &Context,
+    data: &Data,
+    day: &StatusUpdateDay,
+    channel_id: u64,
+) -> anyhow::Result<CreateMessage> {
+    let config = data.config.read().await.clone();
+    let to_member = |result: &MemberUpdateResult| Member {
+        member_id: result.member_id,
+        name: result.name.clone(),
+        discord_id: result.discord_id.clone(),
+        group_id: result.group_id,
+        streak: vec![Streak {
+            current_streak: result.current_streak,
+            max_streak: result.max_streak,
+        }],
+    };
+
+    let members: Vec<Member> = day.members.iter().map(to_member).collect();
+    let streaks: Vec<StreakWithMemberId> = day
+        .members
+        .iter()
+        .map(|result| StreakWithMemberId {
+            member_id: result.member_id,
+            current_streak: result.current_streak,
+            max_streak: result.max_streak,
+        })
+        .collect();
+    let mut naughty_list = GroupedMember::new();
+    for result in day.members.iter().filter(|m| !m.sent_update) {
+        naughty_list
+            .entry(result.group_id as u64)
+            .or_default()
+            .push(to_member(result));
+    }
+    let late_list: Vec<Member> = day
+        .members
+        .iter()
+        .filter(|m| m.late_update)
+        .map(to_member)
+        .collect();
+    let resets = StreakResets {
+        applied: day.resets_applied,
+        pending: reset_approvals::is_pending(&data.storage, day.date).await?,
+        updates_optional: day.updates_optional,
+        shielded: day
+            .members
+            .iter()
+            .filter(|m| m.shielded)
+            .map(|m| m.member_id)
+            .collect(),
+    };
+    let notes = ReportNotes {
+        late_list,
+        milestones: day
+            .senders()
+            .filter_map(|m| {
+                streaks::milestone(&config.status_update.streak_milestones, m.current_streak).map(
+                    |streak| MilestoneContext {
+                        name: m.name.clone(),
+                        streak,
+                    },
+                )
+            })
+            .collect(),
+        duplicates: flags_for(&data.storage, day.date).await?,
+        diff: previous_status_update_day(&data.storage, day.date)
+            .await?
+            .map(|previous| diff_days(&previous, day)),
+    };
+
+    let embed = generate_embed(
+        ctx,
+        &config,
+        get_leaderboard_stats(&members, &streaks),
+        &naughty_list,
+        &notes,
+        &resets,
+        config.status_update.names_defaulters_in(channel_id),
+    )
+    .title(format!(
+        "{} - {}",
+        config.theme.status_update.title,
+        format_date(day.date)
     ));
-    description.push_str(&format_members(&current_highest_members));
 
-    if !naughty_list.is_empty() {
-        description.push_str("# Defaulters\n");
-        description.push_str(&format_defaulters(&naughty_list));
+    Ok(CreateMessage::new()
+        .embed(embed)
+        .components(vec![status_report_buttons(day.date)]))
+}
+
+/// Mentions the mentors of `groups` in the message content, since mentions inside an embed
+/// never ping. Nobody but those mentors can be pinged by the report.
+fn ping_mentors<'a>(
+    message: CreateMessage,
+    config: &StatusUpdateConfig,
+    resets: &StreakResets,
+    groups: impl IntoIterator<Item = &'a u64>,
+) -> CreateMessage {
+    // Missing an optional update is nothing to call mentors over.
+    if resets.updates_optional {
+        return message;
+    }
+    let mentors: Vec<&GroupMentors> = groups
+        .into_iter()
+        .filter_map(|group| config.mentors_for(*group))
+        .collect();
+    let mut user_ids: Vec<UserId> = mentors
+        .iter()
+        .flat_map(|m| m.user_ids.iter().map(|id| UserId::new(*id)))
+        .collect();
+    let mut role_ids: Vec<RoleId> = mentors
+        .iter()
+        .flat_map(|m| m.role_ids.iter().map(|id| RoleId::new(*id)))
+        .collect();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    role_ids.sort_unstable();
+    role_ids.dedup();
+    if user_ids.is_empty() && role_ids.is_empty() {
+        return message;
     }
 
-    let embed = CreateEmbed::new()
-        .title("Status Update Report")
-        .description(description)
-        .color(serenity::all::Colour::new(0xeab308));
+    let content = user_ids
+        .iter()
+        .map(|id| id.mention().to_string())
+        .chain(role_ids.iter().map(|id| id.mention().to_string()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    message
+        .content(content)
+        .allowed_mentions(CreateAllowedMentions::new().users(user_ids).roles(role_ids))
+}
+
+/// What happened to the defaulters' streaks in a check.
+struct StreakResets {
+    /// False when a mentor held back the resets, or weekend rules made updates optional.
+    applied: bool,
+    /// Whether the resets are still waiting for a mentor's approval.
+    pending: bool,
+    updates_optional: bool,
+    /// Member IDs of defaulters whose streak shield was used up instead.
+    shielded: HashSet<i32>,
+}
+
+/// Sections of the full report beyond the leaderboard and defaulters, empty ones are
+/// left out.
+struct ReportNotes {
+    late_list: Vec<Member>,
+    /// Senders whose streak reached one of the configured milestones.
+    milestones: Vec<MilestoneContext>,
+    duplicates: Vec<DuplicateFlag>,
+    /// Changes since the previous report, [`None`] when there is no previous report.
+    diff: Option<ReportDiff>,
+}
+
+/// How a day's results changed from the previous report.
+#[derive(Clone, Serialize)]
+struct ReportDiff {
+    /// The previous report's day, "Yesterday" unless days without a check came between.
+    since: String,
+    new_defaulters: Vec<String>,
+    recovered: Vec<String>,
+    overtaken: Option<Overtaken>,
+}
 
-    Ok(embed)
+/// The new holders of the current highest streak and the members they overtook.
+#[derive(Clone, Serialize)]
+struct Overtaken {
+    leaders: Vec<String>,
+    previous: Vec<String>,
 }
 
-fn format_members(members: &[Member]) -> String {
-    if members.len() <= 5 {
-        let list = members
+impl ReportDiff {
+    fn is_empty(&self) -> bool {
+        self.new_defaulters.is_empty() && self.recovered.is_empty() && self.overtaken.is_none()
+    }
+
+    /// The diff without the lists that give away who missed an update.
+    fn without_defaulters(&self) -> Self {
+        Self {
+            since: self.since.clone(),
+            new_defaulters: Vec::new(),
+            recovered: Vec::new(),
+            overtaken: self.overtaken.clone(),
+        }
+    }
+}
+
+fn diff_days(previous: &StatusUpdateDay, day: &StatusUpdateDay) -> ReportDiff {
+    let previously_sent = |member: &MemberUpdateResult| {
+        previous
+            .members
             .iter()
-            .map(|member| format!("- {}", member.name))
-            .collect::<Vec<_>>()
-            .join("\n");
+            .find(|m| m.member_id == member.member_id)
+            .map(|m| m.sent_update)
+    };
+    let new_defaulters = day
+        .members
+        .iter()
+        .filter(|m| !m.sent_update && previously_sent(m) == Some(true))
+        .map(|m| m.name.clone())
+        .collect();
+    let recovered = day
+        .members
+        .iter()
+        .filter(|m| m.sent_update && previously_sent(m) == Some(false))
+        .map(|m| m.name.clone())
+        .collect();
+
+    let previous_leaders = streak_leaders(previous);
+    let leaders = streak_leaders(day);
+    let overtaken = if previous_leaders.is_empty()
+        || leaders.is_empty()
+        || leaders.iter().any(|leader| {
+            previous_leaders
+                .iter()
+                .any(|p| p.member_id == leader.member_id)
+        }) {
+        None
+    } else {
+        let names = |members: Vec<&MemberUpdateResult>| {
+            members.into_iter().map(|m| m.name.clone()).collect()
+        };
+        Some(Overtaken {
+            leaders: names(leaders),
+            previous: names(previous_leaders),
+        })
+    };
 
-        format!("{}\n", list)
+    let since = if day.date.pred_opt() == Some(previous.date) {
+        String::from("Yesterday")
     } else {
-        String::from("More than five members hold this record!\n")
+        format_date(previous.date)
+    };
+    ReportDiff {
+        since,
+        new_defaulters,
+        recovered,
+        overtaken,
+    }
+}
+
+/// Members sharing the highest current streak, none if nobody has a streak.
+fn streak_leaders(day: &StatusUpdateDay) -> Vec<&MemberUpdateResult> {
+    let highest = day.members.iter().map(|m| m.current_streak).max();
+    match highest {
+        Some(highest) if highest > 0 => day
+            .members
+            .iter()
+            .filter(|m| m.current_streak == highest)
+            .collect(),
+        _ => Vec::new(),
     }
 }
 
-fn format_defaulters(naughty_list: &GroupedMember) -> String {
+fn generate_embed(
+    ctx: &Context,
+    config: &Config,
+    leaderboard: LeaderboardStats,
+    naughty_list: &GroupedMember,
+    notes: &ReportNotes,
+    resets: &StreakResets,
+    name_defaulters: bool,
+) -> CreateEmbed {
+    let theme = &config.theme;
+    let status_theme = &theme.status_update;
+    let (all_time_high, all_time_high_members, current_highest, current_highest_members) =
+        leaderboard;
+
+    let context = ReportContext {
+        theme: status_theme,
+        all_time_high: StreakRecord::new(all_time_high, &all_time_high_members),
+        current_highest: StreakRecord::new(current_highest, &current_highest_members),
+        diff: notes
+            .diff
+            .as_ref()
+            .map(|diff| match name_defaulters {
+                true => diff.clone(),
+                false => diff.without_defaulters(),
+            })
+            .filter(|diff| !diff.is_empty()),
+        late: notes.late_list.iter().map(|m| m.name.as_str()).collect(),
+        milestones: &notes.milestones,
+        duplicates: notes
+            .duplicates
+            .iter()
+            .map(|flag| DuplicateContext {
+                name: &flag.author_name,
+                similarity: format!("{:.0}", flag.similarity * 100.0),
+            })
+            .collect(),
+        defaulters: defaulter_groups(
+            status_theme,
+            &config.status_update,
+            naughty_list,
+            resets,
+            name_defaulters,
+        ),
+        resets_applied: resets.applied,
+        resets_pending: resets.pending,
+        updates_optional: resets.updates_optional,
+    };
+    let description = templates::render(
+        "status_update",
+        config.templates.status_update.as_deref(),
+        templates::STATUS_UPDATE,
+        &context,
+    );
+
+    report_embed(ctx, &theme.embed, &status_theme.title, status_theme.color)
+        .description(description)
+}
+
+/// Anonymized numbers of the report, for public channels.
+fn generate_stats_embed(
+    ctx: &Context,
+    config: &Config,
+    nice_list: &[Member],
+    naughty_list: &GroupedMember,
+    late_list: &[Member],
+) -> CreateEmbed {
+    let status_theme = &config.theme.status_update;
+    let defaulters = naughty_list.values().flatten().count();
+    let total = nice_list.len() + defaulters;
+    let streaks: Vec<i32> = nice_list
+        .iter()
+        .chain(naughty_list.values().flatten())
+        .filter_map(|member| member.streak.first())
+        .map(|streak| streak.current_streak.max(0))
+        .collect();
+    let average_streak = if streaks.is_empty() {
+        0.0
+    } else {
+        streaks.iter().sum::<i32>() as f64 / streaks.len() as f64
+    };
+
+    let description = format!(
+        "- Updates: {}/{}\n- Late: {}\n- Missed: {}\n- Average streak: {:.1} days\n",
+        nice_list.len(),
+        total,
+        late_list.len(),
+        defaulters,
+        average_streak
+    );
+    report_embed(
+        ctx,
+        &config.theme.embed,
+        &status_theme.title,
+        status_theme.color,
+    )
+    .description(description)
+}
+
+/// The part of the report concerning `group`, for that group's channel.
+fn generate_group_embed(
+    ctx: &Context,
+    config: &Config,
+    group: u64,
+    nice_list: &[Member],
+    naughty_list: &GroupedMember,
+    late_list: &[Member],
+    resets: &StreakResets,
+) -> CreateEmbed {
+    let status_theme = &config.theme.status_update;
+    // Group excerpts go to the groups' own channels, never the mentors-only one.
+    let name_defaulters = config.status_update.private_defaulters_channel_id.is_none();
+    let in_group = |member: &&Member| member.group_id as u64 == group;
+    let sent = nice_list.iter().filter(in_group).count();
+    let defaulters = naughty_list.get(&group).cloned().unwrap_or_default();
+
+    let context = GroupReportContext {
+        theme: status_theme,
+        group,
+        sent,
+        total: sent + defaulters.len(),
+        late: late_list
+            .iter()
+            .filter(in_group)
+            .map(|m| m.name.as_str())
+            .collect(),
+        defaulters: defaulter_groups(
+            status_theme,
+            &config.status_update,
+            &HashMap::from([(group, defaulters)]),
+            resets,
+            name_defaulters,
+        ),
+        resets_applied: resets.applied,
+        updates_optional: resets.updates_optional,
+    };
+    let description = templates::render(
+        "status_update_group",
+        config.templates.status_update_group.as_deref(),
+        templates::STATUS_UPDATE_GROUP,
+        &context,
+    );
+
+    report_embed(
+        ctx,
+        &config.theme.embed,
+        format!("{} - Group {}", status_theme.title, group),
+        status_theme.color,
+    )
+    .description(description)
+}
+
+/// Room left in the recognition embed's description for names, under Discord's 4096.
+const RECOGNITION_LENGTH: usize = 4000;
+
+/// Lists the members who sent their update, longest streaks first, optionally only those
+/// in `group`. Names that don't fit in the embed are counted instead.
+fn generate_recognition_embed(
+    ctx: &Context,
+    config: &Config,
+    nice_list: &[Member],
+    group: Option<u64>,
+) -> CreateEmbed {
+    let status_theme = &config.theme.status_update;
+    let mut members: Vec<(&str, i32)> = nice_list
+        .iter()
+        .filter(|member| group.is_none_or(|group| member.group_id as u64 == group))
+        .map(|member| {
+            let streak = member.streak.first().map_or(0, |s| s.current_streak);
+            (member.name.as_str(), streak)
+        })
+        .collect();
+    members.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
     let mut description = String::new();
-    for (group, missed_members) in naughty_list {
-        description.push_str(&format!("## Group {}\n", group));
-        for member in missed_members {
-            let status = match member.streak[0].current_streak {
-                0 => ":x:",
-                -1 => ":x::x:",
-                _ => ":headstone:",
-            };
-            description.push_str(&format!("- {} | {}\n", member.name, status));
+    for (listed, (name, streak)) in members.iter().enumerate() {
+        let line = format!("- {} ({} days)\n", name, streak);
+        if description.len() + line.len() > RECOGNITION_LENGTH {
+            description.push_str(&format!("…and {} more\n", members.len() - listed));
+            break;
         }
+        description.push_str(&line);
+    }
+    if members.is_empty() {
+        description.push_str("No one sent an update today.");
     }
-    description.push('\n');
-    description
+
+    report_embed(
+        ctx,
+        &config.theme.embed,
+        &status_theme.recognition_header,
+        status_theme.recognition_color,
+    )
+    .description(description)
 }
 
-async fn get_leaderboard_stats(
-    members: Vec<Member>,
-) -> anyhow::Result<(i32, Vec<Member>, i32, Vec<Member>)> {
-    let streaks = fetch_streaks().await?;
+/// What the full report template gets, see `src/templates/status_update.md`.
+#[derive(Serialize)]
+struct ReportContext<'a> {
+    theme: &'a StatusUpdateTheme,
+    all_time_high: StreakRecord<'a>,
+    current_highest: StreakRecord<'a>,
+    diff: Option<ReportDiff>,
+    late: Vec<&'a str>,
+    milestones: &'a [MilestoneContext],
+    duplicates: Vec<DuplicateContext<'a>>,
+    defaulters: Vec<DefaulterGroup<'a>>,
+    resets_applied: bool,
+    /// Resets are waiting for a mentor's approval.
+    resets_pending: bool,
+    /// Weekend rules applied, missing an update didn't count against anyone.
+    updates_optional: bool,
+}
+
+/// What the group report template gets, see `src/templates/status_update_group.md`.
+#[derive(Serialize)]
+struct GroupReportContext<'a> {
+    theme: &'a StatusUpdateTheme,
+    group: u64,
+    sent: usize,
+    total: usize,
+    late: Vec<&'a str>,
+    defaulters: Vec<DefaulterGroup<'a>>,
+    resets_applied: bool,
+    /// Weekend rules applied, missing an update didn't count against anyone.
+    updates_optional: bool,
+}
+
+#[derive(Serialize)]
+struct StreakRecord<'a> {
+    streak: i32,
+    members: Vec<&'a str>,
+}
+
+impl<'a> StreakRecord<'a> {
+    fn new(streak: i32, members: &'a [Member]) -> Self {
+        Self {
+            streak,
+            members: members.iter().map(|m| m.name.as_str()).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MilestoneContext {
+    name: String,
+    streak: i32,
+}
+
+#[derive(Serialize)]
+struct DuplicateContext<'a> {
+    name: &'a str,
+    /// Percentage, rounded.
+    similarity: String,
+}
+
+#[derive(Serialize)]
+struct DefaulterGroup<'a> {
+    group: u64,
+    mentors: Option<String>,
+    /// How many members of the group missed their update.
+    count: usize,
+    /// Empty when the report doesn't name defaulters.
+    members: Vec<Defaulter<'a>>,
+}
+
+#[derive(Serialize)]
+struct Defaulter<'a> {
+    name: String,
+    /// How many days in a row they missed as an emoji, or that their shield was used up.
+    /// Unset if resets were held back.
+    status: Option<&'a str>,
+}
+
+fn defaulter_groups<'a>(
+    theme: &'a StatusUpdateTheme,
+    config: &StatusUpdateConfig,
+    naughty_list: &GroupedMember,
+    resets: &StreakResets,
+    name_defaulters: bool,
+) -> Vec<DefaulterGroup<'a>> {
+    naughty_list
+        .iter()
+        .map(|(group, missed_members)| DefaulterGroup {
+            group: *group,
+            mentors: config.mentors_for(*group).map(|mentors| mentors.mentions()),
+            count: missed_members.len(),
+            members: missed_members
+                .iter()
+                .filter(|_| name_defaulters)
+                .map(|member| Defaulter {
+                    name: member.name.clone(),
+                    status: resets.applied.then(|| {
+                        if resets.shielded.contains(&member.member_id) {
+                            return theme.shield_used_emoji.as_str();
+                        }
+                        match member.streak.first().map(|s| s.current_streak) {
+                            Some(0) => theme.missed_once_emoji.as_str(),
+                            Some(-1) => theme.missed_twice_emoji.as_str(),
+                            _ => theme.streak_lost_emoji.as_str(),
+                        }
+                    }),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// The all-time high streak and the current highest streak, with the members holding each.
+type LeaderboardStats = (i32, Vec<Member>, i32, Vec<Member>);
+
+fn get_leaderboard_stats(members: &[Member], streaks: &[StreakWithMemberId]) -> LeaderboardStats {
     let member_map: HashMap<i32, &Member> = members.iter().map(|m| (m.member_id, m)).collect();
 
-    let (all_time_high, all_time_high_members) = find_highest_streak(&streaks, &member_map, true);
+    let (all_time_high, all_time_high_members) = find_highest_streak(streaks, &member_map, true);
     let (current_highest, current_highest_members) =
-        find_highest_streak(&streaks, &member_map, false);
+        find_highest_streak(streaks, &member_map, false);
 
-    Ok((
+    (
         all_time_high,
         all_time_high_members,
         current_highest,
         current_highest_members,
-    ))
+    )
 }
 
 fn find_highest_streak(
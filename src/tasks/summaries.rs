@@ -0,0 +1,115 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use chrono::Utc;
+use serenity::all::{ChannelId, Context, Message};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{debug, warn};
+
+use super::Task;
+use crate::{
+    config::LlmConfig,
+    llm::complete,
+    quiet_hours::{send_or_queue, Destination, QueuedMessage},
+    utils::{scan::scan_channels, time::time_until},
+    Data,
+};
+
+/// Flagged channels with fewer messages than this in a day are not summarized.
+const MIN_MESSAGES_FOR_SUMMARY: usize = 20;
+const SUMMARY_PROMPT: &str = "Summarize the following Discord discussion as a short list of \
+     bullet points for members who missed it. Mention decisions and open questions.";
+
+/// Posts a summary of the day's discussion in every flagged channel each night.
+pub struct NightlySummaries;
+
+#[async_trait]
+impl Task for NightlySummaries {
+    fn name(&self) -> &str {
+        "Nightly Summaries"
+    }
+
+    fn reads_messages(&self) -> bool {
+        true
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until(23, 0)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until(hour, minute))
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        let config = data.config.read().await.llm.clone();
+        if config.endpoint.is_none() {
+            debug!("No LLM endpoint configured, skipping nightly summaries");
+            return Ok(());
+        }
+
+        let since = Utc::now() - chrono::Duration::days(1);
+        for channel_id in config
+            .summarize_channel_ids
+            .iter()
+            .copied()
+            .map(ChannelId::new)
+        {
+            let messages = scan_channels(&ctx, &[channel_id], since, |m| !m.author.bot).await?;
+            if messages.len() < MIN_MESSAGES_FOR_SUMMARY {
+                continue;
+            }
+            let summary = match summarize_messages(&config, messages).await {
+                Ok(summary) => summary,
+                Err(e) => {
+                    warn!("Failed to summarize channel {}: {:?}", channel_id, e);
+                    continue;
+                }
+            };
+            let destination = Destination::Channel(channel_id.get());
+            send_or_queue(&ctx, data, destination, QueuedMessage::text(summary))
+                .await
+                .context("Failed to post summary")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Summarizes `messages` (newest first, as returned by Discord) into a message to post.
+pub async fn summarize_messages(
+    config: &LlmConfig,
+    messages: Vec<Message>,
+) -> anyhow::Result<String> {
+    let transcript = messages
+        .iter()
+        .rev()
+        .filter(|m| !m.content.is_empty())
+        .map(|m| format!("{}: {}", m.author.name, m.content))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let summary = complete(config, SUMMARY_PROMPT, &transcript).await?;
+
+    let content = format!(
+        "**Summary of the last {} messages**\n{}",
+        messages.len(),
+        summary
+    );
+    Ok(content)
+}
@@ -0,0 +1,152 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serenity::all::{Context, CreateMessage};
+use tracing::{debug, warn};
+
+use super::status_update::ReceivedUpdate;
+use crate::{
+    config::LlmConfig,
+    llm::complete,
+    preferences::{allows, Notification},
+    storage::Storage,
+    utils::broadcast::broadcast,
+    Data,
+};
+
+const QUALITY_SCORES_KEY: &str = "update_quality.scores";
+const FEEDBACK_PROMPT: &str = "You review daily status updates from students in a coding \
+     club. Score the update's clarity and completeness from 1 to 10 and give one or two \
+     sentences of gentle, encouraging feedback. Reply only with JSON of the form \
+     {\"score\": <number>, \"feedback\": \"<text>\"}.";
+
+/// Quality score given to a member's status update on a given day.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QualityScore {
+    pub date: NaiveDate,
+    pub discord_id: String,
+    pub score: u8,
+}
+
+#[derive(Deserialize)]
+struct Review {
+    score: u8,
+    feedback: String,
+}
+
+/// Scores every member's first valid update of the day, DMs them the feedback and
+/// stores the scores for the monthly consistency awards. Failures are only logged.
+pub async fn review_updates(
+    ctx: &Context,
+    data: &Data,
+    config: &LlmConfig,
+    date: NaiveDate,
+    updates: &[ReceivedUpdate],
+) {
+    let storage = &data.storage;
+    let mut reviewed = HashSet::new();
+    let mut scores = Vec::new();
+    let mut messages = Vec::new();
+
+    // Updates are sorted oldest first, review each member's earliest update.
+    for update in updates {
+        if !reviewed.insert(update.author_id) {
+            continue;
+        }
+
+        let review = match review_update(config, &update.content).await {
+            Ok(review) => review,
+            Err(e) => {
+                warn!(
+                    "Failed to review update from {}: {:?}",
+                    update.author_name, e
+                );
+                continue;
+            }
+        };
+        debug!("Update from {} scored {}", update.author_name, review.score);
+
+        if !allows(storage, update.author_id, Notification::UpdateFeedback).await {
+            continue;
+        }
+        let dm = CreateMessage::new().content(format!(
+            "Thanks for your status update! {} (clarity score: {}/10)",
+            review.feedback, review.score
+        ));
+        messages.push((update.author_id, dm));
+
+        scores.push(QualityScore {
+            date,
+            discord_id: update.author_id.to_string(),
+            score: review.score,
+        });
+    }
+
+    let result = storage
+        .update(QUALITY_SCORES_KEY, |stored: &mut Vec<QualityScore>| {
+            stored.retain(|s| s.date != date);
+            stored.extend(scores);
+        })
+        .await;
+    if let Err(e) = result {
+        warn!("Failed to store update quality scores: {:?}", e);
+    }
+
+    broadcast(ctx, data, "Update feedback", messages).await;
+}
+
+pub async fn forget_member(storage: &Storage, discord_id: &str) -> anyhow::Result<()> {
+    storage
+        .update(QUALITY_SCORES_KEY, |scores: &mut Vec<QualityScore>| {
+            scores.retain(|s| s.discord_id != discord_id)
+        })
+        .await
+}
+
+async fn review_update(config: &LlmConfig, content: &str) -> anyhow::Result<Review> {
+    let reply = complete(config, FEEDBACK_PROMPT, content).await?;
+    // Models sometimes wrap JSON in a code block, only parse the object itself.
+    let start = reply.find('{');
+    let end = reply.rfind('}');
+    let Some((start, end)) = start.zip(end).filter(|(start, end)| start < end) else {
+        return Err(anyhow!("LLM reply is not JSON: {}", reply));
+    };
+
+    let review: Review = serde_json::from_str(&reply[start..=end])?;
+    Ok(Review {
+        score: review.score.clamp(1, 10),
+        ..review
+    })
+}
+
+/// All stored scores between `from` and `to`, inclusive.
+pub async fn scores_between(
+    storage: &Storage,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<Vec<QualityScore>> {
+    let scores: Vec<QualityScore> = storage.get(QUALITY_SCORES_KEY).await?;
+    Ok(scores
+        .into_iter()
+        .filter(|s| s.date >= from && s.date <= to)
+        .collect())
+}
@@ -0,0 +1,220 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use chrono::Weekday;
+use serenity::all::{Context, CreateAttachment, CreateMessage, Permissions};
+use serenity::async_trait;
+use tokio::time::Duration;
+use tracing::{trace, warn};
+
+use super::Task;
+use crate::{
+    activity::{group_activity, GroupActivity},
+    charts::{render_calendar_heatmap, render_line_chart},
+    history::{latest_resource_week, recent_attendance_days, recent_status_update_days},
+    ids::{self, ChannelKind},
+    inventory::{outstanding_by_member, CheckoutStatus},
+    metrics::timed,
+    utils::{
+        embed::report_embed,
+        permissions::{check_permissions, POST_EMBEDS},
+        time::{local_today, time_until_weekday},
+    },
+    Data,
+};
+
+const SUMMARY_DAYS: usize = 7;
+const STREAK_CHART_DAYS: usize = 30;
+const STREAK_CHART_FILENAME: &str = "club_streak.png";
+/// Twelve weeks of attendance are shown in the heatmap.
+const HEATMAP_DAYS: usize = 12 * 7;
+const HEATMAP_FILENAME: &str = "attendance_heatmap.png";
+
+/// Posts a summary of the past week every Sunday evening.
+pub struct WeeklySummary;
+
+#[async_trait]
+impl Task for WeeklySummary {
+    fn name(&self) -> &str {
+        "Weekly Summary"
+    }
+
+    fn run_in(&self) -> Duration {
+        time_until_weekday(Weekday::Sun, 18, 30)
+    }
+
+    fn run_in_at(&self, hour: u32, minute: u32) -> Option<Duration> {
+        Some(time_until_weekday(Weekday::Sun, hour, minute))
+    }
+
+    fn depends_on(&self) -> &[&'static str] {
+        &["Status Update Check", "Resource Sharing Check"]
+    }
+
+    async fn run(&self, ctx: Context, data: &Data) -> anyhow::Result<()> {
+        post_weekly_summary(ctx, data).await
+    }
+}
+
+async fn post_weekly_summary(ctx: Context, data: &Data) -> anyhow::Result<()> {
+    trace!("Building weekly summary");
+    let days = recent_status_update_days(&data.storage, STREAK_CHART_DAYS).await?;
+    let theme = data.config.read().await.theme.clone();
+
+    let week = &days[days.len().saturating_sub(SUMMARY_DAYS)..];
+    let mut description = String::from("# Status Updates\n");
+    if week.is_empty() {
+        description.push_str("No status update checks were recorded this week.\n");
+    }
+    for day in week {
+        description.push_str(&format!(
+            "- **{}**: {}/{} updates\n",
+            day.date.format("%a, %b %d"),
+            day.senders().count(),
+            day.members.len()
+        ));
+    }
+
+    let today = local_today();
+    let resource_week = latest_resource_week(&data.storage)
+        .await?
+        .filter(|week| today - week.week_ending < chrono::Duration::days(7));
+    if let Some(week) = resource_week {
+        let total = week.shared.len() + week.missing.len();
+        description.push_str(&format!(
+            "# Resource Sharing\n{}/{} members shared a resource this week.\n",
+            week.shared.len(),
+            total
+        ));
+        if !week.missing.is_empty() {
+            description.push_str(&format!("Missing: {}\n", week.missing.join(", ")));
+        }
+    }
+
+    let activity = group_activity(&data.storage, today - chrono::Duration::days(7)).await?;
+    description.push_str(&format_group_activity(&activity));
+    description.push_str(&format_outstanding_items(data).await?);
+
+    let mut embed = report_embed(
+        &ctx,
+        &theme.embed,
+        "Weekly Summary",
+        theme.status_update.color,
+    )
+    .description(description);
+    let mut message = CreateMessage::new();
+
+    let points: Vec<_> = days
+        .iter()
+        .map(|day| (day.date, day.average_streak()))
+        .collect();
+    match render_line_chart("Average Club Streak", "Days", &points) {
+        Ok(png) => {
+            embed = embed.image(format!("attachment://{}", STREAK_CHART_FILENAME));
+            message = message.add_file(CreateAttachment::bytes(png, STREAK_CHART_FILENAME));
+        }
+        Err(e) => warn!("Skipping streak chart in weekly summary: {:?}", e),
+    }
+
+    let mut embeds = vec![embed];
+    let attendance_days = recent_attendance_days(&data.storage, HEATMAP_DAYS).await?;
+    let values: Vec<_> = attendance_days
+        .iter()
+        .map(|day| (day.date, day.attendance_percentage()))
+        .collect();
+    match render_calendar_heatmap("Lab Attendance (%)", &values) {
+        Ok(png) => {
+            embeds.push(
+                report_embed(
+                    &ctx,
+                    &theme.embed,
+                    "Lab Activity",
+                    theme.status_update.color,
+                )
+                .image(format!("attachment://{}", HEATMAP_FILENAME)),
+            );
+            message = message.add_file(CreateAttachment::bytes(png, HEATMAP_FILENAME));
+        }
+        Err(e) => warn!("Skipping attendance heatmap in weekly summary: {:?}", e),
+    }
+
+    check_permissions(
+        &ctx,
+        ids::channel(ChannelKind::StatusUpdate).get(),
+        POST_EMBEDS | Permissions::ATTACH_FILES,
+    )?;
+    timed(
+        "discord.send_message",
+        ids::channel(ChannelKind::StatusUpdate).send_message(&ctx.http, message.embeds(embeds)),
+    )
+    .await
+    .context("Failed to send weekly summary")?;
+
+    Ok(())
+}
+
+/// Hardware each member still has out or waiting for approval, empty if nothing is out.
+async fn format_outstanding_items(data: &Data) -> anyhow::Result<String> {
+    let by_member = outstanding_by_member(&data.storage).await?;
+    if by_member.is_empty() {
+        return Ok(String::new());
+    }
+    let mut description = String::from("# Outstanding Hardware\n");
+    for (user_id, checkouts) in by_member {
+        let items: Vec<String> = checkouts
+            .iter()
+            .map(|checkout| match checkout.status {
+                CheckoutStatus::Pending => format!("{} (awaiting approval)", checkout.item),
+                _ => checkout.item.clone(),
+            })
+            .collect();
+        description.push_str(&format!("- <@{}>: {}\n", user_id, items.join(", ")));
+    }
+    Ok(description)
+}
+
+fn format_group_activity(activity: &[GroupActivity]) -> String {
+    let mut description = String::from("# Group Activity\n");
+    for group in activity {
+        let response = group
+            .average_response
+            .map(|duration| format!(", replies in {}m on average", duration.num_minutes()))
+            .unwrap_or_default();
+        description.push_str(&format!(
+            "- Group {}: {} messages from {} members{}\n",
+            group.group, group.messages, group.active_members, response
+        ));
+    }
+
+    let most_active = activity.iter().max_by_key(|group| group.messages);
+    let quietest = activity.iter().min_by_key(|group| group.messages);
+    if let (Some(most_active), Some(quietest)) = (most_active, quietest) {
+        if most_active.messages > quietest.messages {
+            description.push_str(&format!(
+                "## Most Active Group\nGroup {} with {} messages\n",
+                most_active.group, most_active.messages
+            ));
+            description.push_str(&format!(
+                "## Quietest Group\nGroup {} with {} messages\n",
+                quietest.group, quietest.messages
+            ));
+        }
+    }
+
+    description
+}
@@ -0,0 +1,58 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::Context as _;
+use serde::Serialize;
+use tera::{Context, Tera};
+use tracing::{error, warn};
+
+/// Built-in body of the full status update report.
+pub const STATUS_UPDATE: &str = include_str!("templates/status_update.md");
+/// Built-in body of a group's excerpt of the status update report.
+pub const STATUS_UPDATE_GROUP: &str = include_str!("templates/status_update_group.md");
+
+/// Renders `custom` if the config overrides the template, otherwise `default`. A custom
+/// template that fails to render falls back to the default so the report still goes out.
+pub fn render(name: &str, custom: Option<&str>, default: &str, context: &impl Serialize) -> String {
+    if let Some(custom) = custom {
+        match try_render(custom, context) {
+            Ok(rendered) => return rendered,
+            Err(e) => warn!(
+                "Custom {} template failed, using the default: {:?}",
+                name, e
+            ),
+        }
+    }
+
+    try_render(default, context).unwrap_or_else(|e| {
+        error!("Default {} template failed: {:?}", name, e);
+        String::from("This report could not be rendered, see the logs.")
+    })
+}
+
+/// Parses `template` without rendering it, so a broken template is rejected when the
+/// config is loaded.
+pub fn check(name: &str, template: &str) -> anyhow::Result<()> {
+    Tera::default()
+        .add_raw_template(name, template)
+        .with_context(|| format!("Invalid {} template", name))
+}
+
+fn try_render(template: &str, context: &impl Serialize) -> anyhow::Result<String> {
+    let context = Context::from_serialize(context).context("Failed to build template context")?;
+    Tera::one_off(template, &context, false).context("Failed to render template")
+}
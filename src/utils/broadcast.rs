@@ -0,0 +1,139 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex as StdMutex},
+};
+
+use serenity::all::{ChannelId, Context, CreateMessage, UserId};
+use tokio::{sync::Mutex, time::Duration};
+use tracing::{debug, info, warn};
+
+use crate::{utils::long_message::send_long, Data};
+
+/// Pause between two DMs of a broadcast. Opening a DM channel and sending to it are two
+/// requests, so this keeps a fan-out well clear of Discord's global rate limit.
+const DM_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One lock per member being DMed, so broadcasts run side by side but a member's DMs from
+/// two of them are sent one after the other.
+static RECIPIENT_LOCKS: LazyLock<StdMutex<HashMap<u64, Arc<Mutex<()>>>>> =
+    LazyLock::new(Default::default);
+
+/// How a broadcast went, posted to the ops channel when some DMs failed.
+pub struct BroadcastSummary {
+    pub delivered: usize,
+    /// Members whose DMs failed, usually because they don't accept DMs from the server.
+    pub failed: Vec<u64>,
+}
+
+/// DMs each user their message, paced to stay within rate limits, and posts who couldn't
+/// be reached to the ops channel. `label` names the broadcast in the summary,
+/// e.g. "Defaulter notices". Callers are expected to have filtered out members who opted
+/// out of this kind of DM.
+pub async fn broadcast(
+    ctx: &Context,
+    data: &Data,
+    label: &str,
+    messages: Vec<(u64, CreateMessage)>,
+) -> BroadcastSummary {
+    let mut summary = BroadcastSummary {
+        delivered: 0,
+        failed: Vec::new(),
+    };
+    if messages.is_empty() {
+        return summary;
+    }
+
+    debug!("Broadcasting {} to {} members", label, messages.len());
+    for (index, (user_id, message)) in messages.into_iter().enumerate() {
+        if index > 0 {
+            tokio::time::sleep(DM_INTERVAL).await;
+        }
+        let lock = recipient_lock(user_id);
+        let guard = lock.lock().await;
+        let result = UserId::new(user_id)
+            .direct_message(&ctx.http, message)
+            .await;
+        drop(guard);
+        release_recipient_lock(user_id, lock);
+        match result {
+            Ok(_) => summary.delivered += 1,
+            Err(e) => {
+                warn!("Failed to send {} to {}: {}", label, user_id, e);
+                summary.failed.push(user_id);
+            }
+        }
+    }
+
+    info!(
+        "{}: delivered {}, failed {}",
+        label,
+        summary.delivered,
+        summary.failed.len()
+    );
+    if !summary.failed.is_empty() {
+        report_summary(ctx, data, label, &summary).await;
+    }
+    summary
+}
+
+fn recipient_lock(user_id: u64) -> Arc<Mutex<()>> {
+    RECIPIENT_LOCKS
+        .lock()
+        .expect("Broadcast lock map poisoned")
+        .entry(user_id)
+        .or_default()
+        .clone()
+}
+
+/// Forgets the lock of `user_id` once no broadcast holds or waits for it anymore.
+fn release_recipient_lock(user_id: u64, lock: Arc<Mutex<()>>) {
+    let mut locks = RECIPIENT_LOCKS.lock().expect("Broadcast lock map poisoned");
+    drop(lock);
+    if locks
+        .get(&user_id)
+        .is_some_and(|lock| Arc::strong_count(lock) == 1)
+    {
+        locks.remove(&user_id);
+    }
+}
+
+async fn report_summary(ctx: &Context, data: &Data, label: &str, summary: &BroadcastSummary) {
+    let Some(channel_id) = data.config.read().await.bot.ops_channel_id else {
+        return;
+    };
+
+    let total = summary.delivered + summary.failed.len();
+    let failed: Vec<String> = summary
+        .failed
+        .iter()
+        .map(|id| format!("<@{}>", id))
+        .collect();
+    let content = format!(
+        "{}: delivered {}/{}. Couldn't reach {}.",
+        label,
+        summary.delivered,
+        total,
+        failed.join(", ")
+    );
+    let filename = "broadcast_summary.md";
+    if let Err(e) = send_long(&ctx.http, ChannelId::new(channel_id), &content, filename).await {
+        warn!("Failed to post the {} summary: {}", label, e);
+    }
+}
@@ -0,0 +1,317 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::anyhow;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    AutoArchiveDuration, ChannelId, Context, CreateEmbed, CreateMessage, CreateThread, EditMessage,
+    EditThread, EmbedField, GuildId, Message, MessageId,
+};
+use tracing::warn;
+
+use crate::{
+    config::{ReportDelivery, ReportDetail, ReportKind},
+    metrics::timed,
+    storage::Storage,
+    utils::{
+        permissions::{check_permissions, POST_EMBEDS},
+        time::format_day,
+    },
+    Data,
+};
+
+/// The pinned message of the latest full report of each kind.
+const LATEST_REPORTS_KEY: &str = "reports.latest";
+/// The discussion thread under the latest pinned report of each kind.
+const DISCUSSION_THREADS_KEY: &str = "reports.threads";
+/// Every full report message of each kind by date, so corrections can annotate them.
+const REPORT_MESSAGES_KEY: &str = "reports.messages";
+/// How many days of report messages are kept.
+const REPORT_MESSAGE_DAYS: usize = 31;
+/// Name of the embed field corrections are listed in.
+const CORRECTIONS_FIELD: &str = "Corrections";
+
+type ReportMessages = HashMap<ReportKind, BTreeMap<NaiveDate, Vec<ReportMessage>>>;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ReportMessage {
+    pub channel_id: u64,
+    pub message_id: u64,
+}
+
+impl ReportMessage {
+    pub fn link(&self, guild_id: Option<GuildId>) -> String {
+        MessageId::new(self.message_id).link(ChannelId::new(self.channel_id), guild_id)
+    }
+}
+
+pub async fn latest_report(
+    storage: &Storage,
+    report: ReportKind,
+) -> anyhow::Result<Option<ReportMessage>> {
+    let latest: HashMap<ReportKind, ReportMessage> = storage.get(LATEST_REPORTS_KEY).await?;
+    Ok(latest.get(&report).copied())
+}
+
+/// Sends a report to each configured destination, falling back to `default_channel_id`.
+/// `render` builds the variant of the report for a destination, or returns [`None`] to
+/// skip it. A failing destination doesn't stop the others, but the whole delivery is
+/// reported as failed.
+///
+/// The first full report sent is pinned in place of the previous one, and every full
+/// report is remembered as the report of `date`.
+pub async fn deliver_report(
+    ctx: &Context,
+    data: &Data,
+    report: ReportKind,
+    date: NaiveDate,
+    default_channel_id: u64,
+    render: impl Fn(&ReportDelivery) -> Option<CreateMessage>,
+) -> anyhow::Result<()> {
+    let (deliveries, discussion_threads) = {
+        let config = data.config.read().await;
+        (
+            config.reports.deliveries_for(report, default_channel_id),
+            config.reports.discussion_threads,
+        )
+    };
+
+    let mut failed = 0;
+    let mut missing_permissions = Vec::new();
+    let mut pinned = false;
+    for delivery in &deliveries {
+        let Some(message) = render(delivery) else {
+            continue;
+        };
+        if let Err(e) = check_permissions(ctx, delivery.channel_id, POST_EMBEDS) {
+            warn!("Skipping report delivery: {}", e);
+            missing_permissions.push(e.to_string());
+            failed += 1;
+            continue;
+        }
+        let result = timed(
+            "discord.send_message",
+            ChannelId::new(delivery.channel_id).send_message(&ctx.http, message),
+        )
+        .await;
+        match result {
+            Ok(message) if delivery.detail == ReportDetail::Full => {
+                if !pinned {
+                    pin_report(ctx, &data.storage, report, &message).await;
+                    if discussion_threads {
+                        open_discussion(ctx, &data.storage, report, date, &message).await;
+                    }
+                    pinned = true;
+                }
+                remember_report(&data.storage, report, date, &message).await;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!(
+                    "Failed to deliver report to channel {}: {}",
+                    delivery.channel_id, e
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        let mut error = format!(
+            "Failed to deliver the report to {} of {} channels",
+            failed,
+            deliveries.len()
+        );
+        if !missing_permissions.is_empty() {
+            error.push_str(&format!(": {}", missing_permissions.join(", ")));
+        }
+        return Err(anyhow!(error));
+    }
+    Ok(())
+}
+
+/// Pins `message`, unpins the previous report of the same kind and remembers the new one.
+/// Failures are only logged, the report itself was delivered.
+async fn pin_report(ctx: &Context, storage: &Storage, report: ReportKind, message: &Message) {
+    if let Err(e) = message.pin(&ctx.http).await {
+        warn!("Failed to pin {:?} report: {}", report, e);
+    }
+
+    let current = ReportMessage {
+        channel_id: message.channel_id.get(),
+        message_id: message.id.get(),
+    };
+    let previous = storage
+        .update(
+            LATEST_REPORTS_KEY,
+            |latest: &mut HashMap<ReportKind, ReportMessage>| latest.insert(report, current),
+        )
+        .await;
+    match previous {
+        Ok(Some(previous)) => {
+            if let Err(e) = ChannelId::new(previous.channel_id)
+                .unpin(&ctx.http, previous.message_id)
+                .await
+            {
+                warn!("Failed to unpin previous {:?} report: {}", report, e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to store latest {:?} report: {:?}", report, e),
+    }
+}
+
+/// Opens a discussion thread under `message` and archives the one under the previous
+/// report of the same kind. Failures are only logged, the report itself was delivered.
+async fn open_discussion(
+    ctx: &Context,
+    storage: &Storage,
+    report: ReportKind,
+    date: NaiveDate,
+    message: &Message,
+) {
+    let name = format!("Discussion — {}", format_day(date));
+    let thread = CreateThread::new(name).auto_archive_duration(AutoArchiveDuration::OneDay);
+    let thread_id = match message
+        .channel_id
+        .create_thread_from_message(&ctx.http, message.id, thread)
+        .await
+    {
+        Ok(thread) => thread.id.get(),
+        Err(e) => {
+            warn!(
+                "Failed to open a discussion for the {:?} report: {}",
+                report, e
+            );
+            return;
+        }
+    };
+
+    let previous = storage
+        .update(
+            DISCUSSION_THREADS_KEY,
+            |threads: &mut HashMap<ReportKind, u64>| threads.insert(report, thread_id),
+        )
+        .await;
+    match previous {
+        Ok(Some(previous)) => {
+            if let Err(e) = ChannelId::new(previous)
+                .edit_thread(&ctx.http, EditThread::new().archived(true))
+                .await
+            {
+                warn!(
+                    "Failed to archive the previous {:?} discussion: {}",
+                    report, e
+                );
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to store the {:?} discussion: {:?}", report, e),
+    }
+}
+
+async fn remember_report(
+    storage: &Storage,
+    report: ReportKind,
+    date: NaiveDate,
+    message: &Message,
+) {
+    let current = ReportMessage {
+        channel_id: message.channel_id.get(),
+        message_id: message.id.get(),
+    };
+    let result = storage
+        .update(REPORT_MESSAGES_KEY, |messages: &mut ReportMessages| {
+            let days = messages.entry(report).or_default();
+            days.entry(date).or_default().push(current);
+            while days.len() > REPORT_MESSAGE_DAYS {
+                days.pop_first();
+            }
+        })
+        .await;
+    if let Err(e) = result {
+        warn!("Failed to store {:?} report message: {:?}", report, e);
+    }
+}
+
+/// The full report messages of `date`, oldest first.
+async fn report_messages(
+    storage: &Storage,
+    report: ReportKind,
+    date: NaiveDate,
+) -> anyhow::Result<Vec<ReportMessage>> {
+    let messages: ReportMessages = storage.get(REPORT_MESSAGES_KEY).await?;
+    Ok(messages
+        .get(&report)
+        .and_then(|days| days.get(&date))
+        .cloned()
+        .unwrap_or_default())
+}
+
+/// Adds `note` to the corrections listed under each full report of `date`. Returns how
+/// many messages were annotated, messages that can't be edited any more are skipped.
+pub async fn annotate_report(
+    ctx: &Context,
+    storage: &Storage,
+    report: ReportKind,
+    date: NaiveDate,
+    note: &str,
+) -> anyhow::Result<usize> {
+    let mut annotated = 0;
+    for report_message in report_messages(storage, report, date).await? {
+        let channel_id = ChannelId::new(report_message.channel_id);
+        let mut message = match channel_id
+            .message(&ctx.http, report_message.message_id)
+            .await
+        {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Failed to fetch {:?} report to annotate: {}", report, e);
+                continue;
+            }
+        };
+        let Some(mut embed) = message.embeds.first().cloned() else {
+            continue;
+        };
+
+        let line = format!("- {}", note);
+        match embed
+            .fields
+            .iter_mut()
+            .find(|f| f.name == CORRECTIONS_FIELD)
+        {
+            Some(field) => field.value = format!("{}\n{}", field.value, line),
+            None => embed
+                .fields
+                .push(EmbedField::new(CORRECTIONS_FIELD, line, false)),
+        }
+        let mut embeds: Vec<CreateEmbed> = message.embeds.drain(..).map(Into::into).collect();
+        embeds[0] = embed.into();
+
+        match message
+            .edit(&ctx.http, EditMessage::new().embeds(embeds))
+            .await
+        {
+            Ok(()) => annotated += 1,
+            Err(e) => warn!("Failed to annotate {:?} report: {}", report, e),
+        }
+    }
+    Ok(annotated)
+}
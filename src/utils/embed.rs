@@ -0,0 +1,54 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::Utc;
+use serenity::all::{
+    Colour, Context as SerenityContext, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter,
+};
+
+use crate::config::EmbedTheme;
+
+/// Discord caps the characters across all of a message's embeds at this.
+pub const EMBED_TOTAL_LIMIT: usize = 6000;
+
+/// Builds the skeleton shared by all report embeds (title, author, colour, footer)
+/// from the configured [`EmbedTheme`]. Callers only need to add a description.
+pub fn report_embed(
+    ctx: &SerenityContext,
+    theme: &EmbedTheme,
+    title: impl Into<String>,
+    color: u32,
+) -> CreateEmbed {
+    let bot_avatar_url = ctx.cache.current_user().face();
+
+    let mut embed = CreateEmbed::new()
+        .title(title)
+        .url(&theme.title_url)
+        .author(
+            CreateEmbedAuthor::new(&theme.author_name)
+                .url(&theme.author_url)
+                .icon_url(bot_avatar_url),
+        )
+        .color(Colour::new(color))
+        .timestamp(Utc::now());
+
+    if let Some(footer) = &theme.footer {
+        embed = embed.footer(CreateEmbedFooter::new(footer));
+    }
+
+    embed
+}
@@ -0,0 +1,48 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::{anyhow, Context as _};
+use poise::CreateReply;
+use serde::Serialize;
+use serenity::all::CreateAttachment;
+
+/// The option that asks a data command for its raw data as well.
+pub const JSON_FLAG: &str = "--json";
+
+/// Whether the command's trailing option asks for JSON, rejecting any other option so a
+/// typo doesn't silently fall back to the embed alone.
+pub fn wants_json(option: Option<&str>) -> anyhow::Result<bool> {
+    match option {
+        None => Ok(false),
+        Some(JSON_FLAG) => Ok(true),
+        Some(other) => Err(anyhow!(
+            "Unknown option `{}`, only `{}` is supported",
+            other,
+            JSON_FLAG
+        )),
+    }
+}
+
+/// Attaches `data` as `<name>.json` so scripts can read what the reply shows.
+pub fn attach_json(
+    reply: CreateReply,
+    name: &str,
+    data: &impl Serialize,
+) -> anyhow::Result<CreateReply> {
+    let json = serde_json::to_string_pretty(data).context("Failed to serialize the export")?;
+    Ok(reply.attachment(CreateAttachment::bytes(json, format!("{}.json", name))))
+}
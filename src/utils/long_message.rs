@@ -0,0 +1,166 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use poise::CreateReply;
+use serenity::all::{ChannelId, CreateAllowedMentions, CreateAttachment, CreateMessage, Http};
+
+use crate::{Context, Error};
+
+/// Discord's limit on the content length of a single message.
+pub const CONTENT_LIMIT: usize = 2000;
+/// Content that would take more messages than this is attached as a file instead.
+const MAX_PARTS: usize = 3;
+/// Room kept in every part to close and reopen a code block cut by the split.
+const FENCE_ROOM: usize = 32;
+const FENCE: &str = "```";
+/// How much of attached content is shown in the message itself.
+const PREVIEW_LENGTH: usize = 200;
+
+/// Splits `content` into parts that each fit in a message. Parts break at line ends, or
+/// at spaces within overlong lines, and a code block cut in two is closed and reopened
+/// so both halves still render.
+pub fn split_content(content: &str) -> Vec<String> {
+    let limit = CONTENT_LIMIT - FENCE_ROOM;
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut open_fence: Option<&str> = None;
+
+    for line in content.split_inclusive('\n') {
+        for piece in split_line(line, limit) {
+            if !part.is_empty() && length(&part) + length(piece) > limit {
+                if open_fence.is_some() {
+                    if !part.ends_with('\n') {
+                        part.push('\n');
+                    }
+                    part.push_str(FENCE);
+                }
+                parts.push(std::mem::take(&mut part));
+                if let Some(opener) = open_fence {
+                    part.push_str(opener);
+                    part.push('\n');
+                }
+            }
+            part.push_str(piece);
+        }
+
+        let trimmed = line.trim();
+        if trimmed.starts_with(FENCE) {
+            open_fence = match open_fence {
+                Some(_) => None,
+                // A language tag too long for the room kept is dropped when reopening.
+                None if trimmed.len() < FENCE_ROOM - FENCE.len() => Some(trimmed),
+                None => Some(FENCE),
+            };
+        }
+    }
+    if !part.trim().is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+/// The messages that carry `content`: follow-ups in order, or a single message with the
+/// content attached as `filename` when it would take more than a few.
+pub fn long_messages(content: &str, filename: &str) -> Vec<CreateMessage> {
+    let parts = split_content(content);
+    if parts.len() > MAX_PARTS {
+        return vec![attached(content, filename)];
+    }
+    parts
+        .into_iter()
+        .map(|part| CreateMessage::new().content(part))
+        .collect()
+}
+
+/// A single message carrying `content`, attached as `filename` if it's too long. For
+/// senders that need exactly one message per recipient, like broadcasts.
+pub fn long_message(content: &str, filename: &str) -> CreateMessage {
+    if length(content) <= CONTENT_LIMIT {
+        return CreateMessage::new().content(content);
+    }
+    attached(content, filename)
+}
+
+/// Sends `content` to `channel_id`, however long it is. Meant for reports and lists, so
+/// mentions in it don't ping anyone.
+pub async fn send_long(
+    http: &Http,
+    channel_id: ChannelId,
+    content: &str,
+    filename: &str,
+) -> serenity::Result<()> {
+    for message in long_messages(content, filename) {
+        channel_id
+            .send_message(http, message.allowed_mentions(CreateAllowedMentions::new()))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Replies with `content`, however long it is.
+pub async fn say_long(ctx: Context<'_>, content: &str, filename: &str) -> Result<(), Error> {
+    let parts = split_content(content);
+    if parts.len() > MAX_PARTS {
+        ctx.send(CreateReply::default().content(preview(content)).attachment(
+            CreateAttachment::bytes(content.as_bytes().to_vec(), filename),
+        ))
+        .await?;
+        return Ok(());
+    }
+    for part in parts {
+        ctx.say(part).await?;
+    }
+    Ok(())
+}
+
+fn attached(content: &str, filename: &str) -> CreateMessage {
+    CreateMessage::new()
+        .content(preview(content))
+        .add_file(CreateAttachment::bytes(
+            content.as_bytes().to_vec(),
+            filename,
+        ))
+}
+
+fn preview(content: &str) -> String {
+    let first_line = content.lines().next().unwrap_or_default();
+    let shown: String = first_line.chars().take(PREVIEW_LENGTH).collect();
+    format!("{}…\n*The full text is attached.*", shown.trim_end())
+}
+
+/// Splits a line longer than `limit` at the last space that fits, or mid-word if none does.
+fn split_line(mut line: &str, limit: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    while length(line) > limit {
+        let end = line
+            .char_indices()
+            .nth(limit)
+            .map_or(line.len(), |(index, _)| index);
+        let cut = line[..end]
+            .rfind([' ', '\t'])
+            .map_or(end, |index| index + 1);
+        pieces.push(&line[..cut]);
+        line = &line[cut..];
+    }
+    pieces.push(line);
+    pieces
+}
+
+/// Discord counts characters, not bytes.
+fn length(text: &str) -> usize {
+    text.chars().count()
+}
@@ -15,4 +15,11 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
+pub mod broadcast;
+pub mod delivery;
+pub mod embed;
+pub mod export;
+pub mod long_message;
+pub mod permissions;
+pub mod scan;
 pub mod time;
@@ -0,0 +1,69 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use anyhow::anyhow;
+use serenity::all::{ChannelId, ComponentInteraction, Context, Permissions};
+use tracing::debug;
+
+/// What a task needs to post an embed.
+pub const POST_EMBEDS: Permissions = Permissions::SEND_MESSAGES.union(Permissions::EMBED_LINKS);
+
+/// Checks with the cached guild data that the bot has `needed` in `channel_id`, so a task
+/// fails naming the missing permission instead of with an opaque HTTP error. The error
+/// reaches the ops channel through the scheduler. Channels that aren't cached are assumed
+/// to be fine, the send itself reports any problem then.
+pub fn check_permissions(
+    ctx: &Context,
+    channel_id: u64,
+    needed: Permissions,
+) -> anyhow::Result<()> {
+    let bot_id = ctx.cache.current_user().id;
+    let channel = ChannelId::new(channel_id);
+    let permissions = ctx.cache.guilds().into_iter().find_map(|guild_id| {
+        let guild = ctx.cache.guild(guild_id)?;
+        let channel = guild.channels.get(&channel)?;
+        let member = guild.members.get(&bot_id)?;
+        Some(guild.user_permissions_in(channel, member))
+    });
+    let Some(permissions) = permissions else {
+        debug!(
+            "Channel {} isn't cached, skipping the permission check",
+            channel_id
+        );
+        return Ok(());
+    };
+
+    let missing = needed - permissions;
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "The bot is missing {} in <#{}>",
+        missing,
+        channel_id
+    ))
+}
+
+/// Whether whoever clicked `component` has all of `needed`, for buttons only mentors may
+/// use. Clicks outside a guild never do.
+pub fn clicker_has(component: &ComponentInteraction, needed: Permissions) -> bool {
+    component
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.contains(needed))
+}
@@ -0,0 +1,110 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use chrono::{DateTime, Utc};
+use serenity::all::{CacheHttp, ChannelId, ChannelType, GetMessages, Message};
+use tracing::debug;
+
+use crate::metrics::timed;
+
+/// Discord's maximum page size for fetching channel history.
+pub(crate) const PAGE_SIZE: u8 = 100;
+
+/// Fetches every message sent in `channels` after `since`, paging back through the
+/// history as needed, and keeps only those accepted by `is_valid`.
+///
+/// This is the shared machinery behind the status update and resource sharing checks,
+/// which only differ in the channels they look at and the rules in `is_valid`.
+pub async fn scan_channels(
+    cache_http: impl CacheHttp,
+    channels: &[ChannelId],
+    since: DateTime<Utc>,
+    is_valid: impl Fn(&Message) -> bool,
+) -> anyhow::Result<Vec<Message>> {
+    let mut matching = Vec::new();
+
+    for channel in channels {
+        let mut builder = GetMessages::new().limit(PAGE_SIZE);
+        loop {
+            let messages = timed(
+                "discord.get_messages",
+                channel.messages(cache_http.http(), builder),
+            )
+            .await?;
+            let Some(oldest) = messages.last() else {
+                break;
+            };
+            let reached_since = oldest.timestamp.unix_timestamp() < since.timestamp();
+            let is_last_page = messages.len() < PAGE_SIZE as usize;
+            builder = GetMessages::new().before(oldest.id).limit(PAGE_SIZE);
+
+            matching.extend(messages.into_iter().filter(|message| {
+                message.timestamp.unix_timestamp() >= since.timestamp() && is_valid(message)
+            }));
+
+            if reached_since || is_last_page {
+                break;
+            }
+        }
+        debug!(
+            "Scanned channel {}, {} matches so far",
+            channel,
+            matching.len()
+        );
+    }
+
+    Ok(matching)
+}
+
+/// Replaces the forum channels among `channels` with their posts that were active after
+/// `since`, since a forum's messages live in its posts rather than the forum itself.
+/// Other channels are kept as they are.
+pub async fn expand_forums(
+    cache_http: impl CacheHttp,
+    channels: &[ChannelId],
+    since: DateTime<Utc>,
+) -> anyhow::Result<Vec<ChannelId>> {
+    let mut expanded = Vec::new();
+    for channel_id in channels {
+        let channel = channel_id.to_channel(cache_http.http()).await?;
+        let Some(forum) = channel.guild().filter(|c| c.kind == ChannelType::Forum) else {
+            expanded.push(*channel_id);
+            continue;
+        };
+
+        let active = forum.guild_id.get_active_threads(cache_http.http()).await?;
+        let archived = forum
+            .id
+            .get_archived_public_threads(cache_http.http(), None, Some(PAGE_SIZE as u64))
+            .await?;
+        let posts: Vec<ChannelId> = active
+            .threads
+            .iter()
+            .filter(|thread| thread.parent_id == Some(forum.id))
+            .chain(archived.threads.iter().filter(|thread| {
+                thread
+                    .thread_metadata
+                    .and_then(|metadata| metadata.archive_timestamp)
+                    .is_some_and(|archived_at| archived_at.unix_timestamp() >= since.timestamp())
+            }))
+            .map(|thread| thread.id)
+            .collect();
+        debug!("Forum {} has {} recent posts", forum.id, posts.len());
+        expanded.extend(posts);
+    }
+    Ok(expanded)
+}
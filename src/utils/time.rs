@@ -15,12 +15,117 @@ GNU General Public License for more details.
 You should have received a copy of the GNU General Public License
 along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
-use chrono::{DateTime, Datelike, Local, TimeZone};
-use chrono_tz::Asia::Kolkata;
+use chrono::{
+    DateTime, Datelike, LocalResult, NaiveDate, NaiveTime, Offset, TimeZone, Utc, Weekday,
+};
 use chrono_tz::Tz;
 use tracing::debug;
 
-use std::time::Duration;
+use std::{sync::RwLock, time::Duration};
+
+/// The bot's timezone, see [`configure`].
+static TIMEZONE: RwLock<Tz> = RwLock::new(chrono_tz::Asia::Kolkata);
+
+/// Sets the timezone days, schedules and plain-text times follow, from `bot.timezone`.
+/// Called at startup and whenever the config is reloaded.
+pub fn configure(timezone: Tz) {
+    *TIMEZONE.write().expect("Timezone lock poisoned") = timezone;
+}
+
+/// The bot's timezone.
+pub fn timezone() -> Tz {
+    *TIMEZONE.read().expect("Timezone lock poisoned")
+}
+
+/// The current time in the bot's timezone.
+pub fn local_now() -> DateTime<Tz> {
+    Utc::now().with_timezone(&timezone())
+}
+
+/// Today's date in the bot's timezone.
+pub fn local_today() -> NaiveDate {
+    local_now().date_naive()
+}
+
+/// How Discord renders a `<t:...>` timestamp. Every reader sees it in their own timezone.
+#[derive(Clone, Copy, Debug)]
+pub enum TimestampStyle {
+    /// e.g. "5:30 PM"
+    ShortTime,
+    /// e.g. "Friday, 31 January 2026 5:30 PM"
+    LongDateTime,
+    /// e.g. "in 2 hours"
+    Relative,
+}
+
+/// Discord markup for `time`, use it wherever the text is rendered by Discord.
+pub fn discord_timestamp(time: DateTime<Utc>, style: TimestampStyle) -> String {
+    let style = match style {
+        TimestampStyle::ShortTime => 't',
+        TimestampStyle::LongDateTime => 'F',
+        TimestampStyle::Relative => 'R',
+    };
+    format!("<t:{}:{}>", time.timestamp(), style)
+}
+
+/// A date as shown in report titles, e.g. "January 31, 2026".
+pub fn format_date(date: NaiveDate) -> String {
+    date.format("%B %d, %Y").to_string()
+}
+
+/// A month as shown in monthly report titles, e.g. "January 2026".
+pub fn format_month(date: NaiveDate) -> String {
+    date.format("%B %Y").to_string()
+}
+
+/// A date within the current year, e.g. "January 31".
+pub fn format_day(date: NaiveDate) -> String {
+    date.format("%B %-d").to_string()
+}
+
+/// `time` as plain text in `timezone`, for places where Discord doesn't render
+/// timestamps such as file names.
+pub fn format_local(time: DateTime<Utc>, timezone: Tz, format: &str) -> String {
+    time.with_timezone(&timezone).format(format).to_string()
+}
+
+/// A window that opens at the same wall-clock time every day and stays open until the
+/// next opening, e.g. status updates count from 8 PM until the next evening.
+#[derive(Clone, Copy, Debug)]
+pub struct DailyWindow {
+    pub opens_at: NaiveTime,
+    pub timezone: Tz,
+}
+
+impl DailyWindow {
+    /// When the window open at `now` opened, on the local date of `now` or the one before.
+    pub fn opened(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = now.with_timezone(&self.timezone).date_naive();
+        let opening = local_to_utc(today, self.opens_at, self.timezone);
+        if opening <= now {
+            return opening;
+        }
+        let yesterday = today.pred_opt().expect("Valid date");
+        local_to_utc(yesterday, self.opens_at, self.timezone)
+    }
+}
+
+/// `time` on `date` in `timezone`. A time repeated when the clocks go back resolves to its
+/// first occurrence, and one skipped when they go forward is read with the offset from
+/// before the change, landing as far past the jump as it was into the gap.
+pub fn local_to_utc(date: NaiveDate, time: NaiveTime, timezone: Tz) -> DateTime<Utc> {
+    let local = date.and_time(time);
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time.with_timezone(&Utc),
+        LocalResult::None => {
+            // Clocks change at most once a day, so a day earlier has the old offset.
+            let before = local - chrono::Duration::days(1);
+            let offset = timezone.offset_from_utc_datetime(&before).fix();
+            let utc = local - chrono::Duration::seconds(offset.local_minus_utc().into());
+            Utc.from_utc_datetime(&utc)
+        }
+    }
+}
 
 pub fn time_until(hour: u32, minute: u32) -> Duration {
     debug!(
@@ -28,11 +133,10 @@ pub fn time_until(hour: u32, minute: u32) -> Duration {
         hour, minute
     );
 
-    let now = Local::now().with_timezone(&Kolkata);
-    let today_run = Kolkata
-        .with_ymd_and_hms(now.year(), now.month(), now.day(), hour, minute, 0)
-        .single()
-        .expect("Valid datetime must be created");
+    let timezone = timezone();
+    let now = Utc::now().with_timezone(&timezone);
+    let time = NaiveTime::from_hms_opt(hour, minute, 0).expect("Valid time");
+    let today_run = local_to_utc(now.date_naive(), time, timezone).with_timezone(&timezone);
 
     let next_run = if now < today_run {
         today_run
@@ -44,17 +148,141 @@ pub fn time_until(hour: u32, minute: u32) -> Duration {
 
     let duration = next_run.signed_duration_since(now);
     debug!("duration: {}", duration);
-    Duration::from_secs(duration.num_seconds().max(0) as u64)
+    // Keep sub-second precision, rounding down would wake the scheduler just before the
+    // slot and run the task twice.
+    duration.to_std().unwrap_or_default()
+}
+
+/// Like [`time_until`], but for the next occurrence of `weekday` at `hour:minute` in the
+/// bot's timezone.
+pub fn time_until_weekday(weekday: Weekday, hour: u32, minute: u32) -> Duration {
+    let now = local_now();
+    let days_ahead =
+        (weekday.num_days_from_monday() + 7 - now.weekday().num_days_from_monday()) % 7;
+    let until_time_of_day = time_until(hour, minute);
+
+    // time_until already rolls over to tomorrow when today's slot has passed.
+    let rolled_over =
+        now.time() >= chrono::NaiveTime::from_hms_opt(hour, minute, 0).expect("Valid time");
+    let days = match (days_ahead, rolled_over) {
+        (0, true) => 6,
+        (0, false) => 0,
+        (days, true) => days - 1,
+        (days, false) => days,
+    };
+
+    until_time_of_day + Duration::from_secs(u64::from(days) * 24 * 60 * 60)
 }
 
-pub fn get_five_forty_five_pm_timestamp(now: DateTime<Tz>) -> DateTime<Local> {
-    let date =
-        chrono::NaiveDate::from_ymd_opt(now.year(), now.month(), now.day()).expect("Invalid date");
-    let time = chrono::NaiveTime::from_hms_opt(17, 45, 0).expect("Invalid time");
-    let naive_dt = date.and_time(time);
+pub fn get_five_forty_five_pm_timestamp(now: DateTime<Tz>) -> DateTime<Tz> {
+    let time = NaiveTime::from_hms_opt(17, 45, 0).expect("Invalid time");
+    local_to_utc(now.date_naive(), time, now.timezone()).with_timezone(&now.timezone())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::{America::New_York, Asia::Kolkata};
+
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().expect("Valid timestamp")
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().expect("Valid date")
+    }
+
+    fn time(s: &str) -> NaiveTime {
+        s.parse().expect("Valid time")
+    }
+
+    fn window(opens_at: &str, timezone: Tz) -> DailyWindow {
+        DailyWindow {
+            opens_at: time(opens_at),
+            timezone,
+        }
+    }
+
+    #[test]
+    fn local_to_utc_applies_the_offset() {
+        let converted = local_to_utc(date("2026-01-15"), time("20:00:00"), Kolkata);
+        assert_eq!(converted, utc("2026-01-15T14:30:00Z"));
+    }
+
+    #[test]
+    fn local_to_utc_moves_a_skipped_time_past_the_jump() {
+        // Clocks went from 2:00 EST to 3:00 EDT, so 2:30 never happened.
+        let converted = local_to_utc(date("2026-03-08"), time("02:30:00"), New_York);
+        assert_eq!(converted, utc("2026-03-08T07:30:00Z"));
+    }
+
+    #[test]
+    fn local_to_utc_picks_the_first_of_a_repeated_time() {
+        // Clocks went from 2:00 EDT back to 1:00 EST, so 1:30 happened twice.
+        let converted = local_to_utc(date("2026-11-01"), time("01:30:00"), New_York);
+        assert_eq!(converted, utc("2026-11-01T05:30:00Z"));
+    }
+
+    #[test]
+    fn window_opened_today_once_the_time_passed() {
+        let window = window("20:00:00", Kolkata);
+        assert_eq!(
+            window.opened(utc("2026-01-15T15:00:00Z")),
+            utc("2026-01-15T14:30:00Z")
+        );
+        assert_eq!(
+            window.opened(utc("2026-01-15T14:30:00Z")),
+            utc("2026-01-15T14:30:00Z")
+        );
+    }
+
+    #[test]
+    fn window_opened_yesterday_before_the_time() {
+        let window = window("20:00:00", Kolkata);
+        assert_eq!(
+            window.opened(utc("2026-01-15T14:00:00Z")),
+            utc("2026-01-14T14:30:00Z")
+        );
+    }
+
+    #[test]
+    fn window_stays_open_past_midnight() {
+        // 2 AM IST on the 16th, the window opened the evening before.
+        let evening = window("20:00:00", Kolkata);
+        assert_eq!(
+            evening.opened(utc("2026-01-15T20:30:00Z")),
+            utc("2026-01-15T14:30:00Z")
+        );
+        // 00:10 IST on the 16th, just after a late opening on the 15th.
+        let late = window("23:30:00", Kolkata);
+        assert_eq!(
+            late.opened(utc("2026-01-15T18:40:00Z")),
+            utc("2026-01-15T18:00:00Z")
+        );
+    }
+
+    #[test]
+    fn window_opening_in_a_spring_forward_gap() {
+        let window = window("02:30:00", New_York);
+        assert_eq!(
+            window.opened(utc("2026-03-08T08:00:00Z")),
+            utc("2026-03-08T07:30:00Z")
+        );
+        // Just before the shifted opening, the previous day's window is still open.
+        assert_eq!(
+            window.opened(utc("2026-03-08T07:00:00Z")),
+            utc("2026-03-07T07:30:00Z")
+        );
+    }
 
-    chrono::Local
-        .from_local_datetime(&naive_dt)
-        .single()
-        .expect("Chrono must work.")
+    #[test]
+    fn window_opening_in_a_fall_back_overlap() {
+        let window = window("01:30:00", New_York);
+        // 1:45 EST, the second time through 1:30, the window opened the first time.
+        assert_eq!(
+            window.opened(utc("2026-11-01T06:45:00Z")),
+            utc("2026-11-01T05:30:00Z")
+        );
+    }
 }
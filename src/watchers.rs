@@ -0,0 +1,116 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use serenity::all::{
+    ChannelId, Context as SerenityContext, CreateAllowedMentions, CreateMessage, Message, RoleId,
+};
+use tracing::{info, warn};
+
+use crate::{config::WatcherConfig, Data};
+
+/// How much of the matched message is quoted in the alert.
+const EXCERPT_LENGTH: usize = 300;
+
+/// When each watcher last fired, by name. Kept in memory, a restart resets the cooldowns.
+static LAST_ALERTS: LazyLock<Mutex<HashMap<String, Instant>>> = LazyLock::new(Mutex::default);
+
+/// Alerts for every watcher whose keywords `message` contains, unless it's cooling down.
+pub async fn check_message(ctx: &SerenityContext, data: &Data, message: &Message) {
+    if message.author.bot || message.guild_id.is_none() {
+        return;
+    }
+    let (watchers, ops_channel_id) = {
+        let config = data.config.read().await;
+        (config.watchers.clone(), config.bot.ops_channel_id)
+    };
+
+    for watcher in &watchers {
+        let watches_channel = watcher.channel_ids.is_empty()
+            || watcher.channel_ids.contains(&message.channel_id.get());
+        if !watches_channel {
+            continue;
+        }
+        let Some(keyword) = watcher.matches(&message.content) else {
+            continue;
+        };
+        let Some(channel_id) = watcher.alert_channel_id.or(ops_channel_id) else {
+            continue;
+        };
+        if !start_cooldown(watcher) {
+            continue;
+        }
+
+        info!(
+            "Watcher {} matched \"{}\" in channel {}",
+            watcher.name, keyword, message.channel_id
+        );
+        if let Err(e) = ChannelId::new(channel_id)
+            .send_message(&ctx.http, alert(watcher, keyword, message))
+            .await
+        {
+            warn!("Failed to send the {} watcher alert: {}", watcher.name, e);
+        }
+    }
+}
+
+/// Records that `watcher` fires now, returning false if it fired within its cooldown.
+fn start_cooldown(watcher: &WatcherConfig) -> bool {
+    let cooldown = Duration::from_secs(watcher.cooldown_minutes * 60);
+    let now = Instant::now();
+    let mut last_alerts = LAST_ALERTS.lock().expect("Watcher cooldowns lock poisoned");
+    if last_alerts
+        .get(&watcher.name)
+        .is_some_and(|at| now.duration_since(*at) < cooldown)
+    {
+        return false;
+    }
+    last_alerts.insert(watcher.name.clone(), now);
+    true
+}
+
+fn alert(watcher: &WatcherConfig, keyword: &str, message: &Message) -> CreateMessage {
+    let mut excerpt: String = message.content.chars().take(EXCERPT_LENGTH).collect();
+    if excerpt.len() < message.content.len() {
+        excerpt.push('…');
+    }
+    let quoted = excerpt.replace('\n', "\n> ");
+    let ping = watcher
+        .role_id
+        .map(|role_id| format!("<@&{}> ", role_id))
+        .unwrap_or_default();
+    let content = format!(
+        "{}**{}**: <@{}> mentioned \"{}\" in <#{}>\n> {}\n{}",
+        ping,
+        watcher.name,
+        message.author.id,
+        keyword,
+        message.channel_id,
+        quoted,
+        message.link()
+    );
+
+    let mentions = CreateAllowedMentions::new().roles(watcher.role_id.map(RoleId::new));
+    CreateMessage::new()
+        .content(content)
+        .allowed_mentions(mentions)
+}
@@ -0,0 +1,332 @@
+/*
+amFOSS Daemon: A discord bot for the amFOSS Discord server.
+Copyright (C) 2024 amFOSS
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    ChannelId, Context as SerenityContext, CreateAllowedMentions, CreateMessage, GuildId, Message,
+    RoleId, UserId,
+};
+use tracing::{info, warn};
+
+use crate::{
+    config::XpConfig,
+    ids::{self, ChannelKind},
+    privacy::{erased_members, is_erased},
+    storage::Storage,
+    Data,
+};
+
+/// Every member's XP, keyed by Discord ID.
+const PROGRESS_KEY: &str = "xp.members";
+
+/// Message XP waiting for the next [`flush_message_xp`], so chatting doesn't rewrite the
+/// store on every message. Lost if the bot stops before the flush.
+static MESSAGE_XP: LazyLock<Mutex<MessageXp>> = LazyLock::new(Default::default);
+
+#[derive(Default)]
+struct MessageXp {
+    /// XP earned since the last flush, keyed by Discord ID.
+    pending: HashMap<u64, u64>,
+    /// When a message last earned XP, kept past the flush until the cooldown is over.
+    last_message_at: HashMap<u64, DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Progress {
+    pub xp: u64,
+    /// When a message last earned XP, for the cooldown.
+    last_message_at: Option<DateTime<Utc>>,
+}
+
+impl Progress {
+    pub fn level(&self) -> u32 {
+        let mut level = 0;
+        while self.xp >= xp_for_level(level + 1) {
+            level += 1;
+        }
+        level
+    }
+}
+
+/// Total XP needed to reach `level`: 100 for level 1, 300 for level 2, 600 for level 3,
+/// each level taking 100 more than the one before.
+pub fn xp_for_level(level: u32) -> u64 {
+    let level = level as u64;
+    50 * level * (level + 1)
+}
+
+pub struct LevelUp {
+    pub user_id: u64,
+    pub level: u32,
+}
+
+pub async fn progress(storage: &Storage, user_id: u64) -> anyhow::Result<Progress> {
+    let members: HashMap<u64, Progress> = storage.get(PROGRESS_KEY).await?;
+    Ok(members.get(&user_id).copied().unwrap_or_default())
+}
+
+/// The member's position by XP, starting at 1. [`None`] if they have none yet.
+pub async fn rank(storage: &Storage, user_id: u64) -> anyhow::Result<Option<usize>> {
+    let members: HashMap<u64, Progress> = storage.get(PROGRESS_KEY).await?;
+    let Some(own) = members.get(&user_id).filter(|p| p.xp > 0) else {
+        return Ok(None);
+    };
+    Ok(Some(members.values().filter(|p| p.xp > own.xp).count() + 1))
+}
+
+/// Adds the XP of each `(user_id, xp)` in `awards` and returns who reached a new level.
+/// `message_at` holds when members last earned XP for a message.
+async fn grant(
+    storage: &Storage,
+    awards: &[(u64, u64)],
+    message_at: &HashMap<u64, DateTime<Utc>>,
+) -> anyhow::Result<Vec<LevelUp>> {
+    storage
+        .update(PROGRESS_KEY, |members: &mut HashMap<u64, Progress>| {
+            let mut level_ups = Vec::new();
+            for (user_id, xp) in awards {
+                let progress = members.entry(*user_id).or_default();
+                let before = progress.level();
+                progress.xp = progress.xp.saturating_add(*xp);
+                if let Some(at) = message_at.get(user_id) {
+                    progress.last_message_at = Some(*at);
+                }
+                let after = progress.level();
+                if after > before {
+                    level_ups.push(LevelUp {
+                        user_id: *user_id,
+                        level: after,
+                    });
+                }
+            }
+            level_ups
+        })
+        .await
+}
+
+/// Gives each of `user_ids` `xp` for an activity, then announces level-ups and hands out
+/// level roles. Failures are logged, XP is a bonus and shouldn't fail the caller.
+pub async fn award(
+    ctx: &SerenityContext,
+    data: &Data,
+    user_ids: impl IntoIterator<Item = u64>,
+    xp: u64,
+) {
+    let user_ids: Vec<u64> = match erased_members(&data.storage).await {
+        Ok(erased) => user_ids
+            .into_iter()
+            .filter(|id| !erased.contains(&id.to_string()))
+            .collect(),
+        Err(e) => {
+            warn!("Failed to look up erased members: {:?}", e);
+            return;
+        }
+    };
+    if xp == 0 || user_ids.is_empty() {
+        return;
+    }
+    let awards: Vec<(u64, u64)> = user_ids.into_iter().map(|id| (id, xp)).collect();
+    match grant(&data.storage, &awards, &HashMap::new()).await {
+        Ok(level_ups) => celebrate(ctx, data, None, level_ups).await,
+        Err(e) => warn!("Failed to award XP: {:?}", e),
+    }
+}
+
+/// Gives XP for a message in the server, at most once per cooldown so spamming doesn't pay.
+/// The XP is held in memory until the next [`flush_message_xp`].
+pub async fn record_message(data: &Data, message: &Message) {
+    if message.author.bot || message.guild_id.is_none() {
+        return;
+    }
+    let config = data.config.read().await.xp.clone();
+    if config.per_message == 0 {
+        return;
+    }
+    let user_id = message.author.id.get();
+    match is_erased(&data.storage, &user_id.to_string()).await {
+        Ok(false) => {}
+        Ok(true) => return,
+        Err(e) => {
+            warn!("Failed to check whether the author was erased: {:?}", e);
+            return;
+        }
+    }
+    // Only needed when the member didn't earn message XP since the bot started.
+    let stored = match progress(&data.storage, user_id).await {
+        Ok(progress) => progress.last_message_at,
+        Err(e) => {
+            warn!("Failed to read the XP of {}: {:?}", user_id, e);
+            return;
+        }
+    };
+    let now = Utc::now();
+    let cooldown = Duration::seconds(config.message_cooldown_seconds as i64);
+
+    let mut buffer = MESSAGE_XP.lock().expect("Message XP lock poisoned");
+    let last = buffer.last_message_at.get(&user_id).copied().or(stored);
+    if last.is_some_and(|at| now - at < cooldown) {
+        return;
+    }
+    buffer.last_message_at.insert(user_id, now);
+    let pending = buffer.pending.entry(user_id).or_default();
+    *pending = pending.saturating_add(config.per_message);
+}
+
+/// Adds the message XP held in memory to the store, then announces level-ups.
+pub async fn flush_message_xp(ctx: &SerenityContext, data: &Data) -> anyhow::Result<()> {
+    let cooldown = Duration::seconds(data.config.read().await.xp.message_cooldown_seconds as i64);
+    let (awards, message_at) = {
+        let mut buffer = MESSAGE_XP.lock().expect("Message XP lock poisoned");
+        let awards: Vec<(u64, u64)> = std::mem::take(&mut buffer.pending).into_iter().collect();
+        let message_at: HashMap<u64, DateTime<Utc>> = awards
+            .iter()
+            .filter_map(|(user_id, _)| Some((*user_id, *buffer.last_message_at.get(user_id)?)))
+            .collect();
+        let now = Utc::now();
+        buffer.last_message_at.retain(|_, at| now - *at < cooldown);
+        (awards, message_at)
+    };
+    if awards.is_empty() {
+        return Ok(());
+    }
+
+    let level_ups = grant(&data.storage, &awards, &message_at).await?;
+    celebrate(ctx, data, None, level_ups).await;
+    Ok(())
+}
+
+async fn celebrate(
+    ctx: &SerenityContext,
+    data: &Data,
+    guild_id: Option<GuildId>,
+    level_ups: Vec<LevelUp>,
+) {
+    if level_ups.is_empty() {
+        return;
+    }
+    for level_up in &level_ups {
+        info!("{} reached level {}", level_up.user_id, level_up.level);
+    }
+    let config = data.config.read().await.xp.clone();
+
+    if let Some(channel_id) = config.announce_channel_id {
+        let content = level_ups
+            .iter()
+            .map(|level_up| format!("<@{}> reached level {}!", level_up.user_id, level_up.level))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let message = CreateMessage::new()
+            .content(content)
+            .allowed_mentions(CreateAllowedMentions::new().all_users(true));
+        if let Err(e) = ChannelId::new(channel_id)
+            .send_message(&ctx.http, message)
+            .await
+        {
+            warn!("Failed to announce level-ups: {}", e);
+        }
+    }
+
+    if config.level_roles.is_empty() {
+        return;
+    }
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => match ids::channel(ChannelKind::Lab).to_channel(&ctx.http).await {
+            Ok(channel) => match channel.guild() {
+                Some(channel) => channel.guild_id,
+                None => {
+                    warn!("The lab channel isn't in a server, skipping level roles");
+                    return;
+                }
+            },
+            Err(e) => {
+                warn!("Failed to find the server for level roles: {}", e);
+                return;
+            }
+        },
+    };
+    for level_up in &level_ups {
+        update_level_roles(ctx, &config, guild_id, level_up).await;
+    }
+}
+
+/// Gives the member the role of the highest level they reached and takes the lower
+/// level roles, so they only show one.
+async fn update_level_roles(
+    ctx: &SerenityContext,
+    config: &XpConfig,
+    guild_id: GuildId,
+    level_up: &LevelUp,
+) {
+    let Some(earned) = config
+        .level_roles
+        .iter()
+        .filter(|role| role.level <= level_up.level)
+        .max_by_key(|role| role.level)
+    else {
+        return;
+    };
+    let user_id = UserId::new(level_up.user_id);
+    for role in &config.level_roles {
+        let result = if role.role_id == earned.role_id {
+            ctx.http
+                .add_member_role(
+                    guild_id,
+                    user_id,
+                    RoleId::new(role.role_id),
+                    Some("Reached a new level"),
+                )
+                .await
+        } else if role.level < earned.level {
+            ctx.http
+                .remove_member_role(
+                    guild_id,
+                    user_id,
+                    RoleId::new(role.role_id),
+                    Some("Reached a higher level"),
+                )
+                .await
+        } else {
+            continue;
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to update level role {} of {}: {}",
+                role.role_id, user_id, e
+            );
+        }
+    }
+}
+
+pub async fn forget_member(storage: &Storage, user_id: u64) -> anyhow::Result<()> {
+    {
+        let mut buffer = MESSAGE_XP.lock().expect("Message XP lock poisoned");
+        buffer.pending.remove(&user_id);
+        buffer.last_message_at.remove(&user_id);
+    }
+    storage
+        .update(PROGRESS_KEY, |members: &mut HashMap<u64, Progress>| {
+            members.remove(&user_id);
+        })
+        .await
+}